@@ -1,3 +1,12 @@
+#[path = "build/board.rs"]
+mod board;
+
 fn main() {
-    embuild::espidf::sysenv::output();
+    // 根据 boards/*.toml 生成 board 模块，主机测试同样需要
+    board::generate();
+
+    // 主机测试构建时没有ESP-IDF环境
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("espidf") {
+        embuild::espidf::sysenv::output();
+    }
 }
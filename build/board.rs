@@ -0,0 +1,469 @@
+/**
+ * @file board.rs
+ * @brief 开发板引脚表生成
+ * @details 读取 boards/<开发板>.toml，检查后生成 `board` 模块（$OUT_DIR/board.rs）:
+ *          - 开发板由 `board-*` 特性选择，其次是 BOARD 环境变量，默认为 dnesp32s3
+ *          - 引脚必须是 ESP32-S3 上存在的GPIO，且不能被 Flash/PSRAM 占用
+ *          - 同一个引脚只能属于一个外设，同一SPI总线上的外设可以共用 mosi/miso/sclk
+ *          - 外设名不能是Rust关键字，引脚名和参数名不能互相重名，也不能是 bus/pins
+ *          - 每个外设生成一个子模块，引脚和参数都是带类型的常量
+ *          描述文件的格式见 boards/dnesp32s3.toml
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+use toml::{Table, Value};
+
+/// 未指定开发板时使用的描述文件
+const DEFAULT_BOARD: &str = "dnesp32s3";
+/// 同一SPI总线上的外设可以共用的引脚名
+const SHARED_BUS_PINS: [&str; 3] = ["mosi", "miso", "sclk"];
+/// 外设模块中已经生成的常量（`BUS`、`PINS`），不能再用作引脚名或参数名
+const RESERVED_NAMES: [&str; 2] = ["bus", "pins"];
+/// 不能用作外设模块名的Rust关键字（包括保留字）
+const KEYWORDS: [&str; 50] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+/// 外设表中允许出现的键
+const PERIPHERAL_KEYS: [&str; 4] = ["description", "bus", "pins", "params"];
+
+/// 一个板上外设
+struct Peripheral {
+    name: String,
+    description: Option<String>,
+    bus: Option<String>,
+    pins: Vec<(String, u32)>,
+    params: Vec<(String, Value)>,
+}
+
+/// 整块开发板
+struct Board {
+    file: String,
+    name: String,
+    description: String,
+    /// 八线PSRAM额外占用 GPIO33~37
+    octal_psram: bool,
+    peripherals: Vec<Peripheral>,
+}
+
+/// 生成 `board` 模块，描述文件有误时中止构建
+pub fn generate() {
+    println!("cargo:rerun-if-env-changed=BOARD");
+    println!("cargo:rerun-if-changed=boards");
+    if let Err(message) = run() {
+        panic!("开发板描述文件错误: {}", message);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").map_err(|e| e.to_string())?);
+    let name = selected_board()?;
+    let path = manifest_dir.join("boards").join(format!("{}.toml", name));
+    let text =
+        fs::read_to_string(&path).map_err(|e| format!("无法读取 {}: {}", path.display(), e))?;
+    let table: Table = text.parse().map_err(|e| format!("{}.toml: {}", name, e))?;
+
+    let board = parse_board(format!("{}.toml", name), &table)?;
+    check_pins(&board)?;
+
+    let out = PathBuf::from(env::var("OUT_DIR").map_err(|e| e.to_string())?).join("board.rs");
+    fs::write(&out, render(&board)).map_err(|e| format!("无法写入 {}: {}", out.display(), e))
+}
+
+/// 选择开发板：`board-*` 特性优先，其次是 BOARD 环境变量
+fn selected_board() -> Result<String, String> {
+    let features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_BOARD_")
+                .map(str::to_lowercase)
+        })
+        .collect();
+    match features.as_slice() {
+        [] => Ok(env::var("BOARD").unwrap_or_else(|_| DEFAULT_BOARD.to_string())),
+        [feature] => Ok(feature.clone()),
+        _ => Err(format!(
+            "只能启用一个 board-* 特性，当前启用了 {:?}",
+            features
+        )),
+    }
+}
+
+/// 外设名、引脚名和参数名都会成为Rust标识符
+fn check_ident(file: &str, name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: `{}` 不是合法的名称（小写字母、数字和下划线）",
+            file, name
+        ))
+    }
+}
+
+/// 引脚名和参数名转为大写后成为外设模块中的常量，不能与生成的 `BUS`、`PINS` 重名
+fn check_const_name(file: &str, peripheral: &str, name: &str) -> Result<(), String> {
+    check_ident(file, name)?;
+    if RESERVED_NAMES.contains(&name) {
+        return Err(format!(
+            "{}: {}.{} 与生成的常量 `{}` 重名",
+            file,
+            peripheral,
+            name,
+            name.to_uppercase()
+        ));
+    }
+    Ok(())
+}
+
+fn parse_board(file: String, table: &Table) -> Result<Board, String> {
+    let info = table
+        .get("board")
+        .and_then(Value::as_table)
+        .ok_or_else(|| format!("{}: 缺少 [board] 表", file))?;
+    let name = info
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("{}: [board] 缺少 name", file))?
+        .to_string();
+    let description = info
+        .get("description")
+        .and_then(Value::as_str)
+        .unwrap_or(&name)
+        .to_string();
+    let octal_psram = match info.get("psram").map(|psram| psram.as_str()) {
+        None | Some(Some("none" | "quad")) => false,
+        Some(Some("octal")) => true,
+        Some(_) => {
+            return Err(format!(
+                "{}: [board] psram 只能是 \"none\"、\"quad\" 或 \"octal\"",
+                file
+            ))
+        }
+    };
+
+    let mut peripherals = Vec::new();
+    for (key, value) in table.iter().filter(|(key, _)| key.as_str() != "board") {
+        let peripheral = value
+            .as_table()
+            .ok_or_else(|| format!("{}: `{}` 应该是一个表", file, key))?;
+        peripherals.push(parse_peripheral(&file, key, peripheral)?);
+    }
+
+    Ok(Board {
+        file,
+        name,
+        description,
+        octal_psram,
+        peripherals,
+    })
+}
+
+fn parse_peripheral(file: &str, name: &str, table: &Table) -> Result<Peripheral, String> {
+    check_ident(file, name)?;
+    // 外设名原样成为模块名
+    if name == "_" || KEYWORDS.contains(&name) {
+        return Err(format!("{}: `{}` 是Rust关键字，不能用作外设名", file, name));
+    }
+    if let Some(key) = table
+        .keys()
+        .find(|key| !PERIPHERAL_KEYS.contains(&key.as_str()))
+    {
+        return Err(format!("{}: [{}] 中有未知的键 `{}`", file, name, key));
+    }
+
+    let description = table
+        .get("description")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let bus = match table.get("bus") {
+        None => None,
+        Some(Value::String(bus)) if bus == "spi2" || bus == "spi3" => Some(bus.clone()),
+        Some(_) => {
+            return Err(format!(
+                "{}: [{}] bus 只能是 \"spi2\" 或 \"spi3\"",
+                file, name
+            ))
+        }
+    };
+
+    let mut pins = Vec::new();
+    if let Some(table) = table.get("pins") {
+        let table = table
+            .as_table()
+            .ok_or_else(|| format!("{}: [{}] pins 应该是一个表", file, name))?;
+        for (pin_name, value) in table {
+            check_const_name(file, name, pin_name)?;
+            let pin = value
+                .as_integer()
+                // 与 registry::is_valid_gpio 一致，ESP32-S3 没有 GPIO22~25
+                .filter(|pin| matches!(pin, 0..=21 | 26..=48))
+                .ok_or_else(|| {
+                    format!(
+                        "{}: {}.{} 不是 ESP32-S3 上存在的GPIO（0~21、26~48）",
+                        file, name, pin_name
+                    )
+                })?;
+            pins.push((pin_name.clone(), pin as u32));
+        }
+    }
+
+    let mut params = Vec::new();
+    if let Some(table) = table.get("params") {
+        let table = table
+            .as_table()
+            .ok_or_else(|| format!("{}: [{}] params 应该是一个表", file, name))?;
+        for (param_name, value) in table {
+            check_const_name(file, name, param_name)?;
+            if pins.iter().any(|(pin_name, _)| pin_name == param_name) {
+                return Err(format!(
+                    "{}: {}.{} 同时是引脚和参数",
+                    file, name, param_name
+                ));
+            }
+            match value {
+                Value::Boolean(_) | Value::Float(_) | Value::String(_) => {}
+                Value::Integer(value)
+                    if i32::try_from(*value).is_ok() || u32::try_from(*value).is_ok() => {}
+                _ => {
+                    return Err(format!(
+                        "{}: {}.{} 只能是布尔值、32位整数、浮点数或字符串",
+                        file, name, param_name
+                    ))
+                }
+            }
+            params.push((param_name.clone(), value.clone()));
+        }
+    }
+
+    Ok(Peripheral {
+        name: name.to_string(),
+        description,
+        bus,
+        pins,
+        params,
+    })
+}
+
+/// 检查引脚是否被 Flash/PSRAM 占用，以及是否被多个外设重复使用
+fn check_pins(board: &Board) -> Result<(), String> {
+    let mut owners: BTreeMap<u32, (&Peripheral, &str)> = BTreeMap::new();
+    for peripheral in &board.peripherals {
+        for (pin_name, pin) in &peripheral.pins {
+            let pin = *pin;
+            if (26..=32).contains(&pin) {
+                return Err(format!(
+                    "{}: {}.{} 使用的 GPIO{} 连接 SPI Flash/PSRAM",
+                    board.file, peripheral.name, pin_name, pin
+                ));
+            }
+            if board.octal_psram && (33..=37).contains(&pin) {
+                return Err(format!(
+                    "{}: {}.{} 使用的 GPIO{} 被八线PSRAM占用",
+                    board.file, peripheral.name, pin_name, pin
+                ));
+            }
+            match owners.get(&pin) {
+                None => {
+                    owners.insert(pin, (peripheral, pin_name));
+                }
+                // 同一SPI总线上的设备共用 MOSI/MISO/SCLK
+                Some((owner, owner_pin))
+                    if owner.bus.is_some()
+                        && owner.bus == peripheral.bus
+                        && owner_pin == pin_name
+                        && SHARED_BUS_PINS.contains(&pin_name.as_str()) => {}
+                Some((owner, owner_pin)) => {
+                    return Err(format!(
+                        "{}: GPIO{} 同时被 {}.{} 和 {}.{} 使用",
+                        board.file, pin, owner.name, owner_pin, peripheral.name, pin_name
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 参数常量的类型和值
+fn param_const(value: &Value) -> (&'static str, String) {
+    match value {
+        Value::Boolean(value) => ("bool", value.to_string()),
+        Value::Integer(value) if *value < 0 => ("i32", value.to_string()),
+        Value::Integer(value) => ("u32", value.to_string()),
+        Value::Float(value) => ("f32", format!("{:?}", *value as f32)),
+        Value::String(value) => ("&str", format!("{:?}", value)),
+        _ => unreachable!("参数类型已在解析时检查"),
+    }
+}
+
+/// 生成 `board` 模块的内容
+fn render(board: &Board) -> String {
+    let mut out = String::new();
+    let mut used: Vec<u32> = board
+        .peripherals
+        .iter()
+        .flat_map(|peripheral| peripheral.pins.iter().map(|(_, pin)| *pin))
+        .collect();
+    used.sort_unstable();
+    used.dedup();
+
+    let _ = writeln!(
+        out,
+        "// 由 build.rs 根据 boards/{} 生成，请勿手动修改",
+        board.file
+    );
+    let _ = writeln!(out);
+    let _ = writeln!(out, "/// 开发板名称");
+    let _ = writeln!(out, "pub const NAME: &str = {:?};", board.name);
+    let _ = writeln!(out, "/// 开发板说明");
+    let _ = writeln!(
+        out,
+        "pub const DESCRIPTION: &str = {:?};",
+        board.description
+    );
+    let _ = writeln!(out, "/// 板上外设占用的所有引脚，共用的总线引脚只出现一次");
+    let _ = writeln!(out, "pub const USED_PINS: &[u32] = &{:?};", used);
+
+    for peripheral in &board.peripherals {
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "/// {}",
+            peripheral
+                .description
+                .as_deref()
+                .unwrap_or(&peripheral.name)
+        );
+        let _ = writeln!(out, "pub mod {} {{", peripheral.name);
+        if let Some(bus) = &peripheral.bus {
+            let variant = if bus == "spi2" { "Spi2" } else { "Spi3" };
+            let _ = writeln!(out, "    /// 所在的SPI总线");
+            let _ = writeln!(
+                out,
+                "    pub const BUS: crate::drivers::spi::SpiBus = crate::drivers::spi::SpiBus::{};",
+                variant
+            );
+        }
+        for (name, pin) in &peripheral.pins {
+            let _ = writeln!(out, "    /// {} 引脚", name);
+            let _ = writeln!(out, "    pub const {}: u32 = {};", name.to_uppercase(), pin);
+        }
+        for (name, value) in &peripheral.params {
+            let (ty, value) = param_const(value);
+            let _ = writeln!(out, "    /// 参数 {}", name);
+            let _ = writeln!(
+                out,
+                "    pub const {}: {} = {};",
+                name.to_uppercase(),
+                ty,
+                value
+            );
+        }
+        let pins: Vec<u32> = peripheral.pins.iter().map(|(_, pin)| *pin).collect();
+        let _ = writeln!(out, "    /// 本外设使用的所有引脚");
+        let _ = writeln!(out, "    pub const PINS: &[u32] = &{:?};", pins);
+        let _ = writeln!(out, "}}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(text: &str) -> Result<(), String> {
+        let table: Table = text.parse().map_err(|e| format!("{}", e))?;
+        check_pins(&parse_board("test.toml".to_string(), &table)?)
+    }
+
+    #[test]
+    fn test_shared_bus_pins() {
+        let board = r#"
+            [board]
+            name = "test"
+            [lcd]
+            bus = "spi2"
+            pins = { mosi = 11, sclk = 12, cs = 21 }
+            [sd]
+            bus = "spi2"
+            pins = { mosi = 11, sclk = 12, cs = 10 }
+        "#;
+        assert_eq!(check(board), Ok(()));
+
+        // 片选、DC 等设备引脚即使同名也不能共用
+        let board = r#"
+            [board]
+            name = "test"
+            [lcd]
+            bus = "spi2"
+            pins = { mosi = 11, sclk = 12, cs = 21 }
+            [sd]
+            bus = "spi2"
+            pins = { mosi = 11, sclk = 12, cs = 21 }
+        "#;
+        assert!(check(board).unwrap_err().contains("GPIO21"));
+
+        // 不同总线上的同名引脚也不能共用
+        let board = r#"
+            [board]
+            name = "test"
+            [lcd]
+            bus = "spi2"
+            pins = { sclk = 12 }
+            [sd]
+            bus = "spi3"
+            pins = { sclk = 12 }
+        "#;
+        assert!(check(board).unwrap_err().contains("GPIO12"));
+    }
+
+    #[test]
+    fn test_generated_names() {
+        let board = |peripheral: &str| format!("[board]\nname = \"test\"\n{}\n", peripheral);
+        assert_eq!(check(&board("[lcd_1]\npins = { cs = 21 }")), Ok(()));
+
+        // 外设名不能是关键字
+        for name in ["type", "mod", "self", "_"] {
+            let error = check(&board(&format!("[{}]\npins = {{ cs = 21 }}", name))).unwrap_err();
+            assert!(error.contains("关键字"), "{}", error);
+        }
+
+        // 引脚名和参数名不能与生成的常量重名，也不能互相重名
+        let error = check(&board("[lcd]\npins = { bus = 21 }")).unwrap_err();
+        assert!(error.contains("BUS"), "{}", error);
+        let error = check(&board("[lcd]\npins = { cs = 21 }\nparams = { pins = 1 }")).unwrap_err();
+        assert!(error.contains("PINS"), "{}", error);
+        let error = check(&board("[lcd]\npins = { cs = 21 }\nparams = { cs = 1 }")).unwrap_err();
+        assert!(error.contains("同时是引脚和参数"), "{}", error);
+    }
+
+    #[test]
+    fn test_nonexistent_pins() {
+        let board = |pin| {
+            format!(
+                "[board]\nname = \"test\"\n[led]\npins = {{ gpio = {} }}\n",
+                pin
+            )
+        };
+        assert_eq!(check(&board(21)), Ok(()));
+        assert_eq!(check(&board(48)), Ok(()));
+        for pin in [22, 25, 49, -1] {
+            assert!(check(&board(pin)).unwrap_err().contains("led.gpio"));
+        }
+    }
+}
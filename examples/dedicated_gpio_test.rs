@@ -0,0 +1,64 @@
+/**
+ * @file dedicated_gpio_test.rs
+ * @brief 专用GPIO示例
+ * @details 用专用GPIO组驱动74HC595移位寄存器，数据、时钟和锁存三根线由CPU指令直接写入，
+ *          移位寄存器的输出上依次点亮一个流水灯
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::thread;
+use std::time::Duration;
+
+use esp32_test::drivers::gpio::{DedicatedGpioBundle, GpioPin};
+
+// 74HC595 引脚，组内位号按顺序为 0、1、2
+const DATA_PIN: u32 = 4;
+const CLOCK_PIN: u32 = 5;
+const LATCH_PIN: u32 = 6;
+
+const DATA: u32 = 0b001;
+const CLOCK: u32 = 0b010;
+const LATCH: u32 = 0b100;
+
+/// 移出一个字节（高位在前）并锁存到输出
+fn shift_out(bundle: &DedicatedGpioBundle, byte: u8) {
+    for bit in (0..8).rev() {
+        let data = if byte & (1 << bit) != 0 { DATA } else { 0 };
+        // 数据和时钟下降沿在同一条指令中写入
+        bundle.write(DATA | CLOCK, data);
+        bundle.set_bits(CLOCK);
+    }
+    bundle.clear_bits(CLOCK);
+    bundle.set_bits(LATCH);
+    bundle.clear_bits(LATCH);
+}
+
+fn main() {
+    // 初始化ESP-IDF
+    esp_idf_svc::sys::link_patches();
+    // 初始化日志
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    println!("专用GPIO示例开始运行!");
+
+    let pins = [
+        (DATA_PIN, "hc595.data"),
+        (CLOCK_PIN, "hc595.clock"),
+        (LATCH_PIN, "hc595.latch"),
+    ]
+    .into_iter()
+    .map(|(pin, owner)| {
+        GpioPin::with_owner(pin, owner)
+            .and_then(GpioPin::into_push_pull_output)
+            .expect("GPIO初始化失败")
+    });
+    let bundle = DedicatedGpioBundle::new(pins).expect("创建专用GPIO组失败");
+
+    let mut pattern = 1u8;
+    loop {
+        shift_out(&bundle, pattern);
+        pattern = pattern.rotate_left(1);
+        thread::sleep(Duration::from_millis(100));
+    }
+}
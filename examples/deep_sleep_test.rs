@@ -0,0 +1,67 @@
+/**
+ * @file deep_sleep_test.rs
+ * @brief 睡眠与按键唤醒示例
+ * @details 启动后打印唤醒原因，先浅睡眠等待按键或定时器，再进入深度睡眠，
+ *          深度睡眠期间LED保持点亮，按下按键后芯片重新启动
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::thread;
+use std::time::Duration;
+
+use esp32_test::board;
+use esp32_test::drivers::gpio::GpioPin;
+use esp32_test::power::sleep::{self, SleepManager, WakeLevel};
+
+// 按键引脚（BOOT按键，按下为低电平，RTC GPIO）
+const KEY_GPIO_PIN: u32 = board::key::GPIO;
+// LED引脚
+const LED_GPIO_PIN: u32 = board::led::GPIO;
+
+fn main() {
+    // 初始化ESP-IDF
+    esp_idf_svc::sys::link_patches();
+    // 初始化日志
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    let report = sleep::wakeup_report();
+    println!("启动原因: {}", report);
+
+    let key = GpioPin::with_owner(KEY_GPIO_PIN, "key")
+        .and_then(GpioPin::into_pull_up_input)
+        .expect("按键GPIO初始化失败");
+    let led = GpioPin::with_owner(LED_GPIO_PIN, "led")
+        .and_then(GpioPin::into_push_pull_output)
+        .expect("LED GPIO初始化失败");
+    // 先恢复LED电平，再释放深度睡眠前的锁存，避免LED闪烁
+    led.set_level(u32::from(report.is_wakeup())).unwrap();
+    sleep::release_holds().expect("释放锁存失败");
+
+    // 浅睡眠：按键或5秒后唤醒，醒来后程序继续执行
+    let mut light = SleepManager::new();
+    light.wake_on_gpio(&key, WakeLevel::Low).unwrap();
+    light.wake_after(Duration::from_secs(5)).unwrap();
+    for _ in 0..3 {
+        match light.light_sleep() {
+            Ok(report) => println!("浅睡眠唤醒: {}", report),
+            Err(e) => println!("浅睡眠失败: {}", e),
+        }
+        led.toggle().unwrap();
+        // 等待按键松开，否则会立即再次唤醒
+        while key.is_low() {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    // 深度睡眠：LED保持点亮，按键或60秒后唤醒
+    led.set_high().unwrap();
+    let mut deep = SleepManager::new();
+    deep.wake_on_ext0(&key, WakeLevel::Low).unwrap();
+    deep.wake_after(Duration::from_secs(60)).unwrap();
+    deep.hold(&led);
+    println!("进入深度睡眠，按下按键唤醒");
+    if let Err(e) = deep.deep_sleep() {
+        println!("进入深度睡眠失败: {}", e);
+    }
+}
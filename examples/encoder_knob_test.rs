@@ -0,0 +1,64 @@
+/**
+ * @file encoder_knob_test.rs
+ * @brief 旋转编码器音量旋钮示例
+ * @details 用 PCNT 解码机械旋转编码器，每转过一格（4个计数）产生一个事件，
+ *          事件通过与GPIO中断相同的 `EventStream` 读取
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::time::Duration;
+
+use esp32_test::drivers::gpio::{EventStream, GpioPin};
+use esp32_test::drivers::pcnt::{EncoderConfig, PcntEncoder, PcntEvent, PcntEventKind};
+
+// 编码器A、B相引脚
+const ENCODER_A_PIN: u32 = 4;
+const ENCODER_B_PIN: u32 = 5;
+// 每格的计数（4倍频）
+const COUNTS_PER_DETENT: u32 = 4;
+
+fn main() {
+    // 初始化ESP-IDF
+    esp_idf_svc::sys::link_patches();
+    // 初始化日志
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    println!("旋转编码器示例开始运行!");
+
+    // 机械编码器的公共端接地，A/B相使用内部上拉
+    let pin_a = GpioPin::with_owner(ENCODER_A_PIN, "knob.a")
+        .and_then(GpioPin::into_pull_up_input)
+        .expect("A相GPIO初始化失败");
+    let pin_b = GpioPin::with_owner(ENCODER_B_PIN, "knob.b")
+        .and_then(GpioPin::into_pull_up_input)
+        .expect("B相GPIO初始化失败");
+
+    // 每转过一格产生一个步长事件
+    let config = EncoderConfig::default().glitch_filter(Some(10_000));
+    let mut encoder = PcntEncoder::new(pin_a, pin_b, &config).expect("编码器初始化失败");
+    encoder
+        .add_watch_step(COUNTS_PER_DETENT)
+        .expect("设置步长事件失败");
+
+    let mut events: EventStream<PcntEvent> = EventStream::new();
+    events.listen(&encoder).expect("订阅编码器事件失败");
+
+    let mut volume: i64 = 50;
+    let mut last_position = 0;
+    loop {
+        let Some(event) = events.next_timeout(Duration::from_secs(5)) else {
+            println!("位置: {}", encoder.position().unwrap_or_default());
+            continue;
+        };
+        if event.kind != PcntEventKind::Step {
+            continue;
+        }
+        volume = (volume + (event.position - last_position).signum()).clamp(0, 100);
+        last_position = event.position;
+        println!(
+            "音量: {} (位置 {}, 时间 {}us)",
+            volume, event.position, event.timestamp_us
+        );
+    }
+}
@@ -0,0 +1,64 @@
+/**
+ * @file fan_tach_test.rs
+ * @brief 风扇测速与PWM信号测量示例
+ * @details 用 MCPWM 捕获测量4线风扇的测速信号（每转2个脉冲），
+ *          并测量另一路PWM信号的频率和占空比
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::thread;
+use std::time::Duration;
+
+use esp32_test::drivers::gpio::GpioPin;
+use esp32_test::drivers::mcpwm::{CaptureConfig, CaptureEdges, McpwmCapture};
+
+// 风扇测速引脚（集电极开路输出，需要上拉）
+const TACH_PIN: u32 = 4;
+// 被测PWM信号引脚
+const PWM_IN_PIN: u32 = 5;
+// 每转的测速脉冲数
+const PULSES_PER_REV: f32 = 2.0;
+
+fn main() {
+    // 初始化ESP-IDF
+    esp_idf_svc::sys::link_patches();
+    // 初始化日志
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    println!("输入捕获示例开始运行!");
+
+    let tach_pin = GpioPin::with_owner(TACH_PIN, "fan.tach")
+        .and_then(GpioPin::into_pull_up_input)
+        .expect("测速GPIO初始化失败");
+    // 测速信号只需要周期，捕获下降沿即可
+    let tach_config = CaptureConfig::default().edges(CaptureEdges::Falling);
+    let mut tach = McpwmCapture::new(tach_pin, &tach_config).expect("测速捕获初始化失败");
+
+    let pwm_pin = GpioPin::with_owner(PWM_IN_PIN, "pwm.in")
+        .and_then(GpioPin::into_floating_input)
+        .expect("PWM输入GPIO初始化失败");
+    let mut pwm = McpwmCapture::new(pwm_pin, &CaptureConfig::default()).expect("PWM捕获初始化失败");
+
+    loop {
+        // 只测量此后的信号，风扇停转时1秒内没有完整周期
+        tach.clear();
+        match tach.frequency_hz(4, Duration::from_secs(1)) {
+            Ok(hz) => println!("风扇转速: {:.0} RPM", hz * 60.0 / PULSES_PER_REV),
+            Err(e) => println!("风扇转速: 0 RPM ({})", e),
+        }
+
+        pwm.clear();
+        match pwm.measure(16, Duration::from_millis(100)) {
+            Ok(measurement) => println!(
+                "PWM: {:.1} Hz, 占空比 {:.1}%, 脉宽 {:?}",
+                measurement.frequency_hz(),
+                measurement.duty_percent().unwrap_or_default(),
+                measurement.pulse_width().unwrap_or_default()
+            ),
+            Err(e) => println!("PWM测量失败: {}", e),
+        }
+
+        thread::sleep(Duration::from_millis(500));
+    }
+}
@@ -1,3 +1,4 @@
+use esp32_test::board;
 use esp32_test::drivers::gpio::{GpioHandler, GpioInterruptType, GpioMode, GpioPullMode};
 /**
  * @file gpio_led_test.rs
@@ -9,8 +10,8 @@ use esp32_test::drivers::gpio::{GpioHandler, GpioInterruptType, GpioMode, GpioPu
 use std::thread;
 use std::time::Duration;
 
-// 请根据您的硬件实际情况修改此常量
-const LED_GPIO_PIN: u32 = 1;
+// LED引脚来自开发板引脚表（boards/*.toml）
+const LED_GPIO_PIN: u32 = board::led::GPIO;
 
 fn main() {
     // 初始化ESP-IDF
@@ -21,7 +22,7 @@ fn main() {
     println!("GPIO LED控制示例开始运行!");
 
     // 创建一个GPIO处理实例
-    let led_gpio = GpioHandler::new(LED_GPIO_PIN);
+    let led_gpio = GpioHandler::new(LED_GPIO_PIN).expect("领取LED GPIO失败");
 
     // 将GPIO初始化为输出模式
     led_gpio
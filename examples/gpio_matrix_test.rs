@@ -0,0 +1,77 @@
+/**
+ * @file gpio_matrix_test.rs
+ * @brief GPIO矩阵信号路由示例
+ * @details 演示两种常见用法：
+ *          - 把 SPI2 时钟同时复制到一个测试引脚，方便用示波器观察
+ *          - 把 SPI2 片选反相后输出，驱动高电平有效的器件
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::thread;
+use std::time::Duration;
+
+use esp32_test::board;
+use esp32_test::drivers::gpio::matrix::signal::{FSPICLK_OUT_IDX, FSPICS0_OUT_IDX};
+use esp32_test::drivers::gpio::{GpioMatrix, GpioPin};
+use esp32_test::drivers::spi::{SpiBitOrder, SpiBus, SpiDeviceConfig, SpiMaster, SpiMode};
+
+// SPI2 引脚，与 LCD 示例一致
+const MOSI_PIN: i32 = board::lcd::MOSI as i32;
+const MISO_PIN: i32 = board::lcd::MISO as i32;
+const SCLK_PIN: i32 = board::lcd::SCLK as i32;
+// 高电平有效器件的片选
+const CS_PIN: u32 = 10;
+// 示波器测试点
+const PROBE_PIN: u32 = 21;
+
+fn main() {
+    // 初始化ESP-IDF
+    esp_idf_svc::sys::link_patches();
+    // 初始化日志
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    println!("GPIO矩阵示例开始运行!");
+
+    let mut spi_master = SpiMaster::new(SpiBus::Spi2).expect("创建SPI主机失败");
+    spi_master
+        .initialize(MOSI_PIN, MISO_PIN, SCLK_PIN, 0)
+        .expect("初始化SPI总线失败");
+
+    // 片选不交给SPI驱动，而是由下面的矩阵连接反相输出
+    let spi_config = SpiDeviceConfig {
+        mode: SpiMode::Mode0,
+        clock_speed_hz: 1_000_000,
+        cs_pin: None,
+        command_bits: 0,
+        address_bits: 0,
+        bit_order: SpiBitOrder::MSBFirst,
+        queue_size: 1,
+        ..SpiDeviceConfig::default()
+    };
+    let device = spi_master.add_device(&spi_config).expect("添加SPI设备失败");
+
+    // 片选反相：传输期间输出高电平
+    let cs = GpioPin::with_owner(CS_PIN, "device.cs")
+        .and_then(GpioPin::into_push_pull_output)
+        .expect("片选GPIO初始化失败");
+    GpioMatrix::connect_output(&cs, FSPICS0_OUT_IDX, true).expect("连接片选信号失败");
+
+    // SPI时钟复制到测试引脚，原来的SCLK引脚照常输出
+    let probe = GpioPin::with_owner(PROBE_PIN, "scope.sclk")
+        .and_then(GpioPin::into_push_pull_output)
+        .expect("测试引脚初始化失败");
+    GpioMatrix::connect_output(&probe, FSPICLK_OUT_IDX, false).expect("连接时钟信号失败");
+
+    println!(
+        "在GPIO{}上观察SPI时钟，GPIO{}为高电平有效的片选",
+        PROBE_PIN, CS_PIN
+    );
+
+    loop {
+        device
+            .write(&[0xA5, 0x5A, 0xFF, 0x00])
+            .expect("SPI写入失败");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,65 @@
+/**
+ * @file lcd_stripes_test.rs
+ * @brief LCD条带渲染示例
+ * @details 用排队的SPI事务刷新整屏动画：DMA发送上一个条带时CPU渲染下一个条带，
+ *          每秒打印一次帧率
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::time::Instant;
+
+use esp32_test::board;
+use esp32_test::drivers::atk_md0130;
+
+// 每个条带8行，240像素宽时为3840字节，不超过SPI默认的最大传输长度
+const STRIPE_ROWS: u16 = 8;
+
+fn main() {
+    // 初始化ESP-IDF
+    esp_idf_svc::sys::link_patches();
+    // 初始化日志
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    println!("LCD条带渲染示例开始运行!");
+
+    let mut lcd = atk_md0130::create_atk_md0130(
+        board::lcd::MOSI as i32,
+        board::lcd::MISO as i32,
+        board::lcd::SCLK as i32,
+        board::lcd::CS as i32,
+        board::lcd::DC as i32,
+        board::lcd::RST as i32,
+        Some(board::lcd::BL as i32),
+    )
+    .expect("初始化LCD失败");
+
+    let width = board::lcd::WIDTH as u16;
+    let height = board::lcd::HEIGHT as u16;
+    let mut frame: u16 = 0;
+    let mut frames = 0;
+    let mut last_report = Instant::now();
+
+    loop {
+        // 随帧号滚动的渐变色
+        lcd.draw_stripes(0, 0, width, height, STRIPE_ROWS, |first_row, stripe| {
+            for (index, pixel) in stripe.chunks_exact_mut(2).enumerate() {
+                let x = (index as u16) % width;
+                let y = first_row + (index as u16) / width;
+                let r = ((x + frame) & 0x1F) << 11;
+                let g = ((y + frame) & 0x3F) << 5;
+                let b = (x ^ y) & 0x1F;
+                pixel.copy_from_slice(&(r | g | b).to_be_bytes());
+            }
+        })
+        .expect("刷新失败");
+
+        frame = frame.wrapping_add(1);
+        frames += 1;
+        if last_report.elapsed().as_secs() >= 1 {
+            println!("帧率: {} fps", frames);
+            frames = 0;
+            last_report = Instant::now();
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use esp32_test::board;
 use esp32_test::drivers::atk_md0130::{self, DisplayRotation};
 use esp32_test::drivers::atk_md0130::{color, ATKMD0130};
 use std::thread;
@@ -11,15 +12,15 @@ fn main() {
     println!("ATK-MD0130 LCD测试开始运行!");
 
     // 初始化LCD
-    // 注意: 请根据实际硬件连接修改引脚定义
+    // 引脚来自开发板引脚表，换板时用 board-* 特性或 BOARD 环境变量选择
     let mut lcd = atk_md0130::create_atk_md0130(
-        11,       // MOSI引脚 IO11
-        13,       // MISO引脚 IO13
-        12,       // SCK引脚 IO12
-        21,       // CS引脚 (自定义)
-        40,       // DC引脚 (自定义)
-        8,        // RST引脚 (自定义)
-        Some(14), // BL引脚 (自定义)
+        board::lcd::MOSI as i32,     // MOSI引脚
+        board::lcd::MISO as i32,     // MISO引脚
+        board::lcd::SCLK as i32,     // SCK引脚
+        board::lcd::CS as i32,       // CS引脚
+        board::lcd::DC as i32,       // DC引脚
+        board::lcd::RST as i32,      // RST引脚
+        Some(board::lcd::BL as i32), // BL引脚
     )
     .expect("初始化LCD失败");
 
@@ -1,11 +1,12 @@
+use esp32_test::board;
 use esp32_test::led::Led;
 
-const GPIO_NUM_1: i32 = 1; // GPIO引脚编号
+const LED_GPIO_PIN: i32 = board::led::GPIO as i32; // GPIO引脚编号
 fn main() {
     esp_idf_svc::sys::link_patches();
     esp_idf_svc::log::EspLogger::initialize_default();
 
-    let mut led = Led::new(GPIO_NUM_1);
+    let mut led = Led::new(LED_GPIO_PIN);
     led.on();
     std::thread::sleep(std::time::Duration::from_secs(1));
     led.off();
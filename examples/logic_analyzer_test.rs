@@ -0,0 +1,58 @@
+/**
+ * @file logic_analyzer_test.rs
+ * @brief 逻辑分析仪示例
+ * @details 以100kHz采样BOOT按键，按下（下降沿）触发，统计抖动次数，
+ *          并把采集结果以VCD格式打印到串口，复制到文件后可用 PulseView 或 GTKWave 打开
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::time::Duration;
+
+use esp32_test::board;
+use esp32_test::diagnostics::logic::{LogicAnalyzer, LogicConfig, Trigger};
+use esp32_test::drivers::gpio::GpioPin;
+
+fn main() {
+    // 初始化ESP-IDF
+    esp_idf_svc::sys::link_patches();
+    // 初始化日志
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    println!("逻辑分析仪示例开始运行!");
+
+    let key = GpioPin::with_owner(board::key::GPIO, "key")
+        .and_then(GpioPin::into_pull_up_input)
+        .expect("按键GPIO初始化失败");
+
+    // 10us一个样本，触发前保留5ms，共采集约40ms
+    let config = LogicConfig {
+        sample_rate_hz: 100_000,
+        depth: 4096,
+        pre_trigger: 500,
+        trigger: Trigger::Falling(board::key::GPIO),
+        timeout: Some(Duration::from_secs(10)),
+    };
+    let mut analyzer = LogicAnalyzer::new(&config).expect("创建逻辑分析仪失败");
+    analyzer.add_channel(&key, "key").expect("添加通道失败");
+
+    loop {
+        println!("请按下按键...");
+        let capture = match analyzer.capture() {
+            Ok(capture) => capture,
+            Err(e) => {
+                println!("采集失败: {}", e);
+                continue;
+            }
+        };
+        println!(
+            "采集到 {} 个样本，按键边沿 {} 次，采样延迟 {} 次",
+            capture.len(),
+            capture.edge_count(0),
+            capture.overruns
+        );
+        println!("----- VCD BEGIN -----");
+        print!("{}", capture.to_vcd());
+        println!("----- VCD END -----");
+    }
+}
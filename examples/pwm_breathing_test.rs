@@ -0,0 +1,69 @@
+/**
+ * @file pwm_breathing_test.rs
+ * @brief LEDC PWM 调光示例
+ * @details 演示 LED 亮度调节、呼吸效果，以及渐变完成回调
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use esp32_test::board;
+use esp32_test::drivers::gpio::GpioPin;
+use esp32_test::drivers::pwm::{
+    FadeMode, LedcChannel, LedcChannelNum, LedcTimer, LedcTimerConfig, LedcTimerNum,
+};
+use esp32_test::led::Led;
+
+// LED引脚
+const LED_PIN: u32 = board::led::GPIO;
+
+fn main() {
+    // 初始化ESP-IDF
+    esp_idf_svc::sys::link_patches();
+    // 初始化日志
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    println!("PWM调光示例开始运行!");
+
+    // 5kHz / 13位分辨率
+    let timer = LedcTimer::new(LedcTimerNum::Timer0, &LedcTimerConfig::default())
+        .expect("LEDC定时器初始化失败");
+    let pin = GpioPin::with_owner(LED_PIN, "led.pwm").expect("LED引脚初始化失败");
+    let mut channel =
+        LedcChannel::new(LedcChannelNum::Channel0, &timer, pin).expect("LEDC通道初始化失败");
+
+    // 渐变完成回调运行在中断上下文中，只做计数
+    let fades = Arc::new(AtomicU32::new(0));
+    let counter = fades.clone();
+    channel
+        .on_fade_complete(move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+        })
+        .expect("注册渐变回调失败");
+
+    // 不等待的渐变，主线程可以继续工作
+    channel
+        .fade_to_percent(100.0, Duration::from_millis(500), FadeMode::NoWait)
+        .expect("启动渐变失败");
+    thread::sleep(Duration::from_millis(600));
+    println!("渐变完成 {} 次", fades.load(Ordering::Relaxed));
+
+    let mut led = Led::with_pwm(channel);
+
+    // 几档固定亮度
+    for percent in [10.0, 30.0, 60.0, 100.0] {
+        led.set_brightness(percent).expect("设置亮度失败");
+        led.on();
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    loop {
+        led.breathe(Duration::from_secs(2), 3)
+            .expect("呼吸效果失败");
+        thread::sleep(Duration::from_millis(500));
+    }
+}
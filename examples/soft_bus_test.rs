@@ -0,0 +1,70 @@
+/**
+ * @file soft_bus_test.rs
+ * @brief 软件I2C与软件SPI示例
+ * @details 用扩展板上空闲的引脚模拟总线:
+ *          - 软件I2C扫描总线上应答的从机地址
+ *          - 软件SPI以模式3向只写设备发送数据
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::thread;
+use std::time::Duration;
+
+use esp32_test::drivers::gpio::GpioPin;
+use esp32_test::drivers::i2c::{SoftI2c, SoftI2cConfig};
+use esp32_test::drivers::spi::{SoftSpi, SpiBitOrder, SpiDeviceConfig, SpiMode};
+
+// 软件I2C引脚
+const I2C_SCL_PIN: u32 = 38;
+const I2C_SDA_PIN: u32 = 39;
+// 软件SPI引脚
+const SPI_SCLK_PIN: u32 = 15;
+const SPI_MOSI_PIN: u32 = 16;
+const SPI_CS_PIN: i32 = 17;
+
+fn main() {
+    // 初始化ESP-IDF
+    esp_idf_svc::sys::link_patches();
+    // 初始化日志
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    println!("软件总线示例开始运行!");
+
+    let mut i2c = SoftI2c::new(
+        GpioPin::with_owner(I2C_SCL_PIN, "soft_i2c.scl").expect("SCL引脚被占用"),
+        GpioPin::with_owner(I2C_SDA_PIN, "soft_i2c.sda").expect("SDA引脚被占用"),
+        &SoftI2cConfig::default(),
+    )
+    .expect("创建软件I2C失败");
+
+    for address in 0x08..0x78 {
+        match i2c.probe(address) {
+            Ok(true) => println!("I2C地址 {:#04x} 有从机应答", address),
+            Ok(false) => {}
+            Err(e) => println!("扫描 {:#04x} 失败: {}", address, e),
+        }
+    }
+
+    let spi_config = SpiDeviceConfig {
+        mode: SpiMode::Mode3,
+        clock_speed_hz: 100_000,
+        bit_order: SpiBitOrder::MSBFirst,
+        cs_pin: Some(SPI_CS_PIN),
+        ..SpiDeviceConfig::default()
+    };
+    let spi = SoftSpi::new(
+        GpioPin::with_owner(SPI_SCLK_PIN, "soft_spi.sclk").expect("SCLK引脚被占用"),
+        Some(GpioPin::with_owner(SPI_MOSI_PIN, "soft_spi.mosi").expect("MOSI引脚被占用")),
+        None,
+        &spi_config,
+    )
+    .expect("创建软件SPI失败");
+
+    let mut counter = 0u8;
+    loop {
+        spi.write(&[0xA5, counter]).expect("软件SPI写入失败");
+        counter = counter.wrapping_add(1);
+        thread::sleep(Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,67 @@
+/**
+ * @file ws2812_status_test.rs
+ * @brief WS2812 状态灯示例
+ * @details 用 RMT 驱动一条 WS2812B 灯带，依次显示彩虹流水和呼吸效果，
+ *          开启伽马校正后低亮度时颜色过渡更平滑
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::thread;
+use std::time::Duration;
+
+use esp32_test::drivers::gpio::GpioPin;
+use esp32_test::drivers::rmt::{ColorOrder, Rgbw, Ws2812};
+
+// 灯带数据引脚
+const STRIP_PIN: u32 = 48;
+// 灯珠数量
+const PIXEL_COUNT: usize = 8;
+
+/// 色轮，0~255 依次经过红、绿、蓝
+fn wheel(position: u8) -> Rgbw {
+    let p = position;
+    match p {
+        0..=84 => Rgbw::rgb(255 - p * 3, p * 3, 0),
+        85..=169 => Rgbw::rgb(0, 255 - (p - 85) * 3, (p - 85) * 3),
+        _ => Rgbw::rgb((p - 170) * 3, 0, 255 - (p - 170) * 3),
+    }
+}
+
+fn main() {
+    // 初始化ESP-IDF
+    esp_idf_svc::sys::link_patches();
+    // 初始化日志
+    esp_idf_svc::log::EspLogger::initialize_default();
+
+    println!("WS2812状态灯示例开始运行!");
+
+    let pin = GpioPin::with_owner(STRIP_PIN, "ws2812").expect("GPIO初始化失败");
+    let mut strip = Ws2812::new(pin, PIXEL_COUNT, ColorOrder::Grb).expect("灯带初始化失败");
+    strip.set_gamma(Some(2.8));
+    strip.set_brightness(64);
+
+    loop {
+        // 彩虹流水
+        for offset in 0..=255u8 {
+            for index in 0..strip.len() {
+                let position = (index * 256 / strip.len()) as u8;
+                strip
+                    .set_pixel(index, wheel(position.wrapping_add(offset)))
+                    .expect("像素编号超出范围");
+            }
+            strip.show().expect("灯带刷新失败");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // 绿色呼吸
+        strip.fill(Rgbw::from_hex(0x00FF40));
+        for step in (0..=255u8).chain((0..=255u8).rev()) {
+            strip.set_brightness(step);
+            strip.show().expect("灯带刷新失败");
+            thread::sleep(Duration::from_millis(4));
+        }
+        strip.set_brightness(64);
+        println!("亮度: {}", strip.brightness());
+    }
+}
@@ -0,0 +1,44 @@
+/*
+ * @file board.rs
+ * @brief 开发板引脚表
+ * @details 由 build.rs 根据 boards/<开发板>.toml 生成，示例程序从这里取引脚而不是各自写死:
+ *          - `cargo build --features board-devkitc` 或 `BOARD=devkitc cargo build` 切换开发板
+ *          - 不指定时使用 boards/dnesp32s3.toml
+ *          - 引脚重复、不存在或被 Flash/PSRAM 占用时构建失败
+ *          每个板上外设对应一个子模块，例如 `board::lcd::SCLK`、`board::lcd::WIDTH`
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+include!(concat!(env!("OUT_DIR"), "/board.rs"));
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use crate::drivers::gpio::registry;
+
+    #[test]
+    fn test_used_pins_claimable() {
+        assert!(!NAME.is_empty());
+        assert!(USED_PINS.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // 生成的引脚都能被领取，且相互之间不冲突
+        let claims: Vec<_> = USED_PINS
+            .iter()
+            .map(|&pin| registry::claim(pin, "board").unwrap())
+            .collect();
+        assert_eq!(claims.len(), USED_PINS.len());
+    }
+
+    #[test]
+    fn test_lcd_pins() {
+        let mut pins = lcd::PINS.to_vec();
+        pins.sort_unstable();
+        pins.dedup();
+        assert_eq!(pins.len(), lcd::PINS.len());
+        assert!(lcd::PINS.contains(&lcd::SCLK) && lcd::PINS.contains(&lcd::CS));
+        assert!(lcd::PINS.iter().all(|pin| USED_PINS.contains(pin)));
+        assert_eq!(lcd::BUS, crate::drivers::spi::SpiBus::Spi2);
+        assert_eq!((lcd::WIDTH, lcd::HEIGHT), (240, 240));
+    }
+}
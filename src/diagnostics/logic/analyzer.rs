@@ -0,0 +1,331 @@
+/**
+ * @file analyzer.rs
+ * @brief 逻辑分析仪采样
+ * @details 在调用线程中忙等待采样，每次采样一次性读取所有GPIO的输入寄存器:
+ *          - 等待触发期间，样本进入预触发环形缓冲区
+ *          - 触发后连续采集到设定深度
+ *          - 采样时刻按起始时间加整数个采样间隔计算，个别样本延迟不会累积
+ *          采样期间CPU被占满，在ESP32-S3上建议在单独的高优先级任务中调用
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use crate::diagnostics::logic::types::{
+    Capture, LogicChannel, LogicConfig, LogicError, LogicResult, MAX_CHANNELS,
+};
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::registry::is_valid_gpio;
+use crate::drivers::gpio::GpioPin;
+use crate::error::Error;
+
+/// 采样间隔以微秒延时实现，采样率不能超过1MHz
+const MAX_SAMPLE_RATE_HZ: u32 = 1_000_000;
+
+/// 逻辑分析仪
+///
+/// 借用被采集的 `GpioPin`，保证采集期间引脚不会被释放；引脚原来的功能不受影响，
+/// 由SPI等外设驱动的输出引脚也可以采集。
+pub struct LogicAnalyzer<'a> {
+    config: LogicConfig,
+    channels: Vec<LogicChannel>,
+    _pins: PhantomData<&'a ()>,
+}
+
+impl<'a> LogicAnalyzer<'a> {
+    /// 创建逻辑分析仪
+    ///
+    /// # 返回
+    ///
+    /// 采样率为0或超过1MHz、采集深度为0、预触发深度不小于采集深度、触发引脚不存在时返回 `InvalidParameter`
+    pub fn new(config: &LogicConfig) -> LogicResult<Self> {
+        let invalid_trigger = config.trigger.pin().is_some_and(|pin| !is_valid_gpio(pin));
+        if config.sample_rate_hz == 0
+            || config.sample_rate_hz > MAX_SAMPLE_RATE_HZ
+            || config.depth == 0
+            || config.pre_trigger >= config.depth
+            || invalid_trigger
+        {
+            return Err(LogicError::InvalidParameter.into());
+        }
+        Ok(Self {
+            config: config.clone(),
+            channels: Vec::new(),
+            _pins: PhantomData,
+        })
+    }
+
+    /// 添加一个采集通道
+    ///
+    /// 会打开引脚的输入缓冲以便读回电平，不改变引脚的方向和输出信号。
+    ///
+    /// # 参数
+    ///
+    /// * `pin` - 被采集的引脚
+    /// * `name` - 通道名，作为VCD中的信号名
+    pub fn add_channel<MODE>(
+        &mut self,
+        pin: &'a GpioPin<MODE>,
+        name: impl Into<String>,
+    ) -> LogicResult<()> {
+        if self.channels.len() >= MAX_CHANNELS {
+            return Err(LogicError::TooManyChannels.into());
+        }
+        let number = pin.get_pin_number();
+        Backend::enable_input(number)?;
+        self.channels.push(LogicChannel {
+            pin: number as u32,
+            name: name.into(),
+        });
+        Ok(())
+    }
+
+    /// 采集通道
+    pub fn channels(&self) -> &[LogicChannel] {
+        &self.channels
+    }
+
+    /// 采集配置
+    pub fn config(&self) -> &LogicConfig {
+        &self.config
+    }
+
+    /// 等待触发并采集一次
+    ///
+    /// 触发引脚不必是采集通道，会先打开它的输入缓冲，由外设驱动的 CS/DC 等只输出引脚也能触发。
+    ///
+    /// # 返回
+    ///
+    /// 没有通道时返回 `InvalidParameter`，超时前没有等到触发返回 `TriggerTimeout`
+    pub fn capture(&self) -> LogicResult<Capture> {
+        if self.channels.is_empty() {
+            return Err(LogicError::InvalidParameter.into());
+        }
+        let trigger = self.config.trigger;
+        if let Some(pin) = trigger.pin() {
+            Backend::enable_input(pin as i32)?;
+        }
+        let pre_trigger = self.config.pre_trigger;
+        let post_trigger = self.config.depth - pre_trigger;
+        let mut sampler = Sampler::new(self.config.sample_rate_hz);
+        let start_us = Backend::now_us();
+        let timeout_us = self
+            .config
+            .timeout
+            .map(|timeout| timeout.as_micros() as u64);
+
+        let mut pre = VecDeque::with_capacity(pre_trigger);
+        let mut previous = None;
+        let trigger_sample = loop {
+            let raw = sampler.next();
+            let sample = self.pack(raw);
+            let fired = match (trigger.pin(), previous) {
+                (None, _) => true,
+                (Some(pin), Some(previous)) => trigger.fires(previous, bit(raw, pin)),
+                (Some(_), None) => false,
+            };
+            if fired {
+                break sample;
+            }
+            if let (Some(pin), Some(timeout_us)) = (trigger.pin(), timeout_us) {
+                if Backend::now_us() - start_us >= timeout_us {
+                    return Err(Error::new(LogicError::TriggerTimeout).with_pin(pin));
+                }
+            }
+            if pre_trigger > 0 {
+                if pre.len() == pre_trigger {
+                    pre.pop_front();
+                }
+                pre.push_back(sample);
+            }
+            previous = trigger.pin().map(|pin| bit(raw, pin));
+        };
+
+        let mut samples = Vec::with_capacity(pre.len() + post_trigger);
+        samples.extend(pre);
+        let trigger_index = samples.len();
+        samples.push(trigger_sample);
+        for _ in 1..post_trigger {
+            let raw = sampler.next();
+            samples.push(self.pack(raw));
+        }
+
+        Ok(Capture {
+            channels: self.channels.clone(),
+            samples,
+            sample_period_ns: sampler.period_ns,
+            trigger_index,
+            overruns: sampler.overruns,
+        })
+    }
+
+    /// 从输入寄存器中取出各通道的电平
+    fn pack(&self, raw: u64) -> u32 {
+        self.channels
+            .iter()
+            .enumerate()
+            .fold(0, |sample, (index, channel)| {
+                sample | (bit(raw, channel.pin) << index)
+            })
+    }
+}
+
+/// 输入寄存器中某个引脚的电平
+fn bit(raw: u64, pin: u32) -> u32 {
+    ((raw >> pin) & 1) as u32
+}
+
+/// 按固定间隔读取输入寄存器
+struct Sampler {
+    period_ns: u64,
+    /// 下一次采样的预定时刻（纳秒）
+    deadline_ns: u64,
+    overruns: usize,
+}
+
+impl Sampler {
+    fn new(sample_rate_hz: u32) -> Self {
+        Self {
+            period_ns: 1_000_000_000 / u64::from(sample_rate_hz),
+            deadline_ns: Backend::now_us() * 1000,
+            overruns: 0,
+        }
+    }
+
+    /// 等到预定时刻后采样一次
+    fn next(&mut self) -> u64 {
+        let now_ns = Backend::now_us() * 1000;
+        if now_ns < self.deadline_ns {
+            Backend::delay_us((self.deadline_ns - now_ns).div_ceil(1000) as u32);
+        } else if now_ns - self.deadline_ns >= self.period_ns {
+            self.overruns += 1;
+        }
+        self.deadline_ns += self.period_ns;
+        Backend::read_input_mask()
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::diagnostics::logic::types::Trigger;
+    use crate::drivers::gpio::backend::sim::{self, Waveform};
+    use crate::drivers::gpio::types::GpioMode;
+
+    #[test]
+    fn test_pre_trigger_capture() {
+        let cs = GpioPin::new(4).unwrap().into_floating_input().unwrap();
+        let dc = GpioPin::new(5).unwrap().into_push_pull_output().unwrap();
+        dc.set_high().unwrap();
+
+        // CS在100us处拉低，50us后释放
+        sim::play(
+            4,
+            &Waveform::new()
+                .then(Duration::ZERO, 1)
+                .then(Duration::from_micros(100), 0)
+                .then(Duration::from_micros(50), 1),
+        );
+        let config = LogicConfig {
+            sample_rate_hz: 100_000,
+            depth: 20,
+            pre_trigger: 5,
+            trigger: Trigger::Falling(4),
+            timeout: Some(Duration::from_millis(1)),
+        };
+        let mut analyzer = LogicAnalyzer::new(&config).unwrap();
+        analyzer.add_channel(&cs, "cs").unwrap();
+        // 推挽输出的引脚同样可以读回
+        analyzer.add_channel(&dc, "dc").unwrap();
+
+        let start = sim::now_us();
+        let capture = analyzer.capture().unwrap();
+        assert_eq!(capture.len(), 20);
+        assert_eq!(capture.trigger_index, 5);
+        assert_eq!(capture.sample_period_ns, 10_000);
+        assert_eq!(capture.overruns, 0);
+        assert_eq!(
+            capture.levels(0).collect::<Vec<_>>(),
+            [1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1]
+        );
+        assert!(capture.levels(1).all(|level| level == 1));
+        // 触发发生在第11次采样，之后再采14次
+        assert_eq!(sim::now_us() - start, 240);
+        assert!(capture
+            .to_vcd()
+            .contains("$comment trigger at sample 5 (50000 ns) $end"));
+    }
+
+    #[test]
+    fn test_trigger_timeout_and_config() {
+        let invalid = |config: LogicConfig| {
+            LogicAnalyzer::new(&config).err().unwrap().logic()
+                == Some(&LogicError::InvalidParameter)
+        };
+        assert!(invalid(LogicConfig {
+            sample_rate_hz: 2_000_000,
+            ..LogicConfig::default()
+        }));
+        assert!(invalid(LogicConfig {
+            pre_trigger: 4096,
+            ..LogicConfig::default()
+        }));
+        assert!(invalid(LogicConfig {
+            trigger: Trigger::Rising(49),
+            ..LogicConfig::default()
+        }));
+        assert!(invalid(LogicConfig {
+            trigger: Trigger::Rising(22),
+            ..LogicConfig::default()
+        }));
+
+        let key = GpioPin::new(0).unwrap().into_pull_up_input().unwrap();
+        let config = LogicConfig {
+            trigger: Trigger::Falling(0),
+            timeout: Some(Duration::from_millis(2)),
+            ..LogicConfig::default()
+        };
+        let mut analyzer = LogicAnalyzer::new(&config).unwrap();
+        let error = analyzer.capture().err().unwrap();
+        assert_eq!(error.logic(), Some(&LogicError::InvalidParameter));
+
+        analyzer.add_channel(&key, "key").unwrap();
+        let start = sim::now_us();
+        let error = analyzer.capture().err().unwrap();
+        assert_eq!(error.logic(), Some(&LogicError::TriggerTimeout));
+        assert_eq!(error.resource(), Some(crate::error::Resource::Pin(0)));
+        assert_eq!(sim::now_us() - start, 2000);
+    }
+
+    #[test]
+    fn test_trigger_on_output_only_pin() {
+        sim::reset();
+        let key = GpioPin::new(0).unwrap().into_pull_up_input().unwrap();
+        // 外设驱动的只输出引脚，输入缓冲未打开，也不是采集通道
+        Backend::set_direction(6, GpioMode::OutputOpenDrain).unwrap();
+        Backend::set_level(6, 1).unwrap();
+        sim::play(
+            6,
+            &Waveform::new()
+                .then(Duration::ZERO, 1)
+                .then(Duration::from_micros(100), 0),
+        );
+
+        let config = LogicConfig {
+            sample_rate_hz: 100_000,
+            depth: 4,
+            pre_trigger: 0,
+            trigger: Trigger::Falling(6),
+            timeout: Some(Duration::from_millis(1)),
+        };
+        let mut analyzer = LogicAnalyzer::new(&config).unwrap();
+        analyzer.add_channel(&key, "key").unwrap();
+        let start = sim::now_us();
+        assert_eq!(analyzer.capture().unwrap().len(), 4);
+        assert!(sim::now_us() - start < 1000);
+    }
+}
@@ -0,0 +1,18 @@
+/**
+ * @file mod.rs
+ * @brief 逻辑分析仪
+ * @details 按固定采样率把一组 GpioPin 的电平采集到内存中，用于查看 LCD DC/CS 时序、按键抖动等:
+ *          - 触发条件为某个引脚的边沿，支持预触发深度
+ *          - 采集结果导出为 VCD 文件，可以用 PulseView 或 GTKWave 打开
+ *          采样通过GPIO后端读取输入寄存器，在主机上由模拟后端的虚拟时钟驱动
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+mod analyzer;
+mod types;
+mod vcd;
+
+pub use analyzer::LogicAnalyzer;
+pub use types::*;
+pub use vcd::VcdWriter;
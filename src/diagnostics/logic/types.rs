@@ -0,0 +1,257 @@
+/**
+ * @file types.rs
+ * @brief 逻辑分析仪类型定义
+ * @details 采集配置、触发条件、错误类型和采集结果，与采样方式无关，可以在主机上测试
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use super::vcd::VcdWriter;
+
+/// 最多同时采集的通道数，每个样本用一个 `u32` 保存所有通道的电平
+pub const MAX_CHANNELS: usize = 32;
+
+/// 逻辑分析仪错误类型
+///
+/// 作为 [`crate::error::ErrorKind::Logic`] 出现在统一错误类型中。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogicError {
+    /// 参数错误（没有通道、采样率为0或超过1MHz、预触发深度不小于采集深度等）
+    InvalidParameter,
+    /// 通道数超过 [`MAX_CHANNELS`]
+    TooManyChannels,
+    /// 超时前没有等到触发条件
+    TriggerTimeout,
+}
+
+impl fmt::Display for LogicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogicError::InvalidParameter => write!(f, "参数错误"),
+            LogicError::TooManyChannels => write!(f, "最多支持{}个通道", MAX_CHANNELS),
+            LogicError::TriggerTimeout => write!(f, "等待触发超时"),
+        }
+    }
+}
+
+/// 逻辑分析仪操作结果类型
+pub type LogicResult<T> = Result<T, crate::error::Error>;
+
+/// 触发条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// 不等待，立即开始采集
+    Immediate,
+    /// 指定GPIO的上升沿
+    Rising(u32),
+    /// 指定GPIO的下降沿
+    Falling(u32),
+    /// 指定GPIO的任意边沿
+    AnyEdge(u32),
+}
+
+impl Trigger {
+    /// 触发引脚，立即触发时为 `None`
+    pub fn pin(self) -> Option<u32> {
+        match self {
+            Trigger::Immediate => None,
+            Trigger::Rising(pin) | Trigger::Falling(pin) | Trigger::AnyEdge(pin) => Some(pin),
+        }
+    }
+
+    /// 触发引脚从 `previous` 变为 `current` 时是否满足触发条件
+    pub fn fires(self, previous: u32, current: u32) -> bool {
+        match self {
+            Trigger::Immediate => true,
+            Trigger::Rising(_) => previous == 0 && current != 0,
+            Trigger::Falling(_) => previous != 0 && current == 0,
+            Trigger::AnyEdge(_) => (previous != 0) != (current != 0),
+        }
+    }
+}
+
+/// 采集配置
+#[derive(Debug, Clone)]
+pub struct LogicConfig {
+    /// 采样率（Hz），采样间隔以微秒计，最高1MHz
+    pub sample_rate_hz: u32,
+    /// 采集深度：一次采集最多保存的样本数
+    pub depth: usize,
+    /// 预触发深度：保存触发之前的样本数，必须小于采集深度
+    pub pre_trigger: usize,
+    /// 触发条件
+    pub trigger: Trigger,
+    /// 等待触发的最长时间，`None` 表示一直等待
+    pub timeout: Option<Duration>,
+}
+
+impl Default for LogicConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate_hz: 100_000,
+            depth: 4096,
+            pre_trigger: 512,
+            trigger: Trigger::Immediate,
+            timeout: Some(Duration::from_secs(1)),
+        }
+    }
+}
+
+/// 采集通道
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogicChannel {
+    /// GPIO编号
+    pub pin: u32,
+    /// 通道名，作为VCD中的信号名
+    pub name: String,
+}
+
+/// 一次采集的结果
+///
+/// 第 `i` 个样本的第 `n` 位是第 `n` 个通道的电平，样本 `trigger_index` 是满足触发条件的那一次采样。
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Capture {
+    /// 采集通道，顺序与样本中的位一致
+    pub channels: Vec<LogicChannel>,
+    /// 样本
+    pub samples: Vec<u32>,
+    /// 采样间隔（纳秒）
+    pub sample_period_ns: u64,
+    /// 触发样本的下标，之前的样本来自预触发缓冲区
+    pub trigger_index: usize,
+    /// 比预定时刻晚了一个采样间隔以上的样本数，不为0时说明采样率超出了CPU的能力或采样被中断打断
+    pub overruns: usize,
+}
+
+impl Capture {
+    /// 样本数
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// 是否没有样本
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// 第 `sample` 个样本中第 `channel` 个通道的电平
+    pub fn level(&self, sample: usize, channel: usize) -> u32 {
+        (self.samples[sample] >> channel) & 1
+    }
+
+    /// 某个通道的电平序列
+    pub fn levels(&self, channel: usize) -> impl Iterator<Item = u32> + '_ {
+        self.samples
+            .iter()
+            .map(move |sample| (sample >> channel) & 1)
+    }
+
+    /// 某个通道的边沿数，可用于统计按键抖动次数
+    pub fn edge_count(&self, channel: usize) -> usize {
+        self.samples
+            .windows(2)
+            .filter(|pair| ((pair[0] ^ pair[1]) >> channel) & 1 == 1)
+            .count()
+    }
+
+    /// 以VCD格式写出，时间0为第一个样本
+    pub fn write_vcd<W: io::Write>(&self, out: W) -> io::Result<W> {
+        let names: Vec<&str> = self
+            .channels
+            .iter()
+            .map(|channel| channel.name.as_str())
+            .collect();
+        let comment = format!(
+            "trigger at sample {} ({} ns)",
+            self.trigger_index,
+            self.trigger_index as u64 * self.sample_period_ns
+        );
+        let mut writer = VcdWriter::new(out, &names, Some(&comment))?;
+        let mut levels = vec![0; self.channels.len()];
+        for (index, sample) in self.samples.iter().enumerate() {
+            for (channel, level) in levels.iter_mut().enumerate() {
+                *level = (sample >> channel) & 1;
+            }
+            writer.sample(index as u64 * self.sample_period_ns, &levels)?;
+        }
+        writer.finish(self.samples.len() as u64 * self.sample_period_ns)
+    }
+
+    /// 生成VCD文件内容
+    pub fn to_vcd(&self) -> String {
+        // 写入 Vec 不会失败，输出只包含通道名和ASCII，一定是合法的UTF-8
+        let bytes = self.write_vcd(Vec::new()).unwrap_or_default();
+        String::from_utf8(bytes).unwrap_or_default()
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+
+    fn capture() -> Capture {
+        Capture {
+            channels: vec![
+                LogicChannel {
+                    pin: 21,
+                    name: "lcd cs".to_string(),
+                },
+                LogicChannel {
+                    pin: 40,
+                    name: "lcd_dc".to_string(),
+                },
+            ],
+            // cs: 1 1 0 0 0 1   dc: 0 0 0 1 1 1
+            samples: vec![0b01, 0b01, 0b00, 0b10, 0b10, 0b11],
+            sample_period_ns: 10_000,
+            trigger_index: 2,
+            overruns: 0,
+        }
+    }
+
+    #[test]
+    fn test_trigger_conditions() {
+        assert!(Trigger::Immediate.fires(0, 0));
+        assert!(Trigger::Rising(0).fires(0, 1) && !Trigger::Rising(0).fires(1, 0));
+        assert!(Trigger::Falling(0).fires(1, 0) && !Trigger::Falling(0).fires(0, 1));
+        assert!(Trigger::AnyEdge(0).fires(1, 0) && !Trigger::AnyEdge(0).fires(1, 1));
+        assert_eq!(Trigger::Falling(21).pin(), Some(21));
+
+        let capture = capture();
+        assert_eq!(capture.levels(0).collect::<Vec<_>>(), [1, 1, 0, 0, 0, 1]);
+        assert_eq!(capture.level(3, 1), 1);
+        assert_eq!((capture.edge_count(0), capture.edge_count(1)), (2, 1));
+    }
+
+    #[test]
+    fn test_vcd_export() {
+        let vcd = capture().to_vcd();
+        let expected = "\
+$version esp32_test logic analyzer $end
+$comment trigger at sample 2 (20000 ns) $end
+$timescale 1ns $end
+$scope module logic $end
+$var wire 1 ! lcd_cs $end
+$var wire 1 \" lcd_dc $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+1!
+0\"
+$end
+#20000
+0!
+#30000
+1\"
+#50000
+1!
+#60000
+";
+        assert_eq!(vcd, expected);
+    }
+}
@@ -0,0 +1,149 @@
+/**
+ * @file vcd.rs
+ * @brief VCD（Value Change Dump）写入器
+ * @details 按 IEEE 1364 的VCD格式输出单比特信号，PulseView、GTKWave 都可以直接打开:
+ *          - 时间单位固定为1ns
+ *          - 第一个样本写在 `$dumpvars` 中，之后只记录发生变化的信号
+ *          - 写入目标是任意 `io::Write`，可以是文件、串口或内存
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::io::{self, Write};
+
+/// VCD标识符使用的第一个可打印字符
+const ID_FIRST: u8 = b'!';
+/// VCD标识符可用的字符数（'!' ~ '~'）
+const ID_CHARS: usize = 94;
+
+/// 第 `index` 个信号的标识符：!、"、#、...、~、!!、"!、...
+fn identifier(mut index: usize) -> String {
+    let mut id = String::new();
+    loop {
+        id.push((ID_FIRST + (index % ID_CHARS) as u8) as char);
+        index /= ID_CHARS;
+        if index == 0 {
+            return id;
+        }
+        index -= 1;
+    }
+}
+
+/// 信号名中不能有空白字符，空名称用信号序号代替
+fn reference(name: &str, index: usize) -> String {
+    if name.trim().is_empty() {
+        format!("ch{}", index)
+    } else {
+        name.trim().replace(char::is_whitespace, "_")
+    }
+}
+
+/// VCD写入器
+///
+/// 创建时写出文件头，然后按时间顺序调用 [`sample`](Self::sample)，最后调用 [`finish`](Self::finish)
+/// 写出结束时间。
+pub struct VcdWriter<W: Write> {
+    out: W,
+    ids: Vec<String>,
+    /// 上一次写出的电平，`None` 表示还没有写出过 `$dumpvars`
+    last: Option<Vec<u32>>,
+}
+
+impl<W: Write> VcdWriter<W> {
+    /// 写出文件头并创建写入器
+    ///
+    /// # 参数
+    ///
+    /// * `out` - 写入目标
+    /// * `names` - 各信号的名称，空白字符会替换为下划线
+    /// * `comment` - 写入文件头的说明，例如触发位置
+    pub fn new(mut out: W, names: &[&str], comment: Option<&str>) -> io::Result<Self> {
+        writeln!(out, "$version esp32_test logic analyzer $end")?;
+        if let Some(comment) = comment {
+            writeln!(out, "$comment {} $end", comment)?;
+        }
+        writeln!(out, "$timescale 1ns $end")?;
+        writeln!(out, "$scope module logic $end")?;
+        let ids: Vec<String> = (0..names.len()).map(identifier).collect();
+        for (index, (name, id)) in names.iter().zip(&ids).enumerate() {
+            writeln!(out, "$var wire 1 {} {} $end", id, reference(name, index))?;
+        }
+        writeln!(out, "$upscope $end")?;
+        writeln!(out, "$enddefinitions $end")?;
+        Ok(Self {
+            out,
+            ids,
+            last: None,
+        })
+    }
+
+    /// 写出一个时刻的信号电平，只有发生变化的信号会被记录
+    ///
+    /// `levels` 的长度必须与信号数相同，`time_ns` 不能早于上一次调用。
+    pub fn sample(&mut self, time_ns: u64, levels: &[u32]) -> io::Result<()> {
+        if levels.len() != self.ids.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "电平数与信号数不一致",
+            ));
+        }
+        match &mut self.last {
+            None => {
+                writeln!(self.out, "#{}", time_ns)?;
+                writeln!(self.out, "$dumpvars")?;
+                for (id, level) in self.ids.iter().zip(levels) {
+                    writeln!(self.out, "{}{}", (*level != 0) as u8, id)?;
+                }
+                writeln!(self.out, "$end")?;
+                self.last = Some(levels.to_vec());
+            }
+            Some(last) => {
+                let mut stamped = false;
+                for ((id, level), previous) in self.ids.iter().zip(levels).zip(last.iter_mut()) {
+                    if (*level != 0) == (*previous != 0) {
+                        continue;
+                    }
+                    if !stamped {
+                        writeln!(self.out, "#{}", time_ns)?;
+                        stamped = true;
+                    }
+                    writeln!(self.out, "{}{}", (*level != 0) as u8, id)?;
+                    *previous = *level;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 写出结束时间并返回写入目标，查看器据此确定最后一段电平的长度
+    pub fn finish(mut self, end_ns: u64) -> io::Result<W> {
+        writeln!(self.out, "#{}", end_ns)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifiers_and_names() {
+        assert_eq!(identifier(0), "!");
+        assert_eq!(identifier(93), "~");
+        assert_eq!(identifier(94), "!!");
+        assert_eq!(identifier(95), "\"!");
+        assert_eq!(reference(" key 0 ", 3), "key_0");
+        assert_eq!(reference("", 3), "ch3");
+
+        let mut writer = VcdWriter::new(Vec::new(), &["a", "b"], None).unwrap();
+        writer.sample(0, &[0, 1]).unwrap();
+        // 电平没有变化时不写时间戳
+        writer.sample(10, &[0, 1]).unwrap();
+        writer.sample(20, &[1, 1]).unwrap();
+        assert!(writer.sample(30, &[1]).is_err());
+        let text = String::from_utf8(writer.finish(30).unwrap()).unwrap();
+        assert!(text.ends_with("$dumpvars\n0!\n1\"\n$end\n#20\n1!\n#30\n"));
+        assert!(!text.contains("#10"));
+    }
+}
@@ -0,0 +1,9 @@
+/**
+ * @file mod.rs
+ * @brief 板级诊断工具
+ * @details 不借助外部仪器排查硬件问题，目前包括采样GPIO电平的逻辑分析仪
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+pub mod logic;
@@ -1,16 +1,27 @@
 // ATK-MD0130 LCD驱动模块
 // ST7789V控制器, 1.3英寸, 240x240像素
 
-use super::r#type::{cmd, madctl, ColorFormat, DisplayRotation, DISPLAY_HEIGHT, DISPLAY_WIDTH};
-use crate::drivers::gpio::{GpioInterruptType, GpioMode, GpioPin, GpioPullMode};
+use super::r#type::{
+    cmd, madctl, ColorFormat, DisplayError, DisplayRotation, DISPLAY_HEIGHT, DISPLAY_WIDTH,
+};
+use crate::drivers::gpio::{GpioPin, Output};
+use crate::drivers::pwm::{FadeMode, LedcChannel};
 use crate::drivers::spi::{
-    SpiBitOrder, SpiDevice, SpiDeviceConfig, SpiError, SpiMaster, SpiMode, SpiResult,
+    DmaBuffer, SpiBitOrder, SpiDevice, SpiDeviceConfig, SpiMaster, SpiMode, SpiResult,
 };
 
 use esp_idf_svc::sys::{esp_rom_delay_us, ets_delay_us};
 use std::thread;
 use std::time::Duration;
 
+/// 背光控制方式
+pub enum Backlight {
+    /// 普通GPIO，只能开关（高电平=开启，低电平=关闭）
+    Gpio(GpioPin<Output>),
+    /// LEDC PWM通道，支持亮度调节和渐变
+    Pwm(LedcChannel),
+}
+
 /// ATK-MD0130 LCD显示器驱动
 pub struct ATKMD0130 {
     /// SPI主机控制器
@@ -18,11 +29,11 @@ pub struct ATKMD0130 {
     /// SPI设备
     spi_device: SpiDevice,
     /// 复位引脚
-    rst_pin: GpioPin,
+    rst_pin: GpioPin<Output>,
     /// 数据/命令引脚（高电平=数据，低电平=命令）
-    dc_pin: GpioPin,
-    /// 背光引脚（高电平=开启，低电平=关闭）
-    bl_pin: Option<GpioPin>,
+    dc_pin: GpioPin<Output>,
+    /// 背光控制（可选）
+    backlight: Option<Backlight>,
     /// 当前显示方向
     rotation: DisplayRotation,
     /// 当前颜色格式
@@ -44,9 +55,9 @@ impl ATKMD0130 {
     ///
     /// * `spi_master` - SPI主机控制器
     /// * `spi_device` - SPI设备
-    /// * `rst_pin` - 复位引脚
-    /// * `dc_pin` - 数据/命令引脚
-    /// * `bl_pin` - 背光引脚（可选）
+    /// * `rst_pin` - 复位引脚（推挽输出）
+    /// * `dc_pin` - 数据/命令引脚（推挽输出）
+    /// * `backlight` - 背光控制，GPIO开关或PWM调光（可选）
     ///
     /// # 返回
     ///
@@ -54,45 +65,17 @@ impl ATKMD0130 {
     pub fn new(
         spi_master: SpiMaster,
         spi_device: SpiDevice,
-        rst_pin: GpioPin,
-        dc_pin: GpioPin,
-        bl_pin: Option<GpioPin>,
+        rst_pin: GpioPin<Output>,
+        dc_pin: GpioPin<Output>,
+        backlight: Option<Backlight>,
     ) -> SpiResult<Self> {
-        // 初始化DC引脚为输出
-        dc_pin
-            .init(
-                GpioMode::Output,
-                GpioPullMode::Floating,
-                GpioInterruptType::Disable,
-            )
-            .map_err(|_| SpiError::InvalidParameter)?;
-
-        // 初始化RST引脚为输出
-        rst_pin
-            .init(
-                GpioMode::Output,
-                GpioPullMode::Floating,
-                GpioInterruptType::Disable,
-            )
-            .map_err(|_| SpiError::InvalidParameter)?;
-
-        // 如果有背光引脚，初始化为输出
-        if let Some(ref pin) = bl_pin {
-            pin.init(
-                GpioMode::Output,
-                GpioPullMode::Floating,
-                GpioInterruptType::Disable,
-            )
-            .map_err(|_| SpiError::InvalidParameter)?;
-        }
-
         // 创建LCD实例
         let mut lcd = ATKMD0130 {
             spi_master,
             spi_device,
             rst_pin,
             dc_pin,
-            bl_pin,
+            backlight,
             rotation: DisplayRotation::Portrait,
             color_format: ColorFormat::RGB565,
             window_x_start: 0,
@@ -144,9 +127,7 @@ impl ATKMD0130 {
         thread::sleep(Duration::from_millis(120));
 
         // 如果有背光，打开背光
-        if let Some(ref pin) = self.bl_pin {
-            pin.set_high().map_err(|_| SpiError::DriverError(-1))?;
-        }
+        self.set_backlight(true)?;
 
         // 清屏为黑色
         self.fill_rect(0, 0, self.window_width, self.window_height, 0x0000)?;
@@ -165,29 +146,30 @@ impl ATKMD0130 {
     }
 
     /// 写命令
-    fn write_command(&mut self, cmd: u8) -> SpiResult<()> {
-        self.dc_pin
-            .set_low()
-            .map_err(|_| SpiError::DriverError(-1))?;
-        self.spi_device.write(&[cmd])
+    ///
+    /// 命令和参数只有几个字节，用轮询传输省去中断的开销。
+    fn write_command(&self, cmd: u8) -> SpiResult<()> {
+        self.dc_pin.set_low()?;
+        self.spi_device.polling_write(&[cmd])
     }
 
     /// 写数据
-    fn write_data(&mut self, data: &[u8]) -> SpiResult<()> {
-        self.dc_pin
-            .set_high()
-            .map_err(|_| SpiError::DriverError(-1))?;
-        self.spi_device.write(data)
+    fn write_data(&self, data: &[u8]) -> SpiResult<()> {
+        self.dc_pin.set_high()?;
+        self.spi_device.polling_write(data)
     }
 
     /// 写16位数据
-    fn write_data_u16(&mut self, data: u16) -> SpiResult<()> {
+    fn write_data_u16(&self, data: u16) -> SpiResult<()> {
         let data_bytes = [(data >> 8) as u8, data as u8];
         self.write_data(&data_bytes)
     }
 
     /// 设置地址窗口
     fn set_address_window(&mut self, x0: u16, y0: u16, x1: u16, y1: u16) -> SpiResult<()> {
+        // 连续的短事务期间独占总线，省去每次的总线仲裁
+        let _bus = self.spi_device.lock_bus()?;
+
         // 设置列地址
         self.write_command(cmd::CASET)?;
         self.write_data_u16(x0)?;
@@ -246,16 +228,34 @@ impl ATKMD0130 {
         Ok(())
     }
 
-    /// 设置背光亮度（如果支持）
+    /// 打开或关闭背光（如果支持）
     pub fn set_backlight(&mut self, on: bool) -> SpiResult<()> {
-        if let Some(ref pin) = self.bl_pin {
-            if on {
-                pin.set_high().map_err(|_| SpiError::DriverError(-1))?;
-            } else {
-                pin.set_low().map_err(|_| SpiError::DriverError(-1))?;
-            }
+        match &self.backlight {
+            Some(Backlight::Gpio(pin)) if on => pin.set_high(),
+            Some(Backlight::Gpio(pin)) => pin.set_low(),
+            Some(Backlight::Pwm(pwm)) => pwm.set_duty_percent(if on { 100.0 } else { 0.0 }),
+            None => Ok(()),
+        }
+    }
+
+    /// 设置背光亮度（百分比）
+    ///
+    /// GPIO背光只能开关，亮度大于0视为打开。
+    pub fn set_backlight_brightness(&mut self, percent: f32) -> SpiResult<()> {
+        match &self.backlight {
+            Some(Backlight::Pwm(pwm)) => pwm.set_duty_percent(percent),
+            _ => self.set_backlight(percent > 0.0),
+        }
+    }
+
+    /// 背光在指定时间内渐变到目标亮度，不等待渐变完成
+    ///
+    /// GPIO背光直接切换到目标状态。
+    pub fn fade_backlight(&mut self, percent: f32, duration: Duration) -> SpiResult<()> {
+        match &self.backlight {
+            Some(Backlight::Pwm(pwm)) => pwm.fade_to_percent(percent, duration, FadeMode::NoWait),
+            _ => self.set_backlight(percent > 0.0),
         }
-        Ok(())
     }
 
     /// 绘制像素
@@ -290,9 +290,7 @@ impl ATKMD0130 {
         let num_pixels = (x1 - x + 1) as usize * (y1 - y + 1) as usize;
 
         // 为了提高效率，批量发送颜色数据
-        self.dc_pin
-            .set_high()
-            .map_err(|_| SpiError::DriverError(-1))?;
+        self.dc_pin.set_high()?;
 
         // 每次发送的像素数量
         const CHUNK_SIZE: usize = 64;
@@ -453,6 +451,9 @@ impl ATKMD0130 {
     }
 
     /// 显示图像数据 (RGB565格式)
+    ///
+    /// 图像按行存放，每行 `width` 个像素，超出屏幕的部分被裁掉。
+    /// 数据按条带转换并发送，不受SPI最大传输长度限制。
     pub fn draw_image(
         &mut self,
         x: u16,
@@ -468,30 +469,80 @@ impl ATKMD0130 {
         // 确保坐标在屏幕范围内
         let x_end = (x + width - 1).min(self.window_width - 1);
         let y_end = (y + height - 1).min(self.window_height - 1);
-        let actual_width = x_end - x + 1;
-        let actual_height = y_end - y + 1;
-
-        // 设置地址窗口
-        self.set_address_window(x, y, x_end, y_end)?;
+        let actual_width = (x_end - x + 1) as usize;
+        let actual_height = (y_end - y + 1) as usize;
 
-        // 转换为字节数组并发送
-        let num_pixels = actual_width as usize * actual_height as usize;
-        if num_pixels > image_data.len() {
-            return Err(SpiError::InvalidParameter);
+        // 最后一行只需要屏幕内的部分
+        let stride = width as usize;
+        if (actual_height - 1) * stride + actual_width > image_data.len() {
+            return Err(DisplayError::BufferTooSmall.into());
         }
 
-        // 预备数据
-        let mut data_buffer = Vec::with_capacity(num_pixels * 2);
-        for color in image_data.iter().take(num_pixels) {
-            data_buffer.push((*color >> 8) as u8);
-            data_buffer.push(*color as u8);
+        // 条带行数由 draw_stripes 按最大传输长度限制
+        self.draw_stripes(x, y, width, height, u16::MAX, |first_row, stripe| {
+            let rows = stripe.chunks_exact_mut(actual_width * 2);
+            for (index, row) in rows.enumerate() {
+                let start = ((first_row - y) as usize + index) * stride;
+                let pixels = &image_data[start..start + actual_width];
+                for (bytes, color) in row.chunks_exact_mut(2).zip(pixels) {
+                    bytes.copy_from_slice(&color.to_be_bytes());
+                }
+            }
+        })
+    }
+
+    /// 按条带绘制区域，DMA发送上一个条带的同时渲染下一个条带
+    ///
+    /// 使用两个DMA条带缓冲区轮流提交到SPI事务队列，设备的 `queue_size` 至少为2时才能重叠。
+    ///
+    /// # 参数
+    ///
+    /// * `x`, `y`, `width`, `height` - 绘制区域，超出屏幕的部分被裁掉
+    /// * `stripe_rows` - 每个条带的行数，条带字节数超过SPI总线的最大传输长度时自动减少
+    /// * `render` - 渲染回调，参数为条带第一行的屏幕Y坐标和条带缓冲区（每像素2字节，RGB565大端），
+    ///   缓冲区保留上一次的内容，需要全部写入
+    pub fn draw_stripes(
+        &mut self,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        stripe_rows: u16,
+        mut render: impl FnMut(u16, &mut [u8]),
+    ) -> SpiResult<()> {
+        if x >= self.window_width || y >= self.window_height || width == 0 || height == 0 {
+            return Ok(());
         }
+        let x_end = (x + width - 1).min(self.window_width - 1);
+        let y_end = (y + height - 1).min(self.window_height - 1);
+        let row_bytes = (x_end - x + 1) as usize * 2;
+        // 至少一行一个条带，且每个条带都能在一次传输中发完
+        let max_rows = (self.spi_device.max_transfer_size() / row_bytes).min(u16::MAX as usize);
+        let stripe_rows = stripe_rows.min(max_rows as u16).max(1);
 
-        // 发送数据
-        self.dc_pin
-            .set_high()
-            .map_err(|_| SpiError::DriverError(-1))?;
-        self.spi_device.write(&data_buffer)
+        self.set_address_window(x, y, x_end, y_end)?;
+        self.dc_pin.set_high()?;
+
+        let mut free = (0..2)
+            .map(|_| DmaBuffer::new(row_bytes * stripe_rows as usize))
+            .collect::<SpiResult<Vec<_>>>()?;
+        let mut queue = self.spi_device.queue::<DmaBuffer>()?;
+        let mut row = y;
+        while row <= y_end {
+            let rows = stripe_rows.min(y_end - row + 1);
+            if queue.is_full() {
+                free.push(queue.wait()?);
+            }
+            let mut stripe = match free.pop() {
+                Some(stripe) => stripe,
+                None => queue.wait()?,
+            };
+            stripe.set_len(row_bytes * rows as usize)?;
+            render(row, &mut stripe);
+            queue.write(stripe)?;
+            row += rows;
+        }
+        queue.flush()
     }
 }
 
@@ -517,15 +568,18 @@ pub fn create_atk_md0130(
         command_bits: 0,
         address_bits: 0,
         cs_pin: Some(cs_pin),
-        queue_size: 1,
+        queue_size: 7,
+        ..SpiDeviceConfig::default()
     };
     let spi_device = spi_master.add_device(&config)?;
 
     // 创建GPIO引脚
-    let dc = GpioPin::new(dc_pin);
-    let rst = GpioPin::new(rst_pin);
-    let bl = bl_pin.map(GpioPin::new);
+    let dc = GpioPin::with_owner(dc_pin, "lcd.dc")?.into_push_pull_output()?;
+    let rst = GpioPin::with_owner(rst_pin, "lcd.rst")?.into_push_pull_output()?;
+    let bl = bl_pin
+        .map(|pin| GpioPin::with_owner(pin, "lcd.bl")?.into_push_pull_output())
+        .transpose()?;
 
     // 创建LCD实例
-    ATKMD0130::new(spi_master, spi_device, rst, dc, bl)
+    ATKMD0130::new(spi_master, spi_device, rst, dc, bl.map(Backlight::Gpio))
 }
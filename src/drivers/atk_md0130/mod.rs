@@ -1,10 +1,14 @@
+// LCD驱动直接使用ESP-IDF SPI驱动，主机上只提供类型定义
+#[cfg(target_os = "espidf")]
 mod lcd;
 mod r#type;
 
+#[cfg(target_os = "espidf")]
 pub use lcd::*;
 pub use r#type::*;
 
 /// 创建并初始化ATK-MD0130 LCD实例的辅助函数
+#[cfg(target_os = "espidf")]
 pub fn create_atk_md0130(
     mosi_pin: i32,
     miso_pin: i32,
@@ -13,16 +17,13 @@ pub fn create_atk_md0130(
     dc_pin_num: i32,
     rst_pin_num: i32,
     bl_pin_num: Option<i32>,
-) -> Result<ATKMD0130, Box<dyn std::error::Error>> {
+) -> crate::error::Result<ATKMD0130> {
     use crate::drivers::gpio::GpioPin;
     use crate::drivers::spi::{SpiBitOrder, SpiBus, SpiDeviceConfig, SpiMaster, SpiMode};
 
     // 1. 初始化SPI主机
-    let mut spi_master =
-        SpiMaster::new(SpiBus::Spi2).map_err(|e| format!("Failed to create SpiMaster: {:?}", e))?;
-    spi_master
-        .initialize(mosi_pin, miso_pin, sclk_pin, 0)
-        .map_err(|e| format!("Failed to initialize SpiMaster: {:?}", e))?;
+    let mut spi_master = SpiMaster::new(SpiBus::Spi2)?;
+    spi_master.initialize(mosi_pin, miso_pin, sclk_pin, 0)?;
 
     // 2. 配置SPI设备
     let spi_config = SpiDeviceConfig {
@@ -33,27 +34,30 @@ pub fn create_atk_md0130(
         address_bits: 0,
         bit_order: SpiBitOrder::MSBFirst,
         queue_size: 7,
+        ..SpiDeviceConfig::default()
     };
-    let spi_device = spi_master
-        .add_device(&spi_config)
-        .map_err(|e| format!("Failed to add SPI device: {:?}", e))?;
+    let spi_device = spi_master.add_device(&spi_config)?;
 
     // 3. 初始化GPIO引脚
-    let dc_pin = GpioPin::new(dc_pin_num as u32);
-    let rst_pin = GpioPin::new(rst_pin_num as u32);
-    let bl_pin = bl_pin_num.map(|pin| GpioPin::new(pin as u32));
+    let dc_pin = GpioPin::with_owner(dc_pin_num as u32, "lcd.dc")?.into_push_pull_output()?;
+    let rst_pin = GpioPin::with_owner(rst_pin_num as u32, "lcd.rst")?.into_push_pull_output()?;
+    let bl_pin = bl_pin_num
+        .map(|pin| GpioPin::with_owner(pin as u32, "lcd.bl")?.into_push_pull_output())
+        .transpose()?;
 
     // 4. 创建LCD实例
-    ATKMD0130::new(spi_master, spi_device, rst_pin, dc_pin, bl_pin).map_err(|e| {
-        Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("{:?}", e),
-        )) as Box<dyn std::error::Error>
-    })
+    ATKMD0130::new(
+        spi_master,
+        spi_device,
+        rst_pin,
+        dc_pin,
+        bl_pin.map(Backlight::Gpio),
+    )
 }
 
 // 重新导出模块
 pub mod prelude {
+    #[cfg(target_os = "espidf")]
     pub use super::lcd::*;
     pub use super::r#type::*;
 }
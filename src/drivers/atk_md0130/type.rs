@@ -1,4 +1,25 @@
 /// ATK-MD0130 ST7789V 1.3英寸LCD显示模块类型定义
+use std::fmt;
+
+/// 显示驱动错误类型
+///
+/// 作为 [`crate::error::ErrorKind::Display`] 出现在统一错误类型中。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisplayError {
+    /// 图像数据少于绘制区域的像素数
+    BufferTooSmall,
+    /// 坐标超出屏幕范围
+    OutOfBounds,
+}
+
+impl fmt::Display for DisplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisplayError::BufferTooSmall => write!(f, "图像数据不足"),
+            DisplayError::OutOfBounds => write!(f, "坐标超出屏幕范围"),
+        }
+    }
+}
 
 /// 显示区域的颜色格式
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
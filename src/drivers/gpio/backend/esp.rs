@@ -0,0 +1,634 @@
+/**
+ * @file esp.rs
+ * @brief ESP-IDF GPIO 后端
+ * @details 将 GpioBackend 映射到 ESP-IDF 的 gpio_* 驱动函数
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use esp_idf_sys::{
+    esp_rom_delay_us, esp_rom_gpio_connect_in_signal, esp_rom_gpio_connect_out_signal,
+    esp_rom_gpio_pad_select_gpio, esp_timer_get_time, gpio_config, gpio_config_t,
+    gpio_deep_sleep_hold_dis, gpio_deep_sleep_hold_en, gpio_drive_cap_t,
+    gpio_drive_cap_t_GPIO_DRIVE_CAP_0, gpio_drive_cap_t_GPIO_DRIVE_CAP_1,
+    gpio_drive_cap_t_GPIO_DRIVE_CAP_2, gpio_drive_cap_t_GPIO_DRIVE_CAP_3, gpio_get_level,
+    gpio_hold_dis, gpio_hold_en, gpio_install_isr_service, gpio_int_type_t,
+    gpio_int_type_t_GPIO_INTR_ANYEDGE, gpio_int_type_t_GPIO_INTR_DISABLE,
+    gpio_int_type_t_GPIO_INTR_HIGH_LEVEL, gpio_int_type_t_GPIO_INTR_LOW_LEVEL,
+    gpio_int_type_t_GPIO_INTR_NEGEDGE, gpio_int_type_t_GPIO_INTR_POSEDGE, gpio_intr_disable,
+    gpio_intr_enable, gpio_iomux_in, gpio_iomux_out, gpio_isr_handler_add, gpio_isr_handler_remove,
+    gpio_mode_t, gpio_mode_t_GPIO_MODE_DISABLE, gpio_mode_t_GPIO_MODE_INPUT,
+    gpio_mode_t_GPIO_MODE_INPUT_OUTPUT, gpio_mode_t_GPIO_MODE_INPUT_OUTPUT_OD,
+    gpio_mode_t_GPIO_MODE_OUTPUT, gpio_mode_t_GPIO_MODE_OUTPUT_OD, gpio_pull_mode_t,
+    gpio_pull_mode_t_GPIO_FLOATING, gpio_pull_mode_t_GPIO_PULLDOWN_ONLY,
+    gpio_pull_mode_t_GPIO_PULLUP_ONLY, gpio_pull_mode_t_GPIO_PULLUP_PULLDOWN, gpio_pulldown_dis,
+    gpio_pulldown_en, gpio_pullup_dis, gpio_pullup_en, gpio_reset_pin, gpio_set_direction,
+    gpio_set_drive_capability, gpio_set_intr_type, gpio_set_level, gpio_set_pull_mode,
+    gpio_uninstall_isr_service, gpio_wakeup_disable, gpio_wakeup_enable, ESP_ERR_NOT_SUPPORTED,
+    ESP_OK,
+};
+#[cfg(esp_idf_soc_gpio_support_pin_glitch_filter)]
+use esp_idf_sys::{
+    gpio_del_glitch_filter, gpio_glitch_filter_disable, gpio_glitch_filter_enable,
+    gpio_glitch_filter_handle_t, gpio_new_pin_glitch_filter, gpio_pin_glitch_filter_config_t,
+    soc_periph_glitch_filter_clk_src_t_GLITCH_FILTER_CLK_SRC_DEFAULT, ESP_ERR_INVALID_ARG,
+};
+
+use super::GpioBackend;
+use crate::drivers::gpio::interrupt::GpioIsr;
+use crate::drivers::gpio::matrix::{
+    GPIO_MATRIX_CONST_ONE_INPUT, GPIO_MATRIX_CONST_ZERO_INPUT, SIG_GPIO_OUT_IDX,
+};
+#[cfg(esp_idf_soc_gpio_support_pin_glitch_filter)]
+use crate::drivers::gpio::registry::GPIO_NUM_MAX;
+use crate::drivers::gpio::registry::{is_reserved, is_valid_gpio};
+use crate::drivers::gpio::snapshot::PinSnapshot;
+use crate::drivers::gpio::types::{
+    GlitchFilter, GpioDriveCap, GpioError, GpioInterruptType, GpioMode, GpioPullMode, GpioResult,
+};
+use crate::error::Error;
+
+/// GPIO寄存器基地址（ESP32-S3 技术参考手册 GPIO 章节）
+const DR_REG_GPIO_BASE: usize = 0x6000_4000;
+/// GPIO0~31 输出置位寄存器
+const GPIO_OUT_W1TS_REG: usize = DR_REG_GPIO_BASE + 0x08;
+/// GPIO0~31 输出清零寄存器
+const GPIO_OUT_W1TC_REG: usize = DR_REG_GPIO_BASE + 0x0C;
+/// GPIO32~48 输出置位寄存器
+const GPIO_OUT1_W1TS_REG: usize = DR_REG_GPIO_BASE + 0x14;
+/// GPIO32~48 输出清零寄存器
+const GPIO_OUT1_W1TC_REG: usize = DR_REG_GPIO_BASE + 0x18;
+/// GPIO0~31 输入寄存器
+const GPIO_IN_REG: usize = DR_REG_GPIO_BASE + 0x3C;
+/// GPIO32~48 输入寄存器
+const GPIO_IN1_REG: usize = DR_REG_GPIO_BASE + 0x40;
+/// GPIO0~31 输出寄存器
+const GPIO_OUT_REG: usize = DR_REG_GPIO_BASE + 0x04;
+/// GPIO32~48 输出寄存器
+const GPIO_OUT1_REG: usize = DR_REG_GPIO_BASE + 0x10;
+/// GPIO0~31 输出使能寄存器
+const GPIO_ENABLE_REG: usize = DR_REG_GPIO_BASE + 0x20;
+/// GPIO32~48 输出使能寄存器
+const GPIO_ENABLE1_REG: usize = DR_REG_GPIO_BASE + 0x2C;
+/// GPIO0 配置寄存器（开漏、中断类型和中断使能），每个引脚4字节
+const GPIO_PIN0_REG: usize = DR_REG_GPIO_BASE + 0x74;
+/// GPIO0 输出信号选择寄存器，每个引脚4字节
+const GPIO_FUNC0_OUT_SEL_CFG_REG: usize = DR_REG_GPIO_BASE + 0x554;
+/// IO_MUX GPIO0 配置寄存器（功能号、上下拉、输入使能和驱动能力），每个引脚4字节
+const IO_MUX_GPIO0_REG: usize = 0x6000_9000 + 0x04;
+
+/// 已启用保持功能的引脚掩码（GPIO0~31、GPIO32~48），ESP-IDF 没有提供读取保持状态的接口
+static HOLD_MASK: [AtomicU32; 2] = [AtomicU32::new(0), AtomicU32::new(0)];
+
+/// 读取一个32位寄存器
+#[inline]
+fn read_reg(addr: usize) -> u32 {
+    unsafe { std::ptr::read_volatile(addr as *const u32) }
+}
+
+/// 读取按GPIO编号分为两组的寄存器中的一位
+#[inline]
+fn read_bit(low_reg: usize, high_reg: usize, pin: usize) -> bool {
+    let (reg, bit) = if pin < 32 {
+        (low_reg, pin)
+    } else {
+        (high_reg, pin - 32)
+    };
+    (read_reg(reg) >> bit) & 1 == 1
+}
+
+/// 按GPIO编号掩码写一对 W1TS/W1TC 寄存器，掩码为0的一组不写
+#[inline]
+fn write_mask(low_reg: usize, high_reg: usize, mask: u64) {
+    let low = mask as u32;
+    let high = (mask >> 32) as u32;
+    unsafe {
+        if low != 0 {
+            std::ptr::write_volatile(low_reg as *mut u32, low);
+        }
+        if high != 0 {
+            std::ptr::write_volatile(high_reg as *mut u32, high);
+        }
+    }
+}
+
+/// 输出电平写入共用的临界区
+///
+/// 本后端所有改变输出电平的操作（`set_level`、W1TS/W1TC 掩码写入和 OUT 寄存器的读-改-写）都在其中执行，
+/// 因此任一核或中断经本 crate 写输出时，都不会插入到端口读 OUT 与写回之间而被覆盖。
+/// 绕过本 crate 直接调用 `gpio_set_level` 的代码不受保护。
+static OUT_LOCK: Mutex<CriticalSectionRawMutex, ()> = Mutex::new(());
+
+/// 按掩码修改一个 OUT 寄存器，一次写入；掩码为0时不读写
+///
+/// 需在 `OUT_LOCK` 内调用。
+#[inline]
+fn modify_mask(reg: usize, mask: u32, value: u32) {
+    if mask != 0 {
+        let out = (read_reg(reg) & !mask) | (value & mask);
+        unsafe { std::ptr::write_volatile(reg as *mut u32, out) };
+    }
+}
+
+/// 类型转换辅助函数
+#[inline]
+pub(crate) fn convert_mode(mode: GpioMode) -> gpio_mode_t {
+    match mode {
+        GpioMode::Disable => gpio_mode_t_GPIO_MODE_DISABLE,
+        GpioMode::Input => gpio_mode_t_GPIO_MODE_INPUT,
+        GpioMode::Output => gpio_mode_t_GPIO_MODE_OUTPUT,
+        GpioMode::OutputOpenDrain => gpio_mode_t_GPIO_MODE_OUTPUT_OD,
+        GpioMode::InputOutput => gpio_mode_t_GPIO_MODE_INPUT_OUTPUT,
+        GpioMode::InputOutputOpenDrain => gpio_mode_t_GPIO_MODE_INPUT_OUTPUT_OD,
+    }
+}
+
+/// 类型转换辅助函数
+#[inline]
+pub(crate) fn convert_pull_mode(mode: GpioPullMode) -> gpio_pull_mode_t {
+    match mode {
+        GpioPullMode::PullUp => gpio_pull_mode_t_GPIO_PULLUP_ONLY,
+        GpioPullMode::PullDown => gpio_pull_mode_t_GPIO_PULLDOWN_ONLY,
+        GpioPullMode::PullUpDown => gpio_pull_mode_t_GPIO_PULLUP_PULLDOWN,
+        GpioPullMode::Floating => gpio_pull_mode_t_GPIO_FLOATING,
+    }
+}
+
+/// 类型转换辅助函数
+#[inline]
+pub(crate) fn convert_intr_type(intr_type: GpioInterruptType) -> gpio_int_type_t {
+    match intr_type {
+        GpioInterruptType::Disable => gpio_int_type_t_GPIO_INTR_DISABLE,
+        GpioInterruptType::RisingEdge => gpio_int_type_t_GPIO_INTR_POSEDGE,
+        GpioInterruptType::FallingEdge => gpio_int_type_t_GPIO_INTR_NEGEDGE,
+        GpioInterruptType::AnyEdge => gpio_int_type_t_GPIO_INTR_ANYEDGE,
+        GpioInterruptType::LowLevel => gpio_int_type_t_GPIO_INTR_LOW_LEVEL,
+        GpioInterruptType::HighLevel => gpio_int_type_t_GPIO_INTR_HIGH_LEVEL,
+    }
+}
+
+/// 类型转换辅助函数
+#[inline]
+pub(crate) fn convert_drive_cap(cap: GpioDriveCap) -> gpio_drive_cap_t {
+    match cap {
+        GpioDriveCap::Weak => gpio_drive_cap_t_GPIO_DRIVE_CAP_0,
+        GpioDriveCap::Stronger => gpio_drive_cap_t_GPIO_DRIVE_CAP_1,
+        GpioDriveCap::Medium => gpio_drive_cap_t_GPIO_DRIVE_CAP_2,
+        GpioDriveCap::Strongest => gpio_drive_cap_t_GPIO_DRIVE_CAP_3,
+    }
+}
+
+/// 寄存器中的驱动能力字段转换为驱动能力
+#[inline]
+fn drive_cap_from_bits(bits: u32) -> GpioDriveCap {
+    match bits & 0x3 {
+        0 => GpioDriveCap::Weak,
+        1 => GpioDriveCap::Stronger,
+        2 => GpioDriveCap::Medium,
+        _ => GpioDriveCap::Strongest,
+    }
+}
+
+/// 寄存器中的中断类型字段转换为中断类型
+#[inline]
+fn intr_type_from_bits(bits: u32) -> GpioInterruptType {
+    match bits & 0x7 {
+        1 => GpioInterruptType::RisingEdge,
+        2 => GpioInterruptType::FallingEdge,
+        3 => GpioInterruptType::AnyEdge,
+        4 => GpioInterruptType::LowLevel,
+        5 => GpioInterruptType::HighLevel,
+        _ => GpioInterruptType::Disable,
+    }
+}
+
+/// 将ESP-IDF返回值转换为GpioResult，错误中记录返回值、函数名和引脚
+#[inline]
+fn check(result: i32, error: GpioError, operation: &'static str, pin: i32) -> GpioResult<()> {
+    if result != ESP_OK {
+        return Err(Error::esp(error, result, operation).with_pin(pin));
+    }
+    Ok(())
+}
+
+/// 已创建的毛刺过滤器句柄
+#[cfg(esp_idf_soc_gpio_support_pin_glitch_filter)]
+struct FilterHandle(gpio_glitch_filter_handle_t);
+
+// 句柄只在持有 FILTERS 锁时使用
+#[cfg(esp_idf_soc_gpio_support_pin_glitch_filter)]
+unsafe impl Send for FilterHandle {}
+
+/// 每个引脚上已启用的毛刺过滤器
+#[cfg(esp_idf_soc_gpio_support_pin_glitch_filter)]
+static FILTERS: std::sync::Mutex<Vec<Option<FilterHandle>>> = std::sync::Mutex::new(Vec::new());
+
+/// 禁用并删除一个毛刺过滤器
+#[cfg(esp_idf_soc_gpio_support_pin_glitch_filter)]
+fn delete_glitch_filter(handle: FilterHandle, pin: i32) -> GpioResult<()> {
+    check(
+        unsafe { gpio_glitch_filter_disable(handle.0) },
+        GpioError::ConfigError,
+        "gpio_glitch_filter_disable",
+        pin,
+    )?;
+    check(
+        unsafe { gpio_del_glitch_filter(handle.0) },
+        GpioError::ConfigError,
+        "gpio_del_glitch_filter",
+        pin,
+    )
+}
+
+/// 创建并启用一个毛刺过滤器
+#[cfg(esp_idf_soc_gpio_support_pin_glitch_filter)]
+fn create_glitch_filter(pin: i32, filter: GlitchFilter) -> GpioResult<FilterHandle> {
+    let mut handle: gpio_glitch_filter_handle_t = std::ptr::null_mut();
+    match filter {
+        GlitchFilter::Pin => {
+            let config = gpio_pin_glitch_filter_config_t {
+                clk_src: soc_periph_glitch_filter_clk_src_t_GLITCH_FILTER_CLK_SRC_DEFAULT,
+                gpio_num: pin,
+            };
+            check(
+                unsafe { gpio_new_pin_glitch_filter(&config, &mut handle) },
+                GpioError::ConfigError,
+                "gpio_new_pin_glitch_filter",
+                pin,
+            )?;
+        }
+        // ESP32-S3 没有灵活毛刺过滤器（SOC_GPIO_FLEX_GLITCH_FILTER_NUM 为0）
+        GlitchFilter::Flex { .. } => return Err(unsupported_filter(pin, filter)),
+    }
+
+    let handle = FilterHandle(handle);
+    if let Err(error) = check(
+        unsafe { gpio_glitch_filter_enable(handle.0) },
+        GpioError::ConfigError,
+        "gpio_glitch_filter_enable",
+        pin,
+    ) {
+        unsafe { gpio_del_glitch_filter(handle.0) };
+        return Err(error);
+    }
+    Ok(handle)
+}
+
+/// 芯片不支持所请求的毛刺过滤器
+fn unsupported_filter(pin: i32, filter: GlitchFilter) -> Error {
+    let operation = match filter {
+        GlitchFilter::Pin => "gpio_new_pin_glitch_filter",
+        GlitchFilter::Flex { .. } => "gpio_new_flex_glitch_filter",
+    };
+    Error::esp(GpioError::ConfigError, ESP_ERR_NOT_SUPPORTED, operation).with_pin(pin)
+}
+
+/// 基于ESP-IDF驱动的GPIO后端
+pub struct EspBackend;
+
+impl GpioBackend for EspBackend {
+    fn configure(
+        pin: i32,
+        mode: GpioMode,
+        pull_mode: GpioPullMode,
+        intr_type: GpioInterruptType,
+    ) -> GpioResult<()> {
+        let config = gpio_config_t {
+            pin_bit_mask: 1 << pin,
+            mode: convert_mode(mode),
+            pull_up_en: match pull_mode {
+                GpioPullMode::PullUp | GpioPullMode::PullUpDown => 1,
+                _ => 0,
+            },
+            pull_down_en: match pull_mode {
+                GpioPullMode::PullDown | GpioPullMode::PullUpDown => 1,
+                _ => 0,
+            },
+            intr_type: convert_intr_type(intr_type),
+        };
+
+        check(
+            unsafe { gpio_config(&config) },
+            GpioError::ConfigError,
+            "gpio_config",
+            pin,
+        )
+    }
+
+    fn reset(pin: i32) -> GpioResult<()> {
+        check(
+            unsafe { gpio_reset_pin(pin) },
+            GpioError::ConfigError,
+            "gpio_reset_pin",
+            pin,
+        )
+    }
+
+    fn set_direction(pin: i32, mode: GpioMode) -> GpioResult<()> {
+        check(
+            unsafe { gpio_set_direction(pin, convert_mode(mode)) },
+            GpioError::ConfigError,
+            "gpio_set_direction",
+            pin,
+        )
+    }
+
+    fn set_level(pin: i32, level: u32) -> GpioResult<()> {
+        check(
+            OUT_LOCK.lock(|_| unsafe { gpio_set_level(pin, level) }),
+            GpioError::ConfigError,
+            "gpio_set_level",
+            pin,
+        )
+    }
+
+    fn get_level(pin: i32) -> u32 {
+        unsafe { gpio_get_level(pin) as u32 }
+    }
+
+    fn set_output_mask(mask: u64) {
+        OUT_LOCK.lock(|_| write_mask(GPIO_OUT_W1TS_REG, GPIO_OUT1_W1TS_REG, mask));
+    }
+
+    fn clear_output_mask(mask: u64) {
+        OUT_LOCK.lock(|_| write_mask(GPIO_OUT_W1TC_REG, GPIO_OUT1_W1TC_REG, mask));
+    }
+
+    fn write_output_mask(mask: u64, value: u64) {
+        OUT_LOCK.lock(|_| {
+            modify_mask(GPIO_OUT_REG, mask as u32, value as u32);
+            modify_mask(GPIO_OUT1_REG, (mask >> 32) as u32, (value >> 32) as u32);
+        });
+    }
+
+    fn read_input_mask() -> u64 {
+        unsafe {
+            let low = std::ptr::read_volatile(GPIO_IN_REG as *const u32);
+            let high = std::ptr::read_volatile(GPIO_IN1_REG as *const u32);
+            u64::from(low) | (u64::from(high) << 32)
+        }
+    }
+
+    fn enable_input(pin: i32) -> GpioResult<()> {
+        if !is_valid_gpio(pin as u32) {
+            return Err(Error::new(GpioError::InvalidGpio).with_pin(pin));
+        }
+        // gpio_set_direction 会把输出信号改回GPIO寄存器，这里只置位 IO_MUX 的 FUN_IE
+        let reg = IO_MUX_GPIO0_REG + pin as usize * 4;
+        unsafe { std::ptr::write_volatile(reg as *mut u32, read_reg(reg) | (1 << 9)) };
+        Ok(())
+    }
+
+    fn set_pull_mode(pin: i32, pull_mode: GpioPullMode) -> GpioResult<()> {
+        check(
+            unsafe { gpio_set_pull_mode(pin, convert_pull_mode(pull_mode)) },
+            GpioError::ConfigError,
+            "gpio_set_pull_mode",
+            pin,
+        )
+    }
+
+    fn set_pullup(pin: i32, enable: bool) -> GpioResult<()> {
+        let (result, operation) = unsafe {
+            if enable {
+                (gpio_pullup_en(pin), "gpio_pullup_en")
+            } else {
+                (gpio_pullup_dis(pin), "gpio_pullup_dis")
+            }
+        };
+        check(result, GpioError::ConfigError, operation, pin)
+    }
+
+    fn set_pulldown(pin: i32, enable: bool) -> GpioResult<()> {
+        let (result, operation) = unsafe {
+            if enable {
+                (gpio_pulldown_en(pin), "gpio_pulldown_en")
+            } else {
+                (gpio_pulldown_dis(pin), "gpio_pulldown_dis")
+            }
+        };
+        check(result, GpioError::ConfigError, operation, pin)
+    }
+
+    fn set_drive_capability(pin: i32, drive_cap: GpioDriveCap) -> GpioResult<()> {
+        check(
+            unsafe { gpio_set_drive_capability(pin, convert_drive_cap(drive_cap)) },
+            GpioError::ConfigError,
+            "gpio_set_drive_capability",
+            pin,
+        )
+    }
+
+    #[cfg(esp_idf_soc_gpio_support_pin_glitch_filter)]
+    fn set_glitch_filter(pin: i32, filter: Option<GlitchFilter>) -> GpioResult<()> {
+        let index = usize::try_from(pin)
+            .ok()
+            .filter(|&index| index < GPIO_NUM_MAX as usize)
+            .ok_or_else(|| {
+                Error::esp(
+                    GpioError::InvalidGpio,
+                    ESP_ERR_INVALID_ARG,
+                    "gpio_new_pin_glitch_filter",
+                )
+                .with_pin(pin)
+            })?;
+
+        let mut filters = FILTERS.lock().unwrap_or_else(|e| e.into_inner());
+        if filters.is_empty() {
+            filters.resize_with(GPIO_NUM_MAX as usize, || None);
+        }
+        if let Some(handle) = filters[index].take() {
+            delete_glitch_filter(handle, pin)?;
+        }
+        if let Some(filter) = filter {
+            filters[index] = Some(create_glitch_filter(pin, filter)?);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(esp_idf_soc_gpio_support_pin_glitch_filter))]
+    fn set_glitch_filter(pin: i32, filter: Option<GlitchFilter>) -> GpioResult<()> {
+        match filter {
+            Some(filter) => Err(unsupported_filter(pin, filter)),
+            None => Ok(()),
+        }
+    }
+
+    fn set_hold(pin: i32, enable: bool) -> GpioResult<()> {
+        let (result, operation) = unsafe {
+            if enable {
+                (gpio_hold_en(pin), "gpio_hold_en")
+            } else {
+                (gpio_hold_dis(pin), "gpio_hold_dis")
+            }
+        };
+        check(result, GpioError::ConfigError, operation, pin)?;
+        let (word, bit) = (pin as usize / 32, pin as u32 % 32);
+        if enable {
+            HOLD_MASK[word].fetch_or(1 << bit, Ordering::Relaxed);
+        } else {
+            HOLD_MASK[word].fetch_and(!(1 << bit), Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    fn connect_out_signal(pin: i32, signal: u32, invert: bool) -> GpioResult<()> {
+        unsafe {
+            esp_rom_gpio_pad_select_gpio(pin as u32);
+            esp_rom_gpio_connect_out_signal(pin as u32, signal, invert, false);
+        }
+        Ok(())
+    }
+
+    fn connect_in_signal(pin: i32, signal: u32, invert: bool) -> GpioResult<()> {
+        unsafe { esp_rom_gpio_connect_in_signal(pin as u32, signal, invert) };
+        Ok(())
+    }
+
+    fn connect_in_constant(signal: u32, level: u32) {
+        let source = if level != 0 {
+            GPIO_MATRIX_CONST_ONE_INPUT
+        } else {
+            GPIO_MATRIX_CONST_ZERO_INPUT
+        };
+        unsafe { esp_rom_gpio_connect_in_signal(source, signal, false) };
+    }
+
+    fn iomux_out(pin: i32, func: u32) -> GpioResult<()> {
+        unsafe { gpio_iomux_out(pin as u8, func as i32, false) };
+        Ok(())
+    }
+
+    fn iomux_in(pin: i32, signal: u32) -> GpioResult<()> {
+        unsafe { gpio_iomux_in(pin as u32, signal) };
+        Ok(())
+    }
+
+    fn enable_wakeup(pin: i32, intr_type: GpioInterruptType) -> GpioResult<()> {
+        check(
+            unsafe { gpio_wakeup_enable(pin, convert_intr_type(intr_type)) },
+            GpioError::ConfigError,
+            "gpio_wakeup_enable",
+            pin,
+        )
+    }
+
+    fn disable_wakeup(pin: i32) -> GpioResult<()> {
+        check(
+            unsafe { gpio_wakeup_disable(pin) },
+            GpioError::ConfigError,
+            "gpio_wakeup_disable",
+            pin,
+        )
+    }
+
+    fn set_interrupt_type(pin: i32, intr_type: GpioInterruptType) -> GpioResult<()> {
+        check(
+            unsafe { gpio_set_intr_type(pin, convert_intr_type(intr_type)) },
+            GpioError::InterruptError,
+            "gpio_set_intr_type",
+            pin,
+        )
+    }
+
+    fn set_interrupt_enabled(pin: i32, enable: bool) -> GpioResult<()> {
+        let (result, operation) = unsafe {
+            if enable {
+                (gpio_intr_enable(pin), "gpio_intr_enable")
+            } else {
+                (gpio_intr_disable(pin), "gpio_intr_disable")
+            }
+        };
+        check(result, GpioError::InterruptError, operation, pin)
+    }
+
+    fn install_isr_service(intr_alloc_flags: i32) -> GpioResult<()> {
+        Error::check(
+            unsafe { gpio_install_isr_service(intr_alloc_flags) },
+            GpioError::InterruptError,
+            "gpio_install_isr_service",
+        )
+    }
+
+    fn uninstall_isr_service() {
+        unsafe {
+            gpio_uninstall_isr_service();
+        }
+    }
+
+    fn isr_handler_add(pin: i32, isr_handler: GpioIsr, args: *mut c_void) -> GpioResult<()> {
+        check(
+            unsafe { gpio_isr_handler_add(pin, isr_handler, args) },
+            GpioError::InterruptError,
+            "gpio_isr_handler_add",
+            pin,
+        )
+    }
+
+    fn isr_handler_remove(pin: i32) -> GpioResult<()> {
+        check(
+            unsafe { gpio_isr_handler_remove(pin) },
+            GpioError::InterruptError,
+            "gpio_isr_handler_remove",
+            pin,
+        )
+    }
+
+    fn set_deep_sleep_hold(enable: bool) {
+        unsafe {
+            if enable {
+                gpio_deep_sleep_hold_en();
+            } else {
+                gpio_deep_sleep_hold_dis();
+            }
+        }
+    }
+
+    fn now_us() -> u64 {
+        unsafe { esp_timer_get_time() as u64 }
+    }
+
+    fn delay_us(us: u32) {
+        unsafe { esp_rom_delay_us(us) };
+    }
+
+    fn snapshot(pin: i32) -> GpioResult<PinSnapshot> {
+        if !is_valid_gpio(pin as u32) {
+            return Err(Error::new(GpioError::InvalidGpio).with_pin(pin));
+        }
+        let index = pin as usize;
+        let iomux = read_reg(IO_MUX_GPIO0_REG + index * 4);
+        let config = read_reg(GPIO_PIN0_REG + index * 4);
+        let out_sel = read_reg(GPIO_FUNC0_OUT_SEL_CFG_REG + index * 4) & 0x1FF;
+
+        // IO_MUX: FUN_WPD[7] FUN_WPU[8] FUN_IE[9] FUN_DRV[11:10] MCU_SEL[14:12]
+        let input = (iomux >> 9) & 1 == 1;
+        let output = read_bit(GPIO_ENABLE_REG, GPIO_ENABLE1_REG, index);
+        // GPIO_PINn: PAD_DRIVER[2] INT_TYPE[9:7] INT_ENA[17:13]
+        let open_drain = (config >> 2) & 1 == 1;
+        let level = if input {
+            read_bit(GPIO_IN_REG, GPIO_IN1_REG, index)
+        } else {
+            read_bit(GPIO_OUT_REG, GPIO_OUT1_REG, index)
+        };
+        Ok(PinSnapshot {
+            pin: pin as u32,
+            iomux_func: (iomux >> 12) & 0x7,
+            out_signal: (out_sel != SIG_GPIO_OUT_IDX).then_some(out_sel),
+            mode: GpioMode::from_flags(input, output, open_drain),
+            pull: GpioPullMode::from_flags((iomux >> 8) & 1 == 1, (iomux >> 7) & 1 == 1),
+            drive_cap: drive_cap_from_bits(iomux >> 10),
+            level: u32::from(level),
+            intr_type: intr_type_from_bits(config >> 7),
+            intr_enabled: (config >> 13) & 0x1F != 0,
+            hold: (HOLD_MASK[index / 32].load(Ordering::Relaxed) >> (index % 32)) & 1 == 1,
+            reserved: is_reserved(pin as u32),
+            owner: None,
+        })
+    }
+}
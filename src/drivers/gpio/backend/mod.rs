@@ -0,0 +1,116 @@
+/**
+ * @file mod.rs
+ * @brief GPIO 后端抽象
+ * @details GpioPin 的所有硬件访问都经过 GpioBackend：
+ *          - 在 ESP-IDF 目标上使用 EspBackend，直接调用 gpio_* 驱动函数
+ *          - 在主机上使用 SimBackend，在内存中模拟引脚电平、模式、上下拉和边沿中断，
+ *            使 GpioPin 及其上层驱动可以在 `cargo test` 中运行
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::ffi::c_void;
+
+use crate::drivers::gpio::interrupt::GpioIsr;
+use crate::drivers::gpio::snapshot::PinSnapshot;
+use crate::drivers::gpio::types::{
+    GlitchFilter, GpioDriveCap, GpioInterruptType, GpioMode, GpioPullMode, GpioResult,
+};
+
+#[cfg(target_os = "espidf")]
+mod esp;
+#[cfg(not(target_os = "espidf"))]
+pub mod sim;
+
+#[cfg(target_os = "espidf")]
+pub use esp::EspBackend;
+#[cfg(not(target_os = "espidf"))]
+pub use sim::SimBackend;
+
+/// 当前平台使用的GPIO后端
+#[cfg(target_os = "espidf")]
+pub type ActiveBackend = EspBackend;
+/// 当前平台使用的GPIO后端
+#[cfg(not(target_os = "espidf"))]
+pub type ActiveBackend = SimBackend;
+
+/// GPIO后端接口
+///
+/// 每个方法对应一个 ESP-IDF `gpio_*` 驱动函数，引脚以 `gpio_num_t` 编号传入。
+/// 后端不保存引脚对象，所有状态都由硬件（或模拟器）持有。
+pub trait GpioBackend {
+    /// 按 `gpio_config` 的方式一次性配置模式、上下拉和中断类型
+    fn configure(
+        pin: i32,
+        mode: GpioMode,
+        pull_mode: GpioPullMode,
+        intr_type: GpioInterruptType,
+    ) -> GpioResult<()>;
+    /// 重置引脚到默认状态
+    fn reset(pin: i32) -> GpioResult<()>;
+    /// 设置方向模式
+    fn set_direction(pin: i32, mode: GpioMode) -> GpioResult<()>;
+    /// 设置输出电平
+    fn set_level(pin: i32, level: u32) -> GpioResult<()>;
+    /// 读取输入电平，输入未使能时返回0
+    fn get_level(pin: i32) -> u32;
+    /// 通过 OUT_W1TS 寄存器将掩码中的引脚输出置1，位号即GPIO编号
+    fn set_output_mask(mask: u64);
+    /// 通过 OUT_W1TC 寄存器将掩码中的引脚输出清0，位号即GPIO编号
+    fn clear_output_mask(mask: u64);
+    /// 按掩码写入输出电平，掩码外的引脚不变，位号即GPIO编号
+    ///
+    /// 在所有输出写入共用的临界区内读-改-写 OUT 寄存器，同一组（GPIO0~31 或 GPIO32~48）内的引脚
+    /// 在一次写入中同时变化，不会出现已清零而未置位的中间状态。
+    fn write_output_mask(mask: u64, value: u64);
+    /// 一次读取所有引脚的输入电平，位号即GPIO编号
+    fn read_input_mask() -> u64;
+    /// 只打开引脚的输入缓冲，不改变输出使能和GPIO矩阵的输出信号，用于读回外设驱动的引脚
+    fn enable_input(pin: i32) -> GpioResult<()>;
+    /// 设置上拉/下拉模式
+    fn set_pull_mode(pin: i32, pull_mode: GpioPullMode) -> GpioResult<()>;
+    /// 启用/禁用上拉电阻
+    fn set_pullup(pin: i32, enable: bool) -> GpioResult<()>;
+    /// 启用/禁用下拉电阻
+    fn set_pulldown(pin: i32, enable: bool) -> GpioResult<()>;
+    /// 设置驱动能力
+    fn set_drive_capability(pin: i32, drive_cap: GpioDriveCap) -> GpioResult<()>;
+    /// 为引脚启用毛刺过滤器，`None` 表示禁用；已有过滤器时先将其删除
+    fn set_glitch_filter(pin: i32, filter: Option<GlitchFilter>) -> GpioResult<()>;
+    /// 启用/禁用保持功能
+    fn set_hold(pin: i32, enable: bool) -> GpioResult<()>;
+    /// 经GPIO矩阵将外设输出信号连接到引脚，信号为 `SIG_GPIO_OUT_IDX` 时恢复由GPIO输出寄存器驱动
+    fn connect_out_signal(pin: i32, signal: u32, invert: bool) -> GpioResult<()>;
+    /// 经GPIO矩阵将引脚连接到外设输入信号
+    fn connect_in_signal(pin: i32, signal: u32, invert: bool) -> GpioResult<()>;
+    /// 将外设输入信号连接到固定电平，断开原来的引脚
+    fn connect_in_constant(signal: u32, level: u32);
+    /// 引脚切换到IO_MUX功能，外设输出绕过GPIO矩阵直连引脚
+    fn iomux_out(pin: i32, func: u32) -> GpioResult<()>;
+    /// 外设输入信号绕过GPIO矩阵，直接取自引脚
+    fn iomux_in(pin: i32, signal: u32) -> GpioResult<()>;
+    /// 启用唤醒功能
+    fn enable_wakeup(pin: i32, intr_type: GpioInterruptType) -> GpioResult<()>;
+    /// 禁用唤醒功能
+    fn disable_wakeup(pin: i32) -> GpioResult<()>;
+    /// 设置中断类型
+    fn set_interrupt_type(pin: i32, intr_type: GpioInterruptType) -> GpioResult<()>;
+    /// 启用/禁用中断
+    fn set_interrupt_enabled(pin: i32, enable: bool) -> GpioResult<()>;
+    /// 安装GPIO中断服务
+    fn install_isr_service(intr_alloc_flags: i32) -> GpioResult<()>;
+    /// 卸载GPIO中断服务
+    fn uninstall_isr_service();
+    /// 为指定引脚注册ISR处理程序
+    fn isr_handler_add(pin: i32, isr_handler: GpioIsr, args: *mut c_void) -> GpioResult<()>;
+    /// 移除指定引脚的ISR处理程序
+    fn isr_handler_remove(pin: i32) -> GpioResult<()>;
+    /// 启用/禁用深度睡眠期间的全局保持
+    fn set_deep_sleep_hold(enable: bool);
+    /// 自启动以来的时间（微秒），可在中断上下文中调用
+    fn now_us() -> u64;
+    /// 忙等待指定的微秒数，用于软件实现的总线时序；模拟后端推进虚拟时钟
+    fn delay_us(us: u32);
+    /// 读取引脚当前的配置，占用者由调用者从引脚登记表中补充
+    fn snapshot(pin: i32) -> GpioResult<PinSnapshot>;
+}
@@ -0,0 +1,1179 @@
+/**
+ * @file sim.rs
+ * @brief 主机模拟 GPIO 后端
+ * @details 在内存中模拟 ESP32-S3 的 GPIO 引脚，用于在主机上运行 `cargo test`：
+ *          - 模式、上下拉、驱动能力、保持和唤醒配置
+ *          - 输出电平与外部驱动电平的合成（含开漏线与）
+ *          - 边沿中断：满足中断类型时同步调用已注册的ISR
+ *          - 毛刺过滤：外部电平需保持过滤宽度以上才会被输入端接受
+ *          - GPIO矩阵：外设输出信号路由到引脚、引脚路由到外设输入信号，支持反相
+ *          - 脚本化输入波形：配合虚拟时钟 `advance` 按时间回放
+ *          - 输出电平变化记录，便于断言复位时序等驱动行为
+ *          - 芯片状态是线程局部的，并行运行的测试各自拥有一颗独立的"芯片"
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::time::Duration;
+
+use super::GpioBackend;
+use crate::drivers::gpio::interrupt::GpioIsr;
+use crate::drivers::gpio::matrix::{PIN_FUNC_GPIO, SIG_GPIO_OUT_IDX};
+use crate::drivers::gpio::registry::is_reserved;
+use crate::drivers::gpio::snapshot::PinSnapshot;
+use crate::drivers::gpio::types::{
+    GlitchFilter, GpioDriveCap, GpioError, GpioInterruptType, GpioMode, GpioPullMode, GpioResult,
+};
+use crate::error::esp_codes::{ESP_ERR_INVALID_ARG, ESP_ERR_INVALID_STATE, ESP_ERR_NOT_SUPPORTED};
+use crate::error::Error;
+
+/// ESP32-S3 的GPIO数量（GPIO0 ~ GPIO48）
+pub const GPIO_PIN_COUNT: usize = 49;
+
+/// 单个模拟引脚的状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimPinState {
+    /// 方向模式
+    pub mode: GpioMode,
+    /// 上拉电阻
+    pub pull_up: bool,
+    /// 下拉电阻
+    pub pull_down: bool,
+    /// 驱动能力
+    pub drive_cap: GpioDriveCap,
+    /// 输出寄存器中的电平
+    pub output_level: u32,
+    /// 外部电路驱动的电平，`None` 表示外部未驱动（高阻）
+    pub external_level: Option<u32>,
+    /// 中断类型
+    pub intr_type: GpioInterruptType,
+    /// 中断是否使能
+    pub intr_enabled: bool,
+    /// 保持功能
+    pub hold: bool,
+    /// 唤醒触发类型，`None` 表示未启用唤醒
+    pub wakeup: Option<GpioInterruptType>,
+    /// 毛刺过滤器
+    pub glitch_filter: Option<GlitchFilter>,
+    /// 经GPIO矩阵连接的外设输出信号，`None` 表示由GPIO输出寄存器驱动
+    pub out_signal: Option<SimOutSignal>,
+    /// 经IO_MUX直连时选择的功能号，`None` 表示使用GPIO矩阵
+    pub iomux_func: Option<u32>,
+}
+
+/// 连接到引脚的外设输出信号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimOutSignal {
+    /// 外设信号编号
+    pub signal: u32,
+    /// 是否反相
+    pub invert: bool,
+    /// 外设当前输出的电平（反相前）
+    pub level: u32,
+}
+
+impl Default for SimPinState {
+    fn default() -> Self {
+        SimPinState {
+            mode: GpioMode::Disable,
+            pull_up: false,
+            pull_down: false,
+            drive_cap: GpioDriveCap::Medium,
+            output_level: 0,
+            external_level: None,
+            intr_type: GpioInterruptType::Disable,
+            intr_enabled: false,
+            hold: false,
+            wakeup: None,
+            glitch_filter: None,
+            out_signal: None,
+            iomux_func: None,
+        }
+    }
+}
+
+impl SimPinState {
+    /// 输出驱动器上的电平，连接了外设信号时由外设决定
+    fn driven_level(&self) -> u32 {
+        match self.out_signal {
+            Some(out) => out.level ^ out.invert as u32,
+            None => self.output_level,
+        }
+    }
+
+    /// 引脚上的实际电平（不考虑输入缓冲是否使能）
+    fn pad_level(&self) -> u32 {
+        if self.mode.is_output() && !self.mode.is_open_drain() {
+            return self.driven_level();
+        }
+        if self.mode.is_open_drain() && self.driven_level() == 0 {
+            return 0;
+        }
+        match self.external_level {
+            Some(level) => level,
+            None if self.pull_up => 1,
+            None => 0,
+        }
+    }
+
+    /// 通过输入缓冲读到的电平，输入未使能时为0
+    fn input_level(&self) -> u32 {
+        if self.mode.is_input() {
+            self.pad_level()
+        } else {
+            0
+        }
+    }
+}
+
+/// 输出电平变化记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimEvent {
+    /// 虚拟时间（微秒）
+    pub time_us: u64,
+    /// GPIO编号
+    pub pin: i32,
+    /// 新电平
+    pub level: u32,
+}
+
+/// 脚本化输入波形
+///
+/// 由一系列"等待一段时间后切换到某电平"的步骤组成，
+/// 通过 [`play`] 挂到引脚上后，随 [`advance`] 推进虚拟时钟依次生效。
+#[derive(Debug, Clone, Default)]
+pub struct Waveform {
+    steps: Vec<(u64, u32)>,
+}
+
+impl Waveform {
+    /// 创建一个空波形
+    pub fn new() -> Self {
+        Waveform { steps: Vec::new() }
+    }
+
+    /// 以固定步长依次输出给定电平序列，第一个电平立即生效
+    pub fn from_levels(step: Duration, levels: &[u32]) -> Self {
+        let mut waveform = Waveform::new();
+        for (i, &level) in levels.iter().enumerate() {
+            let delay = if i == 0 { Duration::ZERO } else { step };
+            waveform = waveform.then(delay, level);
+        }
+        waveform
+    }
+
+    /// 在上一步之后等待 `delay`，然后切换到 `level`
+    pub fn then(mut self, delay: Duration, level: u32) -> Self {
+        self.steps.push((delay.as_micros() as u64, level));
+        self
+    }
+
+    /// 波形总时长
+    pub fn duration(&self) -> Duration {
+        Duration::from_micros(self.steps.iter().map(|&(delay, _)| delay).sum())
+    }
+}
+
+/// 到期时执行的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScheduledAction {
+    /// 波形步骤：外部电路驱动到该电平
+    Drive(u32),
+    /// 毛刺过滤器确认外部电平已稳定，输入端接受该电平
+    Settle(Option<u32>),
+}
+
+/// 待回放的电平切换
+#[derive(Debug, Clone, Copy)]
+struct ScheduledLevel {
+    time_us: u64,
+    seq: u64,
+    pin: i32,
+    action: ScheduledAction,
+}
+
+/// 外设输入信号的来源
+#[derive(Debug, Clone, Copy)]
+enum InputRoute {
+    /// 来自引脚
+    Pin { pin: i32, invert: bool },
+    /// 固定电平
+    Constant(u32),
+}
+
+/// 已注册的ISR
+#[derive(Clone, Copy)]
+struct SimHandler {
+    isr: unsafe extern "C" fn(arg: *mut c_void),
+    arg: *mut c_void,
+}
+
+/// 模拟芯片
+struct SimChip {
+    now_us: u64,
+    seq: u64,
+    pins: Vec<SimPinState>,
+    handlers: Vec<Option<SimHandler>>,
+    isr_service_installed: bool,
+    deep_sleep_hold: bool,
+    schedule: Vec<ScheduledLevel>,
+    trace: Vec<SimEvent>,
+    /// 每次改变了引脚电平的操作之后所有引脚的电平，位号即GPIO编号
+    pad_states: Vec<u64>,
+    signal_levels: HashMap<u32, u32>,
+    input_routes: HashMap<u32, InputRoute>,
+}
+
+impl SimChip {
+    fn new() -> Self {
+        SimChip {
+            now_us: 0,
+            seq: 0,
+            pins: vec![SimPinState::default(); GPIO_PIN_COUNT],
+            handlers: vec![None; GPIO_PIN_COUNT],
+            isr_service_installed: false,
+            deep_sleep_hold: false,
+            schedule: Vec::new(),
+            trace: Vec::new(),
+            pad_states: Vec::new(),
+            signal_levels: HashMap::new(),
+            input_routes: HashMap::new(),
+        }
+    }
+
+    fn pin_mut(&mut self, pin: i32) -> GpioResult<&mut SimPinState> {
+        usize::try_from(pin)
+            .ok()
+            .and_then(|index| self.pins.get_mut(index))
+            .ok_or_else(|| invalid_gpio(pin))
+    }
+
+    fn schedule(&mut self, time_us: u64, pin: i32, action: ScheduledAction) {
+        let seq = self.seq;
+        self.seq += 1;
+        self.schedule.push(ScheduledLevel {
+            time_us,
+            seq,
+            pin,
+            action,
+        });
+        self.schedule.sort_by_key(|item| (item.time_us, item.seq));
+    }
+
+    /// 所有引脚的电平，位号即GPIO编号
+    fn pads(&self) -> u64 {
+        self.pins.iter().enumerate().fold(0, |mask, (pin, state)| {
+            mask | (u64::from(state.pad_level()) << pin)
+        })
+    }
+
+    /// 一次操作前后引脚电平不同时记录新的电平
+    fn record_pads(&mut self, before: u64) {
+        let after = self.pads();
+        if after != before {
+            self.pad_states.push(after);
+        }
+    }
+
+    /// 取消引脚上正在等待过滤器确认的电平，返回其中最新的一个
+    fn take_settle(&mut self, pin: i32) -> Option<Option<u32>> {
+        let mut pending = None;
+        self.schedule.retain(|item| match item.action {
+            ScheduledAction::Settle(level) if item.pin == pin => {
+                pending = Some(level);
+                false
+            }
+            _ => true,
+        });
+        pending
+    }
+}
+
+/// 与ESP-IDF一致，无效引脚返回 ESP_ERR_INVALID_ARG
+fn invalid_gpio(pin: i32) -> Error {
+    Error::new(GpioError::InvalidGpio)
+        .with_code(ESP_ERR_INVALID_ARG)
+        .with_pin(pin)
+}
+
+/// 中断相关操作的错误
+fn interrupt_error(code: i32, operation: &'static str, pin: i32) -> Error {
+    Error::esp(GpioError::InterruptError, code, operation).with_pin(pin)
+}
+
+thread_local! {
+    static CHIP: RefCell<SimChip> = RefCell::new(SimChip::new());
+}
+
+/// 判断一次电平变化是否满足中断触发条件
+fn edge_matches(intr_type: GpioInterruptType, old: u32, new: u32) -> bool {
+    if old == new {
+        return false;
+    }
+    match intr_type {
+        GpioInterruptType::Disable => false,
+        GpioInterruptType::RisingEdge => new == 1,
+        GpioInterruptType::FallingEdge => new == 0,
+        GpioInterruptType::AnyEdge => true,
+        // 电平中断在进入有效电平时触发一次
+        GpioInterruptType::LowLevel => new == 0,
+        GpioInterruptType::HighLevel => new == 1,
+    }
+}
+
+impl SimChip {
+    /// 修改一个引脚的状态并记录输出变化，返回产生有效边沿时需要调用的ISR
+    fn apply<R>(
+        &mut self,
+        pin: i32,
+        f: impl FnOnce(&mut SimPinState) -> R,
+    ) -> GpioResult<(R, Option<SimHandler>)> {
+        let now_us = self.now_us;
+        let service = self.isr_service_installed;
+        let state = self.pin_mut(pin)?;
+
+        let old_input = state.input_level();
+        let old_output = state.driven_level();
+        let result = f(state);
+        let new_input = state.input_level();
+        let new_output = state.driven_level();
+        let fire = state.intr_enabled && edge_matches(state.intr_type, old_input, new_input);
+
+        if old_output != new_output && state.mode.is_output() {
+            self.trace.push(SimEvent {
+                time_us: now_us,
+                pin,
+                level: new_output,
+            });
+        }
+
+        let handler = if fire && service {
+            self.handlers[pin as usize]
+        } else {
+            None
+        };
+        Ok((result, handler))
+    }
+}
+
+/// 修改一个引脚的状态，记录输出变化并在产生有效边沿时调用ISR
+///
+/// ISR在释放芯片借用之后调用，因此处理函数内部可以再次访问GPIO。
+fn update_pin<R>(pin: i32, f: impl FnOnce(&mut SimPinState) -> R) -> GpioResult<R> {
+    let (result, handler) = CHIP.with(|chip| {
+        let mut chip = chip.borrow_mut();
+        let before = chip.pads();
+        let applied = chip.apply(pin, f);
+        chip.record_pads(before);
+        applied
+    })?;
+
+    if let Some(handler) = handler {
+        unsafe { (handler.isr)(handler.arg) };
+    }
+    Ok(result)
+}
+
+/// 同时修改掩码中所有引脚的状态，`f` 的第一个参数为GPIO编号
+///
+/// 所有引脚先全部更新，再依次调用ISR，与寄存器一次写入多个引脚的行为一致。
+/// 超出引脚范围的位被忽略。
+fn update_pins(mask: u64, f: impl Fn(i32, &mut SimPinState)) {
+    let handlers: Vec<SimHandler> = CHIP.with(|chip| {
+        let mut chip = chip.borrow_mut();
+        let before = chip.pads();
+        let handlers = (0..GPIO_PIN_COUNT as i32)
+            .filter(|pin| mask & (1 << pin) != 0)
+            .filter_map(|pin| {
+                chip.apply(pin, |state| f(pin, state))
+                    .ok()
+                    .and_then(|(_, handler)| handler)
+            })
+            .collect();
+        chip.record_pads(before);
+        handlers
+    });
+
+    for handler in handlers {
+        unsafe { (handler.isr)(handler.arg) };
+    }
+}
+
+/// 读取引脚状态
+fn read_pin<R>(pin: i32, f: impl FnOnce(&SimPinState) -> R) -> GpioResult<R> {
+    CHIP.with(|chip| {
+        let chip = chip.borrow();
+        usize::try_from(pin)
+            .ok()
+            .and_then(|index| chip.pins.get(index))
+            .map(f)
+            .ok_or_else(|| invalid_gpio(pin))
+    })
+}
+
+/// 主机模拟GPIO后端
+pub struct SimBackend;
+
+impl GpioBackend for SimBackend {
+    fn configure(
+        pin: i32,
+        mode: GpioMode,
+        pull_mode: GpioPullMode,
+        intr_type: GpioInterruptType,
+    ) -> GpioResult<()> {
+        update_pin(pin, |state| {
+            state.mode = mode;
+            state.pull_up = matches!(pull_mode, GpioPullMode::PullUp | GpioPullMode::PullUpDown);
+            state.pull_down =
+                matches!(pull_mode, GpioPullMode::PullDown | GpioPullMode::PullUpDown);
+            state.intr_type = intr_type;
+            // gpio_config 在中断类型非禁用时会同时使能中断
+            state.intr_enabled = intr_type != GpioInterruptType::Disable;
+        })
+    }
+
+    fn reset(pin: i32) -> GpioResult<()> {
+        update_pin(pin, |state| {
+            let external_level = state.external_level;
+            // 毛刺过滤器是独立的驱动对象，不随引脚复位删除
+            let glitch_filter = state.glitch_filter;
+            *state = SimPinState {
+                // gpio_reset_pin 会使能上拉
+                pull_up: true,
+                external_level,
+                glitch_filter,
+                ..SimPinState::default()
+            };
+        })
+    }
+
+    fn set_direction(pin: i32, mode: GpioMode) -> GpioResult<()> {
+        update_pin(pin, |state| state.mode = mode)
+    }
+
+    fn set_level(pin: i32, level: u32) -> GpioResult<()> {
+        update_pin(pin, |state| state.output_level = (level != 0) as u32)
+    }
+
+    fn get_level(pin: i32) -> u32 {
+        read_pin(pin, SimPinState::input_level).unwrap_or(0)
+    }
+
+    fn set_output_mask(mask: u64) {
+        update_pins(mask, |_, state| state.output_level = 1)
+    }
+
+    fn clear_output_mask(mask: u64) {
+        update_pins(mask, |_, state| state.output_level = 0)
+    }
+
+    fn write_output_mask(mask: u64, value: u64) {
+        // 与 OUT、OUT1 两个寄存器一致，每组一次写入，先低组后高组
+        for bank in [0xFFFF_FFFF, !0xFFFF_FFFF] {
+            if mask & bank != 0 {
+                update_pins(mask & bank, |pin, state| {
+                    state.output_level = ((value >> pin) & 1) as u32;
+                })
+            }
+        }
+    }
+
+    fn read_input_mask() -> u64 {
+        CHIP.with(|chip| {
+            chip.borrow()
+                .pins
+                .iter()
+                .enumerate()
+                .fold(0, |mask, (pin, state)| {
+                    mask | (u64::from(state.input_level()) << pin)
+                })
+        })
+    }
+
+    fn enable_input(pin: i32) -> GpioResult<()> {
+        update_pin(pin, |state| {
+            state.mode = match state.mode {
+                GpioMode::Disable => GpioMode::Input,
+                GpioMode::Output => GpioMode::InputOutput,
+                GpioMode::OutputOpenDrain => GpioMode::InputOutputOpenDrain,
+                mode => mode,
+            }
+        })
+    }
+
+    fn set_pull_mode(pin: i32, pull_mode: GpioPullMode) -> GpioResult<()> {
+        update_pin(pin, |state| {
+            state.pull_up = matches!(pull_mode, GpioPullMode::PullUp | GpioPullMode::PullUpDown);
+            state.pull_down =
+                matches!(pull_mode, GpioPullMode::PullDown | GpioPullMode::PullUpDown);
+        })
+    }
+
+    fn set_pullup(pin: i32, enable: bool) -> GpioResult<()> {
+        update_pin(pin, |state| state.pull_up = enable)
+    }
+
+    fn set_pulldown(pin: i32, enable: bool) -> GpioResult<()> {
+        update_pin(pin, |state| state.pull_down = enable)
+    }
+
+    fn set_drive_capability(pin: i32, drive_cap: GpioDriveCap) -> GpioResult<()> {
+        update_pin(pin, |state| state.drive_cap = drive_cap)
+    }
+
+    fn set_glitch_filter(pin: i32, filter: Option<GlitchFilter>) -> GpioResult<()> {
+        // 与ESP32-S3一致，只有引脚毛刺过滤器
+        if let Some(GlitchFilter::Flex { .. }) = filter {
+            return Err(Error::esp(
+                GpioError::ConfigError,
+                ESP_ERR_NOT_SUPPORTED,
+                "gpio_new_flex_glitch_filter",
+            )
+            .with_pin(pin));
+        }
+        set_input_filter(pin, filter)
+    }
+
+    fn set_hold(pin: i32, enable: bool) -> GpioResult<()> {
+        update_pin(pin, |state| state.hold = enable)
+    }
+
+    fn connect_out_signal(pin: i32, signal: u32, invert: bool) -> GpioResult<()> {
+        let level = CHIP.with(|chip| {
+            chip.borrow()
+                .signal_levels
+                .get(&signal)
+                .copied()
+                .unwrap_or(0)
+        });
+        update_pin(pin, |state| {
+            state.iomux_func = None;
+            state.out_signal = (signal != SIG_GPIO_OUT_IDX).then_some(SimOutSignal {
+                signal,
+                invert,
+                level,
+            });
+        })
+    }
+
+    fn connect_in_signal(pin: i32, signal: u32, invert: bool) -> GpioResult<()> {
+        CHIP.with(|chip| {
+            let mut chip = chip.borrow_mut();
+            chip.pin_mut(pin)?;
+            chip.input_routes
+                .insert(signal, InputRoute::Pin { pin, invert });
+            Ok(())
+        })
+    }
+
+    fn connect_in_constant(signal: u32, level: u32) {
+        CHIP.with(|chip| {
+            chip.borrow_mut()
+                .input_routes
+                .insert(signal, InputRoute::Constant((level != 0) as u32))
+        });
+    }
+
+    fn iomux_out(pin: i32, func: u32) -> GpioResult<()> {
+        update_pin(pin, |state| {
+            state.out_signal = None;
+            state.iomux_func = Some(func);
+        })
+    }
+
+    fn iomux_in(pin: i32, signal: u32) -> GpioResult<()> {
+        Self::connect_in_signal(pin, signal, false)
+    }
+
+    fn enable_wakeup(pin: i32, intr_type: GpioInterruptType) -> GpioResult<()> {
+        // 与ESP-IDF一致，只有电平触发可以用于唤醒
+        match intr_type {
+            GpioInterruptType::LowLevel | GpioInterruptType::HighLevel => {
+                update_pin(pin, |state| state.wakeup = Some(intr_type))
+            }
+            _ => Err(Error::esp(
+                GpioError::ConfigError,
+                ESP_ERR_INVALID_ARG,
+                "gpio_wakeup_enable",
+            )
+            .with_pin(pin)),
+        }
+    }
+
+    fn disable_wakeup(pin: i32) -> GpioResult<()> {
+        update_pin(pin, |state| state.wakeup = None)
+    }
+
+    fn set_interrupt_type(pin: i32, intr_type: GpioInterruptType) -> GpioResult<()> {
+        update_pin(pin, |state| state.intr_type = intr_type)
+            .map_err(|_| interrupt_error(ESP_ERR_INVALID_ARG, "gpio_set_intr_type", pin))
+    }
+
+    fn set_interrupt_enabled(pin: i32, enable: bool) -> GpioResult<()> {
+        let operation = if enable {
+            "gpio_intr_enable"
+        } else {
+            "gpio_intr_disable"
+        };
+        update_pin(pin, |state| state.intr_enabled = enable)
+            .map_err(|_| interrupt_error(ESP_ERR_INVALID_ARG, operation, pin))
+    }
+
+    fn install_isr_service(_intr_alloc_flags: i32) -> GpioResult<()> {
+        CHIP.with(|chip| {
+            let mut chip = chip.borrow_mut();
+            // 重复安装时ESP-IDF返回ESP_ERR_INVALID_STATE
+            if chip.isr_service_installed {
+                return Err(Error::esp(
+                    GpioError::InterruptError,
+                    ESP_ERR_INVALID_STATE,
+                    "gpio_install_isr_service",
+                ));
+            }
+            chip.isr_service_installed = true;
+            Ok(())
+        })
+    }
+
+    fn uninstall_isr_service() {
+        CHIP.with(|chip| {
+            let mut chip = chip.borrow_mut();
+            chip.isr_service_installed = false;
+            chip.handlers.iter_mut().for_each(|handler| *handler = None);
+        })
+    }
+
+    fn isr_handler_add(pin: i32, isr_handler: GpioIsr, args: *mut c_void) -> GpioResult<()> {
+        CHIP.with(|chip| {
+            let mut chip = chip.borrow_mut();
+            if !chip.isr_service_installed {
+                return Err(interrupt_error(
+                    ESP_ERR_INVALID_STATE,
+                    "gpio_isr_handler_add",
+                    pin,
+                ));
+            }
+            let slot = usize::try_from(pin)
+                .ok()
+                .and_then(|index| chip.handlers.get_mut(index))
+                .ok_or_else(|| interrupt_error(ESP_ERR_INVALID_ARG, "gpio_isr_handler_add", pin))?;
+            *slot = isr_handler.map(|isr| SimHandler { isr, arg: args });
+            Ok(())
+        })
+    }
+
+    fn isr_handler_remove(pin: i32) -> GpioResult<()> {
+        CHIP.with(|chip| {
+            let mut chip = chip.borrow_mut();
+            if !chip.isr_service_installed {
+                return Err(interrupt_error(
+                    ESP_ERR_INVALID_STATE,
+                    "gpio_isr_handler_remove",
+                    pin,
+                ));
+            }
+            let slot = usize::try_from(pin)
+                .ok()
+                .and_then(|index| chip.handlers.get_mut(index))
+                .ok_or_else(|| {
+                    interrupt_error(ESP_ERR_INVALID_ARG, "gpio_isr_handler_remove", pin)
+                })?;
+            *slot = None;
+            Ok(())
+        })
+    }
+
+    fn set_deep_sleep_hold(enable: bool) {
+        CHIP.with(|chip| chip.borrow_mut().deep_sleep_hold = enable)
+    }
+
+    fn now_us() -> u64 {
+        now_us()
+    }
+
+    fn delay_us(us: u32) {
+        advance(Duration::from_micros(u64::from(us)));
+    }
+
+    fn snapshot(pin: i32) -> GpioResult<PinSnapshot> {
+        read_pin(pin, |state| PinSnapshot {
+            pin: pin as u32,
+            iomux_func: state.iomux_func.unwrap_or(PIN_FUNC_GPIO),
+            out_signal: state.out_signal.map(|out| out.signal),
+            mode: state.mode,
+            pull: GpioPullMode::from_flags(state.pull_up, state.pull_down),
+            drive_cap: state.drive_cap,
+            level: if state.mode.is_input() {
+                state.input_level()
+            } else {
+                state.output_level
+            },
+            intr_type: state.intr_type,
+            intr_enabled: state.intr_enabled,
+            hold: state.hold,
+            reserved: is_reserved(pin as u32),
+            owner: None,
+        })
+    }
+}
+
+/// 外设驱动一个输出信号，经GPIO矩阵连接到该信号的引脚随之变化
+pub fn drive_signal(signal: u32, level: u32) {
+    let level = (level != 0) as u32;
+    let mask = CHIP.with(|chip| {
+        let mut chip = chip.borrow_mut();
+        chip.signal_levels.insert(signal, level);
+        chip.pins
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| state.out_signal.is_some_and(|out| out.signal == signal))
+            .fold(0u64, |mask, (pin, _)| mask | (1 << pin))
+    });
+    update_pins(mask, |_, state| {
+        if let Some(out) = state.out_signal.as_mut() {
+            out.level = level;
+        }
+    });
+}
+
+/// 外设从输入信号读到的电平，未连接的信号读到0
+pub fn input_signal(signal: u32) -> u32 {
+    let route = CHIP.with(|chip| chip.borrow().input_routes.get(&signal).copied());
+    match route {
+        Some(InputRoute::Pin { pin, invert }) => {
+            read_pin(pin, SimPinState::input_level).unwrap_or(0) ^ invert as u32
+        }
+        Some(InputRoute::Constant(level)) => level,
+        None => 0,
+    }
+}
+
+/// 将当前线程的模拟芯片恢复到上电状态
+pub fn reset() {
+    CHIP.with(|chip| *chip.borrow_mut() = SimChip::new());
+}
+
+/// 外部电路驱动引脚到指定电平
+pub fn drive(pin: i32, level: u32) {
+    set_external(pin, Some((level != 0) as u32));
+}
+
+/// 外部电路释放引脚（高阻），电平由上下拉决定
+pub fn release(pin: i32) {
+    set_external(pin, None);
+}
+
+/// 设置引脚输入端的滤波器，用于模拟外设（如PCNT）自带的滤波器
+///
+/// 与GPIO毛刺过滤器共用同一套滤波逻辑，但可以使用灵活窗口；
+/// GPIO本身的过滤器通过 `GpioBackend::set_glitch_filter` 设置。
+pub fn set_input_filter(pin: i32, filter: Option<GlitchFilter>) -> GpioResult<()> {
+    if let Some(filter) = filter {
+        if !filter.is_valid() {
+            return Err(Error::esp(
+                GpioError::ConfigError,
+                ESP_ERR_INVALID_ARG,
+                "gpio_new_flex_glitch_filter",
+            )
+            .with_pin(pin));
+        }
+    }
+    let pending = CHIP.with(|chip| {
+        let mut chip = chip.borrow_mut();
+        chip.pin_mut(pin)?.glitch_filter = filter;
+        Ok::<_, Error>(chip.take_settle(pin))
+    })?;
+    // 更换或禁用过滤器时，正在确认的电平直接生效
+    if let Some(level) = pending {
+        commit_external(pin, level);
+    }
+    Ok(())
+}
+
+/// 外部电平变化，启用毛刺过滤器时需保持过滤宽度以上才会被接受
+///
+/// 虚拟时钟精度为1微秒，过滤宽度向上取整到微秒，
+/// 因此同一虚拟时刻内的来回跳变总是被滤除。
+fn set_external(pin: i32, level: Option<u32>) {
+    let filter = read_pin(pin, |state| state.glitch_filter).expect("无效的模拟GPIO编号");
+    let Some(filter) = filter else {
+        commit_external(pin, level);
+        return;
+    };
+    CHIP.with(|chip| {
+        let mut chip = chip.borrow_mut();
+        // 新的跳变使尚未稳定的电平作废
+        chip.take_settle(pin);
+        if chip.pins[pin as usize].external_level != level {
+            let settle_us = u64::from(filter.threshold_ns()).div_ceil(1000).max(1);
+            let time_us = chip.now_us + settle_us;
+            chip.schedule(time_us, pin, ScheduledAction::Settle(level));
+        }
+    });
+}
+
+/// 外部电平到达输入端
+fn commit_external(pin: i32, level: Option<u32>) {
+    update_pin(pin, |state| state.external_level = level).expect("无效的模拟GPIO编号");
+}
+
+/// 在引脚上回放一段输入波形，时间从当前虚拟时刻开始计算
+pub fn play(pin: i32, waveform: &Waveform) {
+    CHIP.with(|chip| {
+        let mut chip = chip.borrow_mut();
+        let mut time_us = chip.now_us;
+        for &(delay, level) in &waveform.steps {
+            time_us += delay;
+            chip.schedule(time_us, pin, ScheduledAction::Drive(level));
+        }
+    });
+    // 时间为0的步骤立即生效
+    advance(Duration::ZERO);
+}
+
+/// 推进虚拟时钟，按时间顺序应用到期的波形步骤
+pub fn advance(duration: Duration) {
+    let target_us = now_us() + duration.as_micros() as u64;
+    loop {
+        let next = CHIP.with(|chip| {
+            let mut chip = chip.borrow_mut();
+            match chip.schedule.first() {
+                Some(item) if item.time_us <= target_us => {
+                    let item = chip.schedule.remove(0);
+                    chip.now_us = item.time_us;
+                    Some(item)
+                }
+                _ => None,
+            }
+        });
+        match next {
+            Some(item) => match item.action {
+                ScheduledAction::Drive(level) => drive(item.pin, level),
+                ScheduledAction::Settle(level) => commit_external(item.pin, level),
+            },
+            None => break,
+        }
+    }
+    CHIP.with(|chip| chip.borrow_mut().now_us = target_us);
+}
+
+/// 当前虚拟时间（微秒）
+pub fn now_us() -> u64 {
+    CHIP.with(|chip| chip.borrow().now_us)
+}
+
+/// 是否还有未回放的波形步骤或等待过滤器确认的电平
+pub fn has_pending_waveform() -> bool {
+    CHIP.with(|chip| !chip.borrow().schedule.is_empty())
+}
+
+/// 下一个待回放的波形步骤或过滤器确认的虚拟时间（微秒）
+pub fn next_event_us() -> Option<u64> {
+    CHIP.with(|chip| chip.borrow().schedule.first().map(|item| item.time_us))
+}
+
+/// 读取引脚的完整模拟状态
+pub fn pin_state(pin: i32) -> SimPinState {
+    read_pin(pin, SimPinState::clone).expect("无效的模拟GPIO编号")
+}
+
+/// 引脚上的实际电平，不受输入缓冲是否使能影响
+pub fn pad_level(pin: i32) -> u32 {
+    read_pin(pin, SimPinState::pad_level).expect("无效的模拟GPIO编号")
+}
+
+/// GPIO中断服务是否已安装
+pub fn isr_service_installed() -> bool {
+    CHIP.with(|chip| chip.borrow().isr_service_installed)
+}
+
+/// 指定引脚是否注册了ISR
+pub fn has_isr_handler(pin: i32) -> bool {
+    CHIP.with(|chip| {
+        chip.borrow()
+            .handlers
+            .get(pin as usize)
+            .is_some_and(Option::is_some)
+    })
+}
+
+/// 深度睡眠全局保持是否启用
+pub fn deep_sleep_hold_enabled() -> bool {
+    CHIP.with(|chip| chip.borrow().deep_sleep_hold)
+}
+
+/// 所有输出电平变化记录
+pub fn trace() -> Vec<SimEvent> {
+    CHIP.with(|chip| chip.borrow().trace.clone())
+}
+
+/// 指定引脚的输出电平变化序列
+pub fn output_history(pin: i32) -> Vec<u32> {
+    CHIP.with(|chip| {
+        chip.borrow()
+            .trace
+            .iter()
+            .filter(|event| event.pin == pin)
+            .map(|event| event.level)
+            .collect()
+    })
+}
+
+/// 每次改变了引脚电平的操作之后所有引脚的电平，位号即GPIO编号
+///
+/// 一次操作同时改变多个引脚时只有一条记录，可用来检查多引脚写入是否出现中间状态。
+pub fn pad_states() -> Vec<u64> {
+    CHIP.with(|chip| chip.borrow().pad_states.clone())
+}
+
+/// 清空输出电平变化记录和引脚电平记录
+pub fn clear_trace() {
+    CHIP.with(|chip| {
+        let mut chip = chip.borrow_mut();
+        chip.trace.clear();
+        chip.pad_states.clear();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static EDGES: AtomicU32 = AtomicU32::new(0);
+
+    unsafe extern "C" fn count_edge(_arg: *mut c_void) {
+        EDGES.fetch_add(1, Ordering::SeqCst);
+    }
+
+    unsafe extern "C" fn count_arg(arg: *mut c_void) {
+        (*(arg as *const AtomicU32)).fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_pull_and_external_level() {
+        reset();
+        SimBackend::configure(
+            4,
+            GpioMode::Input,
+            GpioPullMode::PullUp,
+            GpioInterruptType::Disable,
+        )
+        .unwrap();
+        assert_eq!(SimBackend::get_level(4), 1);
+
+        drive(4, 0);
+        assert_eq!(SimBackend::get_level(4), 0);
+
+        release(4);
+        SimBackend::set_pull_mode(4, GpioPullMode::PullDown).unwrap();
+        assert_eq!(SimBackend::get_level(4), 0);
+    }
+
+    #[test]
+    fn test_output_only_pin_reads_zero() {
+        reset();
+        SimBackend::configure(
+            5,
+            GpioMode::Output,
+            GpioPullMode::Floating,
+            GpioInterruptType::Disable,
+        )
+        .unwrap();
+        SimBackend::set_level(5, 1).unwrap();
+        assert_eq!(SimBackend::get_level(5), 0);
+        assert_eq!(pad_level(5), 1);
+
+        SimBackend::set_direction(5, GpioMode::InputOutput).unwrap();
+        assert_eq!(SimBackend::get_level(5), 1);
+    }
+
+    #[test]
+    fn test_open_drain_wired_and() {
+        reset();
+        SimBackend::configure(
+            6,
+            GpioMode::InputOutputOpenDrain,
+            GpioPullMode::PullUp,
+            GpioInterruptType::Disable,
+        )
+        .unwrap();
+        SimBackend::set_level(6, 1).unwrap();
+        assert_eq!(SimBackend::get_level(6), 1);
+
+        // 外部器件拉低总线
+        drive(6, 0);
+        assert_eq!(SimBackend::get_level(6), 0);
+
+        release(6);
+        SimBackend::set_level(6, 0).unwrap();
+        assert_eq!(SimBackend::get_level(6), 0);
+    }
+
+    #[test]
+    fn test_edge_interrupt_requires_service() {
+        reset();
+        let counter = AtomicU32::new(0);
+        let arg = &counter as *const AtomicU32 as *mut c_void;
+        assert!(SimBackend::isr_handler_add(7, Some(count_arg), arg).is_err());
+
+        SimBackend::install_isr_service(0).unwrap();
+        assert!(SimBackend::install_isr_service(0).is_err());
+        SimBackend::configure(
+            7,
+            GpioMode::Input,
+            GpioPullMode::PullUp,
+            GpioInterruptType::FallingEdge,
+        )
+        .unwrap();
+        SimBackend::isr_handler_add(7, Some(count_arg), arg).unwrap();
+
+        drive(7, 0);
+        drive(7, 1);
+        drive(7, 0);
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+        SimBackend::set_interrupt_enabled(7, false).unwrap();
+        drive(7, 1);
+        drive(7, 0);
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_waveform_playback() {
+        reset();
+        EDGES.store(0, Ordering::SeqCst);
+        SimBackend::install_isr_service(0).unwrap();
+        SimBackend::configure(
+            0,
+            GpioMode::Input,
+            GpioPullMode::PullUp,
+            GpioInterruptType::AnyEdge,
+        )
+        .unwrap();
+        SimBackend::isr_handler_add(0, Some(count_edge), std::ptr::null_mut()).unwrap();
+
+        // 模拟带抖动的按键按下
+        let bounce = Waveform::new()
+            .then(Duration::from_millis(10), 0)
+            .then(Duration::from_micros(200), 1)
+            .then(Duration::from_micros(300), 0)
+            .then(Duration::from_millis(50), 1);
+        play(0, &bounce);
+        assert_eq!(bounce.duration(), Duration::from_micros(60_500));
+
+        advance(Duration::from_millis(5));
+        assert_eq!(SimBackend::get_level(0), 1);
+        assert_eq!(EDGES.load(Ordering::SeqCst), 0);
+
+        advance(Duration::from_millis(6));
+        assert_eq!(SimBackend::get_level(0), 0);
+        assert_eq!(EDGES.load(Ordering::SeqCst), 3);
+
+        advance(Duration::from_millis(100));
+        assert_eq!(SimBackend::get_level(0), 1);
+        assert_eq!(EDGES.load(Ordering::SeqCst), 4);
+        assert!(!has_pending_waveform());
+        assert_eq!(now_us(), 111_000);
+    }
+
+    #[test]
+    fn test_glitch_filter() {
+        reset();
+        let counter = AtomicU32::new(0);
+        let arg = &counter as *const AtomicU32 as *mut c_void;
+        SimBackend::install_isr_service(0).unwrap();
+        SimBackend::configure(
+            10,
+            GpioMode::Input,
+            GpioPullMode::PullUp,
+            GpioInterruptType::AnyEdge,
+        )
+        .unwrap();
+        SimBackend::isr_handler_add(10, Some(count_arg), arg).unwrap();
+        SimBackend::set_glitch_filter(10, Some(GlitchFilter::Pin)).unwrap();
+
+        // 同一时刻内的来回跳变被滤除
+        drive(10, 0);
+        drive(10, 1);
+        advance(Duration::from_micros(5));
+        assert_eq!(SimBackend::get_level(10), 1);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+
+        // 保持足够久的电平在过滤宽度之后才被接受
+        drive(10, 0);
+        assert_eq!(SimBackend::get_level(10), 1);
+        advance(Duration::from_micros(1));
+        assert_eq!(SimBackend::get_level(10), 0);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        // ESP32-S3 没有灵活毛刺过滤器
+        let flex = GlitchFilter::Flex {
+            window_ns: 5_000,
+            threshold_ns: 3_000,
+        };
+        let error = SimBackend::set_glitch_filter(10, Some(flex)).unwrap_err();
+        assert_eq!(error.code_name(), Some("ESP_ERR_NOT_SUPPORTED"));
+
+        // 外设滤波器可以使用灵活窗口：3us 的保持门限滤掉 2us 的脉冲
+        set_input_filter(10, Some(flex)).unwrap();
+        let pulse = Waveform::new()
+            .then(Duration::from_micros(10), 1)
+            .then(Duration::from_micros(2), 0)
+            .then(Duration::from_micros(10), 1);
+        play(10, &pulse);
+        advance(Duration::from_micros(20));
+        assert_eq!(SimBackend::get_level(10), 0);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        advance(Duration::from_micros(5));
+        assert_eq!(SimBackend::get_level(10), 1);
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+        let invalid = GlitchFilter::Flex {
+            window_ns: 1_000,
+            threshold_ns: 2_000,
+        };
+        let error = set_input_filter(10, Some(invalid)).unwrap_err();
+        assert_eq!(error.code_name(), Some("ESP_ERR_INVALID_ARG"));
+
+        // 禁用后外部电平立即生效
+        SimBackend::set_glitch_filter(10, None).unwrap();
+        drive(10, 0);
+        assert_eq!(SimBackend::get_level(10), 0);
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_output_trace() {
+        reset();
+        SimBackend::configure(
+            8,
+            GpioMode::Output,
+            GpioPullMode::Floating,
+            GpioInterruptType::Disable,
+        )
+        .unwrap();
+        SimBackend::set_level(8, 1).unwrap();
+        advance(Duration::from_millis(10));
+        SimBackend::set_level(8, 0).unwrap();
+        SimBackend::set_level(8, 0).unwrap();
+        advance(Duration::from_millis(10));
+        SimBackend::set_level(8, 1).unwrap();
+
+        assert_eq!(output_history(8), vec![1, 0, 1]);
+        let times: Vec<u64> = trace().iter().map(|event| event.time_us).collect();
+        assert_eq!(times, vec![0, 10_000, 20_000]);
+    }
+
+    #[test]
+    fn test_invalid_pin() {
+        reset();
+        let error = SimBackend::set_level(GPIO_PIN_COUNT as i32, 1).unwrap_err();
+        assert_eq!(error.gpio(), Some(&GpioError::InvalidGpio));
+        assert_eq!(error.code_name(), Some("ESP_ERR_INVALID_ARG"));
+        assert_eq!(SimBackend::get_level(-1), 0);
+    }
+}
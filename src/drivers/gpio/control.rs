@@ -1,12 +1,16 @@
 /**
  * @file control.rs
  * @brief ESP32 GPIO 系统控制功能
- * @details 提供了 GPIO 系统级别的控制功能，如深度睡眠、电源管理等
+ * @details 提供了 GPIO 系统级别的控制功能，如深度睡眠、毛刺过滤、配置快照等
  * @author xwx
  * @date 2025-05-13
  * @version 1.0
  */
-use esp_idf_sys::{gpio_deep_sleep_hold_dis, gpio_deep_sleep_hold_en};
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::registry::{self, is_valid_gpio, GPIO_NUM_MAX};
+use crate::drivers::gpio::snapshot::{GpioSnapshot, PinSnapshot};
+use crate::drivers::gpio::types::{GlitchFilter, GpioError, GpioResult};
+use crate::error::Error;
 
 /// GPIO系统控制
 pub struct GpioControl;
@@ -14,21 +18,61 @@ pub struct GpioControl;
 impl GpioControl {
     /// 启用所有数字GPIO引脚在深度睡眠期间的保持功能
     pub fn enable_deep_sleep_hold() {
-        unsafe {
-            gpio_deep_sleep_hold_en();
-        }
+        Backend::set_deep_sleep_hold(true);
     }
 
     /// 禁用所有数字GPIO引脚在深度睡眠期间的保持功能
     pub fn disable_deep_sleep_hold() {
-        unsafe {
-            gpio_deep_sleep_hold_dis();
+        Backend::set_deep_sleep_hold(false);
+    }
+
+    /// 为一组引脚启用毛刺过滤器
+    ///
+    /// 适用于引脚已交给其他驱动（如中断事件队列、PCNT）而手中没有 `GpioPin` 的情况。
+    /// 每个引脚使用独立的过滤器，重复调用时替换原有的过滤器。
+    ///
+    /// # 参数
+    ///
+    /// * `pins` - GPIO编号
+    /// * `filter` - 过滤器类型及窗口
+    pub fn enable_glitch_filter(pins: &[u32], filter: GlitchFilter) -> GpioResult<()> {
+        pins.iter()
+            .try_for_each(|&pin| Backend::set_glitch_filter(pin as i32, Some(filter)))
+    }
+
+    /// 禁用一组引脚的毛刺过滤器
+    pub fn disable_glitch_filter(pins: &[u32]) -> GpioResult<()> {
+        pins.iter()
+            .try_for_each(|&pin| Backend::set_glitch_filter(pin as i32, None))
+    }
+
+    /// 读取所有引脚当前的配置
+    ///
+    /// 配置直接从硬件读回，包括没有 `GpioPin` 的引脚（如被外设驱动直接配置的引脚）。
+    /// 结果可以用 `{}` 打印为表格，或用 [`GpioSnapshot::to_json`] 序列化。
+    pub fn snapshot() -> GpioSnapshot {
+        GpioSnapshot {
+            pins: (0..GPIO_NUM_MAX)
+                .filter_map(|pin| Self::pin_snapshot(pin).ok())
+                .collect(),
+        }
+    }
+
+    /// 读取一个引脚当前的配置
+    ///
+    /// # 返回
+    ///
+    /// 成功返回引脚快照；引脚不存在时返回 `InvalidGpio`
+    pub fn pin_snapshot(pin: u32) -> GpioResult<PinSnapshot> {
+        if !is_valid_gpio(pin) {
+            return Err(Error::new(GpioError::InvalidGpio).with_pin(pin));
         }
+        let mut snapshot = Backend::snapshot(pin as i32)?;
+        snapshot.owner = registry::owner(pin);
+        Ok(snapshot)
     }
 }
 
 // 可以在此处添加更多系统级的GPIO控制功能，如：
-// - 毛刺过滤器配置
-// - GPIO矩阵配置
 // - 电源管理相关的控制
-// - IO_MUX控制等
+// GPIO矩阵与IO_MUX的信号路由见 matrix 模块
@@ -0,0 +1,122 @@
+/**
+ * @file esp.rs
+ * @brief 专用GPIO硬件通道
+ * @details 通道由 ESP-IDF dedic_gpio 驱动分配（dedic_gpio_new_bundle），
+ *          读写直接使用 ESP32-S3 的专用GPIO指令（与 hal/dedic_gpio_cpu_ll.h 相同）:
+ *          - `ee.wr_mask_gpio_out` 按掩码写输出通道
+ *          - `ee.get_gpio_in` 读输入通道
+ *          - `rur.gpio_out` 读输出通道
+ *          指令只作用于当前CPU核心的通道，组的掩码和偏移在创建时读出，读写时不再调用驱动
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::arch::asm;
+use std::ptr;
+
+use esp_idf_svc::sys::{
+    dedic_gpio_bundle_config_t, dedic_gpio_bundle_handle_t, dedic_gpio_del_bundle,
+    dedic_gpio_get_in_mask, dedic_gpio_get_in_offset, dedic_gpio_get_out_mask,
+    dedic_gpio_get_out_offset, dedic_gpio_new_bundle,
+};
+
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::matrix::SIG_GPIO_OUT_IDX;
+use crate::drivers::gpio::types::{GpioError, GpioResult};
+use crate::error::Error;
+
+/// 专用GPIO组
+///
+/// 句柄是裸指针，因此该类型不能发送到其他线程。
+pub(super) struct DedicUnit {
+    handle: dedic_gpio_bundle_handle_t,
+    pins: Vec<i32>,
+    output: bool,
+    /// 组在当前核心上占用的输入通道掩码及起始通道
+    in_mask: u32,
+    in_offset: u32,
+    /// 组在当前核心上占用的输出通道掩码及起始通道
+    out_mask: u32,
+    out_offset: u32,
+}
+
+impl DedicUnit {
+    /// 分配通道并把引脚连接到专用GPIO信号
+    pub(super) fn new(pins: &[i32], output: bool) -> GpioResult<Self> {
+        let mut config = dedic_gpio_bundle_config_t {
+            gpio_array: pins.as_ptr(),
+            array_size: pins.len(),
+            ..Default::default()
+        };
+        config.flags.set_in_en(1);
+        config.flags.set_out_en(u32::from(output));
+
+        let mut handle: dedic_gpio_bundle_handle_t = ptr::null_mut();
+        let code = unsafe { dedic_gpio_new_bundle(&config, &mut handle) };
+        Error::check(code, GpioError::ConfigError, "dedic_gpio_new_bundle")?;
+
+        let mut unit = DedicUnit {
+            handle,
+            pins: pins.to_vec(),
+            output,
+            in_mask: 0,
+            in_offset: 0,
+            out_mask: 0,
+            out_offset: 0,
+        };
+        // 句柄有效时这些查询不会失败
+        unsafe {
+            dedic_gpio_get_in_mask(handle, &mut unit.in_mask);
+            dedic_gpio_get_in_offset(handle, &mut unit.in_offset);
+            if output {
+                dedic_gpio_get_out_mask(handle, &mut unit.out_mask);
+                dedic_gpio_get_out_offset(handle, &mut unit.out_offset);
+            }
+        }
+        // 通道保留上一次使用时的数值，统一从低电平开始
+        unit.write(u32::MAX, 0);
+        Ok(unit)
+    }
+
+    /// 按掩码写入输出通道
+    #[inline(always)]
+    pub(super) fn write(&self, mask: u32, value: u32) {
+        let mask = (mask << self.out_offset) & self.out_mask;
+        let value = value << self.out_offset;
+        unsafe {
+            asm!("ee.wr_mask_gpio_out {0}, {1}", in(reg) value, in(reg) mask, options(nostack));
+        }
+    }
+
+    /// 读取输入通道
+    #[inline(always)]
+    pub(super) fn read_in(&self) -> u32 {
+        let value: u32;
+        unsafe {
+            asm!("ee.get_gpio_in {0}", out(reg) value, options(nostack));
+        }
+        (value & self.in_mask) >> self.in_offset
+    }
+
+    /// 读取输出通道
+    #[inline(always)]
+    pub(super) fn read_out(&self) -> u32 {
+        let value: u32;
+        unsafe {
+            asm!("rur.gpio_out {0}", out(reg) value, options(nostack));
+        }
+        (value & self.out_mask) >> self.out_offset
+    }
+}
+
+impl Drop for DedicUnit {
+    fn drop(&mut self) {
+        unsafe { dedic_gpio_del_bundle(self.handle) };
+        // dedic_gpio_del_bundle 只归还通道，引脚仍连接在专用GPIO信号上
+        if self.output {
+            for &pin in &self.pins {
+                let _ = Backend::connect_out_signal(pin, SIG_GPIO_OUT_IDX, false);
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+/**
+ * @file mod.rs
+ * @brief 专用GPIO
+ * @details 把最多8个引脚组成一组，经 ESP32-S3 的专用GPIO通道由CPU指令直接读写:
+ *          - `write` / `set_bits` / `clear_bits` 是一条 `ee.wr_mask_gpio_out` 指令，组内引脚在同一周期变化
+ *          - `read` 是一条 `ee.get_gpio_in` 指令，所有引脚在同一时刻采样
+ *          - 不经过 gpio_set_level 的参数检查和寄存器读改写，适合软件实现时序严格的协议
+ *          - 主机上由GPIO模拟后端的矩阵信号模拟，基于它编写的协议代码可以在主机上测试
+ *          组内位号按引脚加入的顺序编号，第 i 个引脚对应 bit i
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+// 通道在ESP-IDF上由 dedic_gpio 驱动分配，在主机上由GPIO模拟后端的矩阵信号模拟
+#[cfg(target_os = "espidf")]
+mod esp;
+#[cfg(not(target_os = "espidf"))]
+mod sim;
+
+#[cfg(target_os = "espidf")]
+use esp::DedicUnit;
+#[cfg(not(target_os = "espidf"))]
+use sim::DedicUnit;
+
+use crate::drivers::gpio::pin::{
+    GpioPin, Input, OpenDrain, Output, OutputDrive, OutputMode, PinMode, PullMode,
+};
+use crate::drivers::gpio::types::GpioResult;
+
+/// 每个CPU核心的专用GPIO通道数，也是一组引脚的最大数量
+pub const DEDIC_GPIO_CHANNELS: usize = 8;
+
+/// 可以加入专用GPIO组的引脚模式
+///
+/// 输入引脚只能读取；输出引脚同时使能了输入缓冲，既可以写也可以读回引脚电平。
+/// 运行时模式 `Dynamic` 需要先转换为具体的模式。
+pub trait DedicatedMode: PinMode {
+    /// 是否使能输出通道
+    const OUTPUT: bool;
+}
+
+impl<P: PullMode> DedicatedMode for Input<P> {
+    const OUTPUT: bool = false;
+}
+impl<M: OutputDrive> DedicatedMode for Output<M> {
+    const OUTPUT: bool = true;
+}
+impl DedicatedMode for OpenDrain {
+    const OUTPUT: bool = true;
+}
+
+/// 专用GPIO组
+///
+/// 持有组内的所有引脚，释放时归还通道，输出引脚恢复由GPIO输出寄存器驱动。
+/// 专用GPIO通道属于创建它的CPU核心，因此该类型不能发送到其他线程，
+/// 使用它的任务应固定在一个核心上运行。
+pub struct DedicatedGpioBundle<MODE: DedicatedMode = Output> {
+    unit: DedicUnit,
+    pins: Vec<GpioPin<MODE>>,
+}
+
+impl<MODE: DedicatedMode> DedicatedGpioBundle<MODE> {
+    /// 由一组引脚创建专用GPIO组，输出引脚创建后为低电平
+    ///
+    /// # 参数
+    ///
+    /// * `pins` - 组内的引脚，第一个引脚对应 bit 0，最多 `DEDIC_GPIO_CHANNELS` 个
+    ///
+    /// # 返回
+    ///
+    /// 引脚数量为0或超过通道数时返回 `ESP_ERR_INVALID_ARG`，
+    /// 当前核心剩余的连续通道不足时返回 `ESP_ERR_NOT_FOUND`
+    pub fn new(pins: impl IntoIterator<Item = GpioPin<MODE>>) -> GpioResult<Self> {
+        let pins: Vec<_> = pins.into_iter().collect();
+        let numbers: Vec<i32> = pins.iter().map(GpioPin::get_pin_number).collect();
+        let unit = DedicUnit::new(&numbers, MODE::OUTPUT)?;
+        Ok(DedicatedGpioBundle { unit, pins })
+    }
+
+    /// 组内引脚数量
+    pub fn len(&self) -> usize {
+        self.pins.len()
+    }
+
+    /// 组内是否不含任何引脚
+    pub fn is_empty(&self) -> bool {
+        self.pins.is_empty()
+    }
+
+    /// 组内的引脚
+    pub fn pins(&self) -> &[GpioPin<MODE>] {
+        &self.pins
+    }
+
+    /// 释放通道，取回所有引脚
+    pub fn into_pins(self) -> Vec<GpioPin<MODE>> {
+        let DedicatedGpioBundle { unit, pins } = self;
+        drop(unit);
+        pins
+    }
+
+    /// 同时读取组内所有引脚的输入电平
+    #[inline(always)]
+    pub fn read(&self) -> u32 {
+        self.unit.read_in()
+    }
+}
+
+impl<MODE: DedicatedMode + OutputMode> DedicatedGpioBundle<MODE> {
+    /// 按掩码写入数值，掩码外的位保持不变
+    ///
+    /// # 参数
+    ///
+    /// * `mask` - 需要修改的位
+    /// * `value` - 新的数值，只有 `mask` 中的位有效
+    #[inline(always)]
+    pub fn write(&self, mask: u32, value: u32) {
+        self.unit.write(mask, value);
+    }
+
+    /// 将掩码中的位同时置1
+    #[inline(always)]
+    pub fn set_bits(&self, mask: u32) {
+        self.unit.write(mask, mask);
+    }
+
+    /// 将掩码中的位同时清0
+    #[inline(always)]
+    pub fn clear_bits(&self, mask: u32) {
+        self.unit.write(mask, 0);
+    }
+
+    /// 翻转掩码中的位（先读输出通道再写入，共两条指令）
+    #[inline(always)]
+    pub fn toggle(&self, mask: u32) {
+        self.unit.write(mask, !self.unit.read_out());
+    }
+
+    /// 读取输出通道当前的数值
+    #[inline(always)]
+    pub fn read_output(&self) -> u32 {
+        self.unit.read_out()
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use crate::drivers::gpio::backend::sim;
+    use crate::error::esp_codes::{ESP_ERR_INVALID_ARG, ESP_ERR_NOT_FOUND};
+
+    fn outputs(pins: &[u32]) -> Vec<GpioPin<Output>> {
+        pins.iter()
+            .map(|&pin| GpioPin::new(pin).unwrap().into_push_pull_output().unwrap())
+            .collect()
+    }
+
+    /// 按74HC595的时序移出一个字节（高位在前）
+    fn shift_out(bundle: &DedicatedGpioBundle, byte: u8) {
+        const DATA: u32 = 0b001;
+        const CLOCK: u32 = 0b010;
+        const LATCH: u32 = 0b100;
+        for bit in (0..8).rev() {
+            let data = if byte & (1 << bit) != 0 { DATA } else { 0 };
+            bundle.write(DATA | CLOCK, data);
+            bundle.set_bits(CLOCK);
+        }
+        bundle.clear_bits(CLOCK);
+        bundle.toggle(LATCH);
+        bundle.toggle(LATCH);
+    }
+
+    #[test]
+    fn test_bit_bang_shift_register() {
+        sim::reset();
+        let bundle = DedicatedGpioBundle::new(outputs(&[4, 5, 6])).unwrap();
+        assert_eq!((bundle.len(), bundle.read_output()), (3, 0));
+
+        sim::clear_trace();
+        shift_out(&bundle, 0xA5);
+
+        // 在时钟上升沿采样数据线，还原移出的字节
+        let mut data = 0;
+        let mut byte = 0u8;
+        for event in sim::trace() {
+            match (event.pin, event.level) {
+                (4, level) => data = level,
+                (5, 1) => byte = (byte << 1) | data as u8,
+                _ => {}
+            }
+        }
+        assert_eq!(byte, 0xA5);
+        assert_eq!(sim::output_history(6), vec![1, 0]);
+        assert_eq!(bundle.read(), bundle.read_output());
+
+        // 释放后引脚恢复由GPIO输出寄存器驱动
+        let pins = bundle.into_pins();
+        pins[2].set_high().unwrap();
+        assert_eq!(sim::pad_level(6), 1);
+    }
+
+    #[test]
+    fn test_channel_allocation_and_input() {
+        sim::reset();
+        let error = DedicatedGpioBundle::new(outputs(&[1, 2, 3, 4, 5, 6, 7, 8, 9]))
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), Some(ESP_ERR_INVALID_ARG));
+
+        let wide = DedicatedGpioBundle::new(outputs(&[1, 2, 3, 4, 5, 6])).unwrap();
+        let error = DedicatedGpioBundle::new(outputs(&[10, 11, 12]))
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), Some(ESP_ERR_NOT_FOUND));
+
+        let inputs: Vec<_> = [10, 11]
+            .iter()
+            .map(|&pin| GpioPin::new(pin).unwrap().into_pull_up_input().unwrap())
+            .collect();
+        let bundle = DedicatedGpioBundle::new(inputs).unwrap();
+        sim::drive(11, 0);
+        assert_eq!(bundle.read(), 0b01);
+        // 输入引脚不被专用GPIO驱动
+        assert_eq!(sim::pin_state(10).out_signal, None);
+
+        // 释放后通道可以重新分配
+        drop(wide);
+        assert!(DedicatedGpioBundle::new(outputs(&[12, 13, 14])).is_ok());
+    }
+}
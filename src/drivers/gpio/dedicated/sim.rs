@@ -0,0 +1,124 @@
+/**
+ * @file sim.rs
+ * @brief 主机模拟专用GPIO通道
+ * @details 按 dedic_gpio 驱动的规则模拟通道分配和读写:
+ *          - 8个通道，每组占用连续的通道，不足时返回 ESP_ERR_NOT_FOUND
+ *          - 引脚经GPIO矩阵连接到专用GPIO信号，写入时一次驱动组内所有信号
+ *          - 通道分配记录是线程局部的，与模拟芯片一致
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::cell::Cell;
+use std::marker::PhantomData;
+
+use super::DEDIC_GPIO_CHANNELS;
+use crate::drivers::gpio::backend::sim as gpio_sim;
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::matrix::signal::{PRO_ALONEGPIO_IN0_IDX, PRO_ALONEGPIO_OUT0_IDX};
+use crate::drivers::gpio::matrix::SIG_GPIO_OUT_IDX;
+use crate::drivers::gpio::types::{GpioError, GpioResult};
+use crate::error::esp_codes::{ESP_ERR_INVALID_ARG, ESP_ERR_NOT_FOUND};
+use crate::error::Error;
+
+thread_local! {
+    /// 已分配的通道掩码
+    static ALLOCATED: Cell<u32> = const { Cell::new(0) };
+}
+
+/// 模拟专用GPIO组
+pub(super) struct DedicUnit {
+    pins: Vec<i32>,
+    offset: u32,
+    output: bool,
+    /// 输出通道的数值
+    out: Cell<u32>,
+    /// 通道属于创建它的CPU核心
+    _core: PhantomData<*const ()>,
+}
+
+impl DedicUnit {
+    /// 分配通道并把引脚连接到专用GPIO信号
+    pub(super) fn new(pins: &[i32], output: bool) -> GpioResult<Self> {
+        if pins.is_empty() || pins.len() > DEDIC_GPIO_CHANNELS {
+            return Err(Error::esp(
+                GpioError::ConfigError,
+                ESP_ERR_INVALID_ARG,
+                "dedic_gpio_new_bundle",
+            ));
+        }
+        let mask = (1u32 << pins.len()) - 1;
+        let offset = ALLOCATED
+            .with(|allocated| {
+                let last = (DEDIC_GPIO_CHANNELS - pins.len()) as u32;
+                let offset = (0..=last).find(|&offset| allocated.get() & (mask << offset) == 0)?;
+                allocated.set(allocated.get() | (mask << offset));
+                Some(offset)
+            })
+            .ok_or_else(|| {
+                Error::esp(
+                    GpioError::ConfigError,
+                    ESP_ERR_NOT_FOUND,
+                    "dedic_gpio_new_bundle",
+                )
+            })?;
+
+        // 先创建再连接，连接失败时由 Drop 归还通道
+        let unit = DedicUnit {
+            pins: pins.to_vec(),
+            offset,
+            output,
+            out: Cell::new(0),
+            _core: PhantomData,
+        };
+        for (channel, &pin) in (offset..).zip(pins) {
+            Backend::connect_in_signal(pin, PRO_ALONEGPIO_IN0_IDX + channel, false)?;
+            if output {
+                gpio_sim::drive_signal(PRO_ALONEGPIO_OUT0_IDX + channel, 0);
+                Backend::connect_out_signal(pin, PRO_ALONEGPIO_OUT0_IDX + channel, false)?;
+            }
+        }
+        Ok(unit)
+    }
+
+    /// 组内的位掩码
+    fn mask(&self) -> u32 {
+        (1 << self.pins.len()) - 1
+    }
+
+    /// 按掩码写入输出通道
+    pub(super) fn write(&self, mask: u32, value: u32) {
+        let mask = mask & self.mask();
+        for bit in (0..self.pins.len() as u32).filter(|bit| mask & (1 << bit) != 0) {
+            gpio_sim::drive_signal(
+                PRO_ALONEGPIO_OUT0_IDX + self.offset + bit,
+                (value >> bit) & 1,
+            );
+        }
+        self.out.set((self.out.get() & !mask) | (value & mask));
+    }
+
+    /// 读取输入通道
+    pub(super) fn read_in(&self) -> u32 {
+        (0..self.pins.len() as u32).fold(0, |value, bit| {
+            value | (gpio_sim::input_signal(PRO_ALONEGPIO_IN0_IDX + self.offset + bit) << bit)
+        })
+    }
+
+    /// 读取输出通道
+    pub(super) fn read_out(&self) -> u32 {
+        self.out.get()
+    }
+}
+
+impl Drop for DedicUnit {
+    fn drop(&mut self) {
+        if self.output {
+            for &pin in &self.pins {
+                let _ = Backend::connect_out_signal(pin, SIG_GPIO_OUT_IDX, false);
+            }
+        }
+        let mask = self.mask() << self.offset;
+        ALLOCATED.with(|allocated| allocated.set(allocated.get() & !mask));
+    }
+}
@@ -0,0 +1,348 @@
+/**
+ * @file event.rs
+ * @brief GPIO 边沿事件队列
+ * @details 将GPIO中断转换为带时间戳的事件流:
+ *          - ISR中把引脚编号、新电平和 esp_timer 微秒时间戳写入无锁环形队列
+ *          - 消费者可以非阻塞读取、阻塞读取、带超时读取或 `.await`
+ *          - 异步等待基于 embassy-sync 的 AtomicWaker，超时基于 embassy-time
+ *          队列和事件流对事件类型是通用的，其他中断源（如脉冲计数器）也通过 `EventStream` 投递事件
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::cell::UnsafeCell;
+use std::future::{poll_fn, Future};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+
+use embassy_sync::waitqueue::AtomicWaker;
+
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::pin::{GpioPin, InputMode};
+use crate::drivers::gpio::types::{GpioError, GpioResult};
+use crate::error::Error;
+
+/// GPIO边沿事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GpioEdgeEvent {
+    /// GPIO编号
+    pub pin: i32,
+    /// 边沿之后的电平(0或1)
+    pub level: u32,
+    /// esp_timer 时间戳（微秒）
+    pub timestamp_us: u64,
+}
+
+impl GpioEdgeEvent {
+    /// 是否为上升沿
+    pub fn is_rising(&self) -> bool {
+        self.level == 1
+    }
+
+    /// 是否为下降沿
+    pub fn is_falling(&self) -> bool {
+        self.level == 0
+    }
+}
+
+/// 单生产者单消费者无锁环形队列
+///
+/// 生产者是ISR（同一中断服务内的处理程序串行执行），消费者是持有 `EventStream` 的任务。
+pub(crate) struct EventQueue<T, const N: usize> {
+    buffer: [UnsafeCell<T>; N],
+    /// 读计数，只由消费者写入
+    head: AtomicUsize,
+    /// 写计数，只由生产者写入
+    tail: AtomicUsize,
+    /// 队列满时丢弃的事件数
+    dropped: AtomicU32,
+    /// 等待事件的任务
+    waker: AtomicWaker,
+}
+
+// 读写槽位由head/tail的Acquire/Release顺序保护
+unsafe impl<T: Send, const N: usize> Sync for EventQueue<T, N> {}
+
+impl<T: Copy + Default, const N: usize> EventQueue<T, N> {
+    fn new() -> Self {
+        EventQueue {
+            buffer: std::array::from_fn(|_| UnsafeCell::new(T::default())),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicU32::new(0),
+            waker: AtomicWaker::new(),
+        }
+    }
+
+    /// 生产者写入事件，队列满时丢弃并计数
+    pub(crate) fn push(&self, event: T) {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) >= N {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        } else {
+            unsafe { *self.buffer[tail % N].get() = event };
+            self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        }
+        self.waker.wake();
+    }
+
+    /// 消费者取出事件
+    fn pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        let event = unsafe { *self.buffer[head % N].get() };
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(event)
+    }
+
+    fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(self.head.load(Ordering::Relaxed))
+    }
+}
+
+/// 事件流的一个事件源，释放时取消订阅
+struct EventSource {
+    /// 事件源编号（GPIO编号、计数单元等），用于取消订阅
+    id: i32,
+    _subscription: Box<dyn Send>,
+}
+
+/// 中断事件流
+///
+/// 每个事件源的中断都会记录为一个事件 `T`。
+/// 队列容量为 `N`，队列满时新事件被丢弃并计入 [`dropped`](Self::dropped)。
+/// 事件流被释放时自动取消所有订阅。
+pub struct EventStream<T, const N: usize = 32> {
+    queue: Arc<EventQueue<T, N>>,
+    sources: Vec<EventSource>,
+}
+
+/// GPIO边沿事件流
+///
+/// 通过 [`listen`](EventStream::listen) 订阅一个或多个引脚，之后每个中断都会记录为一个 [`GpioEdgeEvent`]。
+pub type GpioEventStream<const N: usize = 32> = EventStream<GpioEdgeEvent, N>;
+
+impl<T: Copy + Default + Send + 'static, const N: usize> EventStream<T, N> {
+    /// 创建一个空的事件流
+    pub fn new() -> Self {
+        EventStream {
+            queue: Arc::new(EventQueue::new()),
+            sources: Vec::new(),
+        }
+    }
+
+    /// 事件队列，供事件源的中断回调写入
+    pub(crate) fn queue(&self) -> Arc<EventQueue<T, N>> {
+        self.queue.clone()
+    }
+
+    /// 登记一个事件源，事件流释放或取消订阅时释放订阅句柄
+    pub(crate) fn add_source(&mut self, id: i32, subscription: impl Send + 'static) {
+        self.sources.push(EventSource {
+            id,
+            _subscription: Box::new(subscription),
+        });
+    }
+
+    /// 取消一个事件源的订阅，不存在时返回 false
+    pub(crate) fn remove_source(&mut self, id: i32) -> bool {
+        match self.sources.iter().position(|source| source.id == id) {
+            Some(index) => {
+                drop(self.sources.swap_remove(index));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 非阻塞读取下一个事件
+    pub fn try_next(&mut self) -> Option<T> {
+        self.queue.pop()
+    }
+
+    /// 异步等待下一个事件
+    pub async fn wait_for_event(&mut self) -> T {
+        let queue = &self.queue;
+        poll_fn(|cx| {
+            if let Some(event) = queue.pop() {
+                return Poll::Ready(event);
+            }
+            queue.waker.register(cx.waker());
+            // 注册之后再检查一次，避免错过注册前到达的事件
+            match queue.pop() {
+                Some(event) => Poll::Ready(event),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// 阻塞等待下一个事件
+    pub fn next_blocking(&mut self) -> T {
+        block_on(self.wait_for_event())
+    }
+
+    /// 阻塞等待下一个事件，超时返回 `None`
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<T> {
+        let timeout = embassy_time::Duration::from_micros(timeout.as_micros() as u64);
+        block_on(embassy_time::with_timeout(timeout, self.wait_for_event())).ok()
+    }
+
+    /// 队列中尚未读取的事件数
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// 队列是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 因队列满而丢弃的事件数
+    pub fn dropped(&self) -> u32 {
+        self.queue.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl<T: Copy + Default + Send + 'static, const N: usize> Default for EventStream<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> EventStream<GpioEdgeEvent, N> {
+    /// 开始记录引脚的边沿事件
+    ///
+    /// 触发条件由引脚的中断类型决定，通常使用 `GpioInterruptType::AnyEdge`。
+    pub fn listen<M: InputMode>(&mut self, pin: &GpioPin<M>) -> GpioResult<()> {
+        let gpio_num = pin.get_pin_number();
+        let queue = self.queue();
+        let subscription = pin.subscribe(move || {
+            queue.push(GpioEdgeEvent {
+                pin: gpio_num,
+                level: Backend::get_level(gpio_num),
+                timestamp_us: Backend::now_us(),
+            });
+        })?;
+        self.add_source(gpio_num, subscription);
+        Ok(())
+    }
+
+    /// 停止记录引脚的边沿事件，已入队的事件保留
+    pub fn unlisten<M>(&mut self, pin: &GpioPin<M>) -> GpioResult<()> {
+        let gpio_num = pin.get_pin_number();
+        if !self.remove_source(gpio_num) {
+            return Err(Error::new(GpioError::InvalidGpio).with_pin(gpio_num));
+        }
+        Ok(())
+    }
+
+    /// 异步等待下一个边沿事件
+    pub async fn wait_for_edge(&mut self) -> GpioEdgeEvent {
+        self.wait_for_event().await
+    }
+}
+
+/// 在当前任务中运行一个future直到完成
+#[cfg(target_os = "espidf")]
+fn block_on<F: Future>(future: F) -> F::Output {
+    esp_idf_svc::hal::task::block_on(future)
+}
+
+/// 在当前线程中运行一个future直到完成
+#[cfg(not(target_os = "espidf"))]
+fn block_on<F: Future>(future: F) -> F::Output {
+    use std::task::{Context, Wake, Waker};
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use crate::drivers::gpio::backend::sim::{self, Waveform};
+    use crate::drivers::gpio::{GpioInterruptType, GpioMode, GpioPullMode};
+
+    fn input(pin: u32) -> GpioPin {
+        let input = GpioPin::new(pin).unwrap();
+        input
+            .init(
+                GpioMode::Input,
+                GpioPullMode::PullUp,
+                GpioInterruptType::AnyEdge,
+            )
+            .unwrap();
+        input
+    }
+
+    #[test]
+    fn test_edges_are_timestamped() {
+        sim::reset();
+        let button = input(0);
+        let mut events: GpioEventStream = GpioEventStream::new();
+        events.listen(&button).unwrap();
+
+        sim::play(
+            0,
+            &Waveform::new()
+                .then(Duration::from_micros(100), 0)
+                .then(Duration::from_micros(250), 1),
+        );
+        sim::advance(Duration::from_millis(1));
+
+        assert_eq!(events.len(), 2);
+        let press = events.next_blocking();
+        assert!(press.is_falling());
+        assert_eq!((press.pin, press.timestamp_us), (0, 100));
+        let release = block_on(events.wait_for_edge());
+        assert!(release.is_rising());
+        assert_eq!(release.timestamp_us, 350);
+        assert!(events.try_next().is_none());
+    }
+
+    #[test]
+    fn test_timeout_and_overflow() {
+        sim::reset();
+        let pin = input(5);
+        let mut events: GpioEventStream<2> = GpioEventStream::new();
+        events.listen(&pin).unwrap();
+
+        assert_eq!(events.next_timeout(Duration::from_millis(5)), None);
+
+        for level in [0, 1, 0] {
+            sim::drive(5, level);
+        }
+        assert_eq!(events.len(), 2);
+        assert_eq!(events.dropped(), 1);
+        assert!(events.next_timeout(Duration::from_millis(5)).is_some());
+
+        events.unlisten(&pin).unwrap();
+        assert!(!sim::isr_service_installed());
+        sim::drive(5, 1);
+        assert_eq!(events.len(), 1);
+    }
+}
@@ -0,0 +1,253 @@
+/**
+ * @file hal.rs
+ * @brief GpioPin 的 embedded-hal 1.0 接口实现
+ * @details 让 crates.io 上基于 embedded-hal 的传感器、显示和编解码驱动可以直接使用本板引脚:
+ *          - `embedded_hal::digital::{InputPin, OutputPin, StatefulOutputPin}`
+ *          - `embedded_hal_async::digital::Wait`，基于GPIO中断订阅实现
+ *          引脚模式的限制与类型状态一致：只有输入类模式实现 InputPin/Wait，只有输出类模式实现 OutputPin
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::future::poll_fn;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+
+use embassy_sync::waitqueue::AtomicWaker;
+use embedded_hal::digital::{self, ErrorKind, ErrorType, InputPin, OutputPin, StatefulOutputPin};
+use embedded_hal_async::digital::Wait;
+
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::interrupt;
+use crate::drivers::gpio::pin::{GpioPin, InputMode, OutputMode, PinMode};
+use crate::drivers::gpio::types::{GpioError, GpioInterruptType, GpioResult};
+use crate::error::Error;
+
+impl digital::Error for Error {
+    /// embedded-hal 1.0 的数字IO错误只有 `Other` 一类，GPIO错误都归入此类，
+    /// 具体原因仍可通过 `Error::gpio` 取得
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl<MODE: PinMode> ErrorType for GpioPin<MODE> {
+    type Error = Error;
+}
+
+impl<MODE: InputMode> InputPin for GpioPin<MODE> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(GpioPin::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(GpioPin::is_low(self))
+    }
+}
+
+impl<MODE: OutputMode> OutputPin for GpioPin<MODE> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        GpioPin::set_low(self)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        GpioPin::set_high(self)
+    }
+}
+
+impl<MODE: OutputMode> StatefulOutputPin for GpioPin<MODE> {
+    /// 读回引脚电平，类型化的输出引脚同时使能了输入缓冲
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(Backend::get_level(self.get_pin_number()) == 1)
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(Backend::get_level(self.get_pin_number()) == 0)
+    }
+
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        GpioPin::toggle(self)
+    }
+}
+
+/// 等待一次中断时ISR与任务共享的状态
+struct WaitSignal {
+    fired: AtomicBool,
+    waker: AtomicWaker,
+}
+
+/// 等待期间临时修改的中断类型，释放时恢复原来的类型
+struct InterruptTypeGuard {
+    gpio_num: i32,
+    previous: GpioInterruptType,
+}
+
+impl Drop for InterruptTypeGuard {
+    fn drop(&mut self) {
+        let _ = Backend::set_interrupt_type(self.gpio_num, self.previous);
+    }
+}
+
+impl<MODE: InputMode> GpioPin<MODE> {
+    /// 按指定中断类型等待一次中断
+    ///
+    /// 中断触发后立即在ISR中禁用，避免电平中断持续触发；返回时订阅被取消。
+    /// 电平类型在订阅后若已处于目标电平则直接返回。
+    ///
+    /// 等待期间临时使用一个订阅并修改中断类型，完成或future被释放时恢复原来的中断类型。
+    /// 引脚已有订阅（`subscribe` 或事件流）时返回 `GpioError::InterruptError`，原订阅不受影响。
+    async fn wait_for_interrupt(&self, intr_type: GpioInterruptType) -> GpioResult<()> {
+        let gpio_num = self.get_pin_number();
+        if interrupt::is_subscribed(gpio_num) {
+            return Err(Error::new(GpioError::InterruptError)
+                .with_operation("wait_for_interrupt")
+                .with_pin(gpio_num));
+        }
+        let signal = Arc::new(WaitSignal {
+            fired: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        });
+
+        // 先于订阅声明，订阅取消后才恢复中断类型
+        let _restore = InterruptTypeGuard {
+            gpio_num,
+            previous: Backend::snapshot(gpio_num)?.intr_type,
+        };
+        self.set_interrupt_type(intr_type)?;
+        let isr_signal = signal.clone();
+        let _subscription = self.subscribe(move || {
+            let _ = Backend::set_interrupt_enabled(gpio_num, false);
+            isr_signal.fired.store(true, Ordering::Release);
+            isr_signal.waker.wake();
+        })?;
+
+        let already = match intr_type {
+            GpioInterruptType::HighLevel => self.is_high(),
+            GpioInterruptType::LowLevel => self.is_low(),
+            _ => false,
+        };
+        if already {
+            return Ok(());
+        }
+
+        poll_fn(|cx| {
+            signal.waker.register(cx.waker());
+            if signal.fired.load(Ordering::Acquire) {
+                Poll::Ready(Ok(()))
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl<MODE: InputMode> Wait for GpioPin<MODE> {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_interrupt(GpioInterruptType::HighLevel).await
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_interrupt(GpioInterruptType::LowLevel).await
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_interrupt(GpioInterruptType::RisingEdge).await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_interrupt(GpioInterruptType::FallingEdge)
+            .await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_interrupt(GpioInterruptType::AnyEdge).await
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use crate::drivers::gpio::backend::sim;
+    use std::future::Future;
+    use std::task::{Context, Wake, Waker};
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// 手动轮询一次future
+    fn poll_once<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        std::pin::Pin::new(future).poll(&mut Context::from_waker(&waker))
+    }
+
+    #[test]
+    fn test_digital_traits() {
+        sim::reset();
+        let mut led = GpioPin::new(6).unwrap().into_push_pull_output().unwrap();
+        OutputPin::set_high(&mut led).unwrap();
+        assert!(led.is_set_high().unwrap());
+        StatefulOutputPin::toggle(&mut led).unwrap();
+        assert!(led.is_set_low().unwrap());
+
+        let mut button = GpioPin::new(7).unwrap().into_pull_up_input().unwrap();
+        assert!(InputPin::is_high(&mut button).unwrap());
+        sim::drive(7, 0);
+        assert!(InputPin::is_low(&mut button).unwrap());
+    }
+
+    #[test]
+    fn test_wait_for_edge() {
+        sim::reset();
+        let mut button = GpioPin::new(9).unwrap().into_pull_up_input().unwrap();
+
+        let mut falling = Box::pin(button.wait_for_falling_edge());
+        assert!(poll_once(&mut falling).is_pending());
+        assert!(sim::has_isr_handler(9));
+        sim::drive(9, 0);
+        assert!(matches!(poll_once(&mut falling), Poll::Ready(Ok(()))));
+        drop(falling);
+        assert!(!sim::isr_service_installed());
+
+        // 已经处于低电平时立即返回
+        let mut low = Box::pin(button.wait_for_low());
+        assert!(matches!(poll_once(&mut low), Poll::Ready(Ok(()))));
+    }
+
+    #[test]
+    fn test_wait_restores_interrupt_type() {
+        sim::reset();
+        let mut button = GpioPin::new(10).unwrap().into_pull_up_input().unwrap();
+        button
+            .set_interrupt_type(GpioInterruptType::AnyEdge)
+            .unwrap();
+
+        // 未完成就被释放的等待也恢复原来的中断类型
+        let mut rising = Box::pin(button.wait_for_rising_edge());
+        assert!(poll_once(&mut rising).is_pending());
+        assert_eq!(
+            Backend::snapshot(10).unwrap().intr_type,
+            GpioInterruptType::RisingEdge
+        );
+        drop(rising);
+        assert_eq!(
+            Backend::snapshot(10).unwrap().intr_type,
+            GpioInterruptType::AnyEdge
+        );
+
+        // 已有订阅时不能等待，订阅的中断类型保持不变
+        let subscription = button.subscribe(|| {}).unwrap();
+        let mut falling = Box::pin(button.wait_for_falling_edge());
+        assert!(matches!(poll_once(&mut falling), Poll::Ready(Err(_))));
+        drop(falling);
+        assert_eq!(
+            Backend::snapshot(10).unwrap().intr_type,
+            GpioInterruptType::AnyEdge
+        );
+        drop(subscription);
+    }
+}
@@ -1,18 +1,25 @@
-use esp_idf_sys::{
-    gpio_install_isr_service, gpio_isr_handler_add, gpio_isr_handler_remove, gpio_num_t,
-    gpio_uninstall_isr_service, ESP_OK,
-};
 /**
  * @file interrupt.rs
  * @brief ESP32 GPIO 中断处理
- * @details 提供了 GPIO 中断相关的配置和处理功能
+ * @details 提供了 GPIO 中断相关的配置和处理功能:
+ *          - 底层ISR服务安装和处理程序注册
+ *          - 基于闭包的安全订阅，订阅句柄释放时自动注销并按引用计数卸载服务
  * @author xwx
  * @date 2025-05-13
  * @version 1.0
  */
 use std::ffi::c_void;
 
-use crate::drivers::gpio::gpio_handler::{GpioError, GpioResult};
+#[cfg(target_os = "espidf")]
+use esp_idf_sys::ESP_ERR_INVALID_STATE;
+
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::pin::{GpioPin, InputMode};
+use crate::drivers::gpio::registry::PinClaim;
+use crate::drivers::gpio::types::{GpioError, GpioResult};
+#[cfg(not(target_os = "espidf"))]
+use crate::error::esp_codes::ESP_ERR_INVALID_STATE;
+use crate::error::Error;
 
 /// GPIO中断处理器
 pub struct GpioInterrupt;
@@ -24,19 +31,12 @@ impl GpioInterrupt {
     ///
     /// * `intr_alloc_flags` - 中断分配标志
     pub fn install_service(intr_alloc_flags: i32) -> GpioResult<()> {
-        unsafe {
-            if gpio_install_isr_service(intr_alloc_flags) != ESP_OK {
-                return Err(GpioError::InterruptError);
-            }
-        }
-        Ok(())
+        Backend::install_isr_service(intr_alloc_flags)
     }
 
     /// 卸载GPIO中断服务
     pub fn uninstall_service() {
-        unsafe {
-            gpio_uninstall_isr_service();
-        }
+        Backend::uninstall_isr_service();
     }
 
     /// 为指定的GPIO添加ISR处理程序
@@ -51,12 +51,7 @@ impl GpioInterrupt {
         isr_handler: Option<unsafe extern "C" fn(arg: *mut c_void)>,
         args: *mut c_void,
     ) -> GpioResult<()> {
-        unsafe {
-            if gpio_isr_handler_add(gpio_num as gpio_num_t, isr_handler, args) != ESP_OK {
-                return Err(GpioError::InterruptError);
-            }
-        }
-        Ok(())
+        Backend::isr_handler_add(gpio_num as i32, isr_handler, args)
     }
 
     /// 移除指定GPIO的ISR处理程序
@@ -65,12 +60,7 @@ impl GpioInterrupt {
     ///
     /// * `gpio_num` - GPIO编号
     pub fn remove_handler(gpio_num: u32) -> GpioResult<()> {
-        unsafe {
-            if gpio_isr_handler_remove(gpio_num as gpio_num_t) != ESP_OK {
-                return Err(GpioError::InterruptError);
-            }
-        }
-        Ok(())
+        Backend::isr_handler_remove(gpio_num as i32)
     }
 }
 
@@ -78,6 +68,8 @@ impl GpioInterrupt {
 pub type GpioIsr = Option<unsafe extern "C" fn(arg: *mut c_void)>;
 
 /// 中断参数包装器，用于在中断处理中保存和传递数据
+///
+/// 包装器持有数据的所有权，`as_ptr` 返回的指针在包装器被释放前一直有效。
 pub struct InterruptArg<T> {
     data: Box<T>,
 }
@@ -92,16 +84,274 @@ impl<T> InterruptArg<T> {
 
     /// 获取包装器内部数据的裸指针，用于传递给中断处理函数
     pub fn as_ptr(&self) -> *mut c_void {
-        Box::into_raw(Box::new(&*self.data)) as *mut c_void
+        &*self.data as *const T as *mut c_void
     }
 
     /// 从裸指针中恢复数据引用
     ///
-    /// # 安全性
+    /// # Safety
     ///
-    /// 此函数不安全，因为它需要确保指针是有效的并且来自于`as_ptr`方法
+    /// 指针必须来自同一类型`InterruptArg<T>`的`as_ptr`方法，并且该包装器在引用使用期间未被释放
     pub unsafe fn from_ptr<'a>(ptr: *mut c_void) -> &'a T {
-        let boxed = Box::from_raw(ptr as *mut &T);
-        &**boxed
+        &*(ptr as *const T)
+    }
+}
+
+/// 订阅回调类型
+type IsrCallback = Box<dyn FnMut() + Send + 'static>;
+
+/// 中断服务的引用计数状态
+#[derive(Default)]
+struct IsrServiceState {
+    /// 当前存活的订阅数量
+    users: usize,
+    /// 中断服务是否由订阅机制安装（手动安装的服务不会被自动卸载）
+    owned: bool,
+    /// 已被订阅的引脚位图
+    pins: u64,
+}
+
+/// 访问中断服务状态
+///
+/// 模拟后端的芯片是线程局部的，因此主机上的引用计数也按线程保存。
+#[cfg(target_os = "espidf")]
+fn with_service_state<R>(f: impl FnOnce(&mut IsrServiceState) -> R) -> R {
+    use std::sync::Mutex;
+
+    static STATE: Mutex<IsrServiceState> = Mutex::new(IsrServiceState {
+        users: 0,
+        owned: false,
+        pins: 0,
+    });
+    let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut state)
+}
+
+/// 访问中断服务状态
+///
+/// 模拟后端的芯片是线程局部的，因此主机上的引用计数也按线程保存。
+#[cfg(not(target_os = "espidf"))]
+fn with_service_state<R>(f: impl FnOnce(&mut IsrServiceState) -> R) -> R {
+    use std::cell::RefCell;
+
+    thread_local! {
+        static STATE: RefCell<IsrServiceState> = RefCell::new(IsrServiceState::default());
+    }
+    STATE.with(|state| f(&mut state.borrow_mut()))
+}
+
+/// 登记一个订阅，必要时安装中断服务
+fn acquire_service(gpio_num: i32) -> GpioResult<()> {
+    with_service_state(|state| {
+        let bit = 1u64 << gpio_num;
+        if state.pins & bit != 0 {
+            return Err(Error::new(GpioError::InterruptError)
+                .with_operation("subscribe")
+                .with_pin(gpio_num));
+        }
+        if state.users == 0 {
+            // 服务已被手动安装时ESP-IDF返回ESP_ERR_INVALID_STATE，此时沿用已有服务
+            state.owned = match Backend::install_isr_service(0) {
+                Ok(()) => true,
+                Err(e) if e.code() == Some(ESP_ERR_INVALID_STATE) => false,
+                Err(e) => return Err(e.with_pin(gpio_num)),
+            };
+        }
+        state.users += 1;
+        state.pins |= bit;
+        Ok(())
+    })
+}
+
+/// 注销一个订阅，最后一个订阅释放时卸载自动安装的中断服务
+fn release_service(gpio_num: i32) {
+    with_service_state(|state| {
+        state.pins &= !(1u64 << gpio_num);
+        state.users -= 1;
+        if state.users == 0 && state.owned {
+            Backend::uninstall_isr_service();
+            state.owned = false;
+        }
+    })
+}
+
+/// 引脚当前是否已被订阅
+pub(crate) fn is_subscribed(gpio_num: i32) -> bool {
+    (0..64).contains(&gpio_num) && with_service_state(|state| state.pins & (1u64 << gpio_num) != 0)
+}
+
+/// 所有订阅共用的ISR入口，参数为订阅持有的回调
+unsafe extern "C" fn isr_trampoline(arg: *mut c_void) {
+    let callback = &mut *(arg as *mut IsrCallback);
+    callback();
+}
+
+/// GPIO中断订阅
+///
+/// 由 [`GpioPin::subscribe`] 返回，释放时自动禁用中断、移除ISR处理程序并释放回调。
+/// 最后一个订阅释放后，自动安装的中断服务也会被卸载。
+///
+/// 订阅持有引脚的占用凭据，引脚先于订阅释放时，引脚在订阅释放前仍保持占用。
+#[must_use = "订阅在被释放时会立即取消"]
+pub struct GpioSubscription {
+    gpio_num: i32,
+    callback: *mut IsrCallback,
+    /// 在ISR移除后才释放（字段在 `drop` 之后析构）
+    _claim: PinClaim,
+}
+
+// 回调本身要求Send，裸指针只在注册/注销时访问
+unsafe impl Send for GpioSubscription {}
+
+impl GpioSubscription {
+    /// 获取订阅的GPIO编号
+    pub fn get_pin_number(&self) -> i32 {
+        self.gpio_num
+    }
+}
+
+impl Drop for GpioSubscription {
+    fn drop(&mut self) {
+        let _ = Backend::set_interrupt_enabled(self.gpio_num, false);
+        let _ = Backend::isr_handler_remove(self.gpio_num);
+        // ISR已经移除，回调不会再被调用
+        drop(unsafe { Box::from_raw(self.callback) });
+        release_service(self.gpio_num);
+    }
+}
+
+impl<MODE: InputMode> GpioPin<MODE> {
+    /// 订阅该引脚的中断
+    ///
+    /// 中断类型由 `init` 或 `set_interrupt_type` 决定。首次订阅时自动安装GPIO中断服务，
+    /// 注册回调后使能该引脚的中断。每个引脚同一时间只能有一个订阅。
+    ///
+    /// 回调运行在中断上下文中，应只做标志位、原子计数或队列投递等轻量操作。
+    ///
+    /// # 参数
+    ///
+    /// * `callback` - 中断回调
+    ///
+    /// # 返回
+    ///
+    /// 成功返回订阅句柄，句柄释放时自动取消订阅
+    pub fn subscribe<F>(&self, callback: F) -> GpioResult<GpioSubscription>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        let gpio_num = self.get_pin_number();
+        if !(0..64).contains(&gpio_num) {
+            return Err(Error::new(GpioError::InvalidGpio).with_pin(gpio_num));
+        }
+        acquire_service(gpio_num)?;
+
+        let callback: *mut IsrCallback = Box::into_raw(Box::new(Box::new(callback)));
+        let registered =
+            Backend::isr_handler_add(gpio_num, Some(isr_trampoline), callback as *mut c_void)
+                .and_then(|_| Backend::set_interrupt_enabled(gpio_num, true));
+        if let Err(e) = registered {
+            let _ = Backend::isr_handler_remove(gpio_num);
+            drop(unsafe { Box::from_raw(callback) });
+            release_service(gpio_num);
+            return Err(e);
+        }
+
+        Ok(GpioSubscription {
+            gpio_num,
+            callback,
+            _claim: self.share_claim(),
+        })
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use crate::drivers::gpio::backend::sim;
+    use crate::drivers::gpio::{GpioInterruptType, GpioMode, GpioPullMode};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    fn button(pin: u32) -> GpioPin {
+        let button = GpioPin::new(pin).unwrap();
+        button
+            .init(
+                GpioMode::Input,
+                GpioPullMode::PullUp,
+                GpioInterruptType::FallingEdge,
+            )
+            .unwrap();
+        button
+    }
+
+    #[test]
+    fn test_subscribe_and_drop() {
+        sim::reset();
+        let button = button(0);
+        let count = Arc::new(AtomicU32::new(0));
+
+        let counter = count.clone();
+        let subscription = button
+            .subscribe(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+        assert!(sim::isr_service_installed());
+
+        sim::drive(0, 0);
+        sim::drive(0, 1);
+        sim::drive(0, 0);
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        drop(subscription);
+        assert!(!sim::has_isr_handler(0));
+        assert!(!sim::isr_service_installed());
+        // 回调已释放，只剩测试持有的引用
+        assert_eq!(Arc::strong_count(&count), 1);
+
+        sim::drive(0, 1);
+        sim::drive(0, 0);
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_service_reference_counting() {
+        sim::reset();
+        let pin = button(1);
+        let a = pin.subscribe(|| {}).unwrap();
+        let b = button(2).subscribe(|| {}).unwrap();
+
+        // 同一引脚不能重复订阅
+        assert!(pin.subscribe(|| {}).is_err());
+
+        drop(a);
+        assert!(sim::isr_service_installed());
+        drop(b);
+        assert!(!sim::isr_service_installed());
+    }
+
+    #[test]
+    fn test_manually_installed_service_is_kept() {
+        sim::reset();
+        GpioInterrupt::install_service(0).unwrap();
+        let subscription = button(3).subscribe(|| {}).unwrap();
+        drop(subscription);
+        assert!(sim::isr_service_installed());
+        GpioInterrupt::uninstall_service();
+    }
+
+    #[test]
+    fn test_subscription_keeps_pin_claimed() {
+        sim::reset();
+        let pin = button(4);
+        let subscription = pin.subscribe(|| {}).unwrap();
+        // 引脚先释放时，订阅仍持有占用凭据，ISR仍然有效
+        drop(pin);
+        assert!(GpioPin::new(4).is_err());
+        assert!(sim::has_isr_handler(4));
+
+        drop(subscription);
+        assert!(!sim::has_isr_handler(4));
+        assert!(GpioPin::new(4).is_ok());
     }
 }
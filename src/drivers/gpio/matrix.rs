@@ -0,0 +1,199 @@
+/**
+ * @file matrix.rs
+ * @brief GPIO 矩阵信号路由
+ * @details ESP32-S3 的外设信号经 GPIO 矩阵可以连接到几乎任意引脚:
+ *          - 外设输出信号连接到引脚，可选反相；一个输出信号可以同时驱动多个引脚
+ *          - 引脚连接到外设输入信号，可选反相；也可以把输入信号接到固定电平
+ *          - 内部回环：输出信号驱动引脚的同时，再从该引脚送回一个输入信号
+ *          - 高速引脚可以绕过矩阵，经 IO_MUX 与外设直连
+ *          典型用法：把 SPI 时钟复制到测试引脚上用示波器观察，或把片选反相后驱动高电平有效的器件
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::pin::{GpioPin, InputMode, OutputMode};
+use crate::drivers::gpio::types::GpioResult;
+
+/// 特殊输出信号：由GPIO输出寄存器驱动引脚
+pub const SIG_GPIO_OUT_IDX: u32 = 256;
+/// IO_MUX功能号：经GPIO矩阵（ESP32-S3 所有引脚均为功能1）
+pub const PIN_FUNC_GPIO: u32 = 1;
+/// 特殊输入来源：固定高电平
+pub const GPIO_MATRIX_CONST_ONE_INPUT: u32 = 0x38;
+/// 特殊输入来源：固定低电平
+pub const GPIO_MATRIX_CONST_ZERO_INPUT: u32 = 0x3C;
+
+/// ESP32-S3 常用外设信号编号（soc/gpio_sig_map.h），完整列表见技术参考手册
+pub mod signal {
+    /// SPI2（FSPI）时钟输入
+    pub const FSPICLK_IN_IDX: u32 = 101;
+    /// SPI2（FSPI）时钟输出
+    pub const FSPICLK_OUT_IDX: u32 = 101;
+    /// SPI2（FSPI）MISO 输入
+    pub const FSPIQ_IN_IDX: u32 = 102;
+    /// SPI2（FSPI）MISO 输出
+    pub const FSPIQ_OUT_IDX: u32 = 102;
+    /// SPI2（FSPI）MOSI 输入
+    pub const FSPID_IN_IDX: u32 = 103;
+    /// SPI2（FSPI）MOSI 输出
+    pub const FSPID_OUT_IDX: u32 = 103;
+    /// SPI2（FSPI）片选0 输出
+    pub const FSPICS0_OUT_IDX: u32 = 110;
+    /// SPI2 在 IO_MUX 上的功能号（GPIO9~14）
+    pub const SPI2_FUNC_NUM: u32 = 4;
+    /// CPU0 专用GPIO输入通道0，通道n为 `PRO_ALONEGPIO_IN0_IDX + n`
+    pub const PRO_ALONEGPIO_IN0_IDX: u32 = 235;
+    /// CPU0 专用GPIO输出通道0，通道n为 `PRO_ALONEGPIO_OUT0_IDX + n`
+    pub const PRO_ALONEGPIO_OUT0_IDX: u32 = 235;
+}
+
+/// GPIO矩阵
+pub struct GpioMatrix;
+
+impl GpioMatrix {
+    /// 将外设输出信号连接到引脚
+    ///
+    /// 同一个输出信号可以连接到多个引脚，原来的连接不受影响。
+    ///
+    /// # 参数
+    ///
+    /// * `pin` - 输出引脚
+    /// * `signal` - 外设输出信号编号
+    /// * `invert` - 是否反相
+    pub fn connect_output<MODE: OutputMode>(
+        pin: &GpioPin<MODE>,
+        signal: u32,
+        invert: bool,
+    ) -> GpioResult<()> {
+        Backend::connect_out_signal(pin.get_pin_number(), signal, invert)
+    }
+
+    /// 断开引脚上的外设输出信号，恢复由 `set_level` 等方法控制
+    pub fn disconnect_output<MODE: OutputMode>(pin: &GpioPin<MODE>) -> GpioResult<()> {
+        Backend::connect_out_signal(pin.get_pin_number(), SIG_GPIO_OUT_IDX, false)
+    }
+
+    /// 将引脚连接到外设输入信号
+    ///
+    /// 一个输入信号只能有一个来源，新的连接会替换原来的连接。
+    ///
+    /// # 参数
+    ///
+    /// * `pin` - 输入引脚
+    /// * `signal` - 外设输入信号编号
+    /// * `invert` - 是否反相
+    pub fn connect_input<MODE: InputMode>(
+        pin: &GpioPin<MODE>,
+        signal: u32,
+        invert: bool,
+    ) -> GpioResult<()> {
+        Backend::connect_in_signal(pin.get_pin_number(), signal, invert)
+    }
+
+    /// 断开外设输入信号的引脚，改接到固定电平
+    ///
+    /// # 参数
+    ///
+    /// * `signal` - 外设输入信号编号
+    /// * `level` - 外设读到的固定电平(0或1)
+    pub fn disconnect_input(signal: u32, level: u32) {
+        Backend::connect_in_constant(signal, level);
+    }
+
+    /// 内部回环：输出信号驱动引脚，同时从该引脚送回输入信号
+    ///
+    /// 类型化的输出引脚使能了输入缓冲，因此不需要外部跳线，
+    /// 例如把 SPI 的 MOSI 回环到 MISO 做自检。
+    ///
+    /// # 参数
+    ///
+    /// * `pin` - 回环使用的引脚
+    /// * `out_signal` - 驱动引脚的外设输出信号
+    /// * `in_signal` - 接收引脚电平的外设输入信号
+    pub fn loopback<MODE: OutputMode>(
+        pin: &GpioPin<MODE>,
+        out_signal: u32,
+        in_signal: u32,
+    ) -> GpioResult<()> {
+        Self::connect_output(pin, out_signal, false)?;
+        Backend::connect_in_signal(pin.get_pin_number(), in_signal, false)
+    }
+
+    /// 引脚经IO_MUX直连外设输出，绕过GPIO矩阵
+    ///
+    /// 只有信号的专用引脚可以直连（见技术参考手册 IO_MUX 功能表），
+    /// 直连时不能反相，但可以运行在更高的频率。
+    ///
+    /// # 参数
+    ///
+    /// * `pin` - 输出引脚
+    /// * `func` - 该引脚上外设功能的功能号
+    pub fn iomux_output<MODE: OutputMode>(pin: &GpioPin<MODE>, func: u32) -> GpioResult<()> {
+        Backend::iomux_out(pin.get_pin_number(), func)
+    }
+
+    /// 外设输入信号经IO_MUX直接取自引脚，绕过GPIO矩阵
+    ///
+    /// # 参数
+    ///
+    /// * `pin` - 输入引脚，必须是该信号的专用引脚
+    /// * `signal` - 外设输入信号编号
+    pub fn iomux_input<MODE: InputMode>(pin: &GpioPin<MODE>, signal: u32) -> GpioResult<()> {
+        Backend::iomux_in(pin.get_pin_number(), signal)
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::signal::*;
+    use super::*;
+    use crate::drivers::gpio::backend::sim;
+
+    #[test]
+    fn test_mirror_and_invert() {
+        sim::reset();
+        let sclk = GpioPin::new(12).unwrap().into_push_pull_output().unwrap();
+        let probe = GpioPin::new(21).unwrap().into_push_pull_output().unwrap();
+        let cs = GpioPin::new(10).unwrap().into_push_pull_output().unwrap();
+
+        GpioMatrix::connect_output(&sclk, FSPICLK_OUT_IDX, false).unwrap();
+        GpioMatrix::connect_output(&probe, FSPICLK_OUT_IDX, false).unwrap();
+        GpioMatrix::connect_output(&cs, FSPICS0_OUT_IDX, true).unwrap();
+        assert_eq!(sim::pad_level(10), 1);
+
+        sim::drive_signal(FSPICLK_OUT_IDX, 1);
+        sim::drive_signal(FSPICS0_OUT_IDX, 1);
+        assert_eq!(sim::pad_level(12), 1);
+        assert_eq!(sim::pad_level(21), 1);
+        assert_eq!(sim::pad_level(10), 0);
+        assert_eq!(sim::output_history(21), vec![1]);
+
+        // 断开后恢复由输出寄存器控制
+        GpioMatrix::disconnect_output(&probe).unwrap();
+        assert_eq!(sim::pad_level(21), 0);
+        probe.set_high().unwrap();
+        sim::drive_signal(FSPICLK_OUT_IDX, 0);
+        assert_eq!(sim::pad_level(21), 1);
+    }
+
+    #[test]
+    fn test_input_and_loopback() {
+        sim::reset();
+        let miso = GpioPin::new(13).unwrap().into_floating_input().unwrap();
+        GpioMatrix::connect_input(&miso, FSPIQ_IN_IDX, true).unwrap();
+        sim::drive(13, 0);
+        assert_eq!(sim::input_signal(FSPIQ_IN_IDX), 1);
+
+        GpioMatrix::disconnect_input(FSPIQ_IN_IDX, 0);
+        assert_eq!(sim::input_signal(FSPIQ_IN_IDX), 0);
+
+        // MOSI 在引脚内部回环到 MISO
+        let mosi = GpioPin::new(11).unwrap().into_push_pull_output().unwrap();
+        GpioMatrix::loopback(&mosi, FSPID_OUT_IDX, FSPIQ_IN_IDX).unwrap();
+        sim::drive_signal(FSPID_OUT_IDX, 1);
+        assert_eq!(sim::input_signal(FSPIQ_IN_IDX), 1);
+        sim::drive_signal(FSPID_OUT_IDX, 0);
+        assert_eq!(sim::input_signal(FSPIQ_IN_IDX), 0);
+    }
+}
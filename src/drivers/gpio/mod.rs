@@ -1,3 +1,4 @@
+pub mod backend; // GPIO硬件后端（ESP-IDF / 主机模拟）
 pub mod control; // GPIO系统控制功能
 /**
  * @file mod.rs
@@ -9,19 +10,34 @@ pub mod control; // GPIO系统控制功能
  */
 // GPIO模块按功能拆分为多个子模块
 // 保留旧模块用于兼容性（可以在迁移完成后移除）
+pub mod dedicated; // 专用GPIO（CPU指令直接读写）
+pub mod event; // GPIO边沿事件队列
 pub mod gpio_handler;
+mod hal; // embedded-hal 1.0 接口实现
 pub mod interrupt; // GPIO中断处理
+pub mod matrix; // GPIO矩阵信号路由
 pub mod pin; // GPIO引脚基本操作
+pub mod port; // 多引脚原子读写端口
+pub mod registry; // GPIO引脚所有权登记
+pub mod snapshot; // GPIO配置快照
 pub mod types;
 
 // 重新导出常用的类型和结构体，使它们可以直接从gpio模块访问
-pub use gpio_handler::{
-    GpioDriveCap, GpioError, GpioInterruptType, GpioMode, GpioPullMode, GpioResult,
+pub use types::{
+    GlitchFilter, GpioDriveCap, GpioError, GpioInterruptType, GpioMode, GpioPullMode, GpioResult,
 };
 
 pub use control::GpioControl;
-pub use interrupt::{GpioInterrupt, GpioIsr, InterruptArg};
-pub use pin::GpioPin;
+pub use dedicated::{DedicatedGpioBundle, DedicatedMode};
+pub use event::{EventStream, GpioEdgeEvent, GpioEventStream};
+pub use interrupt::{GpioInterrupt, GpioIsr, GpioSubscription, InterruptArg};
+pub use matrix::GpioMatrix;
+pub use pin::{
+    Disabled, Dynamic, Floating, GpioPin, Input, InputMode, OpenDrain, Output, OutputDrive,
+    OutputMode, PinMode, PullDown, PullMode, PullUp, PushPull,
+};
+pub use port::GpioPort;
+pub use snapshot::{GpioSnapshot, PinSnapshot};
 
 // 为向后兼容，提供别名
 pub use pin::GpioPin as GpioHandler;
@@ -2,82 +2,151 @@
  * @file pin.rs
  * @brief ESP32 GPIO 引脚基本操作
  * @details 提供了 GPIO 引脚的基本操作，如初始化、设置/获取电平、配置模式等
+ *          引脚模式以类型参数表示（类型状态）:
+ *          - `GpioPin<Input<PullUp>>` 只能读取电平和配置中断
+ *          - `GpioPin<Output<PushPull>>` 只能输出电平
+ *          - `GpioPin<OpenDrain>` 开漏双向引脚，可读可写
+ *          - `GpioPin`（即 `GpioPin<Dynamic>`）保留运行时 `init` 接口，兼容旧代码
+ *          模式之间通过消耗原引脚的 `into_*` 方法转换
  * @author xwx
  * @date 2025-05-13
  * @version 1.0
  */
-use esp_idf_sys::{
-    gpio_config, gpio_config_t, gpio_get_level, gpio_hold_dis, gpio_hold_en, gpio_intr_disable,
-    gpio_intr_enable, gpio_num_t, gpio_pulldown_dis, gpio_pulldown_en, gpio_pullup_dis,
-    gpio_pullup_en, gpio_reset_pin, gpio_set_direction, gpio_set_drive_capability,
-    gpio_set_intr_type, gpio_set_level, gpio_set_pull_mode, gpio_wakeup_disable,
-    gpio_wakeup_enable, ESP_OK,
-};
+use std::marker::PhantomData;
 
-use crate::drivers::gpio::gpio_handler::{
-    GpioDriveCap, GpioError, GpioInterruptType, GpioMode, GpioPullMode, GpioResult,
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::registry::{self, PinClaim};
+use crate::drivers::gpio::types::{
+    GlitchFilter, GpioDriveCap, GpioInterruptType, GpioMode, GpioPullMode, GpioResult,
 };
 
-// 类型转换函数
-fn convert_mode(mode: GpioMode) -> u32 {
-    match mode {
-        GpioMode::Disable => 0,              // GPIO_MODE_DISABLE
-        GpioMode::Input => 1,                // GPIO_MODE_INPUT
-        GpioMode::Output => 2,               // GPIO_MODE_OUTPUT
-        GpioMode::OutputOpenDrain => 3,      // GPIO_MODE_OUTPUT_OD
-        GpioMode::InputOutput => 4,          // GPIO_MODE_INPUT_OUTPUT
-        GpioMode::InputOutputOpenDrain => 5, // GPIO_MODE_INPUT_OUTPUT_OD
-    }
+mod sealed {
+    pub trait Sealed {}
 }
 
-fn convert_pull_mode(mode: GpioPullMode) -> u32 {
-    match mode {
-        GpioPullMode::PullUp => 1,     // GPIO_PULLUP_ONLY
-        GpioPullMode::PullDown => 2,   // GPIO_PULLDOWN_ONLY
-        GpioPullMode::PullUpDown => 3, // GPIO_PULLUP_PULLDOWN
-        GpioPullMode::Floating => 0,   // GPIO_FLOATING
-    }
+/// 引脚模式标记
+pub trait PinMode: sealed::Sealed {}
+
+/// 可以读取输入电平和配置中断的模式
+pub trait InputMode: PinMode {}
+
+/// 可以驱动输出电平的模式
+pub trait OutputMode: PinMode {}
+
+/// 输入上下拉标记
+pub trait PullMode: sealed::Sealed {
+    /// 对应的上下拉配置
+    const PULL: GpioPullMode;
 }
 
-fn convert_intr_type(intr_type: GpioInterruptType) -> u32 {
-    match intr_type {
-        GpioInterruptType::Disable => 0,     // GPIO_INTR_DISABLE
-        GpioInterruptType::RisingEdge => 2,  // GPIO_INTR_POSEDGE
-        GpioInterruptType::FallingEdge => 3, // GPIO_INTR_NEGEDGE
-        GpioInterruptType::AnyEdge => 4,     // GPIO_INTR_ANYEDGE
-        GpioInterruptType::LowLevel => 1,    // GPIO_INTR_LOW_LEVEL
-        GpioInterruptType::HighLevel => 5,   // GPIO_INTR_HIGH_LEVEL
-    }
+/// 运行时决定模式（通过 `init` 配置），兼容旧接口
+pub struct Dynamic;
+/// 禁用模式，既不输入也不输出
+pub struct Disabled;
+/// 输入模式，`P` 为上下拉配置
+pub struct Input<P = Floating>(PhantomData<P>);
+/// 输出模式，`M` 为输出驱动方式
+pub struct Output<M = PushPull>(PhantomData<M>);
+/// 推挽输出
+pub struct PushPull;
+/// 开漏：作为 `Output` 的参数时只输出，单独使用时为带上拉的双向开漏引脚
+pub struct OpenDrain;
+/// 浮空输入
+pub struct Floating;
+/// 上拉输入
+pub struct PullUp;
+/// 下拉输入
+pub struct PullDown;
+
+impl sealed::Sealed for Dynamic {}
+impl sealed::Sealed for Disabled {}
+impl<P: PullMode> sealed::Sealed for Input<P> {}
+impl<M: OutputDrive> sealed::Sealed for Output<M> {}
+impl sealed::Sealed for OpenDrain {}
+impl sealed::Sealed for PushPull {}
+impl sealed::Sealed for Floating {}
+impl sealed::Sealed for PullUp {}
+impl sealed::Sealed for PullDown {}
+
+impl PinMode for Dynamic {}
+impl PinMode for Disabled {}
+impl<P: PullMode> PinMode for Input<P> {}
+impl<M: OutputDrive> PinMode for Output<M> {}
+impl PinMode for OpenDrain {}
+
+impl InputMode for Dynamic {}
+impl<P: PullMode> InputMode for Input<P> {}
+impl InputMode for OpenDrain {}
+
+impl OutputMode for Dynamic {}
+impl<M: OutputDrive> OutputMode for Output<M> {}
+impl OutputMode for OpenDrain {}
+
+impl PullMode for Floating {
+    const PULL: GpioPullMode = GpioPullMode::Floating;
+}
+impl PullMode for PullUp {
+    const PULL: GpioPullMode = GpioPullMode::PullUp;
+}
+impl PullMode for PullDown {
+    const PULL: GpioPullMode = GpioPullMode::PullDown;
 }
 
-fn convert_drive_cap(cap: GpioDriveCap) -> u32 {
-    match cap {
-        GpioDriveCap::Weak => 0,      // GPIO_DRIVE_CAP_0
-        GpioDriveCap::Stronger => 1,  // GPIO_DRIVE_CAP_1
-        GpioDriveCap::Medium => 2,    // GPIO_DRIVE_CAP_2
-        GpioDriveCap::Strongest => 3, // GPIO_DRIVE_CAP_3
-    }
+/// 输出驱动方式
+pub trait OutputDrive: sealed::Sealed {
+    /// 对应的GPIO模式
+    ///
+    /// 输出引脚同时使能输入缓冲，以便读回当前输出电平（用于 `toggle`）。
+    const MODE: GpioMode;
+}
+impl OutputDrive for PushPull {
+    const MODE: GpioMode = GpioMode::InputOutput;
+}
+impl OutputDrive for OpenDrain {
+    const MODE: GpioMode = GpioMode::InputOutputOpenDrain;
 }
 
 /// GPIO引脚处理结构体
-pub struct GpioPin {
-    gpio_num: gpio_num_t,
+///
+/// 创建时在引脚登记表中领取引脚，释放时自动归还。
+/// 类型参数 `MODE` 表示引脚当前的模式，决定了哪些操作可用。
+pub struct GpioPin<MODE = Dynamic> {
+    gpio_num: i32,
+    claim: PinClaim,
+    _mode: PhantomData<MODE>,
 }
 
 impl GpioPin {
     /// 创建一个新的GPIO引脚处理实例
     ///
+    /// 新引脚为 [`Dynamic`] 模式，可以用 `init` 配置，或用 `into_*` 转换为确定的模式。
+    ///
     /// # 参数
     ///
     /// * `pin` - GPIO引脚编号
     ///
     /// # 返回
     ///
-    /// 返回一个新的GPIO引脚处理实例
-    pub fn new(pin: u32) -> Self {
-        GpioPin {
-            gpio_num: pin as gpio_num_t,
-        }
+    /// 成功返回GPIO引脚处理实例；引脚不存在、被Flash/PSRAM占用或已被占用时返回错误
+    pub fn new(pin: u32) -> GpioResult<Self> {
+        Self::with_owner(pin, format!("GpioPin({})", pin))
+    }
+
+    /// 以指定的占用者名称创建GPIO引脚处理实例
+    ///
+    /// 占用者名称会出现在引脚冲突的错误信息中，例如 `"lcd.dc"`。
+    ///
+    /// # 参数
+    ///
+    /// * `pin` - GPIO引脚编号
+    /// * `owner` - 占用者名称
+    pub fn with_owner(pin: u32, owner: impl Into<String>) -> GpioResult<Self> {
+        let claim = registry::claim(pin, owner)?;
+        Ok(GpioPin {
+            gpio_num: pin as i32,
+            claim,
+            _mode: PhantomData,
+        })
     }
 
     /// 初始化GPIO引脚
@@ -97,212 +166,187 @@ impl GpioPin {
         pull_mode: GpioPullMode,
         intr_type: GpioInterruptType,
     ) -> GpioResult<()> {
-        let mut config = gpio_config_t {
-            pin_bit_mask: 1 << self.gpio_num,
-            mode: convert_mode(mode),
-            pull_up_en: match pull_mode {
-                GpioPullMode::PullUp | GpioPullMode::PullUpDown => 1,
-                _ => 0,
-            },
-            pull_down_en: match pull_mode {
-                GpioPullMode::PullDown | GpioPullMode::PullUpDown => 1,
-                _ => 0,
-            },
-            intr_type: convert_intr_type(intr_type),
-        };
-
-        unsafe {
-            if gpio_config(&mut config) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-
-        Ok(())
+        Backend::configure(self.gpio_num, mode, pull_mode, intr_type)
     }
 
     /// 重置GPIO引脚到默认状态
     pub fn reset(&self) -> GpioResult<()> {
-        unsafe {
-            if gpio_reset_pin(self.gpio_num) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
+        Backend::reset(self.gpio_num)
     }
 
     /// 设置GPIO方向模式
     pub fn set_direction(&self, mode: GpioMode) -> GpioResult<()> {
-        unsafe {
-            if gpio_set_direction(self.gpio_num, convert_mode(mode)) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
-    }
-
-    /// 设置GPIO输出电平
-    ///
-    /// # 参数
-    ///
-    /// * `level` - 电平值(0或1)
-    pub fn set_level(&self, level: u32) -> GpioResult<()> {
-        unsafe {
-            if gpio_set_level(self.gpio_num, level) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
-    }
-
-    /// 获取GPIO输入电平
-    ///
-    /// # 返回
-    ///
-    /// 返回GPIO电平(0或1)
-    pub fn get_level(&self) -> u32 {
-        unsafe { gpio_get_level(self.gpio_num) as u32 }
+        Backend::set_direction(self.gpio_num, mode)
     }
 
     /// 设置上拉/下拉模式
     pub fn set_pull_mode(&self, pull_mode: GpioPullMode) -> GpioResult<()> {
-        unsafe {
-            if gpio_set_pull_mode(self.gpio_num, convert_pull_mode(pull_mode)) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
+        Backend::set_pull_mode(self.gpio_num, pull_mode)
     }
 
     /// 启用上拉电阻
     pub fn enable_pullup(&self) -> GpioResult<()> {
-        unsafe {
-            if gpio_pullup_en(self.gpio_num) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
+        Backend::set_pullup(self.gpio_num, true)
     }
 
     /// 禁用上拉电阻
     pub fn disable_pullup(&self) -> GpioResult<()> {
-        unsafe {
-            if gpio_pullup_dis(self.gpio_num) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
+        Backend::set_pullup(self.gpio_num, false)
     }
 
     /// 启用下拉电阻
     pub fn enable_pulldown(&self) -> GpioResult<()> {
-        unsafe {
-            if gpio_pulldown_en(self.gpio_num) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
+        Backend::set_pulldown(self.gpio_num, true)
     }
 
     /// 禁用下拉电阻
     pub fn disable_pulldown(&self) -> GpioResult<()> {
-        unsafe {
-            if gpio_pulldown_dis(self.gpio_num) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
+        Backend::set_pulldown(self.gpio_num, false)
     }
+}
 
-    /// 设置驱动能力
-    pub fn set_drive_capability(&self, drive_cap: GpioDriveCap) -> GpioResult<()> {
-        unsafe {
-            if gpio_set_drive_capability(self.gpio_num, convert_drive_cap(drive_cap)) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
+impl<MODE> GpioPin<MODE> {
+    /// 获取GPIO编号
+    pub fn get_pin_number(&self) -> i32 {
+        self.gpio_num
     }
 
-    /// 启用GPIO保持功能
+    /// 克隆引脚的占用凭据，凭据存活期间引脚不会被归还
+    pub(crate) fn share_claim(&self) -> PinClaim {
+        self.claim.clone()
+    }
+
+    /// 按指定模式重新配置引脚，中断保持禁用
+    fn into_mode<NEW>(self, mode: GpioMode, pull_mode: GpioPullMode) -> GpioResult<GpioPin<NEW>> {
+        Backend::configure(self.gpio_num, mode, pull_mode, GpioInterruptType::Disable)?;
+        Ok(GpioPin {
+            gpio_num: self.gpio_num,
+            claim: self.claim,
+            _mode: PhantomData,
+        })
+    }
+
+    /// 转换为禁用模式
+    pub fn into_disabled(self) -> GpioResult<GpioPin<Disabled>> {
+        self.into_mode(GpioMode::Disable, GpioPullMode::Floating)
+    }
+
+    /// 转换为浮空输入
+    pub fn into_floating_input(self) -> GpioResult<GpioPin<Input<Floating>>> {
+        self.into_mode(GpioMode::Input, GpioPullMode::Floating)
+    }
+
+    /// 转换为上拉输入
+    pub fn into_pull_up_input(self) -> GpioResult<GpioPin<Input<PullUp>>> {
+        self.into_mode(GpioMode::Input, GpioPullMode::PullUp)
+    }
+
+    /// 转换为下拉输入
+    pub fn into_pull_down_input(self) -> GpioResult<GpioPin<Input<PullDown>>> {
+        self.into_mode(GpioMode::Input, GpioPullMode::PullDown)
+    }
+
+    /// 转换为推挽输出
+    pub fn into_push_pull_output(self) -> GpioResult<GpioPin<Output<PushPull>>> {
+        self.into_mode(PushPull::MODE, GpioPullMode::Floating)
+    }
+
+    /// 转换为开漏输出，初始为释放（高阻）状态
+    pub fn into_open_drain_output(self) -> GpioResult<GpioPin<Output<OpenDrain>>> {
+        Backend::set_level(self.gpio_num, 1)?;
+        self.into_mode(OpenDrain::MODE, GpioPullMode::Floating)
+    }
+
+    /// 转换为带内部上拉的双向开漏引脚，适用于I2C等线与总线
     ///
-    /// 在深度睡眠或复位时保持GPIO状态
-    pub fn enable_hold(&self) -> GpioResult<()> {
-        unsafe {
-            if gpio_hold_en(self.gpio_num) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
+    /// 配置前先释放总线，避免切换模式时产生低电平毛刺。
+    pub fn into_open_drain(self) -> GpioResult<GpioPin<OpenDrain>> {
+        Backend::set_level(self.gpio_num, 1)?;
+        self.into_mode(GpioMode::InputOutputOpenDrain, GpioPullMode::PullUp)
     }
 
-    /// 禁用GPIO保持功能
-    pub fn disable_hold(&self) -> GpioResult<()> {
-        unsafe {
-            if gpio_hold_dis(self.gpio_num) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
+    /// 转换为运行时模式，保留当前硬件配置
+    pub fn into_dynamic(self) -> GpioPin {
+        GpioPin {
+            gpio_num: self.gpio_num,
+            claim: self.claim,
+            _mode: PhantomData,
         }
-        Ok(())
+    }
+}
+
+impl<MODE: InputMode> GpioPin<MODE> {
+    /// 获取GPIO输入电平
+    ///
+    /// # 返回
+    ///
+    /// 返回GPIO电平(0或1)
+    pub fn get_level(&self) -> u32 {
+        Backend::get_level(self.gpio_num)
+    }
+
+    /// 检查是否为高电平
+    pub fn is_high(&self) -> bool {
+        self.get_level() == 1
+    }
+
+    /// 检查是否为低电平
+    pub fn is_low(&self) -> bool {
+        self.get_level() == 0
     }
 
     /// 启用GPIO唤醒功能
     pub fn enable_wakeup(&self, intr_type: GpioInterruptType) -> GpioResult<()> {
-        unsafe {
-            if gpio_wakeup_enable(self.gpio_num, convert_intr_type(intr_type)) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
+        Backend::enable_wakeup(self.gpio_num, intr_type)
     }
 
     /// 禁用GPIO唤醒功能
     pub fn disable_wakeup(&self) -> GpioResult<()> {
-        unsafe {
-            if gpio_wakeup_disable(self.gpio_num) != ESP_OK {
-                return Err(GpioError::ConfigError);
-            }
-        }
-        Ok(())
+        Backend::disable_wakeup(self.gpio_num)
     }
 
     /// 设置中断类型
     pub fn set_interrupt_type(&self, intr_type: GpioInterruptType) -> GpioResult<()> {
-        unsafe {
-            if gpio_set_intr_type(self.gpio_num, convert_intr_type(intr_type)) != ESP_OK {
-                return Err(GpioError::InterruptError);
-            }
-        }
-        Ok(())
+        Backend::set_interrupt_type(self.gpio_num, intr_type)
     }
 
     /// 启用中断
     pub fn enable_interrupt(&self) -> GpioResult<()> {
-        unsafe {
-            if gpio_intr_enable(self.gpio_num) != ESP_OK {
-                return Err(GpioError::InterruptError);
-            }
-        }
-        Ok(())
+        Backend::set_interrupt_enabled(self.gpio_num, true)
     }
 
     /// 禁用中断
     pub fn disable_interrupt(&self) -> GpioResult<()> {
-        unsafe {
-            if gpio_intr_disable(self.gpio_num) != ESP_OK {
-                return Err(GpioError::InterruptError);
-            }
-        }
-        Ok(())
+        Backend::set_interrupt_enabled(self.gpio_num, false)
     }
 
-    /// 获取GPIO编号
-    pub fn get_pin_number(&self) -> gpio_num_t {
-        self.gpio_num
+    /// 启用毛刺过滤器
+    ///
+    /// 被滤除的脉冲不会改变输入电平，也不会触发中断。重复调用时替换原有的过滤器。
+    ///
+    /// # 参数
+    ///
+    /// * `filter` - 过滤器类型及窗口
+    pub fn enable_glitch_filter(&self, filter: GlitchFilter) -> GpioResult<()> {
+        Backend::set_glitch_filter(self.gpio_num, Some(filter))
+    }
+
+    /// 禁用毛刺过滤器
+    pub fn disable_glitch_filter(&self) -> GpioResult<()> {
+        Backend::set_glitch_filter(self.gpio_num, None)
     }
 }
 
-/// 实现便捷的高/低电平切换方法
-impl GpioPin {
+impl<MODE: OutputMode> GpioPin<MODE> {
+    /// 设置GPIO输出电平
+    ///
+    /// # 参数
+    ///
+    /// * `level` - 电平值(0或1)
+    pub fn set_level(&self, level: u32) -> GpioResult<()> {
+        Backend::set_level(self.gpio_num, level)
+    }
+
     /// 设置为高电平
     pub fn set_high(&self) -> GpioResult<()> {
         self.set_level(1)
@@ -315,17 +359,64 @@ impl GpioPin {
 
     /// 切换电平状态（高变低，低变高）
     pub fn toggle(&self) -> GpioResult<()> {
-        let current_level = self.get_level();
+        let current_level = Backend::get_level(self.gpio_num);
         self.set_level(1 - current_level)
     }
 
-    /// 检查是否为高电平
-    pub fn is_high(&self) -> bool {
-        self.get_level() == 1
+    /// 设置驱动能力
+    pub fn set_drive_capability(&self, drive_cap: GpioDriveCap) -> GpioResult<()> {
+        Backend::set_drive_capability(self.gpio_num, drive_cap)
     }
 
-    /// 检查是否为低电平
-    pub fn is_low(&self) -> bool {
-        self.get_level() == 0
+    /// 启用GPIO保持功能
+    ///
+    /// 在深度睡眠或复位时保持GPIO状态
+    pub fn enable_hold(&self) -> GpioResult<()> {
+        Backend::set_hold(self.gpio_num, true)
+    }
+
+    /// 禁用GPIO保持功能
+    pub fn disable_hold(&self) -> GpioResult<()> {
+        Backend::set_hold(self.gpio_num, false)
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use crate::drivers::gpio::backend::sim;
+
+    #[test]
+    fn test_typed_conversions() {
+        sim::reset();
+        let button = GpioPin::new(4).unwrap().into_pull_up_input().unwrap();
+        assert_eq!(sim::pin_state(4).mode, GpioMode::Input);
+        assert!(sim::pin_state(4).pull_up);
+        assert!(button.is_high());
+
+        let led = button.into_push_pull_output().unwrap();
+        led.set_high().unwrap();
+        led.toggle().unwrap();
+        assert_eq!(sim::pad_level(4), 0);
+        led.toggle().unwrap();
+        assert_eq!(sim::pad_level(4), 1);
+
+        // 转换不会归还引脚
+        assert!(GpioPin::new(4).is_err());
+        drop(led);
+        assert!(GpioPin::new(4).is_ok());
+    }
+
+    #[test]
+    fn test_open_drain_line() {
+        sim::reset();
+        let sda = GpioPin::new(8).unwrap().into_open_drain().unwrap();
+        assert!(sda.is_high());
+        sda.set_low().unwrap();
+        assert!(sda.is_low());
+        sda.set_high().unwrap();
+        // 外部器件拉低总线
+        sim::drive(8, 0);
+        assert!(sda.is_low());
     }
 }
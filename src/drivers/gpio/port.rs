@@ -0,0 +1,169 @@
+/**
+ * @file port.rs
+ * @brief 多引脚原子读写端口
+ * @details 把一组输出引脚组合成一个端口，用一次寄存器写入同时改变多个引脚:
+ *          - 端口位号按引脚加入的顺序编号，第 i 个引脚对应 bit i
+ *          - `set_bits` / `clear_bits` 通过 W1TS/W1TC 寄存器实现，同一组内的引脚在同一周期变化
+ *          - `write` 在所有GPIO输出写入共用的临界区内读-改-写 OUT 寄存器，置位和清零的引脚在同一次写入中变化
+ *          - `read` 一次读取输入寄存器，所有引脚在同一时刻采样
+ *          GPIO0~31 与 GPIO32~48 分属两个寄存器，跨组的引脚按先低组后高组的顺序写入，
+ *          对时序要求严格的并行总线应把数据线放在同一组内
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::pin::{GpioPin, Output, OutputMode};
+
+/// 多引脚端口
+///
+/// 持有组成端口的所有引脚，因此端口存在期间这些引脚不能被其他对象领取。
+pub struct GpioPort<MODE: OutputMode = Output> {
+    pins: Vec<GpioPin<MODE>>,
+}
+
+impl<MODE: OutputMode> GpioPort<MODE> {
+    /// 由一组输出引脚创建端口
+    ///
+    /// # 参数
+    ///
+    /// * `pins` - 组成端口的引脚，第一个引脚对应端口的 bit 0
+    pub fn new(pins: impl IntoIterator<Item = GpioPin<MODE>>) -> Self {
+        GpioPort {
+            pins: pins.into_iter().collect(),
+        }
+    }
+
+    /// 端口宽度（引脚数量）
+    pub fn len(&self) -> usize {
+        self.pins.len()
+    }
+
+    /// 端口是否不含任何引脚
+    pub fn is_empty(&self) -> bool {
+        self.pins.is_empty()
+    }
+
+    /// 组成端口的引脚
+    pub fn pins(&self) -> &[GpioPin<MODE>] {
+        &self.pins
+    }
+
+    /// 拆开端口，取回所有引脚
+    pub fn into_pins(self) -> Vec<GpioPin<MODE>> {
+        self.pins
+    }
+
+    /// 将端口位掩码转换为GPIO编号掩码
+    fn gpio_mask(&self, mask: u64) -> u64 {
+        self.pins
+            .iter()
+            .enumerate()
+            .filter(|&(bit, _)| mask & (1 << bit) != 0)
+            .fold(0, |gpio_mask, (_, pin)| {
+                gpio_mask | (1 << pin.get_pin_number())
+            })
+    }
+
+    /// 将掩码中的位同时置1
+    pub fn set_bits(&self, mask: u64) {
+        Backend::set_output_mask(self.gpio_mask(mask));
+    }
+
+    /// 将掩码中的位同时清0
+    pub fn clear_bits(&self, mask: u64) {
+        Backend::clear_output_mask(self.gpio_mask(mask));
+    }
+
+    /// 按掩码写入数值，掩码外的位保持不变
+    ///
+    /// 同一组内的引脚由一次寄存器写入同时改变，不会出现已清零而未置位的中间状态；
+    /// 跨组时先写低组再写高组。读-改-写期间本 crate 的其他输出写入被挡在临界区外，端口外的引脚不会被覆盖。
+    ///
+    /// # 参数
+    ///
+    /// * `mask` - 需要修改的端口位
+    /// * `value` - 新的数值，只有 `mask` 中的位有效
+    pub fn write(&self, mask: u64, value: u64) {
+        Backend::write_output_mask(self.gpio_mask(mask), self.gpio_mask(mask & value));
+    }
+
+    /// 同时读取所有引脚的电平
+    ///
+    /// 类型化的输出引脚使能了输入缓冲，读到的是引脚上的实际电平。
+    pub fn read(&self) -> u64 {
+        let levels = Backend::read_input_mask();
+        self.pins
+            .iter()
+            .enumerate()
+            .filter(|(_, pin)| levels & (1 << pin.get_pin_number()) != 0)
+            .fold(0, |value, (bit, _)| value | (1 << bit))
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use crate::drivers::gpio::backend::sim;
+    use crate::drivers::gpio::types::GpioResult;
+    use std::time::Duration;
+
+    fn output(pin: u32) -> GpioResult<GpioPin<Output>> {
+        GpioPin::new(pin)?.into_push_pull_output()
+    }
+
+    #[test]
+    fn test_parallel_write() {
+        sim::reset();
+        let pins = [1, 2, 3, 40].map(|pin| output(pin).unwrap());
+        let port = GpioPort::new(pins);
+        assert_eq!(port.len(), 4);
+
+        port.write(0b1111, 0b1010);
+        assert_eq!(port.read(), 0b1010);
+        assert_eq!(sim::pad_level(2), 1);
+        assert_eq!(sim::pad_level(40), 1);
+
+        // 掩码外的位保持不变
+        port.write(0b0011, 0b0001);
+        assert_eq!(port.read(), 0b1001);
+
+        port.set_bits(0b0110);
+        assert_eq!(port.read(), 0b1111);
+        port.clear_bits(0b1100);
+        assert_eq!(port.read(), 0b0011);
+
+        // 同一组内置位和清零的引脚在同一次写入中变化，端口外的引脚不变
+        let other = output(5).unwrap();
+        other.set_high().unwrap();
+        sim::clear_trace();
+        sim::advance(Duration::from_millis(1));
+        port.write(0b0111, 0b0100);
+        assert_eq!(port.read(), 0b0100);
+        let trace = sim::trace();
+        assert_eq!(trace.len(), 3);
+        assert!(trace.iter().all(|event| event.time_us == 1_000));
+        // 没有 GPIO1/2 已清零而 GPIO3 尚未置位的中间状态
+        assert_eq!(sim::pad_states(), vec![(1 << 3) | (1 << 5)]);
+
+        // 跨组时每组一次写入
+        sim::clear_trace();
+        port.write(0b1111, 0b1001);
+        assert_eq!(
+            sim::pad_states(),
+            vec![(1 << 1) | (1 << 5), (1 << 1) | (1 << 5) | (1 << 40)]
+        );
+    }
+
+    #[test]
+    fn test_port_owns_pins() {
+        sim::reset();
+        let port = GpioPort::new([output(5).unwrap(), output(6).unwrap()]);
+        assert!(GpioPin::new(5).is_err());
+
+        let pins = port.into_pins();
+        assert_eq!(pins[1].get_pin_number(), 6);
+        drop(pins);
+        assert!(GpioPin::new(5).is_ok());
+    }
+}
@@ -0,0 +1,193 @@
+/**
+ * @file registry.rs
+ * @brief GPIO 引脚所有权登记
+ * @details 全局记录每个引脚由哪个对象占用:
+ *          - 每个引脚同一时间只能被领取一次，释放凭据时自动归还
+ *          - 拒绝 ESP32-S3 上不存在的引脚（GPIO22~25）和 SPI Flash/PSRAM 占用的引脚
+ *          - 领取启动绑定引脚（GPIO0、3、45、46）时给出警告
+ *          这样 LCD 和 SD 卡同时使用 IO12 之类的冲突会在启动时报错，而不是在硬件上互相干扰
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::sync::Arc;
+
+use crate::drivers::gpio::types::{GpioError, GpioResult};
+use crate::error::Error;
+
+/// ESP32-S3 的GPIO编号上限（不含）
+pub const GPIO_NUM_MAX: u32 = 49;
+
+/// 启动绑定引脚，上电时的电平决定启动模式和Flash电压
+pub const STRAPPING_PINS: [u32; 4] = [0, 3, 45, 46];
+
+/// 判断引脚在 ESP32-S3 上是否存在
+pub fn is_valid_gpio(pin: u32) -> bool {
+    matches!(pin, 0..=21 | 26..=48)
+}
+
+/// 判断引脚是否被 SPI Flash/PSRAM 占用
+///
+/// GPIO26~32 连接 Flash/四线PSRAM；启用八线PSRAM时 GPIO33~37 也被占用。
+pub fn is_reserved(pin: u32) -> bool {
+    if (26..=32).contains(&pin) {
+        return true;
+    }
+    cfg!(esp_idf_spiram_mode_oct) && (33..=37).contains(&pin)
+}
+
+/// 判断引脚是否为启动绑定引脚
+pub fn is_strapping(pin: u32) -> bool {
+    STRAPPING_PINS.contains(&pin)
+}
+
+/// 引脚登记表
+struct PinTable {
+    owners: Vec<Option<String>>,
+}
+
+impl PinTable {
+    const fn new() -> Self {
+        PinTable { owners: Vec::new() }
+    }
+
+    fn slot(&mut self, pin: u32) -> &mut Option<String> {
+        if self.owners.is_empty() {
+            self.owners.resize(GPIO_NUM_MAX as usize, None);
+        }
+        &mut self.owners[pin as usize]
+    }
+}
+
+/// 访问引脚登记表
+#[cfg(target_os = "espidf")]
+fn with_pin_table<R>(f: impl FnOnce(&mut PinTable) -> R) -> R {
+    use std::sync::Mutex;
+
+    static TABLE: Mutex<PinTable> = Mutex::new(PinTable::new());
+    let mut table = TABLE.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut table)
+}
+
+/// 访问引脚登记表
+///
+/// 模拟后端的芯片是线程局部的，因此主机上的登记表也按线程保存。
+#[cfg(not(target_os = "espidf"))]
+fn with_pin_table<R>(f: impl FnOnce(&mut PinTable) -> R) -> R {
+    use std::cell::RefCell;
+
+    thread_local! {
+        static TABLE: RefCell<PinTable> = const { RefCell::new(PinTable::new()) };
+    }
+    TABLE.with(|table| f(&mut table.borrow_mut()))
+}
+
+/// 引脚占用凭据，释放时自动归还引脚
+///
+/// 凭据可以克隆，例如中断订阅持有引脚凭据的克隆，最后一个克隆释放时才归还引脚。
+#[derive(Debug, Clone)]
+pub struct PinClaim {
+    inner: Arc<ClaimedPin>,
+}
+
+/// 被占用的引脚，由所有凭据克隆共享
+#[derive(Debug)]
+struct ClaimedPin {
+    pin: u32,
+}
+
+impl PinClaim {
+    /// 获取引脚编号
+    pub fn pin(&self) -> u32 {
+        self.inner.pin
+    }
+}
+
+impl Drop for ClaimedPin {
+    fn drop(&mut self) {
+        with_pin_table(|table| *table.slot(self.pin) = None);
+    }
+}
+
+/// 领取一个引脚
+///
+/// # 参数
+///
+/// * `pin` - GPIO编号
+/// * `owner` - 占用者名称，用于冲突提示
+///
+/// # 返回
+///
+/// 成功返回占用凭据；引脚不存在返回 `InvalidGpio`，被Flash/PSRAM占用返回 `ReservedGpio`，
+/// 已被其他对象占用返回 `PinInUse`
+pub fn claim(pin: u32, owner: impl Into<String>) -> GpioResult<PinClaim> {
+    let owner = owner.into();
+    if !is_valid_gpio(pin) {
+        return Err(Error::new(GpioError::InvalidGpio).with_pin(pin));
+    }
+    if is_reserved(pin) {
+        return Err(Error::new(GpioError::ReservedGpio(pin)).with_pin(pin));
+    }
+
+    with_pin_table(|table| {
+        let slot = table.slot(pin);
+        if let Some(current) = slot {
+            log::error!("GPIO{} 已被 {} 占用，{} 无法使用", pin, current, owner);
+            return Err(Error::new(GpioError::PinInUse {
+                pin,
+                owner: current.clone(),
+            })
+            .with_pin(pin));
+        }
+        if is_strapping(pin) {
+            log::warn!("GPIO{} 是启动绑定引脚，{} 使用时请注意上电电平", pin, owner);
+        }
+        *slot = Some(owner);
+        Ok(PinClaim {
+            inner: Arc::new(ClaimedPin { pin }),
+        })
+    })
+}
+
+/// 查询引脚当前的占用者
+pub fn owner(pin: u32) -> Option<String> {
+    if pin >= GPIO_NUM_MAX {
+        return None;
+    }
+    with_pin_table(|table| table.slot(pin).clone())
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_and_release() {
+        let lcd = claim(12, "lcd.sclk").unwrap();
+        assert_eq!(owner(12).as_deref(), Some("lcd.sclk"));
+
+        let error = claim(12, "sdcard.sclk").unwrap_err();
+        assert_eq!(
+            error.gpio(),
+            Some(&GpioError::PinInUse {
+                pin: 12,
+                owner: "lcd.sclk".to_string()
+            })
+        );
+        assert_eq!(error.to_string(), "GPIO12 已被 lcd.sclk 占用");
+
+        drop(lcd);
+        assert_eq!(owner(12), None);
+        assert!(claim(12, "sdcard.sclk").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_and_reserved_pins() {
+        let kind = |pin| claim(pin, "x").unwrap_err().gpio().cloned();
+        assert_eq!(kind(23), Some(GpioError::InvalidGpio));
+        assert_eq!(kind(49), Some(GpioError::InvalidGpio));
+        assert_eq!(kind(27), Some(GpioError::ReservedGpio(27)));
+        // 启动绑定引脚只警告，可以正常领取
+        assert!(claim(0, "button").is_ok());
+    }
+}
@@ -0,0 +1,269 @@
+/**
+ * @file snapshot.rs
+ * @brief GPIO 配置快照
+ * @details 读取每个引脚在硬件中的实际配置，用于排查板级问题:
+ *          - IO_MUX 功能号和 GPIO 矩阵输出信号
+ *          - 方向、上下拉、驱动能力、电平、中断类型和保持状态
+ *          - 引脚登记表中的占用者
+ *          快照可以打印为表格，也可以序列化为 JSON 发送到远程诊断端
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::fmt::{self, Write};
+
+use crate::drivers::gpio::types::{GpioDriveCap, GpioInterruptType, GpioMode, GpioPullMode};
+
+/// 单个引脚的配置快照
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinSnapshot {
+    /// GPIO编号
+    pub pin: u32,
+    /// IO_MUX功能号，`PIN_FUNC_GPIO` 表示经GPIO矩阵
+    pub iomux_func: u32,
+    /// 经GPIO矩阵驱动引脚的外设输出信号，`None` 表示由GPIO输出寄存器驱动
+    pub out_signal: Option<u32>,
+    /// 方向模式
+    pub mode: GpioMode,
+    /// 上下拉
+    pub pull: GpioPullMode,
+    /// 驱动能力
+    pub drive_cap: GpioDriveCap,
+    /// 电平：输入使能时为输入电平，否则为输出寄存器中的电平
+    pub level: u32,
+    /// 中断类型
+    pub intr_type: GpioInterruptType,
+    /// 中断是否使能
+    pub intr_enabled: bool,
+    /// 保持功能
+    pub hold: bool,
+    /// 是否被 SPI Flash/PSRAM 占用
+    pub reserved: bool,
+    /// 引脚登记表中的占用者
+    pub owner: Option<String>,
+}
+
+impl PinSnapshot {
+    /// 序列化为 JSON 对象
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.write_json(&mut json);
+        json
+    }
+
+    fn write_json(&self, out: &mut String) {
+        let optional = |value: Option<u32>| value.map_or("null".to_string(), |v| v.to_string());
+        let _ = write!(
+            out,
+            "{{\"pin\":{},\"iomux_func\":{},\"out_signal\":{},\"mode\":\"{:?}\",\"pull\":\"{:?}\",\
+             \"drive_cap\":\"{:?}\",\"level\":{},\"intr_type\":\"{:?}\",\"intr_enabled\":{},\
+             \"hold\":{},\"reserved\":{},\"owner\":",
+            self.pin,
+            self.iomux_func,
+            optional(self.out_signal),
+            self.mode,
+            self.pull,
+            self.drive_cap,
+            self.level,
+            self.intr_type,
+            self.intr_enabled,
+            self.hold,
+            self.reserved,
+        );
+        match &self.owner {
+            Some(owner) => write_json_string(out, owner),
+            None => out.push_str("null"),
+        }
+        out.push('}');
+    }
+}
+
+/// 所有引脚的配置快照
+///
+/// 由 [`GpioControl::snapshot`](crate::drivers::gpio::GpioControl::snapshot) 生成，
+/// `Display` 输出为每个引脚一行的表格。
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GpioSnapshot {
+    /// 按GPIO编号排列的引脚快照
+    pub pins: Vec<PinSnapshot>,
+}
+
+impl GpioSnapshot {
+    /// 查找指定引脚的快照
+    pub fn pin(&self, pin: u32) -> Option<&PinSnapshot> {
+        self.pins.iter().find(|snapshot| snapshot.pin == pin)
+    }
+
+    /// 序列化为 JSON，格式为 `{"pins":[...]}`
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"pins\":[");
+        for (index, pin) in self.pins.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            pin.write_json(&mut json);
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+impl fmt::Display for GpioSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<4} {:<4} {:<4} {:<20} {:<10} {:<9} {:<3} {:<12} {:<4} 占用者",
+            "GPIO", "FUNC", "SIG", "MODE", "PULL", "DRIVE", "LVL", "INTR", "HOLD"
+        )?;
+        for pin in &self.pins {
+            let signal = pin.out_signal.map_or("-".to_string(), |s| s.to_string());
+            let intr = if pin.intr_type == GpioInterruptType::Disable {
+                "-".to_string()
+            } else if pin.intr_enabled {
+                format!("{:?}", pin.intr_type)
+            } else {
+                format!("({:?})", pin.intr_type)
+            };
+            let owner = match (&pin.owner, pin.reserved) {
+                (Some(owner), _) => owner.as_str(),
+                (None, true) => "<Flash/PSRAM>",
+                (None, false) => "-",
+            };
+            writeln!(
+                f,
+                "{:<4} {:<4} {:<4} {:<20} {:<10} {:<9} {:<3} {:<12} {:<4} {}",
+                pin.pin,
+                pin.iomux_func,
+                signal,
+                format!("{:?}", pin.mode),
+                format!("{:?}", pin.pull),
+                format!("{:?}", pin.drive_cap),
+                pin.level,
+                intr,
+                if pin.hold { "on" } else { "-" },
+                owner
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// 写入带转义的 JSON 字符串
+fn write_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use crate::drivers::gpio::backend::sim;
+    use crate::drivers::gpio::matrix::{signal, PIN_FUNC_GPIO};
+    use crate::drivers::gpio::{GpioControl, GpioMatrix, GpioPin};
+
+    #[test]
+    fn test_snapshot_reads_back_configuration() {
+        sim::reset();
+        let button = GpioPin::with_owner(0, "button")
+            .unwrap()
+            .into_pull_up_input()
+            .unwrap();
+        button
+            .set_interrupt_type(GpioInterruptType::FallingEdge)
+            .unwrap();
+        let led = GpioPin::with_owner(1, "led")
+            .unwrap()
+            .into_open_drain_output()
+            .unwrap();
+        led.set_drive_capability(GpioDriveCap::Strongest).unwrap();
+        led.enable_hold().unwrap();
+        let probe = GpioPin::new(2).unwrap().into_push_pull_output().unwrap();
+        GpioMatrix::connect_output(&probe, signal::FSPICLK_OUT_IDX, false).unwrap();
+
+        let snapshot = GpioControl::snapshot();
+        assert_eq!(snapshot.pins.len(), 45);
+        assert!(snapshot.pin(22).is_none());
+
+        let pin = snapshot.pin(0).unwrap();
+        assert_eq!(pin.mode, GpioMode::Input);
+        assert_eq!(pin.pull, GpioPullMode::PullUp);
+        assert_eq!(pin.level, 1);
+        assert_eq!(pin.intr_type, GpioInterruptType::FallingEdge);
+        assert_eq!(pin.owner.as_deref(), Some("button"));
+
+        let pin = snapshot.pin(1).unwrap();
+        assert!(pin.mode.is_open_drain());
+        assert_eq!(pin.drive_cap, GpioDriveCap::Strongest);
+        assert!(pin.hold);
+
+        let pin = snapshot.pin(2).unwrap();
+        assert_eq!(pin.iomux_func, PIN_FUNC_GPIO);
+        assert_eq!(pin.out_signal, Some(signal::FSPICLK_OUT_IDX));
+        assert_eq!(pin.owner.as_deref(), Some("GpioPin(2)"));
+        assert_eq!(snapshot.pin(3).unwrap().owner, None);
+        assert!(snapshot.pin(27).unwrap().reserved);
+
+        drop(button);
+        assert_eq!(GpioControl::pin_snapshot(0).unwrap().owner, None);
+        assert!(GpioControl::pin_snapshot(23).is_err());
+    }
+
+    #[test]
+    fn test_json_and_table() {
+        let pin = PinSnapshot {
+            pin: 5,
+            iomux_func: PIN_FUNC_GPIO,
+            out_signal: None,
+            mode: GpioMode::InputOutput,
+            pull: GpioPullMode::Floating,
+            drive_cap: GpioDriveCap::Medium,
+            level: 1,
+            intr_type: GpioInterruptType::AnyEdge,
+            intr_enabled: false,
+            hold: false,
+            reserved: false,
+            owner: Some("lcd \"dc\"".to_string()),
+        };
+        assert_eq!(
+            pin.to_json(),
+            "{\"pin\":5,\"iomux_func\":1,\"out_signal\":null,\"mode\":\"InputOutput\",\
+             \"pull\":\"Floating\",\"drive_cap\":\"Medium\",\"level\":1,\"intr_type\":\"AnyEdge\",\
+             \"intr_enabled\":false,\"hold\":false,\"reserved\":false,\"owner\":\"lcd \\\"dc\\\"\"}"
+        );
+
+        let snapshot = GpioSnapshot {
+            pins: vec![
+                pin.clone(),
+                PinSnapshot {
+                    pin: 6,
+                    owner: None,
+                    ..pin
+                },
+            ],
+        };
+        let json = snapshot.to_json();
+        assert!(json.starts_with("{\"pins\":[{\"pin\":5,"));
+        assert!(json.ends_with("\"owner\":null}]}"));
+
+        let table = snapshot.to_string();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("GPIO FUNC SIG"));
+        assert!(lines[1].contains("(AnyEdge)"));
+        assert!(lines[1].ends_with("lcd \"dc\""));
+        assert!(lines[2].ends_with(" -"));
+    }
+}
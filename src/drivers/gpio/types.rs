@@ -1,33 +1,57 @@
-/**
+/*!
  * @file types.rs
  * @brief ESP32 GPIO 类型定义
  * @details 包含 GPIO 操作相关的枚举、错误类型等定义
+ *          这些类型与平台无关，ESP-IDF 常量的转换放在 backend::esp 中
  * @author xwx
  * @date 2025-05-13
  * @version 1.0
  */
-use esp_idf_sys::{
-    gpio_drive_cap_t, gpio_int_type_t, gpio_mode_t, gpio_pull_mode_t,
-};
+use std::fmt;
 
 /// GPIO操作错误类型
-#[derive(Debug)]
+///
+/// 作为 [`crate::error::ErrorKind::Gpio`] 出现在统一错误类型中。
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GpioError {
     /// 配置错误
     ConfigError,
-    /// 无效的GPIO编号
+    /// 无效的GPIO编号（芯片上不存在该引脚）
     InvalidGpio,
+    /// 引脚被 SPI Flash/PSRAM 占用
+    ReservedGpio(u32),
+    /// 引脚已被其他对象占用
+    PinInUse {
+        /// GPIO编号
+        pin: u32,
+        /// 当前占用者
+        owner: String,
+    },
     /// 中断设置错误
     InterruptError,
     /// 系统错误
     SystemError,
 }
 
+impl fmt::Display for GpioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpioError::ConfigError => write!(f, "配置错误"),
+            GpioError::InvalidGpio => write!(f, "无效的GPIO编号"),
+            // 引脚编号由统一错误中的资源信息给出
+            GpioError::ReservedGpio(_) => write!(f, "被Flash/PSRAM占用"),
+            GpioError::PinInUse { owner, .. } => write!(f, "已被 {} 占用", owner),
+            GpioError::InterruptError => write!(f, "中断设置错误"),
+            GpioError::SystemError => write!(f, "系统错误"),
+        }
+    }
+}
+
 /// GPIO操作结果类型
-pub type GpioResult<T> = Result<T, GpioError>;
+pub type GpioResult<T> = Result<T, crate::error::Error>;
 
 /// GPIO引脚模式
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GpioMode {
     /// 禁用(既不是输入也不是输出)
     Disable,
@@ -43,8 +67,49 @@ pub enum GpioMode {
     InputOutputOpenDrain,
 }
 
+impl GpioMode {
+    /// 是否使能了输入缓冲
+    pub fn is_input(self) -> bool {
+        matches!(
+            self,
+            GpioMode::Input | GpioMode::InputOutput | GpioMode::InputOutputOpenDrain
+        )
+    }
+
+    /// 是否使能了输出驱动
+    pub fn is_output(self) -> bool {
+        matches!(
+            self,
+            GpioMode::Output
+                | GpioMode::OutputOpenDrain
+                | GpioMode::InputOutput
+                | GpioMode::InputOutputOpenDrain
+        )
+    }
+
+    /// 是否为开漏输出
+    pub fn is_open_drain(self) -> bool {
+        matches!(
+            self,
+            GpioMode::OutputOpenDrain | GpioMode::InputOutputOpenDrain
+        )
+    }
+
+    /// 由输入使能、输出使能和开漏标志组合出模式，未使能输出时忽略开漏标志
+    pub fn from_flags(input: bool, output: bool, open_drain: bool) -> Self {
+        match (input, output, open_drain) {
+            (false, false, _) => GpioMode::Disable,
+            (true, false, _) => GpioMode::Input,
+            (false, true, false) => GpioMode::Output,
+            (false, true, true) => GpioMode::OutputOpenDrain,
+            (true, true, false) => GpioMode::InputOutput,
+            (true, true, true) => GpioMode::InputOutputOpenDrain,
+        }
+    }
+}
+
 /// GPIO上拉/下拉模式
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GpioPullMode {
     /// 仅上拉
     PullUp,
@@ -56,8 +121,20 @@ pub enum GpioPullMode {
     Floating,
 }
 
+impl GpioPullMode {
+    /// 由上拉、下拉电阻的使能状态组合出上下拉模式
+    pub fn from_flags(pull_up: bool, pull_down: bool) -> Self {
+        match (pull_up, pull_down) {
+            (true, true) => GpioPullMode::PullUpDown,
+            (true, false) => GpioPullMode::PullUp,
+            (false, true) => GpioPullMode::PullDown,
+            (false, false) => GpioPullMode::Floating,
+        }
+    }
+}
+
 /// GPIO中断类型
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GpioInterruptType {
     /// 禁用中断
     Disable,
@@ -74,7 +151,7 @@ pub enum GpioInterruptType {
 }
 
 /// GPIO驱动能力
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GpioDriveCap {
     /// 弱驱动
     Weak,
@@ -86,50 +163,46 @@ pub enum GpioDriveCap {
     Strongest,
 }
 
-/// 类型转换辅助函数
-#[inline]
-pub(crate) fn convert_mode(mode: GpioMode) -> gpio_mode_t {
-    match mode {
-        GpioMode::Disable => 0,      // GPIO_MODE_DISABLE
-        GpioMode::Input => 1,        // GPIO_MODE_INPUT
-        GpioMode::Output => 2,       // GPIO_MODE_OUTPUT
-        GpioMode::OutputOpenDrain => 3, // GPIO_MODE_OUTPUT_OD
-        GpioMode::InputOutput => 4,  // GPIO_MODE_INPUT_OUTPUT
-        GpioMode::InputOutputOpenDrain => 5, // GPIO_MODE_INPUT_OUTPUT_OD
-    }
+/// GPIO毛刺过滤器
+///
+/// 过滤器作用在输入信号上，被滤除的脉冲既不会改变读到的电平，也不会触发中断。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlitchFilter {
+    /// 引脚毛刺过滤器，滤除宽度小于2个IO_MUX时钟周期（80MHz时为25ns）的脉冲
+    Pin,
+    /// 灵活毛刺过滤器，窗口可配置
+    ///
+    /// ESP32-S3 没有灵活毛刺过滤器，启用时返回 `ESP_ERR_NOT_SUPPORTED`；保留该类型供其他芯片使用。
+    /// 在 `window_ns` 的采样窗口内，电平保持时间不足 `threshold_ns` 的脉冲被视为毛刺。
+    /// `threshold_ns` 不能为0，也不能大于 `window_ns`。
+    Flex {
+        /// 采样窗口宽度（纳秒）
+        window_ns: u32,
+        /// 判定为有效电平所需的保持时间（纳秒）
+        threshold_ns: u32,
+    },
 }
 
-/// 类型转换辅助函数
-#[inline]
-pub(crate) fn convert_pull_mode(mode: GpioPullMode) -> gpio_pull_mode_t {
-    match mode {
-        GpioPullMode::PullUp => 1,     // GPIO_PULLUP_ONLY
-        GpioPullMode::PullDown => 2,   // GPIO_PULLDOWN_ONLY
-        GpioPullMode::PullUpDown => 3, // GPIO_PULLUP_PULLDOWN
-        GpioPullMode::Floating => 0,   // GPIO_FLOATING
-    }
-}
+impl GlitchFilter {
+    /// 引脚毛刺过滤器的过滤宽度（纳秒）
+    pub const PIN_FILTER_NS: u32 = 25;
 
-/// 类型转换辅助函数
-#[inline]
-pub(crate) fn convert_intr_type(intr_type: GpioInterruptType) -> gpio_int_type_t {
-    match intr_type {
-        GpioInterruptType::Disable => 0,     // GPIO_INTR_DISABLE
-        GpioInterruptType::RisingEdge => 2,  // GPIO_INTR_POSEDGE
-        GpioInterruptType::FallingEdge => 3, // GPIO_INTR_NEGEDGE
-        GpioInterruptType::AnyEdge => 4,     // GPIO_INTR_ANYEDGE
-        GpioInterruptType::LowLevel => 1,    // GPIO_INTR_LOW_LEVEL
-        GpioInterruptType::HighLevel => 5,   // GPIO_INTR_HIGH_LEVEL
+    /// 输入电平需要保持多久才会被接受（纳秒）
+    pub fn threshold_ns(&self) -> u32 {
+        match *self {
+            GlitchFilter::Pin => Self::PIN_FILTER_NS,
+            GlitchFilter::Flex { threshold_ns, .. } => threshold_ns,
+        }
     }
-}
 
-/// 类型转换辅助函数
-#[inline]
-pub(crate) fn convert_drive_cap(cap: GpioDriveCap) -> gpio_drive_cap_t {
-    match cap {
-        GpioDriveCap::Weak => 0,      // GPIO_DRIVE_CAP_0
-        GpioDriveCap::Stronger => 1,  // GPIO_DRIVE_CAP_1
-        GpioDriveCap::Medium => 2,    // GPIO_DRIVE_CAP_2
-        GpioDriveCap::Strongest => 3, // GPIO_DRIVE_CAP_3
+    /// 检查灵活过滤器的窗口参数
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            GlitchFilter::Pin => true,
+            GlitchFilter::Flex {
+                window_ns,
+                threshold_ns,
+            } => threshold_ns > 0 && threshold_ns <= window_ns,
+        }
     }
 }
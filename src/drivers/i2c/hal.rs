@@ -0,0 +1,40 @@
+/**
+ * @file hal.rs
+ * @brief SoftI2c 的 embedded-hal 1.0 接口实现
+ * @details 让基于 embedded-hal 的I2C传感器和编解码驱动可以直接使用软件I2C主机:
+ *          - `embedded_hal::i2c::I2c`，事务内读写方向改变时发送重复起始条件
+ *          - 地址、数据无应答和仲裁失败映射到对应的 `ErrorKind`，其他错误归入 `Other`
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use embedded_hal::i2c::{self, ErrorKind, ErrorType, NoAcknowledgeSource, Operation};
+
+use crate::drivers::i2c::soft::SoftI2c;
+use crate::drivers::i2c::types::I2cError;
+use crate::error::Error;
+
+impl i2c::Error for Error {
+    fn kind(&self) -> ErrorKind {
+        match self.i2c() {
+            Some(I2cError::AddressNack) => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+            Some(I2cError::DataNack) => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+            Some(I2cError::ArbitrationLost) => ErrorKind::ArbitrationLoss,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+impl ErrorType for SoftI2c {
+    type Error = Error;
+}
+
+impl i2c::I2c for SoftI2c {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        SoftI2c::transaction(self, address, operations)
+    }
+}
@@ -0,0 +1,14 @@
+/**
+ * @file mod.rs
+ * @brief I2C模块导出文件
+ * @details 目前只有软件I2C主机，可以使用任意两个GPIO，适合外接板上随意分配的传感器引脚
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+mod hal; // embedded-hal 1.0 接口实现
+mod soft;
+mod types;
+
+pub use soft::SoftI2c;
+pub use types::*;
@@ -0,0 +1,494 @@
+/**
+ * @file soft.rs
+ * @brief 软件I2C主机
+ * @details 用任意两个GPIO模拟I2C主机时序:
+ *          - SCL、SDA 配置为带内部上拉的开漏双向引脚（GpioMode::InputOutputOpenDrain）
+ *          - 释放SCL后等待从机松开时钟，支持时钟延展，超时返回错误
+ *          - 一次事务中读写方向改变时发送重复起始条件，中间不发送停止条件
+ *          - 发送1时检测SDA是否被其他主机拉低（仲裁失败）
+ *          延时由GPIO后端提供，在主机上推进模拟芯片的虚拟时钟，可以配合模拟从机测试
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use embedded_hal::i2c::Operation;
+
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::pin::{GpioPin, OpenDrain};
+use crate::drivers::i2c::types::{I2cError, I2cResult, SoftI2cConfig};
+use crate::error::Error;
+
+/// 软件I2C主机
+pub struct SoftI2c {
+    scl: GpioPin<OpenDrain>,
+    sda: GpioPin<OpenDrain>,
+    /// 半个时钟周期（微秒）
+    half_period_us: u32,
+    /// 时钟延展超时（微秒）
+    stretch_timeout_us: u32,
+}
+
+impl SoftI2c {
+    /// 创建软件I2C主机
+    ///
+    /// # 参数
+    ///
+    /// * `scl` - 时钟引脚，任意模式，创建时转换为开漏
+    /// * `sda` - 数据引脚，任意模式，创建时转换为开漏
+    /// * `config` - 频率和时钟延展超时
+    ///
+    /// # 返回
+    ///
+    /// 频率为0时返回 `I2cError::InvalidParameter`
+    pub fn new<SCL, SDA>(
+        scl: GpioPin<SCL>,
+        sda: GpioPin<SDA>,
+        config: &SoftI2cConfig,
+    ) -> I2cResult<Self> {
+        if config.frequency_hz == 0 {
+            return Err(I2cError::InvalidParameter.into());
+        }
+        Ok(SoftI2c {
+            scl: scl.into_open_drain()?,
+            sda: sda.into_open_drain()?,
+            half_period_us: 500_000u32.div_ceil(config.frequency_hz),
+            stretch_timeout_us: config.stretch_timeout.as_micros().min(u32::MAX as u128) as u32,
+        })
+    }
+
+    /// 向从机写入数据
+    ///
+    /// # 参数
+    ///
+    /// * `address` - 7位从机地址
+    /// * `bytes` - 发送数据，为空时只发送地址
+    pub fn write(&mut self, address: u8, bytes: &[u8]) -> I2cResult<()> {
+        self.transaction(address, &mut [Operation::Write(bytes)])
+    }
+
+    /// 从从机读取数据，最后一个字节回复NACK
+    ///
+    /// # 参数
+    ///
+    /// * `address` - 7位从机地址
+    /// * `buffer` - 接收数据缓冲区
+    pub fn read(&mut self, address: u8, buffer: &mut [u8]) -> I2cResult<()> {
+        self.transaction(address, &mut [Operation::Read(buffer)])
+    }
+
+    /// 先写后读，中间使用重复起始条件，常用于读取寄存器
+    ///
+    /// # 参数
+    ///
+    /// * `address` - 7位从机地址
+    /// * `bytes` - 发送数据（通常是寄存器地址）
+    /// * `buffer` - 接收数据缓冲区
+    pub fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> I2cResult<()> {
+        self.transaction(
+            address,
+            &mut [Operation::Write(bytes), Operation::Read(buffer)],
+        )
+    }
+
+    /// 检查地址上是否有从机应答
+    pub fn probe(&mut self, address: u8) -> I2cResult<bool> {
+        match self.write(address, &[]) {
+            Ok(()) => Ok(true),
+            Err(e) if e.i2c() == Some(&I2cError::AddressNack) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 在一次起始和停止条件之间依次执行所有操作
+    ///
+    /// 相邻的同方向操作连续传输，方向改变时发送重复起始条件和地址。
+    /// 出错时同样发送停止条件释放总线，返回最先出现的错误。
+    ///
+    /// # 参数
+    ///
+    /// * `address` - 7位从机地址
+    /// * `operations` - 读写操作
+    pub fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> I2cResult<()> {
+        if address > 0x7F {
+            return Err(I2cError::InvalidParameter.into());
+        }
+        if operations.is_empty() {
+            return Ok(());
+        }
+        let result = self.run(address, operations);
+        let stop = self.stop();
+        result.and(stop)
+    }
+
+    /// 释放I2C主机，取回两个引脚
+    pub fn release(self) -> (GpioPin<OpenDrain>, GpioPin<OpenDrain>) {
+        (self.scl, self.sda)
+    }
+
+    /// 执行事务中的操作，不发送停止条件
+    fn run(&self, address: u8, operations: &mut [Operation<'_>]) -> I2cResult<()> {
+        let reads: Vec<bool> = operations
+            .iter()
+            .map(|op| matches!(op, Operation::Read(_)))
+            .collect();
+        for (index, operation) in operations.iter_mut().enumerate() {
+            let read = reads[index];
+            if index == 0 || reads[index - 1] != read {
+                self.start()?;
+                if !self.write_byte((address << 1) | u8::from(read))? {
+                    return Err(I2cError::AddressNack.into());
+                }
+            }
+            // 后面紧跟读操作时最后一个字节仍回复ACK，继续读取
+            let more = reads.get(index + 1) == Some(&true);
+            match operation {
+                Operation::Write(bytes) => {
+                    for &byte in bytes.iter() {
+                        if !self.write_byte(byte)? {
+                            return Err(I2cError::DataNack.into());
+                        }
+                    }
+                }
+                Operation::Read(buffer) => {
+                    let len = buffer.len();
+                    for (i, byte) in buffer.iter_mut().enumerate() {
+                        *byte = self.read_byte(i + 1 < len || more)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 等待半个时钟周期
+    fn delay(&self) {
+        Backend::delay_us(self.half_period_us);
+    }
+
+    /// 释放SCL，等待从机松开时钟
+    fn release_scl(&self) -> I2cResult<()> {
+        self.scl.set_high()?;
+        let mut waited = 0;
+        while self.scl.is_low() {
+            if waited >= self.stretch_timeout_us {
+                return Err(Error::new(I2cError::Timeout).with_pin(self.scl.get_pin_number()));
+            }
+            Backend::delay_us(1);
+            waited += 1;
+        }
+        Ok(())
+    }
+
+    /// 起始条件，SCL为低时调用即为重复起始条件
+    fn start(&self) -> I2cResult<()> {
+        self.sda.set_high()?;
+        self.delay();
+        self.release_scl()?;
+        self.delay();
+        self.sda.set_low()?;
+        self.delay();
+        self.scl.set_low()
+    }
+
+    /// 停止条件，结束后两根线都被释放
+    fn stop(&self) -> I2cResult<()> {
+        self.sda.set_low()?;
+        self.delay();
+        self.release_scl()?;
+        self.delay();
+        self.sda.set_high()?;
+        self.delay();
+        Ok(())
+    }
+
+    /// 发送一位，SCL为低时设置SDA，高电平期间从机采样
+    fn write_bit(&self, bit: bool) -> I2cResult<()> {
+        self.sda.set_level(u32::from(bit))?;
+        self.delay();
+        self.release_scl()?;
+        // 释放的SDA被拉低，说明另一个主机正在发送0
+        if bit && self.sda.is_low() {
+            return Err(Error::new(I2cError::ArbitrationLost).with_pin(self.sda.get_pin_number()));
+        }
+        self.delay();
+        self.scl.set_low()
+    }
+
+    /// 接收一位，在SCL高电平结束前采样
+    fn read_bit(&self) -> I2cResult<bool> {
+        self.sda.set_high()?;
+        self.delay();
+        self.release_scl()?;
+        self.delay();
+        let bit = self.sda.is_high();
+        self.scl.set_low()?;
+        Ok(bit)
+    }
+
+    /// 发送一个字节（高位在前），返回从机是否应答
+    fn write_byte(&self, byte: u8) -> I2cResult<bool> {
+        for bit in (0..8).rev() {
+            self.write_bit(byte & (1 << bit) != 0)?;
+        }
+        Ok(!self.read_bit()?)
+    }
+
+    /// 接收一个字节（高位在前），`ack` 为 false 时回复NACK表示读取结束
+    fn read_byte(&self, ack: bool) -> I2cResult<u8> {
+        let mut byte = 0;
+        for _ in 0..8 {
+            byte = (byte << 1) | u8::from(self.read_bit()?);
+        }
+        self.write_bit(!ack)?;
+        Ok(byte)
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::ffi::c_void;
+    use std::ptr;
+    use std::time::Duration;
+
+    use crate::drivers::gpio::backend::sim::{self, Waveform};
+    use crate::drivers::gpio::types::GpioInterruptType;
+
+    const SCL: i32 = 4;
+    const SDA: i32 = 5;
+    const ADDRESS: u8 = 0x48;
+
+    /// 模拟从机的收发阶段
+    #[derive(Default, PartialEq)]
+    enum Phase {
+        #[default]
+        Idle,
+        Address,
+        Write,
+        Read,
+    }
+
+    /// 挂在模拟总线上的4字节寄存器从机
+    ///
+    /// 写入的第一个字节为寄存器指针，之后的字节依次写入寄存器，指针越界时回复NACK；
+    /// 读取从指针处开始，循环递增。
+    #[derive(Default)]
+    struct Device {
+        registers: [u8; 4],
+        pointer: usize,
+        phase: Phase,
+        /// 写操作中是否已经收到寄存器指针
+        has_pointer: bool,
+        shift: u8,
+        /// 当前字节已经过的时钟数，8表示应答位
+        bits: u8,
+        /// 应答地址后拉低SCL的时间，`Some(None)` 表示一直不松开
+        stretch: Option<Option<u64>>,
+        starts: u32,
+        stops: u32,
+    }
+
+    impl Device {
+        /// SCL上升沿：采样主机发送的位或主机的应答
+        fn on_scl_rising(&mut self, sda: u32) {
+            match self.phase {
+                Phase::Address | Phase::Write if self.bits < 8 => {
+                    self.shift = (self.shift << 1) | sda as u8;
+                    self.bits += 1;
+                }
+                Phase::Read if self.bits < 8 => self.bits += 1,
+                // 主机回复NACK，读取结束
+                Phase::Read if sda == 1 => self.phase = Phase::Idle,
+                Phase::Read => self.bits = 9,
+                _ => {}
+            }
+        }
+
+        /// SCL下降沿：返回SDA的新电平（`None` 为保持）以及是否延展时钟
+        fn on_scl_falling(&mut self) -> (Option<u32>, bool) {
+            match (&self.phase, self.bits) {
+                (Phase::Address, 8) => {
+                    if self.shift >> 1 != ADDRESS {
+                        self.phase = Phase::Idle;
+                        return (None, false);
+                    }
+                    self.bits = 9;
+                    (Some(0), false)
+                }
+                (Phase::Address, 9) => {
+                    let stretch = self.stretch.is_some();
+                    self.bits = 0;
+                    if self.shift & 1 == 1 {
+                        self.phase = Phase::Read;
+                        (Some(self.next_bit()), stretch)
+                    } else {
+                        self.phase = Phase::Write;
+                        self.has_pointer = false;
+                        (Some(1), stretch)
+                    }
+                }
+                (Phase::Write, 8) => {
+                    if !self.has_pointer {
+                        self.pointer = self.shift as usize;
+                        self.has_pointer = true;
+                    } else if self.pointer < self.registers.len() {
+                        self.registers[self.pointer] = self.shift;
+                        self.pointer += 1;
+                    } else {
+                        self.phase = Phase::Idle;
+                        return (None, false);
+                    }
+                    self.bits = 9;
+                    (Some(0), false)
+                }
+                (Phase::Write, 9) => {
+                    self.bits = 0;
+                    (Some(1), false)
+                }
+                // 释放SDA，等待主机应答
+                (Phase::Read, 8) => (Some(1), false),
+                (Phase::Read, _) => {
+                    self.bits %= 9;
+                    if self.bits == 0 {
+                        self.pointer = (self.pointer + 1) % self.registers.len();
+                    }
+                    (Some(self.next_bit()), false)
+                }
+                _ => (None, false),
+            }
+        }
+
+        /// 当前读取字节中下一个要发送的位
+        fn next_bit(&self) -> u32 {
+            u32::from(self.registers[self.pointer] >> (7 - self.bits) & 1)
+        }
+    }
+
+    thread_local! {
+        static DEVICE: RefCell<Device> = RefCell::new(Device::default());
+    }
+
+    unsafe extern "C" fn on_scl(_arg: *mut c_void) {
+        let rising = sim::pad_level(SCL) == 1;
+        let sda = sim::pad_level(SDA);
+        let (level, stretch) = DEVICE.with(|device| {
+            let mut device = device.borrow_mut();
+            if rising {
+                device.on_scl_rising(sda);
+                (None, None)
+            } else {
+                let (level, stretch) = device.on_scl_falling();
+                (level, stretch.then_some(device.stretch).flatten())
+            }
+        });
+        // 释放借用后再驱动引脚，引脚变化会再次进入ISR
+        if let Some(level) = level {
+            sim::drive(SDA, level);
+        }
+        if let Some(stretch) = stretch {
+            sim::drive(SCL, 0);
+            if let Some(us) = stretch {
+                sim::play(SCL, &Waveform::new().then(Duration::from_micros(us), 1));
+            }
+        }
+    }
+
+    unsafe extern "C" fn on_sda(_arg: *mut c_void) {
+        if sim::pad_level(SCL) == 0 {
+            return;
+        }
+        let rising = sim::pad_level(SDA) == 1;
+        DEVICE.with(|device| {
+            let mut device = device.borrow_mut();
+            if rising {
+                device.stops += 1;
+                device.phase = Phase::Idle;
+            } else {
+                device.starts += 1;
+                device.phase = Phase::Address;
+                device.shift = 0;
+                device.bits = 0;
+            }
+        });
+    }
+
+    /// 创建主机并把模拟从机挂到总线上
+    fn attach(config: &SoftI2cConfig) -> SoftI2c {
+        sim::reset();
+        DEVICE.with(|device| *device.borrow_mut() = Device::default());
+        let i2c = SoftI2c::new(
+            GpioPin::new(SCL as u32).unwrap(),
+            GpioPin::new(SDA as u32).unwrap(),
+            config,
+        )
+        .unwrap();
+        Backend::install_isr_service(0).unwrap();
+        for (pin, isr) in [(SCL, on_scl as unsafe extern "C" fn(_)), (SDA, on_sda)] {
+            Backend::set_interrupt_type(pin, GpioInterruptType::AnyEdge).unwrap();
+            Backend::set_interrupt_enabled(pin, true).unwrap();
+            Backend::isr_handler_add(pin, Some(isr), ptr::null_mut()).unwrap();
+        }
+        i2c
+    }
+
+    #[test]
+    fn test_register_write_and_repeated_start_read() {
+        let mut i2c = attach(&SoftI2cConfig::default());
+
+        i2c.write(ADDRESS, &[0x01, 0xAB, 0xCD]).unwrap();
+        assert_eq!(DEVICE.with(|d| d.borrow().registers), [0, 0xAB, 0xCD, 0]);
+
+        // 写寄存器指针和读取之间是重复起始条件，没有停止条件
+        let mut buffer = [0; 3];
+        i2c.write_read(ADDRESS, &[0x01], &mut buffer).unwrap();
+        assert_eq!(buffer, [0xAB, 0xCD, 0x00]);
+        assert_eq!(
+            DEVICE.with(|d| (d.borrow().starts, d.borrow().stops)),
+            (3, 2)
+        );
+        assert_eq!((sim::pad_level(SCL), sim::pad_level(SDA)), (1, 1));
+
+        let error = i2c.write(0x50, &[0x00]).unwrap_err();
+        assert_eq!(error.i2c(), Some(&I2cError::AddressNack));
+        let error = i2c.write(ADDRESS, &[0x03, 0x11, 0x22]).unwrap_err();
+        assert_eq!(error.i2c(), Some(&I2cError::DataNack));
+        assert_eq!(DEVICE.with(|d| d.borrow().registers[3]), 0x11);
+
+        assert!(i2c.probe(ADDRESS).unwrap());
+        assert!(!i2c.probe(0x50).unwrap());
+        assert_eq!(
+            i2c.write(0x80, &[]).unwrap_err().i2c(),
+            Some(&I2cError::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn test_clock_stretching_and_timeout() {
+        let config = SoftI2cConfig {
+            stretch_timeout: Duration::from_micros(200),
+            ..SoftI2cConfig::default()
+        };
+        let mut i2c = attach(&config);
+        let mut buffer = [0; 1];
+
+        let start = sim::now_us();
+        i2c.read(ADDRESS, &mut buffer).unwrap();
+        let normal = sim::now_us() - start;
+
+        // 从机在应答位的下降沿拉低SCL，主机半个周期后释放时钟，再等待45us
+        DEVICE.with(|d| d.borrow_mut().stretch = Some(Some(50)));
+        let start = sim::now_us();
+        i2c.read(ADDRESS, &mut buffer).unwrap();
+        assert_eq!(sim::now_us() - start, normal + 45);
+
+        // 从机一直不松开时钟
+        DEVICE.with(|d| d.borrow_mut().stretch = Some(None));
+        let error = i2c.read(ADDRESS, &mut buffer).unwrap_err();
+        assert_eq!(error.i2c(), Some(&I2cError::Timeout));
+        assert_eq!(
+            error.resource(),
+            Some(crate::error::Resource::Pin(SCL as u32))
+        );
+    }
+}
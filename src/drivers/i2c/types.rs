@@ -0,0 +1,60 @@
+/**
+ * @file types.rs
+ * @brief I2C 类型定义
+ * @details 软件I2C主机的配置和错误类型，与具体实现无关，可以在主机上测试
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::fmt;
+use std::time::Duration;
+
+/// I2C错误类型
+///
+/// 作为 [`crate::error::ErrorKind::I2c`] 出现在统一错误类型中。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum I2cError {
+    /// 参数错误（地址超过7位、频率为0等）
+    InvalidParameter,
+    /// 从机没有应答地址
+    AddressNack,
+    /// 从机没有应答数据
+    DataNack,
+    /// 释放SDA后总线仍为低电平，被其他主机占用
+    ArbitrationLost,
+    /// 从机拉低SCL（时钟延展）超时
+    Timeout,
+}
+
+impl fmt::Display for I2cError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            I2cError::InvalidParameter => write!(f, "参数错误"),
+            I2cError::AddressNack => write!(f, "地址无应答"),
+            I2cError::DataNack => write!(f, "数据无应答"),
+            I2cError::ArbitrationLost => write!(f, "总线仲裁失败"),
+            I2cError::Timeout => write!(f, "时钟延展超时"),
+        }
+    }
+}
+
+/// I2C操作结果类型
+pub type I2cResult<T> = Result<T, crate::error::Error>;
+
+/// 软件I2C配置
+#[derive(Debug, Clone)]
+pub struct SoftI2cConfig {
+    /// SCL频率（Hz），实际频率受微秒级延时精度限制，只会比设定值低
+    pub frequency_hz: u32,
+    /// 释放SCL后等待从机松开时钟的最长时间
+    pub stretch_timeout: Duration,
+}
+
+impl Default for SoftI2cConfig {
+    fn default() -> Self {
+        Self {
+            frequency_hz: 100_000, // 标准模式100kHz
+            stretch_timeout: Duration::from_millis(1),
+        }
+    }
+}
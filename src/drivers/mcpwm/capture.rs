@@ -0,0 +1,258 @@
+/**
+ * @file capture.rs
+ * @brief 输入捕获
+ * @details 记录输入引脚上每个边沿的捕获定时器计数值，并由此测量外部信号:
+ *          - 边沿在中断中写入队列，可以逐个读取，适合红外等脉冲序列解码
+ *          - 频率、周期、占空比和脉宽测量取多个完整周期的平均值，并支持超时
+ *          - 捕获通道在 ESP-IDF 上是硬件 MCPWM 捕获，在主机上由GPIO模拟后端的边沿中断模拟
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::time::{Duration, Instant};
+
+#[cfg(target_os = "espidf")]
+use super::esp::CaptureUnit;
+#[cfg(not(target_os = "espidf"))]
+use super::sim::CaptureUnit;
+use crate::drivers::gpio::{EventStream, GpioPin, InputMode};
+use crate::drivers::mcpwm::types::{
+    analyze, CaptureConfig, CaptureEdges, CaptureEvent, McpwmError, McpwmResult, PulseMeasurement,
+    CAPTURE_QUEUE_LEN,
+};
+use crate::error::Error;
+
+/// 输入捕获通道
+///
+/// 持有输入引脚，上下拉由引脚模式决定。释放时删除捕获通道并归还引脚，
+/// 同一MCPWM组的捕获定时器由该组的所有捕获通道共用。
+pub struct McpwmCapture {
+    unit: CaptureUnit,
+    events: EventStream<CaptureEvent, CAPTURE_QUEUE_LEN>,
+    /// 上次检查时队列的丢弃计数
+    dropped: u32,
+    config: CaptureConfig,
+    pin: GpioPin,
+}
+
+impl McpwmCapture {
+    /// 创建捕获通道并开始捕获
+    ///
+    /// # 参数
+    ///
+    /// * `pin` - 输入引脚
+    /// * `config` - 捕获的边沿和输入是否反相
+    pub fn new<M: InputMode>(pin: GpioPin<M>, config: &CaptureConfig) -> McpwmResult<Self> {
+        let pin = pin.into_dynamic();
+        let events = EventStream::new();
+        let unit = CaptureUnit::new(&pin, config, events.queue())?;
+        Ok(McpwmCapture {
+            unit,
+            events,
+            dropped: 0,
+            config: *config,
+            pin,
+        })
+    }
+
+    /// 捕获配置
+    pub fn config(&self) -> &CaptureConfig {
+        &self.config
+    }
+
+    /// 输入引脚的GPIO编号
+    pub fn pin_number(&self) -> i32 {
+        self.pin.get_pin_number()
+    }
+
+    /// 捕获定时器的分辨率（Hz）
+    pub fn resolution_hz(&self) -> u32 {
+        self.unit.resolution_hz()
+    }
+
+    /// 非阻塞读取下一个边沿
+    pub fn try_next_edge(&mut self) -> Option<CaptureEvent> {
+        self.events.try_next()
+    }
+
+    /// 等待下一个边沿，超时返回 `McpwmError::Timeout`
+    pub fn next_edge(&mut self, timeout: Duration) -> McpwmResult<CaptureEvent> {
+        self.events
+            .next_timeout(timeout)
+            .ok_or_else(|| self.timeout_error("next_edge"))
+    }
+
+    /// 丢弃队列中尚未读取的边沿
+    pub fn clear(&mut self) {
+        while self.events.try_next().is_some() {}
+        self.dropped = self.events.dropped();
+    }
+
+    /// 因队列满而丢弃的边沿数
+    pub fn dropped(&self) -> u32 {
+        self.events.dropped()
+    }
+
+    /// 测量若干个完整周期的平均周期和脉宽
+    ///
+    /// 队列中已有的边沿也参与测量，只需要测量此后的信号时先调用 [`clear`](Self::clear)。
+    /// 队列曾经溢出时，已有边沿与之后的边沿之间不连续，会被丢弃后重新开始。
+    ///
+    /// # 参数
+    ///
+    /// * `periods` - 参与平均的周期数
+    /// * `timeout` - 整个测量的最长时间，信号停止或频率过低时返回 `McpwmError::Timeout`
+    pub fn measure(&mut self, periods: usize, timeout: Duration) -> McpwmResult<PulseMeasurement> {
+        if periods == 0 {
+            return Err(Error::new(McpwmError::InvalidParameter).with_pin(self.pin_number()));
+        }
+        let deadline = Instant::now() + timeout;
+        let resolution_hz = self.resolution_hz();
+        let mut edges = Vec::with_capacity(periods * 2 + 2);
+        loop {
+            if self.events.dropped() != self.dropped {
+                self.clear();
+                edges.clear();
+            }
+            let event = match self.events.try_next() {
+                Some(event) => event,
+                None => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    self.events
+                        .next_timeout(remaining)
+                        .ok_or_else(|| self.timeout_error("measure"))?
+                }
+            };
+            edges.push(event);
+            if let Some(measurement) = analyze(&edges, resolution_hz) {
+                if measurement.periods() as usize >= periods {
+                    return Ok(measurement);
+                }
+            }
+        }
+    }
+
+    /// 测量平均频率（Hz）
+    pub fn frequency_hz(&mut self, periods: usize, timeout: Duration) -> McpwmResult<f32> {
+        Ok(self.measure(periods, timeout)?.frequency_hz())
+    }
+
+    /// 测量平均周期
+    pub fn period(&mut self, periods: usize, timeout: Duration) -> McpwmResult<Duration> {
+        Ok(self.measure(periods, timeout)?.period())
+    }
+
+    /// 测量平均占空比（0~100），需要捕获两种边沿
+    pub fn duty_percent(&mut self, periods: usize, timeout: Duration) -> McpwmResult<f32> {
+        self.require_both_edges()?;
+        let measurement = self.measure(periods, timeout)?;
+        Ok(measurement.duty_percent().unwrap_or_default())
+    }
+
+    /// 测量平均脉宽（上升沿到下降沿），需要捕获两种边沿
+    pub fn pulse_width(&mut self, periods: usize, timeout: Duration) -> McpwmResult<Duration> {
+        self.require_both_edges()?;
+        let measurement = self.measure(periods, timeout)?;
+        Ok(measurement.pulse_width().unwrap_or_default())
+    }
+
+    fn require_both_edges(&self) -> McpwmResult<()> {
+        if self.config.edges != CaptureEdges::Both {
+            return Err(Error::new(McpwmError::InvalidParameter).with_pin(self.pin_number()));
+        }
+        Ok(())
+    }
+
+    fn timeout_error(&self, operation: &'static str) -> Error {
+        Error::new(McpwmError::Timeout)
+            .with_operation(operation)
+            .with_pin(self.pin_number())
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use crate::drivers::gpio::backend::sim::{self, Waveform};
+    use crate::drivers::gpio::Input;
+    use crate::drivers::mcpwm::types::{CaptureEdge, MCPWM_CAPTURE_CLK_HZ};
+
+    fn input(pin: u32) -> GpioPin<Input> {
+        GpioPin::new(pin).unwrap().into_floating_input().unwrap()
+    }
+
+    /// 从低电平开始的方波，每个周期先低后高
+    fn square(low: Duration, high: Duration, cycles: usize) -> Waveform {
+        (0..cycles).fold(Waveform::new(), |waveform, _| {
+            waveform.then(low, 1).then(high, 0)
+        })
+    }
+
+    #[test]
+    fn test_measure_pwm_signal() {
+        sim::reset();
+        sim::drive(8, 0);
+        let mut capture = McpwmCapture::new(input(8), &CaptureConfig::default()).unwrap();
+        assert_eq!(capture.resolution_hz(), MCPWM_CAPTURE_CLK_HZ);
+
+        // 1kHz，占空比25%
+        let waveform = square(Duration::from_micros(750), Duration::from_micros(250), 6);
+        sim::play(8, &waveform);
+        sim::advance(waveform.duration());
+
+        let first = capture.try_next_edge().unwrap();
+        assert_eq!(first.edge, CaptureEdge::Rising);
+        assert_eq!((first.ticks, first.timestamp_us), (750 * 80, 750));
+
+        let measurement = capture.measure(4, Duration::from_millis(10)).unwrap();
+        assert_eq!(measurement.periods(), 4);
+        assert_eq!(measurement.period(), Duration::from_millis(1));
+        assert_eq!(measurement.frequency_hz(), 1_000.0);
+        assert_eq!(measurement.duty_percent(), Some(25.0));
+        assert_eq!(measurement.pulse_width(), Some(Duration::from_micros(250)));
+
+        // 剩余边沿不足一个完整周期，信号停止后超时
+        let error = capture.measure(1, Duration::from_millis(5)).unwrap_err();
+        assert_eq!(error.mcpwm(), Some(&McpwmError::Timeout));
+        assert!(capture.measure(0, Duration::from_millis(5)).is_err());
+    }
+
+    #[test]
+    fn test_single_edge_invert_and_overflow() {
+        sim::reset();
+        sim::drive(9, 0);
+        // 反相后只捕获上升沿，即引脚上的下降沿
+        let config = CaptureConfig::default()
+            .edges(CaptureEdges::Rising)
+            .invert(true);
+        let mut capture = McpwmCapture::new(input(9), &config).unwrap();
+
+        let waveform = square(Duration::from_micros(100), Duration::from_micros(400), 5);
+        sim::play(9, &waveform);
+        sim::advance(waveform.duration());
+        assert_eq!(capture.next_edge(Duration::ZERO).unwrap().timestamp_us, 500);
+        assert_eq!(
+            capture.frequency_hz(3, Duration::from_millis(10)).unwrap(),
+            2_000.0
+        );
+        assert!(capture.duty_percent(3, Duration::from_millis(10)).is_err());
+        drop(capture);
+
+        // 队列溢出后已有的边沿被丢弃，重新开始测量
+        let mut capture = McpwmCapture::new(input(9), &CaptureConfig::default()).unwrap();
+        let waveform = square(Duration::from_micros(100), Duration::from_micros(100), 40);
+        sim::play(9, &waveform);
+        sim::advance(waveform.duration());
+        assert_eq!(capture.dropped(), 80 - CAPTURE_QUEUE_LEN as u32);
+        assert!(capture.period(2, Duration::from_millis(5)).is_err());
+        assert!(capture.try_next_edge().is_none());
+
+        let waveform = square(Duration::from_micros(300), Duration::from_micros(200), 3);
+        sim::play(9, &waveform);
+        sim::advance(waveform.duration());
+        assert_eq!(
+            capture.pulse_width(2, Duration::from_millis(5)).unwrap(),
+            Duration::from_micros(200)
+        );
+    }
+}
@@ -0,0 +1,282 @@
+/**
+ * @file esp.rs
+ * @brief MCPWM 硬件捕获通道
+ * @details 基于 ESP-IDF mcpwm_cap 驱动（mcpwm_new_capture_timer / mcpwm_new_capture_channel）:
+ *          - 每个MCPWM组只有一个捕获定时器，由该组的捕获通道共用，最后一个通道释放时删除
+ *          - 捕获定时器使用 APB 时钟，边沿到来时硬件锁存计数值，中断中写入边沿队列
+ *          - 通道释放时依次关闭并删除通道，再释放定时器
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+
+use esp_idf_svc::sys::{
+    esp_timer_get_time, mcpwm_cap_channel_handle_t, mcpwm_cap_timer_handle_t,
+    mcpwm_capture_channel_config_t, mcpwm_capture_channel_disable, mcpwm_capture_channel_enable,
+    mcpwm_capture_channel_register_event_callbacks, mcpwm_capture_edge_t_MCPWM_CAP_EDGE_POS,
+    mcpwm_capture_event_callbacks_t, mcpwm_capture_event_data_t, mcpwm_capture_timer_config_t,
+    mcpwm_capture_timer_disable, mcpwm_capture_timer_enable, mcpwm_capture_timer_get_resolution,
+    mcpwm_capture_timer_start, mcpwm_capture_timer_stop, mcpwm_del_capture_channel,
+    mcpwm_del_capture_timer, mcpwm_new_capture_channel, mcpwm_new_capture_timer,
+    soc_periph_mcpwm_capture_clk_src_t_MCPWM_CAPTURE_CLK_SRC_DEFAULT, ESP_ERR_NOT_FOUND, ESP_OK,
+};
+
+use crate::drivers::gpio::event::EventQueue;
+use crate::drivers::gpio::{GpioControl, GpioPin, GpioPullMode};
+use crate::drivers::mcpwm::types::{
+    CaptureConfig, CaptureEdge, CaptureEvent, McpwmError, McpwmResult, CAPTURE_QUEUE_LEN,
+    MCPWM_CAPTURE_CHANNELS, MCPWM_CAPTURE_CLK_HZ, MCPWM_GROUPS,
+};
+use crate::error::Error;
+
+type CaptureQueue = EventQueue<CaptureEvent, CAPTURE_QUEUE_LEN>;
+
+/// 一个MCPWM组的捕获定时器
+struct SharedTimer {
+    handle: mcpwm_cap_timer_handle_t,
+    resolution_hz: u32,
+    /// 使用该定时器的通道数
+    users: usize,
+}
+
+// 句柄只在持有锁时使用
+unsafe impl Send for SharedTimer {}
+
+/// 各MCPWM组的捕获定时器
+static TIMERS: Mutex<[Option<SharedTimer>; MCPWM_GROUPS]> = Mutex::new([None, None]);
+
+/// 获取一个还有空闲通道的捕获定时器，必要时在空闲的组中创建
+///
+/// # 返回
+///
+/// 成功返回组号、定时器句柄和分辨率
+fn acquire_timer(pin: i32) -> McpwmResult<(usize, mcpwm_cap_timer_handle_t, u32)> {
+    let mut timers = TIMERS.lock().unwrap_or_else(|e| e.into_inner());
+    let available = timers
+        .iter()
+        .position(|timer| {
+            timer
+                .as_ref()
+                .is_some_and(|t| t.users < MCPWM_CAPTURE_CHANNELS)
+        })
+        .or_else(|| timers.iter().position(Option::is_none));
+    let Some(group) = available else {
+        return Err(Error::esp(
+            McpwmError::ChannelError,
+            ESP_ERR_NOT_FOUND,
+            "mcpwm_new_capture_channel",
+        )
+        .with_pin(pin));
+    };
+
+    let mut timer = match timers[group].take() {
+        Some(timer) => timer,
+        None => create_timer(group).map_err(|e| e.with_pin(pin))?,
+    };
+    timer.users += 1;
+    let acquired = (group, timer.handle, timer.resolution_hz);
+    timers[group] = Some(timer);
+    Ok(acquired)
+}
+
+/// 释放捕获定时器，最后一个通道释放时删除定时器
+fn release_timer(group: usize) {
+    let mut timers = TIMERS.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(timer) = timers[group].as_mut() else {
+        return;
+    };
+    timer.users -= 1;
+    if timer.users == 0 {
+        let handle = timer.handle;
+        timers[group] = None;
+        unsafe {
+            mcpwm_capture_timer_stop(handle);
+            mcpwm_capture_timer_disable(handle);
+            mcpwm_del_capture_timer(handle);
+        }
+    }
+}
+
+/// 在指定组中创建并启动捕获定时器
+fn create_timer(group: usize) -> McpwmResult<SharedTimer> {
+    let config = mcpwm_capture_timer_config_t {
+        group_id: group as i32,
+        clk_src: soc_periph_mcpwm_capture_clk_src_t_MCPWM_CAPTURE_CLK_SRC_DEFAULT,
+        ..Default::default()
+    };
+    let mut handle: mcpwm_cap_timer_handle_t = ptr::null_mut();
+    Error::check(
+        unsafe { mcpwm_new_capture_timer(&config, &mut handle) },
+        McpwmError::TimerError,
+        "mcpwm_new_capture_timer",
+    )?;
+
+    let mut resolution_hz = MCPWM_CAPTURE_CLK_HZ;
+    let result = unsafe {
+        Error::check(
+            mcpwm_capture_timer_get_resolution(handle, &mut resolution_hz),
+            McpwmError::TimerError,
+            "mcpwm_capture_timer_get_resolution",
+        )
+        .and_then(|_| {
+            Error::check(
+                mcpwm_capture_timer_enable(handle),
+                McpwmError::TimerError,
+                "mcpwm_capture_timer_enable",
+            )
+        })
+    };
+    if let Err(error) = result {
+        unsafe { mcpwm_del_capture_timer(handle) };
+        return Err(error);
+    }
+    let code = unsafe { mcpwm_capture_timer_start(handle) };
+    if let Err(error) = Error::check(code, McpwmError::TimerError, "mcpwm_capture_timer_start") {
+        unsafe {
+            mcpwm_capture_timer_disable(handle);
+            mcpwm_del_capture_timer(handle);
+        }
+        return Err(error);
+    }
+    Ok(SharedTimer {
+        handle,
+        resolution_hz,
+        users: 0,
+    })
+}
+
+/// 捕获中断入口，参数为边沿队列
+unsafe extern "C" fn capture_trampoline(
+    _channel: mcpwm_cap_channel_handle_t,
+    edata: *const mcpwm_capture_event_data_t,
+    user_ctx: *mut c_void,
+) -> bool {
+    let queue = &*(user_ctx as *const CaptureQueue);
+    let edge = if (*edata).cap_edge == mcpwm_capture_edge_t_MCPWM_CAP_EDGE_POS {
+        CaptureEdge::Rising
+    } else {
+        CaptureEdge::Falling
+    };
+    queue.push(CaptureEvent {
+        edge,
+        ticks: (*edata).cap_value,
+        timestamp_us: esp_timer_get_time() as u64,
+    });
+    // 回调中没有唤醒更高优先级的任务
+    false
+}
+
+/// MCPWM硬件捕获通道
+pub(super) struct CaptureUnit {
+    channel: mcpwm_cap_channel_handle_t,
+    enabled: bool,
+    /// 捕获定时器所在的组
+    group: usize,
+    resolution_hz: u32,
+    /// 输入引脚，用于错误信息
+    pin: i32,
+    /// 中断回调参数指向的边沿队列，通道删除前保持有效
+    _queue: Arc<CaptureQueue>,
+}
+
+// 句柄只在持有者的任务中使用
+unsafe impl Send for CaptureUnit {}
+
+impl CaptureUnit {
+    /// 创建捕获通道并开始捕获
+    pub(super) fn new(
+        pin: &GpioPin,
+        config: &CaptureConfig,
+        queue: Arc<CaptureQueue>,
+    ) -> McpwmResult<Self> {
+        let gpio_num = pin.get_pin_number();
+        let (group, timer, resolution_hz) = acquire_timer(gpio_num)?;
+        let mut unit = CaptureUnit {
+            channel: ptr::null_mut(),
+            enabled: false,
+            group,
+            resolution_hz,
+            pin: gpio_num,
+            _queue: queue,
+        };
+
+        // 创建通道时驱动会按 flags 重新配置引脚的上下拉，这里沿用引脚当前的上下拉，
+        // 否则上拉输入的按键在开始捕获后会变成浮空
+        let pull = GpioControl::pin_snapshot(gpio_num as u32)?.pull;
+        let mut channel_config = mcpwm_capture_channel_config_t {
+            gpio_num,
+            prescale: 1,
+            ..Default::default()
+        };
+        channel_config.flags.set_pull_up(u32::from(matches!(
+            pull,
+            GpioPullMode::PullUp | GpioPullMode::PullUpDown
+        )));
+        channel_config.flags.set_pull_down(u32::from(matches!(
+            pull,
+            GpioPullMode::PullDown | GpioPullMode::PullUpDown
+        )));
+        let rising = config.edges.captures(CaptureEdge::Rising);
+        let falling = config.edges.captures(CaptureEdge::Falling);
+        channel_config.flags.set_pos_edge(u32::from(rising));
+        channel_config.flags.set_neg_edge(u32::from(falling));
+        channel_config
+            .flags
+            .set_invert_cap_signal(u32::from(config.invert));
+        let code = unsafe { mcpwm_new_capture_channel(timer, &channel_config, &mut unit.channel) };
+        unit.check(code, McpwmError::ChannelError, "mcpwm_new_capture_channel")?;
+
+        let callbacks = mcpwm_capture_event_callbacks_t {
+            on_cap: Some(capture_trampoline),
+        };
+        let user_ctx = Arc::as_ptr(&unit._queue) as *mut c_void;
+        let code = unsafe {
+            mcpwm_capture_channel_register_event_callbacks(unit.channel, &callbacks, user_ctx)
+        };
+        unit.check(
+            code,
+            McpwmError::ChannelError,
+            "mcpwm_capture_channel_register_event_callbacks",
+        )?;
+
+        let code = unsafe { mcpwm_capture_channel_enable(unit.channel) };
+        unit.check(
+            code,
+            McpwmError::ChannelError,
+            "mcpwm_capture_channel_enable",
+        )?;
+        unit.enabled = true;
+        Ok(unit)
+    }
+
+    /// 捕获定时器的分辨率（Hz）
+    pub(super) fn resolution_hz(&self) -> u32 {
+        self.resolution_hz
+    }
+
+    /// 检查ESP-IDF返回值
+    fn check(&self, code: i32, error: McpwmError, operation: &'static str) -> McpwmResult<()> {
+        if code != ESP_OK {
+            return Err(Error::esp(error, code, operation).with_pin(self.pin));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for CaptureUnit {
+    fn drop(&mut self) {
+        unsafe {
+            if self.enabled {
+                mcpwm_capture_channel_disable(self.channel);
+            }
+            // 通道删除后中断不再触发，边沿队列随后才会释放
+            if !self.channel.is_null() {
+                mcpwm_del_capture_channel(self.channel);
+            }
+        }
+        release_timer(self.group);
+    }
+}
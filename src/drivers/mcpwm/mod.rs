@@ -0,0 +1,21 @@
+/**
+ * @file mod.rs
+ * @brief 电机控制PWM（MCPWM）捕获驱动
+ * @details 用于测量风扇测速、红外接收和传感器PWM输出等外部信号:
+ *          - 捕获通道以 APB 时钟分辨率记录输入引脚上的每个边沿
+ *          - 在边沿时间戳上测量频率、周期、占空比和脉宽，取多个周期的平均值
+ *          - 边沿和测量都支持超时，信号停止时不会一直等待
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+// 捕获通道在ESP-IDF上使用硬件MCPWM捕获，在主机上由GPIO模拟后端模拟
+mod capture;
+#[cfg(target_os = "espidf")]
+mod esp;
+#[cfg(not(target_os = "espidf"))]
+mod sim;
+mod types;
+
+pub use capture::*;
+pub use types::*;
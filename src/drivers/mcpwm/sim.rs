@@ -0,0 +1,62 @@
+/**
+ * @file sim.rs
+ * @brief 主机模拟捕获通道
+ * @details 订阅输入引脚在GPIO模拟后端上的边沿中断，按硬件捕获通道的规则记录边沿:
+ *          - 计数值由虚拟时钟换算为 APB 时钟周期，32位循环计数
+ *          - 按配置过滤边沿方向，输入反相时边沿方向随之反转
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::sync::Arc;
+
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::event::EventQueue;
+use crate::drivers::gpio::{GpioInterruptType, GpioPin, GpioSubscription};
+use crate::drivers::mcpwm::types::{
+    CaptureConfig, CaptureEdge, CaptureEvent, McpwmResult, CAPTURE_QUEUE_LEN, MCPWM_CAPTURE_CLK_HZ,
+};
+
+/// 模拟捕获通道
+pub(super) struct CaptureUnit {
+    _subscription: GpioSubscription,
+}
+
+impl CaptureUnit {
+    /// 创建捕获通道并开始捕获
+    pub(super) fn new(
+        pin: &GpioPin,
+        config: &CaptureConfig,
+        queue: Arc<EventQueue<CaptureEvent, CAPTURE_QUEUE_LEN>>,
+    ) -> McpwmResult<Self> {
+        let gpio_num = pin.get_pin_number();
+        let config = *config;
+        pin.set_interrupt_type(GpioInterruptType::AnyEdge)?;
+        let subscription = pin.subscribe(move || {
+            let level = Backend::get_level(gpio_num) ^ u32::from(config.invert);
+            let edge = if level == 1 {
+                CaptureEdge::Rising
+            } else {
+                CaptureEdge::Falling
+            };
+            if !config.edges.captures(edge) {
+                return;
+            }
+            let timestamp_us = Backend::now_us();
+            let ticks_per_us = u64::from(MCPWM_CAPTURE_CLK_HZ / 1_000_000);
+            queue.push(CaptureEvent {
+                edge,
+                ticks: timestamp_us.wrapping_mul(ticks_per_us) as u32,
+                timestamp_us,
+            });
+        })?;
+        Ok(CaptureUnit {
+            _subscription: subscription,
+        })
+    }
+
+    /// 捕获定时器的分辨率（Hz）
+    pub(super) fn resolution_hz(&self) -> u32 {
+        MCPWM_CAPTURE_CLK_HZ
+    }
+}
@@ -0,0 +1,270 @@
+/**
+ * @file types.rs
+ * @brief MCPWM 捕获类型定义
+ * @details 错误类型、捕获配置、边沿事件以及由边沿时间戳计算周期和占空比的测量结果，
+ *          这些定义不依赖硬件，可以在主机上测试
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::fmt;
+use std::time::Duration;
+
+/// 捕获定时器时钟频率（APB，80MHz），时间戳分辨率为12.5ns
+pub const MCPWM_CAPTURE_CLK_HZ: u32 = 80_000_000;
+
+/// MCPWM组数，每组有一个捕获定时器
+pub const MCPWM_GROUPS: usize = 2;
+
+/// 每组的捕获通道数
+pub const MCPWM_CAPTURE_CHANNELS: usize = 3;
+
+/// 每个捕获通道的边沿队列容量
+pub const CAPTURE_QUEUE_LEN: usize = 64;
+
+/// MCPWM错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McpwmError {
+    /// 参数错误（测量周期数为0，或边沿配置不支持所需的测量）
+    InvalidParameter,
+    /// 捕获定时器创建或控制失败
+    TimerError,
+    /// 捕获通道创建或控制失败
+    ChannelError,
+    /// 在超时时间内没有捕获到足够的边沿
+    Timeout,
+}
+
+impl fmt::Display for McpwmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            McpwmError::InvalidParameter => write!(f, "参数错误"),
+            McpwmError::TimerError => write!(f, "捕获定时器错误"),
+            McpwmError::ChannelError => write!(f, "捕获通道错误"),
+            McpwmError::Timeout => write!(f, "捕获超时"),
+        }
+    }
+}
+
+/// MCPWM操作结果类型
+pub type McpwmResult<T> = Result<T, crate::error::Error>;
+
+/// 边沿方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaptureEdge {
+    /// 上升沿
+    #[default]
+    Rising,
+    /// 下降沿
+    Falling,
+}
+
+/// 捕获哪些边沿
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaptureEdges {
+    /// 只捕获上升沿，可测量周期和频率
+    Rising,
+    /// 只捕获下降沿，可测量周期和频率
+    Falling,
+    /// 捕获两种边沿，还可以测量占空比和脉宽
+    #[default]
+    Both,
+}
+
+impl CaptureEdges {
+    /// 是否捕获指定方向的边沿
+    pub fn captures(self, edge: CaptureEdge) -> bool {
+        match self {
+            CaptureEdges::Rising => edge == CaptureEdge::Rising,
+            CaptureEdges::Falling => edge == CaptureEdge::Falling,
+            CaptureEdges::Both => true,
+        }
+    }
+}
+
+/// 捕获通道配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CaptureConfig {
+    /// 捕获的边沿
+    pub edges: CaptureEdges,
+    /// 捕获前是否将输入反相，低电平有效的信号（如红外接收头）反相后脉宽即为低电平时间
+    pub invert: bool,
+}
+
+impl CaptureConfig {
+    /// 指定捕获的边沿
+    pub fn edges(mut self, edges: CaptureEdges) -> Self {
+        self.edges = edges;
+        self
+    }
+
+    /// 指定输入是否反相
+    pub fn invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+}
+
+/// 捕获到的边沿
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CaptureEvent {
+    /// 边沿方向（输入反相时为反相后的方向）
+    pub edge: CaptureEdge,
+    /// 捕获定时器的计数值，32位循环计数，两次捕获之差即为间隔
+    pub ticks: u32,
+    /// esp_timer 时间戳（微秒）
+    pub timestamp_us: u64,
+}
+
+/// 周期和脉宽测量结果
+///
+/// 保存若干个完整周期的累计时长，各项结果为平均值。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PulseMeasurement {
+    resolution_hz: u32,
+    periods: u32,
+    period_ticks: u64,
+    pulses: u32,
+    high_ticks: u64,
+}
+
+impl PulseMeasurement {
+    /// 参与平均的周期数
+    pub fn periods(&self) -> u32 {
+        self.periods
+    }
+
+    /// 平均周期
+    pub fn period(&self) -> Duration {
+        ticks_to_duration(self.period_ticks, self.periods, self.resolution_hz)
+    }
+
+    /// 平均频率（Hz）
+    pub fn frequency_hz(&self) -> f32 {
+        (f64::from(self.resolution_hz) * f64::from(self.periods) / self.period_ticks as f64) as f32
+    }
+
+    /// 平均脉宽（上升沿到下降沿），只捕获一种边沿时为 `None`
+    pub fn pulse_width(&self) -> Option<Duration> {
+        (self.pulses > 0)
+            .then(|| ticks_to_duration(self.high_ticks, self.pulses, self.resolution_hz))
+    }
+
+    /// 占空比（0~100），只捕获一种边沿时为 `None`
+    pub fn duty_percent(&self) -> Option<f32> {
+        (self.pulses > 0).then(|| {
+            let high = self.high_ticks as f64 / f64::from(self.pulses);
+            let period = self.period_ticks as f64 / f64::from(self.periods);
+            (high * 100.0 / period) as f32
+        })
+    }
+}
+
+/// 累计计数值换算为平均时长
+fn ticks_to_duration(ticks: u64, count: u32, resolution_hz: u32) -> Duration {
+    let nanos = u128::from(ticks) * 1_000_000_000 / (u128::from(resolution_hz) * u128::from(count));
+    Duration::from_nanos(nanos as u64)
+}
+
+/// 由一串连续的边沿计算周期和脉宽
+///
+/// 两种边沿都有时以上升沿为周期起点，一个周期内必须恰好有一个下降沿，
+/// 否则说明丢失了边沿，该周期不参与平均。只有一种边沿时相邻两个边沿之间为一个周期。
+/// 计数值按32位循环处理，单个周期不能超过计数器的回绕时间（APB时钟下约53秒）。
+///
+/// # 返回
+///
+/// 至少有一个完整周期时返回测量结果
+pub fn analyze(events: &[CaptureEvent], resolution_hz: u32) -> Option<PulseMeasurement> {
+    let has = |edge| events.iter().any(|event| event.edge == edge);
+    let both = has(CaptureEdge::Rising) && has(CaptureEdge::Falling);
+    let reference = if both {
+        CaptureEdge::Rising
+    } else {
+        events.first()?.edge
+    };
+
+    let mut measurement = PulseMeasurement {
+        resolution_hz,
+        periods: 0,
+        period_ticks: 0,
+        pulses: 0,
+        high_ticks: 0,
+    };
+    // 当前周期的起点，以及周期内的下降沿数和高电平时长
+    let mut start: Option<u32> = None;
+    let mut falls = 0;
+    let mut high = 0;
+    for event in events {
+        if event.edge != reference {
+            if let Some(start) = start {
+                falls += 1;
+                high = event.ticks.wrapping_sub(start);
+            }
+            continue;
+        }
+        if let Some(start) = start {
+            if !both || falls == 1 {
+                measurement.periods += 1;
+                measurement.period_ticks += u64::from(event.ticks.wrapping_sub(start));
+            }
+            if both && falls == 1 {
+                measurement.pulses += 1;
+                measurement.high_ticks += u64::from(high);
+            }
+        }
+        start = Some(event.ticks);
+        falls = 0;
+    }
+    (measurement.periods > 0).then_some(measurement)
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+
+    fn edge(edge: CaptureEdge, ticks: u32) -> CaptureEvent {
+        CaptureEvent {
+            edge,
+            ticks,
+            timestamp_us: 0,
+        }
+    }
+
+    #[test]
+    fn test_analyze_skips_broken_periods() {
+        use CaptureEdge::{Falling, Rising};
+
+        // 周期100、高电平30，计数器在第二个周期内回绕；第三个周期丢失了下降沿
+        let start = u32::MAX - 49;
+        let events = [
+            edge(Falling, start.wrapping_sub(70)),
+            edge(Rising, start),
+            edge(Falling, start.wrapping_add(30)),
+            edge(Rising, start.wrapping_add(100)),
+            edge(Falling, start.wrapping_add(130)),
+            edge(Rising, start.wrapping_add(200)),
+            edge(Rising, start.wrapping_add(300)),
+            edge(Falling, start.wrapping_add(330)),
+            edge(Rising, start.wrapping_add(400)),
+        ];
+        let measurement = analyze(&events, 1_000).unwrap();
+        assert_eq!(measurement.periods(), 3);
+        assert_eq!(measurement.period(), Duration::from_millis(100));
+        assert_eq!(measurement.frequency_hz(), 10.0);
+        assert_eq!(measurement.pulse_width(), Some(Duration::from_millis(30)));
+        assert_eq!(measurement.duty_percent(), Some(30.0));
+
+        // 只有一种边沿时只能测量周期
+        let falling: Vec<_> = (0..4).map(|i| edge(Falling, i * 250)).collect();
+        let measurement = analyze(&falling, 1_000).unwrap();
+        assert_eq!(measurement.periods(), 3);
+        assert_eq!(measurement.frequency_hz(), 4.0);
+        assert_eq!(measurement.duty_percent(), None);
+
+        assert!(analyze(&events[..2], 1_000).is_none());
+        assert!(analyze(&[], 1_000).is_none());
+        assert!(CaptureEdges::Both.captures(Falling));
+        assert!(!CaptureEdges::Rising.captures(Falling));
+    }
+}
@@ -1,3 +1,8 @@
 pub mod atk_md0130;
 pub mod gpio;
+pub mod i2c;
+pub mod mcpwm;
+pub mod pcnt;
+pub mod pwm;
+pub mod rmt;
 pub mod spi;
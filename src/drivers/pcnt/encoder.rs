@@ -0,0 +1,515 @@
+/**
+ * @file encoder.rs
+ * @brief 正交编码器
+ * @details 用一个计数单元解码两路正交信号:
+ *          - 计数寄存器只有16位，到达上下限时清零，溢出次数累加为 i64 位置
+ *          - 观察点事件、上下限事件和按步长的位置变化事件通过回调或 `EventStream` 投递，与GPIO中断事件使用同一套队列
+ *          - 计数单元在 ESP-IDF 上是硬件 PCNT，在主机上由GPIO模拟后端的边沿中断软件解码
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicI32, AtomicI64, AtomicU32, Ordering};
+use std::sync::Arc;
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+
+#[cfg(target_os = "espidf")]
+use super::esp::PcntUnit;
+#[cfg(not(target_os = "espidf"))]
+use super::sim::PcntUnit;
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::{EventStream, GpioPin, InputMode};
+use crate::drivers::pcnt::types::{
+    check_watch_point, EncoderConfig, PcntError, PcntEvent, PcntEventKind, PcntResult,
+};
+use crate::error::Error;
+
+/// 事件回调类型
+type EventCallback = Box<dyn FnMut(PcntEvent) + Send + 'static>;
+
+/// 计数单元的共享状态，由计数中断和编码器共同访问
+pub(super) struct Counter {
+    low_limit: i32,
+    high_limit: i32,
+    /// 计数到达上限的次数
+    overflows: AtomicI32,
+    /// 计数到达下限的次数
+    underflows: AtomicI32,
+    /// 溢出次数的更新序号，更新前后各加1，奇数表示正在更新
+    sequence: AtomicU32,
+    /// 位置变化事件的步长，0表示不产生
+    step: AtomicU32,
+    /// 最近一次位置变化事件时到达的步长倍数
+    last_step: AtomicI64,
+    /// 事件回调，中断和任务都在临界区内访问
+    callback: Mutex<CriticalSectionRawMutex, RefCell<Option<EventCallback>>>,
+}
+
+impl Counter {
+    fn new(config: &EncoderConfig) -> Self {
+        Counter {
+            low_limit: config.low_limit,
+            high_limit: config.high_limit,
+            overflows: AtomicI32::new(0),
+            underflows: AtomicI32::new(0),
+            sequence: AtomicU32::new(0),
+            step: AtomicU32::new(0),
+            last_step: AtomicI64::new(0),
+            callback: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// 计数到达观察点，在中断上下文中调用
+    ///
+    /// 到达上下限时计数器已被硬件清零，先累加溢出次数再投递事件。
+    pub(super) fn on_watch_point(&self, value: i32) {
+        let (kind, count) = if value == self.high_limit {
+            self.update(|| self.overflows.fetch_add(1, Ordering::AcqRel));
+            (PcntEventKind::Overflow, 0)
+        } else if value == self.low_limit {
+            self.update(|| self.underflows.fetch_add(1, Ordering::AcqRel));
+            (PcntEventKind::Underflow, 0)
+        } else {
+            (PcntEventKind::WatchPoint, value)
+        };
+        self.emit(kind, value, self.position(count));
+    }
+
+    /// 计数变化后调用，在中断上下文中执行
+    ///
+    /// 位置跨过步长的整数倍时投递 `Step` 事件。正转时向下取整、反转时向上取整，
+    /// 在同一个倍数附近来回抖动不会重复产生事件，漏读中间的计数也不会漏掉跨过的倍数。
+    pub(super) fn on_position(&self, position: i64) {
+        let step = i64::from(self.step.load(Ordering::Acquire));
+        if step == 0 {
+            return;
+        }
+        let last = self.last_step.load(Ordering::Acquire);
+        let reached = if position >= (last + 1) * step {
+            position.div_euclid(step)
+        } else if position <= (last - 1) * step {
+            -(-position).div_euclid(step)
+        } else {
+            return;
+        };
+        self.last_step.store(reached, Ordering::Release);
+        self.emit(PcntEventKind::Step, step as i32, position);
+    }
+
+    /// 投递事件给订阅的回调
+    fn emit(&self, kind: PcntEventKind, watch_point: i32, position: i64) {
+        let event = PcntEvent {
+            kind,
+            watch_point,
+            position,
+            timestamp_us: Backend::now_us(),
+        };
+        self.callback.lock(|callback| {
+            if let Some(callback) = callback.borrow_mut().as_mut() {
+                callback(event);
+            }
+        });
+    }
+
+    /// 设置位置变化事件的步长，0表示停止产生；以当前位置作为起点
+    fn set_step(&self, step: u32, position: i64) {
+        self.step.store(0, Ordering::Release);
+        if step != 0 {
+            self.last_step
+                .store(position.div_euclid(i64::from(step)), Ordering::Release);
+            self.step.store(step, Ordering::Release);
+        }
+    }
+
+    /// 在序号保护下更新溢出次数
+    fn update<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.sequence.fetch_add(1, Ordering::AcqRel);
+        let result = f();
+        self.sequence.fetch_add(1, Ordering::AcqRel);
+        result
+    }
+
+    /// 由当前计数得到累计位置
+    pub(super) fn position(&self, count: i32) -> i64 {
+        i64::from(self.overflows.load(Ordering::Acquire)) * i64::from(self.high_limit)
+            + i64::from(self.underflows.load(Ordering::Acquire)) * i64::from(self.low_limit)
+            + i64::from(count)
+    }
+
+    /// 读取计数并得到累计位置
+    ///
+    /// 读取计数与读取溢出次数之间可能发生溢出中断，此时计数已被清零而溢出次数又加了一次，
+    /// 同一段计数会被算两次。读取前后比较更新序号，序号变化或正在更新时重新读取。
+    pub(super) fn read_position(&self, count: impl Fn() -> PcntResult<i32>) -> PcntResult<i64> {
+        loop {
+            let sequence = self.sequence.load(Ordering::Acquire);
+            if sequence % 2 != 0 {
+                std::hint::spin_loop();
+                continue;
+            }
+            let position = self.position(count()?);
+            if self.sequence.load(Ordering::Acquire) == sequence {
+                return Ok(position);
+            }
+        }
+    }
+
+    /// 清除累计的溢出次数
+    fn reset(&self) {
+        self.update(|| {
+            self.overflows.store(0, Ordering::Release);
+            self.underflows.store(0, Ordering::Release);
+        });
+        self.last_step.store(0, Ordering::Release);
+    }
+
+    /// 替换事件回调，返回原来的回调
+    fn replace_callback(&self, callback: Option<EventCallback>) -> Option<EventCallback> {
+        self.callback.lock(|slot| slot.replace(callback))
+    }
+
+    /// 是否已注册事件回调
+    fn has_callback(&self) -> bool {
+        self.callback.lock(|slot| slot.borrow().is_some())
+    }
+}
+
+/// 正交编码器
+///
+/// 持有A、B两路输入引脚，A相超前B相时计数增加。每个完整的正交周期计4次（4倍频）。
+/// 释放时停止计数、删除计数单元并归还引脚。
+pub struct PcntEncoder {
+    unit: PcntUnit,
+    counter: Arc<Counter>,
+    config: EncoderConfig,
+    pins: [GpioPin; 2],
+}
+
+impl PcntEncoder {
+    /// 创建编码器并开始计数
+    ///
+    /// # 参数
+    ///
+    /// * `pin_a` - A相输入引脚，上下拉由引脚模式决定（机械编码器通常使用上拉输入）
+    /// * `pin_b` - B相输入引脚
+    /// * `config` - 计数范围和毛刺过滤宽度
+    pub fn new<A: InputMode, B: InputMode>(
+        pin_a: GpioPin<A>,
+        pin_b: GpioPin<B>,
+        config: &EncoderConfig,
+    ) -> PcntResult<Self> {
+        config.validate()?;
+        let pins = [pin_a.into_dynamic(), pin_b.into_dynamic()];
+        let counter = Arc::new(Counter::new(config));
+        let unit = PcntUnit::new(&pins, config, counter.clone())?;
+        Ok(PcntEncoder {
+            unit,
+            counter,
+            config: *config,
+            pins,
+        })
+    }
+
+    /// 编码器配置
+    pub fn config(&self) -> &EncoderConfig {
+        &self.config
+    }
+
+    /// A相引脚的GPIO编号，同时作为编码器在事件流中的编号
+    pub fn pin_number(&self) -> i32 {
+        self.pins[0].get_pin_number()
+    }
+
+    /// 计数寄存器的当前值，范围为计数上下限之间
+    pub fn count(&self) -> PcntResult<i32> {
+        self.unit.count()
+    }
+
+    /// 累计位置，包含计数到达上下限时清零的部分
+    pub fn position(&self) -> PcntResult<i64> {
+        self.counter.read_position(|| self.unit.count())
+    }
+
+    /// 将位置清零
+    pub fn reset(&mut self) -> PcntResult<()> {
+        self.unit.clear()?;
+        self.counter.reset();
+        Ok(())
+    }
+
+    /// 添加观察点，计数到达该值时产生 `PcntEventKind::WatchPoint` 事件
+    ///
+    /// 除0以外最多可以添加 `PCNT_THRES_POINTS` 个观察点，上下限已被编码器用于累计位置。
+    pub fn add_watch_point(&mut self, value: i32) -> PcntResult<()> {
+        check_watch_point(value, &self.config).map_err(|e| e.with_pin(self.pin_number()))?;
+        self.unit.add_watch_point(value)
+    }
+
+    /// 删除观察点，上下限不能删除
+    pub fn remove_watch_point(&mut self, value: i32) -> PcntResult<()> {
+        if value == self.config.low_limit || value == self.config.high_limit {
+            return Err(Error::new(PcntError::InvalidParameter).with_pin(self.pin_number()));
+        }
+        self.unit.remove_watch_point(value)
+    }
+
+    /// 按步长产生位置变化事件
+    ///
+    /// 累计位置每跨过 `step` 的整数倍产生一个 `PcntEventKind::Step` 事件，不受计数上下限影响。
+    /// 例如每格4个计数的旋钮使用 `add_watch_step(4)`，每转过一格产生一个事件。再次调用替换原来的步长。
+    ///
+    /// ESP32-S3 的 PCNT 没有步长比较器，硬件上由两路引脚的GPIO边沿中断驱动，
+    /// 每个边沿读取一次经过毛刺过滤的计数，因此两路引脚不能再单独订阅中断。
+    ///
+    /// # 返回
+    ///
+    /// 步长为0或超过 `i32::MAX` 时返回 `InvalidParameter`
+    pub fn add_watch_step(&mut self, step: u32) -> PcntResult<()> {
+        if step == 0 || step > i32::MAX as u32 {
+            return Err(Error::new(PcntError::InvalidParameter).with_pin(self.pin_number()));
+        }
+        self.unit.watch_steps(&self.pins)?;
+        self.counter.set_step(step, self.position()?);
+        Ok(())
+    }
+
+    /// 停止产生位置变化事件
+    pub fn remove_watch_step(&mut self) -> PcntResult<()> {
+        if self.counter.step.load(Ordering::Acquire) == 0 {
+            return Err(Error::new(PcntError::InvalidParameter).with_pin(self.pin_number()));
+        }
+        self.counter.set_step(0, 0);
+        self.unit.unwatch_steps();
+        Ok(())
+    }
+
+    /// 订阅计数事件
+    ///
+    /// 回调运行在中断上下文中，应只做标志位、原子计数或队列投递等轻量操作。
+    /// 同一时间只能有一个订阅。
+    ///
+    /// # 返回
+    ///
+    /// 成功返回订阅句柄，句柄释放时自动取消订阅
+    pub fn subscribe<F>(&self, callback: F) -> PcntResult<PcntSubscription>
+    where
+        F: FnMut(PcntEvent) + Send + 'static,
+    {
+        if self.counter.has_callback() {
+            return Err(Error::new(PcntError::UnitError)
+                .with_operation("subscribe")
+                .with_pin(self.pin_number()));
+        }
+        self.counter.replace_callback(Some(Box::new(callback)));
+        Ok(PcntSubscription {
+            counter: self.counter.clone(),
+        })
+    }
+}
+
+/// 计数事件订阅
+///
+/// 由 [`PcntEncoder::subscribe`] 返回，释放时注销回调。
+#[must_use = "订阅在被释放时会立即取消"]
+pub struct PcntSubscription {
+    counter: Arc<Counter>,
+}
+
+impl Drop for PcntSubscription {
+    fn drop(&mut self) {
+        // 回调在临界区外释放
+        drop(self.counter.replace_callback(None));
+    }
+}
+
+impl<const N: usize> EventStream<PcntEvent, N> {
+    /// 开始记录编码器的计数事件
+    pub fn listen(&mut self, encoder: &PcntEncoder) -> PcntResult<()> {
+        let queue = self.queue();
+        let subscription = encoder.subscribe(move |event| queue.push(event))?;
+        self.add_source(encoder.pin_number(), subscription);
+        Ok(())
+    }
+
+    /// 停止记录编码器的计数事件，已入队的事件保留
+    pub fn unlisten(&mut self, encoder: &PcntEncoder) -> PcntResult<()> {
+        if !self.remove_source(encoder.pin_number()) {
+            return Err(Error::new(PcntError::InvalidParameter).with_pin(encoder.pin_number()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use crate::drivers::gpio::backend::sim::{self, Waveform};
+    use crate::drivers::gpio::Input;
+    use crate::drivers::pcnt::types::PCNT_THRES_POINTS;
+    use std::time::Duration;
+
+    fn pins(a: u32, b: u32) -> (GpioPin<Input>, GpioPin<Input>) {
+        let a = GpioPin::new(a).unwrap().into_floating_input().unwrap();
+        let b = GpioPin::new(b).unwrap().into_floating_input().unwrap();
+        (a, b)
+    }
+
+    /// 正交波形：A相超前为正方向，每个周期4个边沿
+    fn turn(a: i32, b: i32, cycles: usize, forward: bool) {
+        let step = Duration::from_micros(50);
+        let (lead, lag) = if forward { (a, b) } else { (b, a) };
+        for _ in 0..cycles {
+            for (pin, level) in [(lead, 1), (lag, 1), (lead, 0), (lag, 0)] {
+                sim::play(pin, &Waveform::new().then(step, level));
+                sim::advance(step * 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_position_accumulates_overflow() {
+        sim::reset();
+        let (a, b) = pins(4, 5);
+        let config = EncoderConfig::default().limits(-8, 8);
+        let mut encoder = PcntEncoder::new(a, b, &config).unwrap();
+        let mut events: EventStream<PcntEvent> = EventStream::new();
+        events.listen(&encoder).unwrap();
+
+        // 5个周期共20个计数，跨过两次上限
+        turn(4, 5, 5, true);
+        assert_eq!(encoder.count().unwrap(), 4);
+        assert_eq!(encoder.position().unwrap(), 20);
+        let overflow = events.try_next().unwrap();
+        assert_eq!(overflow.kind, PcntEventKind::Overflow);
+        assert_eq!(overflow.position, 8);
+        assert_eq!(events.try_next().unwrap().position, 16);
+        assert!(events.try_next().is_none());
+
+        turn(4, 5, 8, false);
+        assert_eq!(encoder.position().unwrap(), -12);
+        assert_eq!(events.len(), 3);
+
+        encoder.reset().unwrap();
+        assert_eq!(encoder.position().unwrap(), 0);
+        events.unlisten(&encoder).unwrap();
+        assert!(events.unlisten(&encoder).is_err());
+    }
+
+    #[test]
+    fn test_watch_points_and_glitch_filter() {
+        sim::reset();
+        let (a, b) = pins(6, 7);
+        let config = EncoderConfig::default().glitch_filter(Some(2_000));
+        let mut encoder = PcntEncoder::new(a, b, &config).unwrap();
+
+        encoder.add_watch_point(6).unwrap();
+        encoder.add_watch_point(0).unwrap();
+        encoder.add_watch_point(-3).unwrap();
+        assert_eq!(PCNT_THRES_POINTS, 2);
+        assert!(encoder.add_watch_point(9).is_err());
+        assert!(encoder.add_watch_point(6).is_err());
+        assert!(encoder.remove_watch_point(config.high_limit).is_err());
+
+        let reached = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = reached.clone();
+        let subscription = encoder
+            .subscribe(move |event| log.lock().unwrap().push(event.watch_point))
+            .unwrap();
+        assert!(encoder.subscribe(|_| {}).is_err());
+
+        // 边沿间隔短于过滤宽度，被当作毛刺忽略
+        for _ in 0..4 {
+            sim::play(6, &Waveform::new().then(Duration::from_micros(1), 1));
+            sim::play(6, &Waveform::new().then(Duration::from_micros(2), 0));
+            sim::advance(Duration::from_micros(10));
+        }
+        assert_eq!(encoder.count().unwrap(), 0);
+
+        // 慢速转动，每个边沿间隔大于过滤宽度
+        let step = Duration::from_micros(100);
+        for (pin, level) in [(6, 1), (7, 1), (6, 0), (7, 0)].repeat(2) {
+            sim::play(pin, &Waveform::new().then(step, level));
+            sim::advance(step * 2);
+        }
+        assert_eq!(encoder.count().unwrap(), 8);
+        assert_eq!(*reached.lock().unwrap(), vec![6]);
+
+        drop(subscription);
+        encoder.remove_watch_point(6).unwrap();
+        assert!(encoder.subscribe(|_| {}).is_ok());
+    }
+
+    #[test]
+    fn test_watch_step_events() {
+        sim::reset();
+        let (a, b) = pins(8, 9);
+        let config = EncoderConfig::default().limits(-8, 8);
+        let mut encoder = PcntEncoder::new(a, b, &config).unwrap();
+        assert!(encoder.add_watch_step(0).is_err());
+        assert!(encoder.remove_watch_step().is_err());
+        encoder.add_watch_step(4).unwrap();
+        let mut events: EventStream<PcntEvent> = EventStream::new();
+        events.listen(&encoder).unwrap();
+
+        // 每个正交周期4个计数，每转过一格产生一个事件，跨过上限时先投递溢出事件
+        turn(8, 9, 3, true);
+        let kinds: Vec<_> = std::iter::from_fn(|| events.try_next())
+            .map(|event| (event.kind, event.position))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (PcntEventKind::Step, 4),
+                (PcntEventKind::Overflow, 8),
+                (PcntEventKind::Step, 8),
+                (PcntEventKind::Step, 12),
+            ]
+        );
+
+        // 反转一格回到8，只产生一个事件
+        turn(8, 9, 1, false);
+        let step = events.try_next().unwrap();
+        assert_eq!(
+            (step.kind, step.position, step.watch_point),
+            (PcntEventKind::Step, 8, 4)
+        );
+        assert!(events.try_next().is_none());
+
+        // 在格点附近来回抖动不产生事件
+        let edge = Duration::from_micros(50);
+        for level in [1, 0] {
+            sim::play(8, &Waveform::new().then(edge, level));
+            sim::advance(edge * 2);
+        }
+        assert_eq!(encoder.position().unwrap(), 8);
+        assert!(events.try_next().is_none());
+
+        encoder.remove_watch_step().unwrap();
+        turn(8, 9, 1, true);
+        assert!(events.try_next().is_none());
+    }
+
+    #[test]
+    fn test_position_rereads_after_overflow() {
+        sim::reset();
+        let counter = Counter::new(&EncoderConfig::default().limits(-8, 8));
+        let reads = std::cell::Cell::new(0);
+        // 第一次读到7后计数到达上限并被清零，溢出中断在读取溢出次数之前发生
+        let position = counter
+            .read_position(|| {
+                reads.set(reads.get() + 1);
+                if reads.get() == 1 {
+                    counter.on_watch_point(8);
+                    Ok(7)
+                } else {
+                    Ok(0)
+                }
+            })
+            .unwrap();
+        assert_eq!(position, 8);
+        assert_eq!(reads.get(), 2);
+    }
+}
@@ -0,0 +1,294 @@
+/**
+ * @file esp.rs
+ * @brief PCNT 硬件计数单元
+ * @details 基于 ESP-IDF pulse_cnt 驱动（pcnt_new_unit / pcnt_new_channel）:
+ *          - 两个通道互为边沿和电平输入，组成4倍频正交解码
+ *          - 上下限作为观察点加入，到达时由中断累加溢出次数
+ *          - 芯片没有步长比较器，按步长的位置变化事件由两路引脚的GPIO边沿中断读取计数产生
+ *          - 单元释放时依次停止计数、删除通道和单元
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::Arc;
+
+use esp_idf_svc::sys::{
+    pcnt_chan_config_t, pcnt_channel_edge_action_t_PCNT_CHANNEL_EDGE_ACTION_DECREASE,
+    pcnt_channel_edge_action_t_PCNT_CHANNEL_EDGE_ACTION_INCREASE, pcnt_channel_handle_t,
+    pcnt_channel_level_action_t_PCNT_CHANNEL_LEVEL_ACTION_INVERSE,
+    pcnt_channel_level_action_t_PCNT_CHANNEL_LEVEL_ACTION_KEEP, pcnt_channel_set_edge_action,
+    pcnt_channel_set_level_action, pcnt_del_channel, pcnt_del_unit, pcnt_event_callbacks_t,
+    pcnt_glitch_filter_config_t, pcnt_new_channel, pcnt_new_unit, pcnt_unit_add_watch_point,
+    pcnt_unit_clear_count, pcnt_unit_config_t, pcnt_unit_disable, pcnt_unit_enable,
+    pcnt_unit_get_count, pcnt_unit_handle_t, pcnt_unit_register_event_callbacks,
+    pcnt_unit_remove_watch_point, pcnt_unit_set_glitch_filter, pcnt_unit_start, pcnt_unit_stop,
+    pcnt_watch_event_data_t, ESP_OK,
+};
+
+use super::encoder::Counter;
+use crate::drivers::gpio::{GpioInterruptType, GpioPin, GpioSubscription};
+use crate::drivers::pcnt::types::{EncoderConfig, PcntError, PcntResult};
+use crate::error::Error;
+
+/// 观察点中断入口，参数为编码器的共享状态
+unsafe extern "C" fn watch_trampoline(
+    _unit: pcnt_unit_handle_t,
+    edata: *const pcnt_watch_event_data_t,
+    user_ctx: *mut c_void,
+) -> bool {
+    let counter = &*(user_ctx as *const Counter);
+    counter.on_watch_point((*edata).watch_point_value);
+    // 回调中没有唤醒更高优先级的任务
+    false
+}
+
+/// 在GPIO中断回调中读取计数的单元句柄
+#[derive(Clone, Copy)]
+struct UnitHandle(pcnt_unit_handle_t);
+
+// pcnt_unit_get_count 可以在中断中调用，句柄在订阅取消后才会删除
+unsafe impl Send for UnitHandle {}
+
+impl UnitHandle {
+    fn count(self) -> PcntResult<i32> {
+        let mut count = 0;
+        let code = unsafe { pcnt_unit_get_count(self.0, &mut count) };
+        if code != ESP_OK {
+            return Err(Error::esp(
+                PcntError::UnitError,
+                code,
+                "pcnt_unit_get_count",
+            ));
+        }
+        Ok(count)
+    }
+}
+
+/// PCNT硬件计数单元
+pub(super) struct PcntUnit {
+    unit: pcnt_unit_handle_t,
+    channels: [pcnt_channel_handle_t; 2],
+    enabled: bool,
+    /// 按步长通知时两路引脚的边沿中断订阅
+    step_subscriptions: Vec<GpioSubscription>,
+    /// A相引脚，用于错误信息
+    pin: i32,
+    /// 中断回调参数指向的共享状态，单元删除前保持有效
+    _counter: Arc<Counter>,
+}
+
+// 句柄只在持有者的任务中使用
+unsafe impl Send for PcntUnit {}
+
+impl PcntUnit {
+    /// 创建计数单元并开始计数
+    pub(super) fn new(
+        pins: &[GpioPin; 2],
+        config: &EncoderConfig,
+        counter: Arc<Counter>,
+    ) -> PcntResult<Self> {
+        let [a, b] = [pins[0].get_pin_number(), pins[1].get_pin_number()];
+        let mut unit = PcntUnit {
+            unit: ptr::null_mut(),
+            channels: [ptr::null_mut(); 2],
+            enabled: false,
+            step_subscriptions: Vec::new(),
+            pin: a,
+            _counter: counter,
+        };
+
+        let unit_config = pcnt_unit_config_t {
+            low_limit: config.low_limit,
+            high_limit: config.high_limit,
+            ..Default::default()
+        };
+        let code = unsafe { pcnt_new_unit(&unit_config, &mut unit.unit) };
+        unit.check(code, PcntError::UnitError, "pcnt_new_unit")?;
+
+        if let Some(ns) = config.glitch_filter_ns {
+            let filter_config = pcnt_glitch_filter_config_t { max_glitch_ns: ns };
+            unit.check(
+                unsafe { pcnt_unit_set_glitch_filter(unit.unit, &filter_config) },
+                PcntError::UnitError,
+                "pcnt_unit_set_glitch_filter",
+            )?;
+        }
+
+        // 通道0：A相边沿、B相电平；通道1：B相边沿、A相电平
+        for (index, (edge, level)) in [(a, b), (b, a)].into_iter().enumerate() {
+            let channel_config = pcnt_chan_config_t {
+                edge_gpio_num: edge,
+                level_gpio_num: level,
+                ..Default::default()
+            };
+            let code =
+                unsafe { pcnt_new_channel(unit.unit, &channel_config, &mut unit.channels[index]) };
+            unit.check(code, PcntError::ChannelError, "pcnt_new_channel")?;
+        }
+        unit.set_channel_actions()?;
+
+        for limit in [config.low_limit, config.high_limit] {
+            unit.add_watch_point(limit)?;
+        }
+        let callbacks = pcnt_event_callbacks_t {
+            on_reach: Some(watch_trampoline),
+        };
+        let user_ctx = Arc::as_ptr(&unit._counter) as *mut c_void;
+        unit.check(
+            unsafe { pcnt_unit_register_event_callbacks(unit.unit, &callbacks, user_ctx) },
+            PcntError::UnitError,
+            "pcnt_unit_register_event_callbacks",
+        )?;
+
+        unit.check(
+            unsafe { pcnt_unit_enable(unit.unit) },
+            PcntError::UnitError,
+            "pcnt_unit_enable",
+        )?;
+        unit.enabled = true;
+        unit.clear()?;
+        unit.check(
+            unsafe { pcnt_unit_start(unit.unit) },
+            PcntError::UnitError,
+            "pcnt_unit_start",
+        )?;
+        Ok(unit)
+    }
+
+    /// 配置正交解码规则，A相超前B相时计数增加
+    fn set_channel_actions(&self) -> PcntResult<()> {
+        let [channel_a, channel_b] = self.channels;
+        let results = unsafe {
+            [
+                pcnt_channel_set_edge_action(
+                    channel_a,
+                    pcnt_channel_edge_action_t_PCNT_CHANNEL_EDGE_ACTION_DECREASE,
+                    pcnt_channel_edge_action_t_PCNT_CHANNEL_EDGE_ACTION_INCREASE,
+                ),
+                pcnt_channel_set_level_action(
+                    channel_a,
+                    pcnt_channel_level_action_t_PCNT_CHANNEL_LEVEL_ACTION_KEEP,
+                    pcnt_channel_level_action_t_PCNT_CHANNEL_LEVEL_ACTION_INVERSE,
+                ),
+                pcnt_channel_set_edge_action(
+                    channel_b,
+                    pcnt_channel_edge_action_t_PCNT_CHANNEL_EDGE_ACTION_INCREASE,
+                    pcnt_channel_edge_action_t_PCNT_CHANNEL_EDGE_ACTION_DECREASE,
+                ),
+                pcnt_channel_set_level_action(
+                    channel_b,
+                    pcnt_channel_level_action_t_PCNT_CHANNEL_LEVEL_ACTION_KEEP,
+                    pcnt_channel_level_action_t_PCNT_CHANNEL_LEVEL_ACTION_INVERSE,
+                ),
+            ]
+        };
+        for code in results {
+            self.check(code, PcntError::ChannelError, "pcnt_channel_set_action")?;
+        }
+        Ok(())
+    }
+
+    /// 读取计数
+    pub(super) fn count(&self) -> PcntResult<i32> {
+        let mut count = 0;
+        self.check(
+            unsafe { pcnt_unit_get_count(self.unit, &mut count) },
+            PcntError::UnitError,
+            "pcnt_unit_get_count",
+        )?;
+        Ok(count)
+    }
+
+    /// 计数清零
+    pub(super) fn clear(&self) -> PcntResult<()> {
+        self.check(
+            unsafe { pcnt_unit_clear_count(self.unit) },
+            PcntError::UnitError,
+            "pcnt_unit_clear_count",
+        )
+    }
+
+    /// 添加观察点
+    pub(super) fn add_watch_point(&self, value: i32) -> PcntResult<()> {
+        self.check(
+            unsafe { pcnt_unit_add_watch_point(self.unit, value) },
+            PcntError::WatchPointError,
+            "pcnt_unit_add_watch_point",
+        )
+    }
+
+    /// 删除观察点
+    pub(super) fn remove_watch_point(&self, value: i32) -> PcntResult<()> {
+        self.check(
+            unsafe { pcnt_unit_remove_watch_point(self.unit, value) },
+            PcntError::WatchPointError,
+            "pcnt_unit_remove_watch_point",
+        )
+    }
+
+    /// 开始按步长通知：订阅两路引脚的边沿中断，每个边沿读取一次计数
+    ///
+    /// 毛刺过滤使计数比GPIO中断晚最多一个过滤宽度更新，中断响应通常比这更慢；
+    /// 即使读到旧值，下一个边沿也会补上跨过的倍数。
+    pub(super) fn watch_steps(&mut self, pins: &[GpioPin; 2]) -> PcntResult<()> {
+        if !self.step_subscriptions.is_empty() {
+            return Ok(());
+        }
+        for pin in pins {
+            pin.set_interrupt_type(GpioInterruptType::AnyEdge)?;
+            let counter = self._counter.clone();
+            let unit = UnitHandle(self.unit);
+            let subscription = pin.subscribe(move || {
+                if let Ok(position) = counter.read_position(|| unit.count()) {
+                    counter.on_position(position);
+                }
+            });
+            match subscription {
+                Ok(subscription) => self.step_subscriptions.push(subscription),
+                Err(e) => {
+                    self.unwatch_steps();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 停止按步长通知
+    pub(super) fn unwatch_steps(&mut self) {
+        self.step_subscriptions.clear();
+    }
+
+    /// 检查ESP-IDF返回值
+    fn check(&self, code: i32, error: PcntError, operation: &'static str) -> PcntResult<()> {
+        if code != ESP_OK {
+            return Err(Error::esp(error, code, operation).with_pin(self.pin));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PcntUnit {
+    fn drop(&mut self) {
+        // 边沿中断回调持有单元句柄，先于单元取消
+        self.step_subscriptions.clear();
+        if self.unit.is_null() {
+            return;
+        }
+        unsafe {
+            if self.enabled {
+                pcnt_unit_stop(self.unit);
+                pcnt_unit_disable(self.unit);
+            }
+            for channel in self.channels {
+                if !channel.is_null() {
+                    pcnt_del_channel(channel);
+                }
+            }
+            // 单元删除后中断不再触发，共享状态随后才会释放
+            pcnt_del_unit(self.unit);
+        }
+    }
+}
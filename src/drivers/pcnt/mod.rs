@@ -0,0 +1,22 @@
+/**
+ * @file mod.rs
+ * @brief 脉冲计数器（PCNT）驱动
+ * @details 用于旋转编码器等正交信号输入:
+ *          - 两个 GpioPin 组成一个正交编码器，4倍频解码
+ *          - 16位计数寄存器到达上下限时自动清零，溢出累加为 i64 位置
+ *          - 观察点、上下限和按步长的位置变化事件可以通过回调或 `EventStream` 读取
+ *          - 内置毛刺过滤器，滤除机械触点抖动
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+// 计数单元在ESP-IDF上使用硬件PCNT，在主机上由GPIO模拟后端软件解码
+mod encoder;
+#[cfg(target_os = "espidf")]
+mod esp;
+#[cfg(not(target_os = "espidf"))]
+mod sim;
+mod types;
+
+pub use encoder::*;
+pub use types::*;
@@ -0,0 +1,182 @@
+/**
+ * @file sim.rs
+ * @brief 主机模拟计数单元
+ * @details 订阅两路引脚在GPIO模拟后端上的边沿中断，按硬件 PCNT 的规则软件解码:
+ *          - 4倍频正交解码，计数到达上下限时清零并触发观察点事件，每次计数变化检查步长事件
+ *          - 观察点数量与 ESP32-S3 相同（零点之外 `PCNT_THRES_POINTS` 个）
+ *          - 毛刺过滤使用引脚的灵活毛刺过滤器模拟
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::sync::{Arc, Mutex};
+
+use super::encoder::Counter;
+use crate::drivers::gpio::backend::{sim, ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::{GlitchFilter, GpioInterruptType, GpioPin, GpioSubscription};
+use crate::drivers::pcnt::types::{
+    quadrature_phase, quadrature_step, EncoderConfig, PcntError, PcntResult, PCNT_THRES_POINTS,
+};
+use crate::error::esp_codes::{ESP_ERR_INVALID_STATE, ESP_ERR_NOT_FOUND};
+use crate::error::Error;
+
+/// 模拟计数寄存器
+struct SimCounter {
+    count: i32,
+    /// 上一次的正交相位
+    phase: u8,
+    low_limit: i32,
+    high_limit: i32,
+    /// 已添加的观察点，包含上下限
+    watch_points: Vec<i32>,
+}
+
+/// 模拟计数单元
+pub(super) struct PcntUnit {
+    state: Arc<Mutex<SimCounter>>,
+    pins: [i32; 2],
+    filtered: bool,
+    subscriptions: Vec<GpioSubscription>,
+}
+
+impl PcntUnit {
+    /// 创建计数单元并开始计数
+    pub(super) fn new(
+        pins: &[GpioPin; 2],
+        config: &EncoderConfig,
+        counter: Arc<Counter>,
+    ) -> PcntResult<Self> {
+        let numbers = [pins[0].get_pin_number(), pins[1].get_pin_number()];
+        let [a, b] = numbers;
+        let state = Arc::new(Mutex::new(SimCounter {
+            count: 0,
+            phase: quadrature_phase(Backend::get_level(a), Backend::get_level(b)),
+            low_limit: config.low_limit,
+            high_limit: config.high_limit,
+            watch_points: vec![config.low_limit, config.high_limit],
+        }));
+        let mut unit = PcntUnit {
+            state,
+            pins: numbers,
+            filtered: false,
+            subscriptions: Vec::new(),
+        };
+
+        if let Some(ns) = config.glitch_filter_ns {
+            let filter = GlitchFilter::Flex {
+                window_ns: ns,
+                threshold_ns: ns,
+            };
+            unit.filtered = true;
+            for pin in numbers {
+                sim::set_input_filter(pin, Some(filter))?;
+            }
+        }
+
+        for pin in pins {
+            pin.set_interrupt_type(GpioInterruptType::AnyEdge)?;
+            let state = unit.state.clone();
+            let counter = counter.clone();
+            let subscription = pin.subscribe(move || on_edge(&state, &counter, a, b))?;
+            unit.subscriptions.push(subscription);
+        }
+        Ok(unit)
+    }
+
+    /// 读取计数
+    pub(super) fn count(&self) -> PcntResult<i32> {
+        Ok(self.lock().count)
+    }
+
+    /// 计数清零
+    pub(super) fn clear(&self) -> PcntResult<()> {
+        self.lock().count = 0;
+        Ok(())
+    }
+
+    /// 添加观察点
+    pub(super) fn add_watch_point(&self, value: i32) -> PcntResult<()> {
+        let mut state = self.lock();
+        if state.watch_points.contains(&value) {
+            return Err(self.error(ESP_ERR_INVALID_STATE, "pcnt_unit_add_watch_point"));
+        }
+        // 零点和上下限有专用的比较器，其他观察点共用阈值比较器
+        let thresholds = state
+            .watch_points
+            .iter()
+            .filter(|&&point| point != 0 && point != state.low_limit && point != state.high_limit)
+            .count();
+        if value != 0 && thresholds >= PCNT_THRES_POINTS {
+            return Err(self.error(ESP_ERR_NOT_FOUND, "pcnt_unit_add_watch_point"));
+        }
+        state.watch_points.push(value);
+        Ok(())
+    }
+
+    /// 删除观察点
+    pub(super) fn remove_watch_point(&self, value: i32) -> PcntResult<()> {
+        let mut state = self.lock();
+        let Some(index) = state.watch_points.iter().position(|&point| point == value) else {
+            return Err(self.error(ESP_ERR_INVALID_STATE, "pcnt_unit_remove_watch_point"));
+        };
+        state.watch_points.swap_remove(index);
+        Ok(())
+    }
+
+    /// 开始按步长通知，边沿解码时已经检查步长，无需额外订阅
+    pub(super) fn watch_steps(&mut self, _pins: &[GpioPin; 2]) -> PcntResult<()> {
+        Ok(())
+    }
+
+    /// 停止按步长通知
+    pub(super) fn unwatch_steps(&mut self) {}
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SimCounter> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn error(&self, code: i32, operation: &'static str) -> Error {
+        Error::esp(PcntError::WatchPointError, code, operation).with_pin(self.pins[0])
+    }
+}
+
+impl Drop for PcntUnit {
+    fn drop(&mut self) {
+        self.subscriptions.clear();
+        if self.filtered {
+            for pin in self.pins {
+                let _ = sim::set_input_filter(pin, None);
+            }
+        }
+    }
+}
+
+/// 任一路引脚出现边沿时解码，计数到达观察点或计数变化时通知共享状态
+fn on_edge(state: &Mutex<SimCounter>, counter: &Counter, a: i32, b: i32) {
+    let (reached, count) = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        let phase = quadrature_phase(Backend::get_level(a), Backend::get_level(b));
+        let step = quadrature_step(state.phase, phase);
+        state.phase = phase;
+        if step == 0 {
+            return;
+        }
+        state.count += step;
+        let reached = if state.count == state.low_limit || state.count == state.high_limit {
+            // 到达上下限时计数器清零
+            let limit = state.count;
+            state.count = 0;
+            Some(limit)
+        } else if state.watch_points.contains(&state.count) {
+            Some(state.count)
+        } else {
+            None
+        };
+        (reached, state.count)
+    };
+    // 回调可能读取计数，先释放锁；溢出次数累加之后才能得到正确的位置
+    if let Some(value) = reached {
+        counter.on_watch_point(value);
+    }
+    counter.on_position(counter.position(count));
+}
@@ -0,0 +1,201 @@
+/**
+ * @file types.rs
+ * @brief 脉冲计数器类型定义
+ * @details 错误类型、编码器配置、计数事件以及正交解码的相位换算，
+ *          这些定义不依赖硬件，可以在主机上测试
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::fmt;
+
+use crate::error::Error;
+
+/// 计数上限的最大值（计数寄存器为16位有符号数）
+pub const PCNT_LIMIT_MAX: i32 = i16::MAX as i32;
+
+/// 计数下限的最小值
+pub const PCNT_LIMIT_MIN: i32 = i16::MIN as i32;
+
+/// 毛刺过滤器可过滤的最大脉宽（纳秒），即 1023 个 APB 时钟周期
+pub const PCNT_MAX_GLITCH_NS: u32 = 12_787;
+
+/// 每个计数单元除零点和上下限以外可用的观察点数量
+pub const PCNT_THRES_POINTS: usize = 2;
+
+/// 脉冲计数器错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcntError {
+    /// 参数错误（计数范围、过滤宽度或观察点超出范围）
+    InvalidParameter,
+    /// 计数单元创建或控制失败
+    UnitError,
+    /// 计数通道配置失败
+    ChannelError,
+    /// 观察点添加或删除失败
+    WatchPointError,
+}
+
+impl fmt::Display for PcntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PcntError::InvalidParameter => write!(f, "参数错误"),
+            PcntError::UnitError => write!(f, "计数单元错误"),
+            PcntError::ChannelError => write!(f, "计数通道错误"),
+            PcntError::WatchPointError => write!(f, "观察点错误"),
+        }
+    }
+}
+
+/// 脉冲计数器操作结果类型
+pub type PcntResult<T> = Result<T, crate::error::Error>;
+
+/// 正交编码器配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncoderConfig {
+    /// 计数下限，必须小于0；计数到达下限时清零并累加到位置中
+    pub low_limit: i32,
+    /// 计数上限，必须大于0；计数到达上限时清零并累加到位置中
+    pub high_limit: i32,
+    /// 毛刺过滤宽度（纳秒），短于该宽度的脉冲被忽略，`None` 表示不过滤
+    pub glitch_filter_ns: Option<u32>,
+}
+
+impl EncoderConfig {
+    /// 指定计数范围
+    ///
+    /// 每次计数到达上下限都会产生一个事件，例如每个棘爪4个计数的旋钮可以使用 `limits(-4, 4)`，
+    /// 使每转过一格产生一个事件。
+    pub fn limits(mut self, low_limit: i32, high_limit: i32) -> Self {
+        self.low_limit = low_limit;
+        self.high_limit = high_limit;
+        self
+    }
+
+    /// 指定毛刺过滤宽度，`None` 表示不过滤
+    pub fn glitch_filter(mut self, ns: Option<u32>) -> Self {
+        self.glitch_filter_ns = ns;
+        self
+    }
+
+    /// 检查计数范围和过滤宽度
+    pub fn validate(&self) -> PcntResult<()> {
+        let valid = (PCNT_LIMIT_MIN..0).contains(&self.low_limit)
+            && (1..=PCNT_LIMIT_MAX).contains(&self.high_limit)
+            && self
+                .glitch_filter_ns
+                .into_iter()
+                .all(|ns| (1..=PCNT_MAX_GLITCH_NS).contains(&ns));
+        if valid {
+            Ok(())
+        } else {
+            Err(PcntError::InvalidParameter.into())
+        }
+    }
+}
+
+impl Default for EncoderConfig {
+    fn default() -> Self {
+        Self {
+            low_limit: PCNT_LIMIT_MIN,
+            high_limit: PCNT_LIMIT_MAX,
+            glitch_filter_ns: Some(1_000), // 默认过滤1us以内的触点抖动
+        }
+    }
+}
+
+/// 计数事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PcntEventKind {
+    /// 计数到达用户添加的观察点
+    #[default]
+    WatchPoint,
+    /// 计数到达上限，已清零
+    Overflow,
+    /// 计数到达下限，已清零
+    Underflow,
+    /// 累计位置跨过步长的整数倍（见 `PcntEncoder::add_watch_step`）
+    Step,
+}
+
+/// 计数事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PcntEvent {
+    /// 事件类型
+    pub kind: PcntEventKind,
+    /// 触发事件的观察点（上下限事件为对应的限值，步长事件为步长）
+    pub watch_point: i32,
+    /// 事件发生时的累计位置
+    pub position: i64,
+    /// esp_timer 时间戳（微秒）
+    pub timestamp_us: u64,
+}
+
+/// 两路输入电平组成的正交相位，A为高位（主机模拟计数单元使用）
+#[cfg(not(target_os = "espidf"))]
+pub(crate) fn quadrature_phase(a: u32, b: u32) -> u8 {
+    ((a & 1) << 1 | (b & 1)) as u8
+}
+
+/// 相位变化对应的计数增量（4倍频解码）
+///
+/// A相超前B相（00 → 10 → 11 → 01）为正方向；两路同时变化无法判断方向，不计数。
+#[cfg(not(target_os = "espidf"))]
+pub(crate) fn quadrature_step(old: u8, new: u8) -> i32 {
+    // 相位在正方向序列中的位置
+    const ORDER: [u8; 4] = [0, 3, 1, 2];
+    match (ORDER[new as usize & 3] + 4 - ORDER[old as usize & 3]) % 4 {
+        1 => 1,
+        3 => -1,
+        _ => 0,
+    }
+}
+
+/// 检查观察点是否在计数范围内
+pub fn check_watch_point(value: i32, config: &EncoderConfig) -> PcntResult<()> {
+    if value < config.low_limit || value > config.high_limit {
+        return Err(Error::new(PcntError::InvalidParameter));
+    }
+    Ok(())
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_validate() {
+        assert!(EncoderConfig::default().validate().is_ok());
+        assert!(EncoderConfig::default().limits(-4, 4).validate().is_ok());
+        assert!(EncoderConfig::default().limits(0, 4).validate().is_err());
+        assert!(EncoderConfig::default()
+            .limits(-4, 40_000)
+            .validate()
+            .is_err());
+        assert!(EncoderConfig::default()
+            .glitch_filter(Some(20_000))
+            .validate()
+            .is_err());
+        assert!(EncoderConfig::default()
+            .glitch_filter(None)
+            .validate()
+            .is_ok());
+
+        let config = EncoderConfig::default().limits(-100, 100);
+        assert!(check_watch_point(100, &config).is_ok());
+        assert!(check_watch_point(-101, &config).is_err());
+    }
+
+    #[test]
+    fn test_quadrature_step() {
+        // A相超前：每个边沿+1
+        let forward = [0b00, 0b10, 0b11, 0b01, 0b00];
+        for pair in forward.windows(2) {
+            assert_eq!(quadrature_step(pair[0], pair[1]), 1);
+            assert_eq!(quadrature_step(pair[1], pair[0]), -1);
+        }
+        assert_eq!(quadrature_step(0b00, 0b00), 0);
+        assert_eq!(quadrature_step(0b00, 0b11), 0);
+        assert_eq!(quadrature_phase(1, 0), 0b10);
+    }
+}
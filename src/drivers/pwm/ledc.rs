@@ -0,0 +1,455 @@
+/**
+ * @file ledc.rs
+ * @brief LEDC 定时器和通道
+ * @details 基于 ESP-IDF ledc_* 驱动:
+ *          - 定时器和通道在创建时登记占用，释放时自动归还
+ *          - 通道持有输出引脚的 GpioPin，引脚不会被其他驱动重复使用
+ *          - 渐变功能在首次使用时自动安装，完成回调运行在中断上下文中
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use esp_idf_svc::sys::{
+    ledc_cb_event_t_LEDC_FADE_END_EVT, ledc_cb_param_t, ledc_cb_register, ledc_cbs_t,
+    ledc_channel_config, ledc_channel_config_t, ledc_fade_func_install,
+    ledc_fade_mode_t_LEDC_FADE_NO_WAIT, ledc_fade_mode_t_LEDC_FADE_WAIT_DONE, ledc_fade_start,
+    ledc_get_duty, ledc_intr_type_t_LEDC_INTR_DISABLE, ledc_mode_t,
+    ledc_mode_t_LEDC_LOW_SPEED_MODE, ledc_set_duty, ledc_set_fade_with_time, ledc_set_freq,
+    ledc_stop, ledc_timer_config, ledc_timer_config_t, ledc_timer_pause, ledc_timer_resume,
+    ledc_update_duty, soc_periph_ledc_clk_src_legacy_t_LEDC_AUTO_CLK, ESP_ERR_INVALID_STATE,
+    ESP_OK,
+};
+
+use crate::drivers::gpio::GpioPin;
+use crate::drivers::pwm::types::{
+    check_duty, duty_from_percent, max_duty, percent_from_duty, FadeMode, LedcChannelNum,
+    LedcTimerConfig, LedcTimerNum, PwmError, PwmResult,
+};
+use crate::error::Error;
+
+/// ESP32-S3 只有低速模式
+const SPEED_MODE: ledc_mode_t = ledc_mode_t_LEDC_LOW_SPEED_MODE;
+
+/// 已占用的定时器位图
+static TIMERS_IN_USE: AtomicU8 = AtomicU8::new(0);
+/// 已占用的通道位图
+static CHANNELS_IN_USE: AtomicU8 = AtomicU8::new(0);
+
+/// 登记占用，已被占用时返回 false
+fn acquire(in_use: &AtomicU8, index: u32) -> bool {
+    let bit = 1 << index;
+    in_use.fetch_or(bit, Ordering::AcqRel) & bit == 0
+}
+
+/// 归还占用
+fn release(in_use: &AtomicU8, index: u32) {
+    in_use.fetch_and(!(1 << index), Ordering::AcqRel);
+}
+
+/// 定时器操作失败
+fn timer_error(code: i32, operation: &'static str, timer: LedcTimerNum) -> Error {
+    Error::esp(PwmError::TimerError, code, operation).with_ledc_timer(timer.index())
+}
+
+/// 通道操作失败
+fn channel_error(
+    error: PwmError,
+    code: i32,
+    operation: &'static str,
+    channel: LedcChannelNum,
+) -> Error {
+    Error::esp(error, code, operation).with_ledc_channel(channel.index())
+}
+
+/// 安装渐变功能，只在首次使用时安装一次
+fn ensure_fade_installed() -> PwmResult<()> {
+    static INSTALLED: Mutex<bool> = Mutex::new(false);
+
+    let mut installed = INSTALLED.lock().unwrap_or_else(|e| e.into_inner());
+    if !*installed {
+        let code = unsafe { ledc_fade_func_install(0) };
+        // 渐变功能已被其他代码安装时返回ESP_ERR_INVALID_STATE，沿用已有安装
+        if code != ESP_OK && code != ESP_ERR_INVALID_STATE {
+            return Err(Error::esp(
+                PwmError::FadeError,
+                code,
+                "ledc_fade_func_install",
+            ));
+        }
+        *installed = true;
+    }
+    Ok(())
+}
+
+/// 定时器共享状态，最后一个持有者释放时暂停定时器
+struct TimerInner {
+    timer: LedcTimerNum,
+    resolution_bits: u8,
+    frequency_hz: AtomicU32,
+}
+
+impl Drop for TimerInner {
+    fn drop(&mut self) {
+        unsafe { ledc_timer_pause(SPEED_MODE, self.timer.index()) };
+        release(&TIMERS_IN_USE, self.timer.index());
+    }
+}
+
+/// LEDC定时器
+///
+/// 克隆得到的句柄指向同一个硬件定时器；使用它的通道也持有一份句柄，
+/// 因此通道存在期间定时器不会被释放。
+#[derive(Clone)]
+pub struct LedcTimer {
+    inner: Arc<TimerInner>,
+}
+
+impl LedcTimer {
+    /// 配置并启动一个定时器
+    ///
+    /// # 参数
+    ///
+    /// * `timer` - 定时器编号
+    /// * `config` - 频率和分辨率
+    ///
+    /// # 返回
+    ///
+    /// 成功返回定时器；参数无法由时钟分频得到返回 `InvalidParameter`，已被占用返回 `InUse`
+    pub fn new(timer: LedcTimerNum, config: &LedcTimerConfig) -> PwmResult<Self> {
+        config
+            .validate()
+            .map_err(|e| e.with_ledc_timer(timer.index()))?;
+        if !acquire(&TIMERS_IN_USE, timer.index()) {
+            return Err(Error::new(PwmError::InUse).with_ledc_timer(timer.index()));
+        }
+
+        let timer_config = ledc_timer_config_t {
+            speed_mode: SPEED_MODE,
+            duty_resolution: u32::from(config.resolution_bits),
+            timer_num: timer.index(),
+            freq_hz: config.frequency_hz,
+            clk_cfg: soc_periph_ledc_clk_src_legacy_t_LEDC_AUTO_CLK,
+            ..Default::default()
+        };
+        let code = unsafe { ledc_timer_config(&timer_config) };
+        if code != ESP_OK {
+            release(&TIMERS_IN_USE, timer.index());
+            return Err(timer_error(code, "ledc_timer_config", timer));
+        }
+
+        Ok(LedcTimer {
+            inner: Arc::new(TimerInner {
+                timer,
+                resolution_bits: config.resolution_bits,
+                frequency_hz: AtomicU32::new(config.frequency_hz),
+            }),
+        })
+    }
+
+    /// 定时器编号
+    pub fn timer(&self) -> LedcTimerNum {
+        self.inner.timer
+    }
+
+    /// 占空比分辨率（位）
+    pub fn resolution_bits(&self) -> u8 {
+        self.inner.resolution_bits
+    }
+
+    /// 当前频率（Hz）
+    pub fn frequency(&self) -> u32 {
+        self.inner.frequency_hz.load(Ordering::Relaxed)
+    }
+
+    /// 修改频率，分辨率保持不变，使用该定时器的所有通道同时生效
+    pub fn set_frequency(&self, frequency_hz: u32) -> PwmResult<()> {
+        LedcTimerConfig {
+            frequency_hz,
+            resolution_bits: self.inner.resolution_bits,
+        }
+        .validate()
+        .map_err(|e| e.with_ledc_timer(self.inner.timer.index()))?;
+
+        let code = unsafe { ledc_set_freq(SPEED_MODE, self.inner.timer.index(), frequency_hz) };
+        if code != ESP_OK {
+            return Err(timer_error(code, "ledc_set_freq", self.inner.timer));
+        }
+        self.inner
+            .frequency_hz
+            .store(frequency_hz, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// 暂停计数，所有通道保持当前输出电平
+    pub fn pause(&self) -> PwmResult<()> {
+        let code = unsafe { ledc_timer_pause(SPEED_MODE, self.inner.timer.index()) };
+        if code != ESP_OK {
+            return Err(timer_error(code, "ledc_timer_pause", self.inner.timer));
+        }
+        Ok(())
+    }
+
+    /// 恢复计数
+    pub fn resume(&self) -> PwmResult<()> {
+        let code = unsafe { ledc_timer_resume(SPEED_MODE, self.inner.timer.index()) };
+        if code != ESP_OK {
+            return Err(timer_error(code, "ledc_timer_resume", self.inner.timer));
+        }
+        Ok(())
+    }
+}
+
+/// 渐变完成回调类型
+type FadeCallback = Box<dyn FnMut() + Send + 'static>;
+
+/// 所有通道共用的渐变回调入口，参数为通道持有的回调
+unsafe extern "C" fn fade_trampoline(param: *const ledc_cb_param_t, arg: *mut c_void) -> bool {
+    if (*param).event == ledc_cb_event_t_LEDC_FADE_END_EVT {
+        let callback = &mut *(arg as *mut FadeCallback);
+        callback();
+    }
+    // 回调中没有唤醒更高优先级的任务
+    false
+}
+
+/// LEDC通道
+///
+/// 释放时停止输出（低电平）、注销渐变回调并归还通道和引脚。
+pub struct LedcChannel {
+    channel: LedcChannelNum,
+    timer: LedcTimer,
+    pin: GpioPin,
+    fade_callback: Option<*mut FadeCallback>,
+}
+
+// 回调本身要求Send，裸指针只在注册/注销时访问
+unsafe impl Send for LedcChannel {}
+
+impl LedcChannel {
+    /// 配置一个通道，输出到指定引脚，初始占空比为0
+    ///
+    /// # 参数
+    ///
+    /// * `channel` - 通道编号
+    /// * `timer` - 使用的定时器
+    /// * `pin` - 输出引脚，任意模式均可，通道会将其切换为LEDC输出
+    pub fn new<MODE>(
+        channel: LedcChannelNum,
+        timer: &LedcTimer,
+        pin: GpioPin<MODE>,
+    ) -> PwmResult<Self> {
+        if !acquire(&CHANNELS_IN_USE, channel.index()) {
+            return Err(Error::new(PwmError::InUse).with_ledc_channel(channel.index()));
+        }
+
+        let config = ledc_channel_config_t {
+            gpio_num: pin.get_pin_number(),
+            speed_mode: SPEED_MODE,
+            channel: channel.index(),
+            intr_type: ledc_intr_type_t_LEDC_INTR_DISABLE,
+            timer_sel: timer.timer().index(),
+            duty: 0,
+            hpoint: 0,
+            ..Default::default()
+        };
+        let code = unsafe { ledc_channel_config(&config) };
+        if code != ESP_OK {
+            release(&CHANNELS_IN_USE, channel.index());
+            return Err(channel_error(
+                PwmError::ChannelError,
+                code,
+                "ledc_channel_config",
+                channel,
+            )
+            .with_pin(pin.get_pin_number()));
+        }
+
+        Ok(LedcChannel {
+            channel,
+            timer: timer.clone(),
+            pin: pin.into_dynamic(),
+            fade_callback: None,
+        })
+    }
+
+    /// 通道编号
+    pub fn channel(&self) -> LedcChannelNum {
+        self.channel
+    }
+
+    /// 使用的定时器
+    pub fn timer(&self) -> &LedcTimer {
+        &self.timer
+    }
+
+    /// 输出引脚的GPIO编号
+    pub fn pin_number(&self) -> i32 {
+        self.pin.get_pin_number()
+    }
+
+    /// 最大占空比（100%）
+    pub fn max_duty(&self) -> u32 {
+        max_duty(self.timer.resolution_bits())
+    }
+
+    /// 设置原始占空比
+    ///
+    /// # 参数
+    ///
+    /// * `duty` - 占空比，取值 0 ~ `max_duty()`
+    pub fn set_duty(&self, duty: u32) -> PwmResult<()> {
+        check_duty(duty, self.timer.resolution_bits())
+            .map_err(|e| e.with_ledc_channel(self.channel.index()))?;
+        let channel = self.channel.index();
+        let code = unsafe { ledc_set_duty(SPEED_MODE, channel, duty) };
+        if code != ESP_OK {
+            return Err(self.error(PwmError::ChannelError, code, "ledc_set_duty"));
+        }
+        let code = unsafe { ledc_update_duty(SPEED_MODE, channel) };
+        if code != ESP_OK {
+            return Err(self.error(PwmError::ChannelError, code, "ledc_update_duty"));
+        }
+        Ok(())
+    }
+
+    /// 按百分比设置占空比，超出 0~100 的值被截断
+    pub fn set_duty_percent(&self, percent: f32) -> PwmResult<()> {
+        self.set_duty(duty_from_percent(percent, self.timer.resolution_bits()))
+    }
+
+    /// 当前原始占空比
+    pub fn duty(&self) -> u32 {
+        unsafe { ledc_get_duty(SPEED_MODE, self.channel.index()) }
+    }
+
+    /// 当前占空比百分比
+    pub fn duty_percent(&self) -> f32 {
+        percent_from_duty(self.duty(), self.timer.resolution_bits())
+    }
+
+    /// 停止输出，引脚保持在指定电平
+    pub fn stop(&self, idle_level: u32) -> PwmResult<()> {
+        let code = unsafe { ledc_stop(SPEED_MODE, self.channel.index(), idle_level) };
+        if code != ESP_OK {
+            return Err(self.error(PwmError::ChannelError, code, "ledc_stop"));
+        }
+        Ok(())
+    }
+
+    /// 硬件渐变到目标占空比
+    ///
+    /// # 参数
+    ///
+    /// * `target_duty` - 目标原始占空比
+    /// * `duration` - 渐变时间
+    /// * `mode` - 是否等待渐变完成
+    pub fn fade_to(&self, target_duty: u32, duration: Duration, mode: FadeMode) -> PwmResult<()> {
+        check_duty(target_duty, self.timer.resolution_bits())
+            .map_err(|e| e.with_ledc_channel(self.channel.index()))?;
+        ensure_fade_installed()?;
+
+        let channel = self.channel.index();
+        let time_ms = duration.as_millis().min(i32::MAX as u128) as i32;
+        let code = unsafe { ledc_set_fade_with_time(SPEED_MODE, channel, target_duty, time_ms) };
+        if code != ESP_OK {
+            return Err(self.error(PwmError::FadeError, code, "ledc_set_fade_with_time"));
+        }
+
+        let fade_mode = match mode {
+            FadeMode::NoWait => ledc_fade_mode_t_LEDC_FADE_NO_WAIT,
+            FadeMode::WaitDone => ledc_fade_mode_t_LEDC_FADE_WAIT_DONE,
+        };
+        let code = unsafe { ledc_fade_start(SPEED_MODE, channel, fade_mode) };
+        if code != ESP_OK {
+            return Err(self.error(PwmError::FadeError, code, "ledc_fade_start"));
+        }
+        Ok(())
+    }
+
+    /// 按百分比硬件渐变
+    pub fn fade_to_percent(
+        &self,
+        percent: f32,
+        duration: Duration,
+        mode: FadeMode,
+    ) -> PwmResult<()> {
+        self.fade_to(
+            duty_from_percent(percent, self.timer.resolution_bits()),
+            duration,
+            mode,
+        )
+    }
+
+    /// 注册渐变完成回调，替换原有的回调
+    ///
+    /// 回调运行在中断上下文中，应只做标志位、原子计数或队列投递等轻量操作，
+    /// 不能在回调中启动下一次渐变。
+    pub fn on_fade_complete<F>(&mut self, callback: F) -> PwmResult<()>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        ensure_fade_installed()?;
+        let callback: *mut FadeCallback = Box::into_raw(Box::new(Box::new(callback)));
+        let mut callbacks = ledc_cbs_t {
+            fade_cb: Some(fade_trampoline),
+        };
+        let code = unsafe {
+            ledc_cb_register(
+                SPEED_MODE,
+                self.channel.index(),
+                &mut callbacks,
+                callback as *mut c_void,
+            )
+        };
+        if code != ESP_OK {
+            drop(unsafe { Box::from_raw(callback) });
+            return Err(self.error(PwmError::FadeError, code, "ledc_cb_register"));
+        }
+
+        // 新回调已生效，旧回调不会再被调用
+        if let Some(old) = self.fade_callback.replace(callback) {
+            drop(unsafe { Box::from_raw(old) });
+        }
+        Ok(())
+    }
+
+    /// 注销渐变完成回调
+    pub fn clear_fade_callback(&mut self) -> PwmResult<()> {
+        let Some(callback) = self.fade_callback.take() else {
+            return Ok(());
+        };
+        let mut callbacks = ledc_cbs_t { fade_cb: None };
+        let code = unsafe {
+            ledc_cb_register(
+                SPEED_MODE,
+                self.channel.index(),
+                &mut callbacks,
+                std::ptr::null_mut(),
+            )
+        };
+        if code != ESP_OK {
+            self.fade_callback = Some(callback);
+            return Err(self.error(PwmError::FadeError, code, "ledc_cb_register"));
+        }
+        drop(unsafe { Box::from_raw(callback) });
+        Ok(())
+    }
+
+    /// 通道操作失败时的错误
+    fn error(&self, error: PwmError, code: i32, operation: &'static str) -> Error {
+        channel_error(error, code, operation, self.channel)
+    }
+}
+
+impl Drop for LedcChannel {
+    fn drop(&mut self) {
+        let _ = self.stop(0);
+        let _ = self.clear_fade_callback();
+        release(&CHANNELS_IN_USE, self.channel.index());
+    }
+}
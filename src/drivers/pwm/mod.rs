@@ -0,0 +1,19 @@
+/**
+ * @file mod.rs
+ * @brief LEDC 硬件PWM驱动
+ * @details 封装 ESP32-S3 的 LEDC 外设，用于 LED 调光和 LCD 背光亮度:
+ *          - 4 个定时器决定频率和占空比分辨率，多个通道可以共用一个定时器
+ *          - 8 个通道各自输出到一个 GpioPin，占空比可按百分比或原始值设置
+ *          - 硬件渐变，可注册渐变完成回调，实现呼吸灯等效果
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+// LEDC驱动直接调用ESP-IDF，主机上只提供类型定义和占空比换算
+#[cfg(target_os = "espidf")]
+mod ledc;
+mod types;
+
+#[cfg(target_os = "espidf")]
+pub use ledc::*;
+pub use types::*;
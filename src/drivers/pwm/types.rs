@@ -0,0 +1,218 @@
+/**
+ * @file types.rs
+ * @brief LEDC PWM 类型定义
+ * @details 定时器/通道编号、定时器配置以及占空比与百分比之间的换算，
+ *          这些计算不依赖硬件，可以在主机上测试
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::fmt;
+
+use crate::error::Error;
+
+/// LEDC 时钟源频率（APB，80MHz）
+pub const LEDC_APB_CLK_HZ: u32 = 80_000_000;
+
+/// ESP32-S3 LEDC 定时器支持的最大占空比分辨率（位）
+pub const LEDC_MAX_RESOLUTION: u8 = 14;
+
+/// PWM错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PwmError {
+    /// 参数错误（频率、分辨率或占空比超出范围）
+    InvalidParameter,
+    /// 定时器或通道已被占用
+    InUse,
+    /// 定时器配置失败
+    TimerError,
+    /// 通道配置或占空比更新失败
+    ChannelError,
+    /// 渐变失败
+    FadeError,
+}
+
+impl fmt::Display for PwmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PwmError::InvalidParameter => write!(f, "参数错误"),
+            PwmError::InUse => write!(f, "已被占用"),
+            PwmError::TimerError => write!(f, "定时器错误"),
+            PwmError::ChannelError => write!(f, "通道错误"),
+            PwmError::FadeError => write!(f, "渐变错误"),
+        }
+    }
+}
+
+/// PWM操作结果类型
+pub type PwmResult<T> = Result<T, crate::error::Error>;
+
+/// LEDC 定时器编号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedcTimerNum {
+    /// 定时器0
+    Timer0,
+    /// 定时器1
+    Timer1,
+    /// 定时器2
+    Timer2,
+    /// 定时器3
+    Timer3,
+}
+
+impl LedcTimerNum {
+    /// 定时器编号的数值
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+}
+
+/// LEDC 通道编号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedcChannelNum {
+    /// 通道0
+    Channel0,
+    /// 通道1
+    Channel1,
+    /// 通道2
+    Channel2,
+    /// 通道3
+    Channel3,
+    /// 通道4
+    Channel4,
+    /// 通道5
+    Channel5,
+    /// 通道6
+    Channel6,
+    /// 通道7
+    Channel7,
+}
+
+impl LedcChannelNum {
+    /// 通道编号的数值
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+}
+
+/// 渐变模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FadeMode {
+    /// 启动渐变后立即返回
+    NoWait,
+    /// 等待渐变完成后返回
+    WaitDone,
+}
+
+/// LEDC 定时器配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LedcTimerConfig {
+    /// PWM频率（Hz）
+    pub frequency_hz: u32,
+    /// 占空比分辨率（位），占空比取值范围为 0 ~ 2^resolution_bits
+    pub resolution_bits: u8,
+}
+
+impl LedcTimerConfig {
+    /// 按频率创建配置，自动选择该频率下可用的最高分辨率
+    pub fn new(frequency_hz: u32) -> Self {
+        LedcTimerConfig {
+            frequency_hz,
+            resolution_bits: max_resolution(frequency_hz).unwrap_or(1),
+        }
+    }
+
+    /// 指定占空比分辨率
+    pub fn resolution(mut self, bits: u8) -> Self {
+        self.resolution_bits = bits;
+        self
+    }
+
+    /// 检查频率和分辨率能否由 LEDC 时钟分频得到
+    pub fn validate(&self) -> PwmResult<()> {
+        let valid = self.frequency_hz > 0
+            && (1..=LEDC_MAX_RESOLUTION).contains(&self.resolution_bits)
+            && u64::from(self.frequency_hz) << self.resolution_bits <= u64::from(LEDC_APB_CLK_HZ);
+        if valid {
+            Ok(())
+        } else {
+            Err(PwmError::InvalidParameter.into())
+        }
+    }
+}
+
+impl Default for LedcTimerConfig {
+    fn default() -> Self {
+        Self {
+            frequency_hz: 5_000, // 默认5kHz，LED无可见闪烁
+            resolution_bits: 13,
+        }
+    }
+}
+
+/// 指定分辨率下的最大占空比（100%）
+pub fn max_duty(resolution_bits: u8) -> u32 {
+    1 << resolution_bits
+}
+
+/// 指定频率下可用的最高分辨率，频率过高时返回 `None`
+pub fn max_resolution(frequency_hz: u32) -> Option<u8> {
+    if frequency_hz == 0 {
+        return None;
+    }
+    (1..=LEDC_MAX_RESOLUTION)
+        .rev()
+        .find(|&bits| u64::from(frequency_hz) << bits <= u64::from(LEDC_APB_CLK_HZ))
+}
+
+/// 百分比转换为原始占空比，超出 0~100 的值被截断
+pub fn duty_from_percent(percent: f32, resolution_bits: u8) -> u32 {
+    let max = max_duty(resolution_bits);
+    (percent.clamp(0.0, 100.0) / 100.0 * max as f32).round() as u32
+}
+
+/// 原始占空比转换为百分比
+pub fn percent_from_duty(duty: u32, resolution_bits: u8) -> f32 {
+    duty as f32 * 100.0 / max_duty(resolution_bits) as f32
+}
+
+/// 检查原始占空比是否在分辨率范围内
+pub fn check_duty(duty: u32, resolution_bits: u8) -> PwmResult<()> {
+    if duty > max_duty(resolution_bits) {
+        return Err(Error::new(PwmError::InvalidParameter));
+    }
+    Ok(())
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolution_limits() {
+        assert_eq!(max_resolution(1_000), Some(LEDC_MAX_RESOLUTION));
+        assert_eq!(max_resolution(5_000), Some(13));
+        assert_eq!(max_resolution(1_000_000), Some(6));
+        assert_eq!(max_resolution(40_000_000), Some(1));
+        assert_eq!(max_resolution(50_000_000), None);
+
+        assert!(LedcTimerConfig::default().validate().is_ok());
+        assert_eq!(LedcTimerConfig::new(1_000_000).resolution_bits, 6);
+        let error = LedcTimerConfig::new(1_000_000)
+            .resolution(10)
+            .validate()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "参数错误");
+    }
+
+    #[test]
+    fn test_duty_conversion() {
+        assert_eq!(duty_from_percent(50.0, 13), 4096);
+        assert_eq!(duty_from_percent(100.0, 8), 256);
+        assert_eq!(duty_from_percent(150.0, 8), 256);
+        assert_eq!(duty_from_percent(-1.0, 8), 0);
+        assert_eq!(percent_from_duty(64, 8), 25.0);
+        assert!(check_duty(256, 8).is_ok());
+        assert!(check_duty(257, 8).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+/**
+ * @file led_strip.rs
+ * @brief WS2812 可寻址RGB灯带
+ * @details 在 RMT 发送通道上驱动任意数量的 WS2812/SK6812 灯珠:
+ *          - 像素先写入内存缓冲区，调用 `show` 时编码为一帧发送
+ *          - 颜色顺序、全局亮度和伽马校正由 `Ws2812Encoder` 处理
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use crate::drivers::gpio::GpioPin;
+use crate::drivers::rmt::tx::RmtTxChannel;
+use crate::drivers::rmt::types::{RmtError, RmtResult, RmtSymbol, RmtTxConfig};
+use crate::drivers::rmt::ws2812::{ColorOrder, Rgbw, Ws2812Encoder};
+use crate::error::Error;
+
+/// 超过该像素数时使用DMA发送，避免存储器乒乓填充跟不上
+const DMA_PIXEL_THRESHOLD: usize = 64;
+
+/// WS2812灯带
+pub struct Ws2812 {
+    channel: RmtTxChannel,
+    encoder: Ws2812Encoder,
+    pixels: Vec<Rgbw>,
+    /// 编码缓冲区，重复使用以避免每帧分配
+    symbols: Vec<RmtSymbol>,
+}
+
+impl Ws2812 {
+    /// 创建灯带，所有像素初始为熄灭
+    ///
+    /// # 参数
+    ///
+    /// * `pin` - 数据引脚
+    /// * `count` - 灯珠数量
+    /// * `order` - 颜色通道顺序，WS2812B 为 `ColorOrder::Grb`
+    pub fn new<MODE>(pin: GpioPin<MODE>, count: usize, order: ColorOrder) -> RmtResult<Self> {
+        let config = RmtTxConfig {
+            with_dma: count > DMA_PIXEL_THRESHOLD,
+            ..Default::default()
+        };
+        let channel = RmtTxChannel::new(pin, &config)?;
+        let encoder = Ws2812Encoder::new(order, channel.resolution_hz())?;
+        Ok(Ws2812 {
+            channel,
+            encoder,
+            pixels: vec![Rgbw::OFF; count],
+            symbols: Vec::new(),
+        })
+    }
+
+    /// 灯珠数量
+    pub fn len(&self) -> usize {
+        self.pixels.len()
+    }
+
+    /// 灯带是否不含任何灯珠
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// 设置一个像素的颜色，调用 `show` 后生效
+    pub fn set_pixel(&mut self, index: usize, color: Rgbw) -> RmtResult<()> {
+        let pin = self.channel.pin_number();
+        let pixel = self
+            .pixels
+            .get_mut(index)
+            .ok_or_else(|| Error::new(RmtError::InvalidParameter).with_pin(pin))?;
+        *pixel = color;
+        Ok(())
+    }
+
+    /// 一个像素的颜色（校正前）
+    pub fn pixel(&self, index: usize) -> Option<Rgbw> {
+        self.pixels.get(index).copied()
+    }
+
+    /// 所有像素的颜色，可直接修改
+    pub fn pixels_mut(&mut self) -> &mut [Rgbw] {
+        &mut self.pixels
+    }
+
+    /// 所有像素设置为同一颜色
+    pub fn fill(&mut self, color: Rgbw) {
+        self.pixels.fill(color);
+    }
+
+    /// 所有像素熄灭，调用 `show` 后生效
+    pub fn clear(&mut self) {
+        self.fill(Rgbw::OFF);
+    }
+
+    /// 设置全局亮度（0~255），调用 `show` 后生效
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.encoder.set_brightness(brightness);
+    }
+
+    /// 全局亮度
+    pub fn brightness(&self) -> u8 {
+        self.encoder.brightness()
+    }
+
+    /// 启用或禁用伽马校正，常用伽马值为2.8
+    pub fn set_gamma(&mut self, gamma: Option<f32>) {
+        self.encoder.set_gamma(gamma);
+    }
+
+    /// 将缓冲区中的像素发送到灯带，等待发送完成
+    pub fn show(&mut self) -> RmtResult<()> {
+        self.encoder.encode(&self.pixels, &mut self.symbols);
+        self.channel.transmit(&self.symbols)
+    }
+}
+
+impl Drop for Ws2812 {
+    fn drop(&mut self) {
+        // 熄灭所有灯珠，避免释放后保持最后一帧
+        self.clear();
+        let _ = self.show();
+    }
+}
@@ -0,0 +1,25 @@
+/**
+ * @file mod.rs
+ * @brief 红外遥控收发器（RMT）驱动
+ * @details RMT 按预先编码的电平/时长符号输出精确波形:
+ *          - `RmtTxChannel` 封装 ESP-IDF 的 RMT 发送通道
+ *          - `Ws2812` 在发送通道上驱动 WS2812/SK6812 可寻址灯带
+ *          - `Ws2812Encoder` 将像素编码为 RMT 符号，可以在主机上测试
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+// 帧编码与硬件无关，发送通道和灯带只在ESP-IDF上编译
+#[cfg(target_os = "espidf")]
+mod led_strip;
+#[cfg(target_os = "espidf")]
+mod tx;
+mod types;
+mod ws2812;
+
+#[cfg(target_os = "espidf")]
+pub use led_strip::*;
+#[cfg(target_os = "espidf")]
+pub use tx::*;
+pub use types::*;
+pub use ws2812::*;
@@ -0,0 +1,162 @@
+/**
+ * @file tx.rs
+ * @brief RMT 发送通道
+ * @details 基于 ESP-IDF rmt_tx 驱动（rmt_new_tx_channel / rmt_transmit）:
+ *          - 通道持有输出引脚的 GpioPin，引脚不会被其他驱动重复使用
+ *          - 使用拷贝编码器直接发送预先编码好的 RMT 符号
+ *          - 发送接口等待发送完成后返回，符号缓冲区在发送期间保持有效
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::ffi::c_void;
+use std::ptr;
+use std::time::Duration;
+
+use esp_idf_svc::sys::{
+    rmt_channel_handle_t, rmt_copy_encoder_config_t, rmt_del_channel, rmt_del_encoder, rmt_disable,
+    rmt_enable, rmt_encoder_handle_t, rmt_new_copy_encoder, rmt_new_tx_channel, rmt_transmit,
+    rmt_transmit_config_t, rmt_tx_channel_config_t, rmt_tx_wait_all_done,
+    soc_periph_rmt_clk_src_t_RMT_CLK_SRC_DEFAULT, ESP_ERR_TIMEOUT, ESP_OK,
+};
+
+use crate::drivers::gpio::GpioPin;
+use crate::drivers::rmt::types::{RmtError, RmtResult, RmtSymbol, RmtTxConfig};
+use crate::error::Error;
+
+/// RMT发送通道
+///
+/// 释放时关闭通道、删除编码器并归还引脚。
+pub struct RmtTxChannel {
+    channel: rmt_channel_handle_t,
+    encoder: rmt_encoder_handle_t,
+    enabled: bool,
+    resolution_hz: u32,
+    pin: GpioPin,
+}
+
+// 句柄只在持有者的任务中使用
+unsafe impl Send for RmtTxChannel {}
+
+impl RmtTxChannel {
+    /// 创建发送通道
+    ///
+    /// # 参数
+    ///
+    /// * `pin` - 输出引脚，任意模式均可，通道会将其切换为RMT输出
+    /// * `config` - 分辨率、存储器大小等配置
+    pub fn new<MODE>(pin: GpioPin<MODE>, config: &RmtTxConfig) -> RmtResult<Self> {
+        config
+            .validate()
+            .map_err(|e| e.with_pin(pin.get_pin_number()))?;
+        let mut tx = RmtTxChannel {
+            channel: ptr::null_mut(),
+            encoder: ptr::null_mut(),
+            enabled: false,
+            resolution_hz: config.resolution_hz,
+            pin: pin.into_dynamic(),
+        };
+
+        let mut channel_config = rmt_tx_channel_config_t {
+            gpio_num: tx.pin.get_pin_number(),
+            clk_src: soc_periph_rmt_clk_src_t_RMT_CLK_SRC_DEFAULT,
+            resolution_hz: config.resolution_hz,
+            mem_block_symbols: config.mem_block_symbols,
+            trans_queue_depth: config.trans_queue_depth,
+            ..Default::default()
+        };
+        channel_config
+            .flags
+            .set_invert_out(u32::from(config.invert_out));
+        channel_config
+            .flags
+            .set_with_dma(u32::from(config.with_dma));
+        let code = unsafe { rmt_new_tx_channel(&channel_config, &mut tx.channel) };
+        tx.check(code, RmtError::ChannelError, "rmt_new_tx_channel")?;
+
+        let encoder_config = rmt_copy_encoder_config_t::default();
+        let code = unsafe { rmt_new_copy_encoder(&encoder_config, &mut tx.encoder) };
+        tx.check(code, RmtError::EncoderError, "rmt_new_copy_encoder")?;
+
+        let code = unsafe { rmt_enable(tx.channel) };
+        tx.check(code, RmtError::ChannelError, "rmt_enable")?;
+        tx.enabled = true;
+        Ok(tx)
+    }
+
+    /// 通道分辨率（Hz）
+    pub fn resolution_hz(&self) -> u32 {
+        self.resolution_hz
+    }
+
+    /// 输出引脚的GPIO编号
+    pub fn pin_number(&self) -> i32 {
+        self.pin.get_pin_number()
+    }
+
+    /// 发送一组符号并等待发送完成
+    pub fn transmit(&mut self, symbols: &[RmtSymbol]) -> RmtResult<()> {
+        self.transmit_timeout(symbols, None)
+    }
+
+    /// 发送一组符号，最多等待 `timeout`，`None` 表示一直等待
+    ///
+    /// 超时返回 `RmtError::Timeout`，未完成的发送被中止，返回后驱动不再读取符号缓冲区。
+    pub fn transmit_timeout(
+        &mut self,
+        symbols: &[RmtSymbol],
+        timeout: Option<Duration>,
+    ) -> RmtResult<()> {
+        if symbols.is_empty() {
+            return Ok(());
+        }
+        let transmit_config = rmt_transmit_config_t::default();
+        let code = unsafe {
+            rmt_transmit(
+                self.channel,
+                self.encoder,
+                symbols.as_ptr() as *const c_void,
+                std::mem::size_of_val(symbols),
+                &transmit_config,
+            )
+        };
+        self.check(code, RmtError::TransmitError, "rmt_transmit")?;
+
+        let timeout_ms = timeout.map_or(-1, |t| t.as_millis().min(i32::MAX as u128) as i32);
+        let code = unsafe { rmt_tx_wait_all_done(self.channel, timeout_ms) };
+        if code == ESP_ERR_TIMEOUT {
+            // 符号缓冲区由调用者借出，关闭通道中止发送后再重新使能
+            unsafe {
+                rmt_disable(self.channel);
+                self.enabled = rmt_enable(self.channel) == ESP_OK;
+            }
+            return Err(Error::esp(RmtError::Timeout, code, "rmt_tx_wait_all_done")
+                .with_pin(self.pin_number()));
+        }
+        self.check(code, RmtError::TransmitError, "rmt_tx_wait_all_done")
+    }
+
+    /// 检查ESP-IDF返回值
+    fn check(&self, code: i32, error: RmtError, operation: &'static str) -> RmtResult<()> {
+        if code != ESP_OK {
+            return Err(Error::esp(error, code, operation).with_pin(self.pin_number()));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RmtTxChannel {
+    fn drop(&mut self) {
+        unsafe {
+            if self.enabled {
+                rmt_disable(self.channel);
+            }
+            if !self.encoder.is_null() {
+                rmt_del_encoder(self.encoder);
+            }
+            if !self.channel.is_null() {
+                rmt_del_channel(self.channel);
+            }
+        }
+    }
+}
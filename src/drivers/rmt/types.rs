@@ -0,0 +1,174 @@
+/**
+ * @file types.rs
+ * @brief RMT 类型定义
+ * @details 错误类型、发送通道配置以及 RMT 符号的位域编码，
+ *          这些定义不依赖硬件，可以在主机上测试
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::fmt;
+
+use crate::error::Error;
+
+/// RMT 时钟源频率（APB，80MHz）
+pub const RMT_APB_CLK_HZ: u32 = 80_000_000;
+
+/// 单个电平段的最大时长（15位，单位为分辨率的一个周期）
+pub const RMT_MAX_DURATION: u16 = 0x7FFF;
+
+/// ESP32-S3 每个通道一块存储器可容纳的符号数
+pub const RMT_MEM_BLOCK_SYMBOLS: usize = 48;
+
+/// RMT错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RmtError {
+    /// 参数错误（分辨率、时长或像素编号超出范围）
+    InvalidParameter,
+    /// 通道创建或使能失败
+    ChannelError,
+    /// 编码器创建失败
+    EncoderError,
+    /// 发送失败
+    TransmitError,
+    /// 等待发送完成超时
+    Timeout,
+}
+
+impl fmt::Display for RmtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RmtError::InvalidParameter => write!(f, "参数错误"),
+            RmtError::ChannelError => write!(f, "通道错误"),
+            RmtError::EncoderError => write!(f, "编码器错误"),
+            RmtError::TransmitError => write!(f, "发送错误"),
+            RmtError::Timeout => write!(f, "发送超时"),
+        }
+    }
+}
+
+/// RMT操作结果类型
+pub type RmtResult<T> = Result<T, crate::error::Error>;
+
+/// RMT符号，与 ESP-IDF 的 `rmt_symbol_word_t` 内存布局相同
+///
+/// 一个符号由两个电平段组成：先输出 `level0` 持续 `duration0`，再输出 `level1` 持续 `duration1`。
+/// 时长以通道分辨率的周期为单位，最大为 [`RMT_MAX_DURATION`]。
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RmtSymbol(u32);
+
+impl RmtSymbol {
+    /// 由两个电平段创建符号，时长超出15位的部分被截断
+    pub fn new(level0: u32, duration0: u16, level1: u32, duration1: u16) -> Self {
+        RmtSymbol(
+            u32::from(duration0 & RMT_MAX_DURATION)
+                | (level0 & 1) << 15
+                | u32::from(duration1 & RMT_MAX_DURATION) << 16
+                | (level1 & 1) << 31,
+        )
+    }
+
+    /// 第一段电平
+    pub fn level0(self) -> u32 {
+        self.0 >> 15 & 1
+    }
+
+    /// 第一段时长
+    pub fn duration0(self) -> u16 {
+        (self.0 & 0x7FFF) as u16
+    }
+
+    /// 第二段电平
+    pub fn level1(self) -> u32 {
+        self.0 >> 31
+    }
+
+    /// 第二段时长
+    pub fn duration1(self) -> u16 {
+        (self.0 >> 16 & 0x7FFF) as u16
+    }
+
+    /// 原始32位符号
+    pub fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// 纳秒转换为分辨率周期数，四舍五入，至少为1
+pub fn ticks_from_ns(ns: u32, resolution_hz: u32) -> RmtResult<u16> {
+    let ticks = ((u64::from(ns) * u64::from(resolution_hz) + 500_000_000) / 1_000_000_000).max(1);
+    if ticks > u64::from(RMT_MAX_DURATION) {
+        return Err(Error::new(RmtError::InvalidParameter));
+    }
+    Ok(ticks as u16)
+}
+
+/// RMT发送通道配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RmtTxConfig {
+    /// 通道分辨率（Hz），符号时长以 1/resolution_hz 为单位
+    pub resolution_hz: u32,
+    /// 通道存储器大小（符号数），不使用DMA时至少一块
+    pub mem_block_symbols: usize,
+    /// 发送队列深度
+    pub trans_queue_depth: usize,
+    /// 输出是否反相
+    pub invert_out: bool,
+    /// 是否使用DMA，长帧可以避免存储器乒乓填充的中断开销
+    pub with_dma: bool,
+}
+
+impl RmtTxConfig {
+    /// 指定通道分辨率
+    pub fn resolution(mut self, resolution_hz: u32) -> Self {
+        self.resolution_hz = resolution_hz;
+        self
+    }
+
+    /// 检查分辨率和存储器大小
+    pub fn validate(&self) -> RmtResult<()> {
+        let valid = (1..=RMT_APB_CLK_HZ).contains(&self.resolution_hz)
+            && self.mem_block_symbols >= RMT_MEM_BLOCK_SYMBOLS
+            && self.trans_queue_depth > 0;
+        if valid {
+            Ok(())
+        } else {
+            Err(RmtError::InvalidParameter.into())
+        }
+    }
+}
+
+impl Default for RmtTxConfig {
+    fn default() -> Self {
+        Self {
+            resolution_hz: 10_000_000, // 10MHz，一个周期100ns
+            mem_block_symbols: RMT_MEM_BLOCK_SYMBOLS,
+            trans_queue_depth: 4,
+            invert_out: false,
+            with_dma: false,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_layout() {
+        let symbol = RmtSymbol::new(1, 4, 0, 9);
+        assert_eq!(symbol.raw(), 0x0009_8004);
+        assert_eq!((symbol.level0(), symbol.duration0()), (1, 4));
+        assert_eq!((symbol.level1(), symbol.duration1()), (0, 9));
+        let symbol = RmtSymbol::new(0, RMT_MAX_DURATION, 1, 1);
+        assert_eq!(symbol.raw(), 0x8001_7FFF);
+
+        assert_eq!(ticks_from_ns(400, 10_000_000).unwrap(), 4);
+        assert_eq!(ticks_from_ns(850, 10_000_000).unwrap(), 9);
+        assert_eq!(ticks_from_ns(10, 10_000_000).unwrap(), 1);
+        assert!(ticks_from_ns(5_000_000, 10_000_000).is_err());
+        assert!(RmtTxConfig::default().validate().is_ok());
+        assert!(RmtTxConfig::default().resolution(0).validate().is_err());
+    }
+}
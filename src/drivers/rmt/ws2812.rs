@@ -0,0 +1,284 @@
+/**
+ * @file ws2812.rs
+ * @brief WS2812 帧编码
+ * @details 将像素数据编码为 RMT 符号序列:
+ *          - 支持 RGB/GRB 三通道和 RGBW/GRBW 四通道灯珠
+ *          - 全局亮度缩放和伽马校正，亮度先缩放再校正，调暗时颜色保持均匀
+ *          - 每个数据位编码为一个符号，高位在前，帧尾附加复位低电平
+ *          编码过程不依赖硬件，可以在主机上测试
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use crate::drivers::rmt::types::{ticks_from_ns, RmtResult, RmtSymbol, RMT_MAX_DURATION};
+
+/// 像素颜色，三通道灯珠忽略白色分量
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rgbw {
+    /// 红
+    pub r: u8,
+    /// 绿
+    pub g: u8,
+    /// 蓝
+    pub b: u8,
+    /// 白（仅四通道灯珠）
+    pub w: u8,
+}
+
+impl Rgbw {
+    /// 熄灭
+    pub const OFF: Rgbw = Rgbw::rgb(0, 0, 0);
+
+    /// 三通道颜色
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Rgbw { r, g, b, w: 0 }
+    }
+
+    /// 四通道颜色
+    pub const fn new(r: u8, g: u8, b: u8, w: u8) -> Self {
+        Rgbw { r, g, b, w }
+    }
+
+    /// 由 0xRRGGBB 创建三通道颜色
+    pub const fn from_hex(rgb: u32) -> Self {
+        Rgbw::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+}
+
+/// 灯珠的颜色通道顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorOrder {
+    /// 红绿蓝
+    Rgb,
+    /// 绿红蓝（WS2812B）
+    Grb,
+    /// 红绿蓝白
+    Rgbw,
+    /// 绿红蓝白（SK6812 RGBW）
+    Grbw,
+}
+
+impl ColorOrder {
+    /// 每个像素的字节数
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            ColorOrder::Rgb | ColorOrder::Grb => 3,
+            ColorOrder::Rgbw | ColorOrder::Grbw => 4,
+        }
+    }
+
+    /// 按发送顺序排列的颜色分量，只有前 `bytes_per_pixel` 个有效
+    fn channels(self, color: Rgbw) -> [u8; 4] {
+        match self {
+            ColorOrder::Rgb | ColorOrder::Rgbw => [color.r, color.g, color.b, color.w],
+            ColorOrder::Grb | ColorOrder::Grbw => [color.g, color.r, color.b, color.w],
+        }
+    }
+}
+
+/// 数据位时序（纳秒）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ws2812Timing {
+    /// 0码高电平时间
+    pub t0h_ns: u32,
+    /// 0码低电平时间
+    pub t0l_ns: u32,
+    /// 1码高电平时间
+    pub t1h_ns: u32,
+    /// 1码低电平时间
+    pub t1l_ns: u32,
+    /// 帧间复位低电平时间
+    pub reset_ns: u32,
+}
+
+impl Default for Ws2812Timing {
+    fn default() -> Self {
+        // WS2812B 数据手册，复位时间按新批次的 280us 取值
+        Self {
+            t0h_ns: 400,
+            t0l_ns: 850,
+            t1h_ns: 800,
+            t1l_ns: 450,
+            reset_ns: 280_000,
+        }
+    }
+}
+
+/// WS2812 帧编码器
+#[derive(Debug, Clone)]
+pub struct Ws2812Encoder {
+    order: ColorOrder,
+    brightness: u8,
+    gamma: Option<[u8; 256]>,
+    bit0: RmtSymbol,
+    bit1: RmtSymbol,
+    reset: RmtSymbol,
+}
+
+impl Ws2812Encoder {
+    /// 按默认时序创建编码器，亮度为最大，不做伽马校正
+    ///
+    /// # 参数
+    ///
+    /// * `order` - 颜色通道顺序
+    /// * `resolution_hz` - RMT通道分辨率
+    pub fn new(order: ColorOrder, resolution_hz: u32) -> RmtResult<Self> {
+        Self::with_timing(order, resolution_hz, &Ws2812Timing::default())
+    }
+
+    /// 按指定时序创建编码器
+    pub fn with_timing(
+        order: ColorOrder,
+        resolution_hz: u32,
+        timing: &Ws2812Timing,
+    ) -> RmtResult<Self> {
+        let ticks = |ns| ticks_from_ns(ns, resolution_hz);
+        // 复位时间较长，拆成两段低电平
+        let reset = ticks(timing.reset_ns.div_ceil(2)).unwrap_or(RMT_MAX_DURATION);
+        Ok(Ws2812Encoder {
+            order,
+            brightness: u8::MAX,
+            gamma: None,
+            bit0: RmtSymbol::new(1, ticks(timing.t0h_ns)?, 0, ticks(timing.t0l_ns)?),
+            bit1: RmtSymbol::new(1, ticks(timing.t1h_ns)?, 0, ticks(timing.t1l_ns)?),
+            reset: RmtSymbol::new(0, reset, 0, reset),
+        })
+    }
+
+    /// 颜色通道顺序
+    pub fn order(&self) -> ColorOrder {
+        self.order
+    }
+
+    /// 设置全局亮度（0~255）
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+    }
+
+    /// 全局亮度
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// 启用或禁用伽马校正
+    ///
+    /// # 参数
+    ///
+    /// * `gamma` - 伽马值，常用2.8；`None` 表示禁用
+    pub fn set_gamma(&mut self, gamma: Option<f32>) {
+        self.gamma = gamma.map(gamma_table);
+    }
+
+    /// 一个颜色分量经亮度缩放和伽马校正后的输出值
+    pub fn correct(&self, value: u8) -> u8 {
+        let scaled = ((u16::from(value) * u16::from(self.brightness) + 127) / 255) as u8;
+        match &self.gamma {
+            Some(table) => table[scaled as usize],
+            None => scaled,
+        }
+    }
+
+    /// 一帧编码后的符号数
+    pub fn frame_len(&self, pixels: usize) -> usize {
+        pixels * self.order.bytes_per_pixel() * 8 + 1
+    }
+
+    /// 将一帧像素编码为RMT符号，写入 `symbols`（原有内容被清除）
+    pub fn encode(&self, pixels: &[Rgbw], symbols: &mut Vec<RmtSymbol>) {
+        symbols.clear();
+        symbols.reserve(self.frame_len(pixels.len()));
+        let bytes = self.order.bytes_per_pixel();
+        for &pixel in pixels {
+            for &channel in &self.order.channels(pixel)[..bytes] {
+                let value = self.correct(channel);
+                for bit in (0..8).rev() {
+                    let symbol = if value >> bit & 1 == 1 {
+                        self.bit1
+                    } else {
+                        self.bit0
+                    };
+                    symbols.push(symbol);
+                }
+            }
+        }
+        symbols.push(self.reset);
+    }
+}
+
+/// 生成伽马校正表
+fn gamma_table(gamma: f32) -> [u8; 256] {
+    std::array::from_fn(|i| ((i as f32 / 255.0).powf(gamma) * 255.0 + 0.5) as u8)
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+
+    /// 把符号还原为字节，用于断言编码结果
+    fn decode(encoder: &Ws2812Encoder, symbols: &[RmtSymbol]) -> Vec<u8> {
+        symbols
+            .chunks(8)
+            .filter(|chunk| chunk.len() == 8)
+            .map(|chunk| {
+                chunk.iter().fold(0, |byte, &symbol| {
+                    byte << 1 | (symbol == encoder.bit1) as u8
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_encode_grb_frame() {
+        let encoder = Ws2812Encoder::new(ColorOrder::Grb, 10_000_000).unwrap();
+        let pixels = [Rgbw::from_hex(0xFF8001), Rgbw::rgb(0, 0, 0x10)];
+        let mut symbols = Vec::new();
+        encoder.encode(&pixels, &mut symbols);
+
+        assert_eq!(symbols.len(), encoder.frame_len(2));
+        assert_eq!(symbols.len(), 2 * 24 + 1);
+        assert_eq!(
+            decode(&encoder, &symbols),
+            vec![0x80, 0xFF, 0x01, 0x00, 0x00, 0x10]
+        );
+
+        // 10MHz下0码为400ns高、900ns低，1码为800ns高、500ns低（向最近的100ns取整）
+        let bit0 = symbols[1];
+        let bit1 = symbols[0];
+        assert_eq!(
+            (bit0.level0(), bit0.duration0(), bit0.duration1()),
+            (1, 4, 9)
+        );
+        assert_eq!(
+            (bit1.level0(), bit1.duration0(), bit1.duration1()),
+            (1, 8, 5)
+        );
+        let reset = *symbols.last().unwrap();
+        assert_eq!((reset.level0(), reset.level1()), (0, 0));
+        assert_eq!(
+            u32::from(reset.duration0()) + u32::from(reset.duration1()),
+            2800
+        );
+    }
+
+    #[test]
+    fn test_rgbw_brightness_and_gamma() {
+        let mut encoder = Ws2812Encoder::new(ColorOrder::Grbw, 10_000_000).unwrap();
+        let mut symbols = Vec::new();
+        encoder.encode(&[Rgbw::new(1, 2, 3, 4)], &mut symbols);
+        assert_eq!(decode(&encoder, &symbols), vec![2, 1, 3, 4]);
+
+        encoder.set_brightness(128);
+        assert_eq!(encoder.correct(255), 128);
+        assert_eq!(encoder.correct(100), 50);
+        encoder.set_brightness(0);
+        assert_eq!(encoder.correct(255), 0);
+
+        encoder.set_brightness(255);
+        encoder.set_gamma(Some(2.8));
+        assert_eq!(encoder.correct(0), 0);
+        assert_eq!(encoder.correct(255), 255);
+        assert_eq!(encoder.correct(128), 37);
+        encoder.set_gamma(None);
+        assert_eq!(encoder.correct(128), 128);
+    }
+}
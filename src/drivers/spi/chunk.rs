@@ -0,0 +1,364 @@
+/**
+ * @file chunk.rs
+ * @brief 超过最大传输长度的SPI传输
+ * @details 驱动的单个事务不能超过总线的 max_transfer_sz，更长的传输在这里拆分:
+ *          - 块长为4字节的整数倍，除最后一块外保持片选有效，设备看到的仍是一次连续的传输
+ *          - 后续块省略命令、地址和dummy阶段，避免这些阶段混入数据流
+ *          - DMA不能直接访问的数据经由一块可复用的中转缓冲区发送或接收
+ *          - 片选跨事务保持有效需要独占总线；调用者已经独占时不再重复获取和释放
+ *          底层操作由 `ChunkTransport` 提供，主机测试中用模拟设备代替 `SpiDevice`
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::ops::DerefMut;
+
+use embedded_hal::spi::Operation;
+
+use crate::drivers::spi::types::{SpiError, SpiResult};
+
+/// 分块传输需要的底层操作
+pub(crate) trait ChunkTransport {
+    /// 中转缓冲区
+    type Buffer: DerefMut<Target = [u8]>;
+
+    /// 单个事务的最大字节数
+    fn max_transfer(&self) -> usize;
+
+    /// 是否为半双工设备
+    fn half_duplex(&self) -> bool;
+
+    /// 数据能否不经复制直接交给DMA
+    fn dma_capable(&self, data: &[u8]) -> bool;
+
+    /// 分配指定长度的中转缓冲区
+    fn bounce_buffer(&self, len: usize) -> SpiResult<Self::Buffer>;
+
+    /// 本设备当前是否已经独占总线
+    fn bus_locked(&self) -> bool;
+
+    /// 独占总线
+    fn acquire_bus(&self) -> SpiResult<()>;
+
+    /// 释放由 `acquire_bus` 独占的总线
+    fn release_bus(&self);
+
+    /// 执行一个事务
+    ///
+    /// # 参数
+    /// * `continuation` - 是否为后续块，后续块省略命令、地址和dummy阶段
+    /// * `keep_cs` - 事务结束后片选是否保持有效
+    fn transmit_chunk(
+        &self,
+        tx_data: Option<&[u8]>,
+        rx_data: Option<&mut [u8]>,
+        continuation: bool,
+        keep_cs: bool,
+    ) -> SpiResult<()>;
+}
+
+/// 在独占总线期间执行 `f`，调用者已经独占时直接执行
+fn with_bus<T: ChunkTransport>(device: &T, f: impl FnOnce() -> SpiResult<()>) -> SpiResult<()> {
+    if device.bus_locked() {
+        return f();
+    }
+    device.acquire_bus()?;
+    let result = f();
+    device.release_bus();
+    result
+}
+
+/// 执行一次传输，超过最大传输长度时分块
+///
+/// `tx_data` 和 `rx_data` 同时给出时长度必须相同。只需一块时直接交给驱动；
+/// 半双工设备先发送再接收，收发传输不能拆分，同样直接交给驱动。
+///
+/// # 参数
+/// * `keep_cs` - 传输结束后片选是否保持有效
+///
+/// # 返回
+/// * `SpiResult<()>` - 需要分块但最大传输长度不足4字节时返回参数错误
+pub(crate) fn transmit<T: ChunkTransport>(
+    device: &T,
+    tx_data: Option<&[u8]>,
+    mut rx_data: Option<&mut [u8]>,
+    keep_cs: bool,
+) -> SpiResult<()> {
+    let tx_len = tx_data.map_or(0, <[u8]>::len);
+    let rx_len = rx_data.as_ref().map_or(0, |rx| rx.len());
+    let len = tx_len.max(rx_len);
+    if len <= device.max_transfer() || (device.half_duplex() && tx_len > 0 && rx_len > 0) {
+        return device.transmit_chunk(tx_data, rx_data, false, keep_cs);
+    }
+
+    // 块长保持4字节的整数倍，后续块的地址仍然对齐
+    let chunk = device.max_transfer() & !3;
+    if chunk == 0 {
+        return Err(SpiError::InvalidParameter.into());
+    }
+    let mut tx_bounce = match tx_data {
+        Some(tx) if !device.dma_capable(tx) => Some(device.bounce_buffer(chunk)?),
+        _ => None,
+    };
+    let mut rx_bounce = match &rx_data {
+        Some(rx) if !device.dma_capable(rx) => Some(device.bounce_buffer(chunk)?),
+        _ => None,
+    };
+
+    with_bus(device, || {
+        for start in (0..len).step_by(chunk) {
+            let end = (start + chunk).min(len);
+            let size = end - start;
+
+            let tx = match (tx_data, tx_bounce.as_deref_mut()) {
+                (Some(tx), Some(bounce)) => {
+                    bounce[..size].copy_from_slice(&tx[start..end]);
+                    Some(&bounce[..size])
+                }
+                (Some(tx), None) => Some(&tx[start..end]),
+                (None, _) => None,
+            };
+            let rx = match (rx_data.as_deref_mut(), rx_bounce.as_deref_mut()) {
+                (Some(_), Some(bounce)) => Some(&mut bounce[..size]),
+                (Some(rx), None) => Some(&mut rx[start..end]),
+                (None, _) => None,
+            };
+            // 除最后一块外保持片选有效
+            device.transmit_chunk(tx, rx, start > 0, end < len || keep_cs)?;
+
+            if let (Some(rx), Some(bounce)) = (rx_data.as_deref_mut(), rx_bounce.as_deref()) {
+                rx[start..end].copy_from_slice(&bounce[..size]);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// 在一次片选有效期内依次执行 embedded-hal 的操作
+///
+/// 执行期间独占总线，除最后一个数据操作外片选都保持有效，因此片选在操作之间（包括延时）不会释放。
+/// 每个操作都可以超过最大传输长度。
+///
+/// # 参数
+/// * `delay_ns` - 执行 `DelayNs` 操作的延时函数
+pub(crate) fn run_operations<T: ChunkTransport>(
+    device: &T,
+    operations: &mut [Operation<'_, u8>],
+    delay_ns: impl Fn(u32),
+) -> SpiResult<()> {
+    let last_data = operations.iter().rposition(|op| match op {
+        Operation::Read(buf) => !buf.is_empty(),
+        Operation::Write(buf) => !buf.is_empty(),
+        Operation::Transfer(read, write) => !read.is_empty() || !write.is_empty(),
+        Operation::TransferInPlace(buf) => !buf.is_empty(),
+        Operation::DelayNs(_) => false,
+    });
+
+    with_bus(device, || {
+        operations
+            .iter_mut()
+            .enumerate()
+            .try_for_each(|(index, op)| {
+                let keep_cs = Some(index) != last_data;
+                match op {
+                    Operation::Read(buf) if !buf.is_empty() => {
+                        transmit(device, None, Some(&mut buf[..]), keep_cs)
+                    }
+                    Operation::Write(buf) if !buf.is_empty() => {
+                        transmit(device, Some(&buf[..]), None, keep_cs)
+                    }
+                    Operation::Transfer(read, write) if !read.is_empty() || !write.is_empty() => {
+                        // 发送和接收长度不同时，按较长者补齐
+                        let len = read.len().max(write.len());
+                        let mut tx = write.to_vec();
+                        tx.resize(len, 0);
+                        let mut rx = vec![0u8; len];
+                        transmit(device, Some(&tx[..]), Some(&mut rx[..]), keep_cs)?;
+                        let read_len = read.len();
+                        read.copy_from_slice(&rx[..read_len]);
+                        Ok(())
+                    }
+                    Operation::TransferInPlace(buf) if !buf.is_empty() => {
+                        let tx = buf.to_vec();
+                        transmit(device, Some(&tx[..]), Some(&mut buf[..]), keep_cs)
+                    }
+                    Operation::DelayNs(ns) => {
+                        delay_ns(*ns);
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                }
+            })
+    })
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    /// 模拟设备记录的事件
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Event {
+        Acquire,
+        Release,
+        /// 块长、是否为后续块、片选是否保持有效
+        Chunk(usize, bool, bool),
+    }
+
+    /// 全双工模拟设备，接收到的数据为发送数据取反，没有发送数据时为块内偏移
+    struct Device {
+        max_transfer: usize,
+        dma_capable: bool,
+        locked: Cell<bool>,
+        events: RefCell<Vec<Event>>,
+        sent: RefCell<Vec<u8>>,
+    }
+
+    impl Device {
+        fn new(max_transfer: usize, dma_capable: bool) -> Self {
+            Device {
+                max_transfer,
+                dma_capable,
+                locked: Cell::new(false),
+                events: RefCell::new(Vec::new()),
+                sent: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn events(&self) -> Vec<Event> {
+            self.events.borrow().clone()
+        }
+    }
+
+    impl ChunkTransport for Device {
+        type Buffer = Vec<u8>;
+
+        fn max_transfer(&self) -> usize {
+            self.max_transfer
+        }
+
+        fn half_duplex(&self) -> bool {
+            false
+        }
+
+        fn dma_capable(&self, _data: &[u8]) -> bool {
+            self.dma_capable
+        }
+
+        fn bounce_buffer(&self, len: usize) -> SpiResult<Vec<u8>> {
+            Ok(vec![0; len])
+        }
+
+        fn bus_locked(&self) -> bool {
+            self.locked.get()
+        }
+
+        fn acquire_bus(&self) -> SpiResult<()> {
+            // 重复获取在真实驱动上会一直阻塞
+            assert!(!self.locked.replace(true), "总线被重复获取");
+            self.events.borrow_mut().push(Event::Acquire);
+            Ok(())
+        }
+
+        fn release_bus(&self) {
+            assert!(self.locked.replace(false), "总线被重复释放");
+            self.events.borrow_mut().push(Event::Release);
+        }
+
+        fn transmit_chunk(
+            &self,
+            tx_data: Option<&[u8]>,
+            rx_data: Option<&mut [u8]>,
+            continuation: bool,
+            keep_cs: bool,
+        ) -> SpiResult<()> {
+            let len = tx_data.map_or(0, <[u8]>::len);
+            let len = len.max(rx_data.as_ref().map_or(0, |rx| rx.len()));
+            assert!(len <= self.max_transfer);
+            self.events
+                .borrow_mut()
+                .push(Event::Chunk(len, continuation, keep_cs));
+            if let Some(tx) = tx_data {
+                self.sent.borrow_mut().extend_from_slice(tx);
+            }
+            if let Some(rx) = rx_data {
+                for (i, byte) in rx.iter_mut().enumerate() {
+                    *byte = tx_data.map_or(i as u8, |tx| !tx[i]);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_chunked_transfer_and_bus_lock() {
+        // 最大传输10字节时块长为8，经由中转缓冲区收发
+        let device = Device::new(10, false);
+        let tx: Vec<u8> = (0..21).collect();
+        let mut rx = vec![0; 21];
+        transmit(&device, Some(&tx), Some(&mut rx), false).unwrap();
+        assert!(rx.iter().zip(&tx).all(|(rx, tx)| *rx == !tx));
+        assert_eq!(
+            device.events(),
+            vec![
+                Event::Acquire,
+                Event::Chunk(8, false, true),
+                Event::Chunk(8, true, true),
+                Event::Chunk(5, true, false),
+                Event::Release,
+            ]
+        );
+
+        // 调用者已经独占总线（lock_bus）时不再获取和释放
+        let device = Device::new(10, true);
+        device.locked.set(true);
+        transmit(&device, Some(&tx), None, false).unwrap();
+        assert_eq!(*device.sent.borrow(), tx);
+        assert_eq!(device.events().first(), Some(&Event::Chunk(8, false, true)));
+        assert!(!device.events().contains(&Event::Release));
+        assert!(device.bus_locked());
+
+        // 一块就能发完时直接交给驱动，不独占总线
+        let device = Device::new(10, true);
+        transmit(&device, Some(&tx[..10]), None, false).unwrap();
+        assert_eq!(device.events(), vec![Event::Chunk(10, false, false)]);
+
+        // 最大传输长度不足4字节时无法分块
+        let device = Device::new(3, true);
+        let error = transmit(&device, Some(&tx), None, false).unwrap_err();
+        assert_eq!(error.spi(), Some(&SpiError::InvalidParameter));
+    }
+
+    #[test]
+    fn test_operations_are_chunked() {
+        let device = Device::new(8, true);
+        let header = [0x2C];
+        let frame = vec![0x5A; 20];
+        let mut status = [0; 2];
+        let mut operations = [
+            Operation::Write(&header),
+            Operation::DelayNs(1_000),
+            Operation::Write(&frame),
+            Operation::Read(&mut status),
+        ];
+        let delays = Cell::new(0);
+        run_operations(&device, &mut operations, |ns| delays.set(delays.get() + ns)).unwrap();
+
+        assert_eq!(delays.get(), 1_000);
+        assert_eq!(status, [0, 1]);
+        // 只独占一次总线，片选在最后一个操作结束时才释放
+        assert_eq!(
+            device.events(),
+            vec![
+                Event::Acquire,
+                Event::Chunk(1, false, true),
+                Event::Chunk(8, false, true),
+                Event::Chunk(8, true, true),
+                Event::Chunk(4, true, true),
+                Event::Chunk(2, false, false),
+                Event::Release,
+            ]
+        );
+    }
+}
@@ -1,30 +1,42 @@
 // SPI控制器实现
+use crate::drivers::gpio::registry::{self, PinClaim};
+use crate::drivers::spi::chunk::{self, ChunkTransport};
+use crate::drivers::spi::dma::{self, DmaBuffer};
+use crate::drivers::spi::queue;
 use crate::drivers::spi::types::*;
+use crate::error::Error;
 use esp_idf_svc::sys;
+use std::cell::Cell;
+use std::ops::Deref;
 use std::ptr;
 use std::vec::Vec;
 
-/// SPI主机总线编号
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum SpiBus {
-    /// SPI1，通常用于Flash访问
-    Spi1 = 1,
-    /// SPI2，通常可用于用户应用
-    Spi2 = 2,
-    /// SPI3，通常可用于用户应用 (ESP32-S3有SPI3)
-    Spi3 = 3,
-}
-
 /// SPI设备句柄结构体
 pub struct SpiDevice {
-    handle: sys::spi_device_handle_t,
+    pub(super) handle: sys::spi_device_handle_t,
+    host: SpiBus,
+    /// 同时在途的最大事务数
+    pub(super) queue_size: usize,
+    /// 半双工设备的接收阶段不需要发送长度
+    pub(super) half_duplex: bool,
+    /// 总线可用的数据线数
+    lines: SpiLines,
+    /// 单个事务的最大字节数
+    max_transfer: usize,
+    /// 本设备是否已经独占总线，避免在 `lock_bus` 期间重复获取
+    bus_locked: Cell<bool>,
 }
 
 /// SPI主机控制器
 pub struct SpiMaster {
     host: SpiBus,
     initialized: bool,
+    /// 总线可用的数据线数
+    lines: SpiLines,
+    /// 驱动实际采用的单个事务最大字节数
+    max_transfer: usize,
     devices: Vec<sys::spi_device_handle_t>, // 跟踪添加到此总线的所有SPI设备
+    pins: Vec<PinClaim>,                    // 总线和片选占用的引脚
 }
 
 impl SpiMaster {
@@ -39,12 +51,15 @@ impl SpiMaster {
         let spi = SpiMaster {
             host,
             initialized: false,
+            lines: SpiLines::Single,
+            max_transfer: 0,
             devices: Vec::new(),
+            pins: Vec::new(),
         };
         Ok(spi)
     }
 
-    /// 初始化SPI总线
+    /// 初始化单线SPI总线
     ///
     /// # 参数
     /// * `mosi_pin` - MOSI引脚编号
@@ -61,34 +76,70 @@ impl SpiMaster {
         sclk_pin: i32,
         max_transfer_size: usize,
     ) -> SpiResult<()> {
+        self.initialize_with(
+            &SpiBusConfig::new(mosi_pin, miso_pin, sclk_pin).max_transfer_size(max_transfer_size),
+        )
+    }
+
+    /// 按总线配置初始化SPI总线，可以提供四线和八线传输需要的数据引脚
+    ///
+    /// # 参数
+    /// * `config` - 总线配置
+    ///
+    /// # 返回
+    /// * `SpiResult<()>` - 成功返回Ok(())，引脚组合无效时返回参数错误
+    pub fn initialize_with(&mut self, config: &SpiBusConfig) -> SpiResult<()> {
         if self.initialized {
             return Ok(());
         }
+        config.validate()?;
+
+        let [data4, data5, data6, data7] = config.octal_pins.unwrap_or([-1; 4]);
+        let signals = [
+            (config.mosi_pin, "mosi"),
+            (config.miso_pin, "miso"),
+            (config.sclk_pin, "sclk"),
+            (config.quadwp_pin.unwrap_or(-1), "wp"),
+            (config.quadhd_pin.unwrap_or(-1), "hd"),
+            (data4, "d4"),
+            (data5, "d5"),
+            (data6, "d6"),
+            (data7, "d7"),
+        ];
+
+        // 先领取引脚，与其他驱动冲突时在这里报错
+        let mut pins = Vec::new();
+        for (pin, signal) in signals {
+            if pin >= 0 {
+                pins.push(registry::claim(pin as u32, self.pin_owner(signal))?);
+            }
+        }
 
         // SPI总线配置
         let mut bus_config = sys::spi_bus_config_t::default();
-        // 设置MOSI引脚
-        bus_config.__bindgen_anon_1.mosi_io_num = mosi_pin;
-        // 设置MISO引脚
-        bus_config.__bindgen_anon_2.miso_io_num = miso_pin;
+        // 设置MOSI引脚（D0）
+        bus_config.__bindgen_anon_1.mosi_io_num = config.mosi_pin;
+        // 设置MISO引脚（D1）
+        bus_config.__bindgen_anon_2.miso_io_num = config.miso_pin;
         // 设置时钟引脚
-        bus_config.sclk_io_num = sclk_pin;
-        // 设置WP和HD引脚 (不使用)
-        bus_config.__bindgen_anon_3.quadwp_io_num = -1;
-        bus_config.__bindgen_anon_4.quadhd_io_num = -1;
-        // 设置其他引脚 (不使用)
-        bus_config.data4_io_num = -1;
-        bus_config.data5_io_num = -1;
-        bus_config.data6_io_num = -1;
-        bus_config.data7_io_num = -1;
+        bus_config.sclk_io_num = config.sclk_pin;
+        // 设置WP和HD引脚（D2、D3），-1表示不使用
+        bus_config.__bindgen_anon_3.quadwp_io_num = config.quadwp_pin.unwrap_or(-1);
+        bus_config.__bindgen_anon_4.quadhd_io_num = config.quadhd_pin.unwrap_or(-1);
+        // 设置D4~D7引脚，-1表示不使用
+        bus_config.data4_io_num = data4;
+        bus_config.data5_io_num = data5;
+        bus_config.data6_io_num = data6;
+        bus_config.data7_io_num = data7;
         // 设置最大传输大小
-        bus_config.max_transfer_sz = if max_transfer_size > 0 {
-            max_transfer_size as i32
-        } else {
-            0
+        bus_config.max_transfer_sz = config.max_transfer_size as i32;
+        // 要求驱动检查多线传输需要的引脚，八线模式必须指定
+        bus_config.flags = match config.lines() {
+            SpiLines::Octal => sys::SPICOMMON_BUSFLAG_OCTAL,
+            SpiLines::Quad => sys::SPICOMMON_BUSFLAG_QUAD,
+            // 双线复用MOSI、MISO，不需要额外的引脚
+            SpiLines::Dual | SpiLines::Single => 0,
         };
-        // 设置其他标志
-        bus_config.flags = 0;
         bus_config.isr_cpu_id = 0; // 默认CPU
 
         // 初始化SPI总线
@@ -101,13 +152,39 @@ impl SpiMaster {
         };
 
         if result != sys::ESP_OK {
-            return Err(SpiError::DriverError(result));
+            return Err(self.driver_error(result, "spi_bus_initialize"));
         }
 
+        // 驱动会把最大传输大小向上取整到DMA描述符的整数倍，0时约为4KB
+        let mut max_transfer = 0;
+        let result = unsafe {
+            sys::spi_bus_get_max_transaction_len(
+                self.host as sys::spi_host_device_t,
+                &mut max_transfer,
+            )
+        };
+        if result != sys::ESP_OK {
+            unsafe { sys::spi_bus_free(self.host as sys::spi_host_device_t) };
+            return Err(self.driver_error(result, "spi_bus_get_max_transaction_len"));
+        }
+
+        self.pins = pins;
+        self.max_transfer = max_transfer;
+        self.lines = config.lines();
         self.initialized = true;
         Ok(())
     }
 
+    /// 由ESP-IDF返回值构造驱动错误
+    fn driver_error(&self, code: i32, operation: &'static str) -> Error {
+        Error::esp(SpiError::DriverError, code, operation).with_spi_host(self.host as u32)
+    }
+
+    /// 引脚登记表中使用的占用者名称，例如 `spi2.sclk`
+    fn pin_owner(&self, signal: &str) -> String {
+        format!("spi{}.{}", self.host as u32, signal)
+    }
+
     /// 添加SPI设备
     ///
     /// # 参数
@@ -117,30 +194,36 @@ impl SpiMaster {
     /// * `SpiResult<SpiDevice>` - 成功返回设备句柄，失败返回错误
     pub fn add_device(&mut self, config: &SpiDeviceConfig) -> SpiResult<SpiDevice> {
         if !self.initialized {
-            return Err(SpiError::InvalidParameter);
+            return Err(SpiError::InvalidParameter.into());
         }
 
+        let cs_claim = match config.cs_pin {
+            Some(pin) if pin >= 0 => Some(registry::claim(pin as u32, self.pin_owner("cs"))?),
+            _ => None,
+        };
+
         // SPI设备接口配置
         let device_config = sys::spi_device_interface_config_t {
             command_bits: config.command_bits,
             address_bits: config.address_bits,
-            dummy_bits: 0,
+            dummy_bits: config.dummy_bits,
             mode: config.mode as u8,
-            duty_cycle_pos: 0,
-            cs_ena_pretrans: 0,
-            cs_ena_posttrans: 0,
+            duty_cycle_pos: config.duty_cycle,
+            cs_ena_pretrans: config.cs_setup_cycles,
+            cs_ena_posttrans: config.cs_hold_cycles,
             clock_speed_hz: config.clock_speed_hz as i32,
-            input_delay_ns: 0,
+            input_delay_ns: config.input_delay_ns,
             spics_io_num: config.cs_pin.unwrap_or(-1),
-            flags: if config.bit_order == SpiBitOrder::LSBFirst {
-                sys::SPI_DEVICE_BIT_LSBFIRST as u32
-            } else {
-                0
-            },
+            flags: device_flags(config),
             queue_size: config.queue_size as i32,
             pre_cb: None,
-            post_cb: None,
-            clock_source: 0, // Default clock source
+            // 通知 SpiQueue 事务已完成，其他事务的 user 为空，回调直接返回
+            post_cb: Some(queue::post_transaction),
+            clock_source: match config.clock_source {
+                SpiClockSource::Default => sys::soc_periph_spi_clk_src_t_SPI_CLK_SRC_DEFAULT,
+                SpiClockSource::Apb => sys::soc_periph_spi_clk_src_t_SPI_CLK_SRC_APB,
+                SpiClockSource::Xtal => sys::soc_periph_spi_clk_src_t_SPI_CLK_SRC_XTAL,
+            },
         };
 
         // 添加SPI设备
@@ -154,14 +237,23 @@ impl SpiMaster {
         };
 
         if result != sys::ESP_OK {
-            return Err(SpiError::DriverError(result));
+            return Err(self.driver_error(result, "spi_bus_add_device"));
         }
 
         // 跟踪设备句柄
         self.devices.push(handle);
+        self.pins.extend(cs_claim);
 
         // 返回设备句柄
-        Ok(SpiDevice { handle })
+        Ok(SpiDevice {
+            handle,
+            host: self.host,
+            queue_size: config.queue_size,
+            half_duplex: config.flags.half_duplex,
+            lines: self.lines,
+            max_transfer: self.max_transfer,
+            bus_locked: Cell::new(false),
+        })
     }
 
     /// 释放SPI总线
@@ -177,12 +269,13 @@ impl SpiMaster {
             }
         }
         self.devices.clear();
+        self.pins.clear();
 
         // 释放SPI总线
         let result = unsafe { sys::spi_bus_free(self.host as sys::spi_host_device_t) };
 
         if result != sys::ESP_OK {
-            return Err(SpiError::DriverError(result));
+            return Err(self.driver_error(result, "spi_bus_free"));
         }
 
         self.initialized = false;
@@ -190,6 +283,44 @@ impl SpiMaster {
     }
 }
 
+/// 把事务的多线方式转换为 `SPI_TRANS_*` 位
+fn line_flags(mode: SpiLineMode) -> u32 {
+    let data = match mode.data {
+        SpiLines::Single => return 0,
+        SpiLines::Dual => sys::SPI_TRANS_MODE_DIO,
+        SpiLines::Quad => sys::SPI_TRANS_MODE_QIO,
+        SpiLines::Octal => sys::SPI_TRANS_MODE_OCT,
+    };
+    let address = if mode.address {
+        sys::SPI_TRANS_MULTILINE_ADDR
+    } else {
+        0
+    };
+    let command = if mode.command {
+        sys::SPI_TRANS_MULTILINE_CMD
+    } else {
+        0
+    };
+    data | address | command
+}
+
+/// 把设备配置中的位序和标志转换为 `SPI_DEVICE_*` 位
+fn device_flags(config: &SpiDeviceConfig) -> u32 {
+    [
+        (
+            config.bit_order == SpiBitOrder::LSBFirst,
+            sys::SPI_DEVICE_BIT_LSBFIRST,
+        ),
+        (config.flags.half_duplex, sys::SPI_DEVICE_HALFDUPLEX),
+        (config.flags.three_wire, sys::SPI_DEVICE_3WIRE),
+        (config.flags.cs_active_high, sys::SPI_DEVICE_POSITIVE_CS),
+        (config.flags.no_dummy, sys::SPI_DEVICE_NO_DUMMY),
+    ]
+    .into_iter()
+    .filter(|&(enabled, _)| enabled)
+    .fold(0, |flags, (_, bit)| flags | bit as u32)
+}
+
 impl Drop for SpiMaster {
     fn drop(&mut self) {
         // 尝试释放资源
@@ -198,8 +329,20 @@ impl Drop for SpiMaster {
 }
 
 impl SpiDevice {
+    /// 由ESP-IDF返回值构造驱动错误
+    pub(super) fn driver_error(&self, code: i32, operation: &'static str) -> Error {
+        Error::esp(SpiError::DriverError, code, operation).with_spi_host(self.host as u32)
+    }
+
+    /// 单个事务的最大字节数，`write`、`transfer`、`read` 超过时自动分块
+    pub fn max_transfer_size(&self) -> usize {
+        self.max_transfer
+    }
+
     /// 发送并接收数据
     ///
+    /// 超过总线最大传输长度时自动分块，见 [`write`](Self::write)。
+    ///
     /// # 参数
     /// * `tx_data` - 发送数据
     /// * `rx_data` - 接收数据缓冲区
@@ -209,34 +352,21 @@ impl SpiDevice {
     pub fn transfer(&self, tx_data: &[u8], rx_data: &mut [u8]) -> SpiResult<()> {
         let len = tx_data.len().min(rx_data.len());
         if len == 0 {
-            return Err(SpiError::InvalidParameter);
-        }
-
-        // 创建SPI事务
-        let mut transaction = sys::spi_transaction_t::default();
-        transaction.flags = 0;
-        transaction.cmd = 0;
-        transaction.addr = 0;
-        transaction.length = (len * 8) as usize; // 以位为单位
-        transaction.rxlength = (len * 8) as usize; // 设置接收长度
-        transaction.user = ptr::null_mut();
-
-        // 设置发送和接收缓冲区
-        transaction.__bindgen_anon_1.tx_buffer = tx_data.as_ptr() as *const _;
-        transaction.__bindgen_anon_2.rx_buffer = rx_data.as_mut_ptr() as *mut _;
-
-        // 执行SPI事务
-        let result = unsafe { sys::spi_device_transmit(self.handle, &mut transaction) };
-
-        if result != sys::ESP_OK {
-            return Err(SpiError::DriverError(result));
+            return Err(SpiError::InvalidParameter.into());
         }
-
-        Ok(())
+        chunk::transmit(
+            self,
+            Some(&tx_data[..len]),
+            Some(&mut rx_data[..len]),
+            false,
+        )
     }
 
     /// 只发送数据
     ///
+    /// 超过总线最大传输长度时按最大长度分块，期间独占总线并保持片选有效，
+    /// 设备看到的仍是一次连续的传输；DMA不能直接访问的数据（PSRAM等）经由DMA缓冲区中转。
+    ///
     /// # 参数
     /// * `tx_data` - 发送数据
     ///
@@ -244,34 +374,15 @@ impl SpiDevice {
     /// * `SpiResult<()>` - 成功返回Ok(())，失败返回错误
     pub fn write(&self, tx_data: &[u8]) -> SpiResult<()> {
         if tx_data.is_empty() {
-            return Err(SpiError::InvalidParameter);
+            return Err(SpiError::InvalidParameter.into());
         }
-
-        // 创建SPI事务
-        let mut transaction = sys::spi_transaction_t::default();
-        transaction.flags = 0;
-        transaction.cmd = 0;
-        transaction.addr = 0;
-        transaction.length = (tx_data.len() * 8) as usize; // 以位为单位
-        transaction.rxlength = 0; // 不需要接收数据
-        transaction.user = ptr::null_mut();
-
-        // 设置发送缓冲区
-        transaction.__bindgen_anon_1.tx_buffer = tx_data.as_ptr() as *const _;
-        transaction.__bindgen_anon_2.rx_buffer = ptr::null_mut();
-
-        // 执行SPI事务
-        let result = unsafe { sys::spi_device_transmit(self.handle, &mut transaction) };
-
-        if result != sys::ESP_OK {
-            return Err(SpiError::DriverError(result));
-        }
-
-        Ok(())
+        chunk::transmit(self, Some(tx_data), None, false)
     }
 
     /// 只接收数据
     ///
+    /// 超过总线最大传输长度时自动分块，见 [`write`](Self::write)。
+    ///
     /// # 参数
     /// * `rx_data` - 接收数据缓冲区
     ///
@@ -279,30 +390,10 @@ impl SpiDevice {
     /// * `SpiResult<()>` - 成功返回Ok(())，失败返回错误
     pub fn read(&self, rx_data: &mut [u8]) -> SpiResult<()> {
         if rx_data.is_empty() {
-            return Err(SpiError::InvalidParameter);
-        }
-
-        // 创建SPI事务，发送全为0的数据
-        let mut transaction = sys::spi_transaction_t::default();
-        transaction.flags = 0;
-        transaction.cmd = 0;
-        transaction.addr = 0;
-        transaction.length = (rx_data.len() * 8) as usize; // 以位为单位
-        transaction.rxlength = (rx_data.len() * 8) as usize; // 接收长度
-        transaction.user = ptr::null_mut();
-
-        // 设置发送和接收缓冲区
-        transaction.__bindgen_anon_1.tx_buffer = ptr::null(); // 将自动发送0
-        transaction.__bindgen_anon_2.rx_buffer = rx_data.as_mut_ptr() as *mut _;
-
-        // 执行SPI事务
-        let result = unsafe { sys::spi_device_transmit(self.handle, &mut transaction) };
-
-        if result != sys::ESP_OK {
-            return Err(SpiError::DriverError(result));
+            return Err(SpiError::InvalidParameter.into());
         }
-
-        Ok(())
+        // 全双工时发送内容不确定
+        chunk::transmit(self, None, Some(rx_data), false)
     }
 
     /// 带命令和地址的写数据
@@ -344,11 +435,297 @@ impl SpiDevice {
         let result = unsafe { sys::spi_device_transmit(self.handle, &mut transaction) };
 
         if result != sys::ESP_OK {
-            return Err(SpiError::DriverError(result));
+            return Err(self.driver_error(result, "spi_device_transmit"));
         }
 
         Ok(())
     }
+
+    /// 执行一次全双工事务
+    ///
+    /// 事务长度取发送和接收缓冲区中较长者，`tx_data` 为 `None` 时发送内容不确定；
+    /// 半双工设备先发送再接收。
+    ///
+    /// # 参数
+    /// * `tx_data` - 发送数据
+    /// * `rx_data` - 接收数据缓冲区
+    /// * `flags` - 事务标志（`SPI_TRANS_*`）
+    pub(super) fn transmit(
+        &self,
+        tx_data: Option<&[u8]>,
+        rx_data: Option<&mut [u8]>,
+        flags: u32,
+    ) -> SpiResult<()> {
+        self.execute(tx_data, rx_data, flags, false)
+    }
+
+    /// 以轮询方式只发送数据
+    ///
+    /// `spi_device_polling_transmit` 忙等待事务完成，省去中断和任务切换的开销，
+    /// 适合命令、参数等很短的传输；配合 [`lock_bus`](Self::lock_bus) 时开销最小。
+    ///
+    /// # 参数
+    /// * `tx_data` - 发送数据
+    ///
+    /// # 返回
+    /// * `SpiResult<()>` - 成功返回Ok(())，失败返回错误
+    pub fn polling_write(&self, tx_data: &[u8]) -> SpiResult<()> {
+        if tx_data.is_empty() {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        self.execute(Some(tx_data), None, 0, true)
+    }
+
+    /// 以轮询方式发送并接收数据，长度取两者中较短者
+    ///
+    /// # 参数
+    /// * `tx_data` - 发送数据
+    /// * `rx_data` - 接收数据缓冲区
+    ///
+    /// # 返回
+    /// * `SpiResult<()>` - 成功返回Ok(())，失败返回错误
+    pub fn polling_transfer(&self, tx_data: &[u8], rx_data: &mut [u8]) -> SpiResult<()> {
+        let len = tx_data.len().min(rx_data.len());
+        if len == 0 {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        self.execute(Some(&tx_data[..len]), Some(&mut rx_data[..len]), 0, true)
+    }
+
+    /// 独占总线直到返回的守卫被释放
+    ///
+    /// 期间本设备的事务不再参与总线仲裁，其他设备的事务会等待，适合连续的一串短事务。
+    /// 持有守卫时可以直接通过它调用设备的方法。
+    ///
+    /// # 返回
+    /// * `SpiResult<SpiBusLock>` - 已经独占总线时返回 `BusBusy`
+    pub fn lock_bus(&self) -> SpiResult<SpiBusLock<'_>> {
+        if self.bus_locked.get() {
+            return Err(SpiError::BusBusy.into());
+        }
+        self.acquire_bus()?;
+        Ok(SpiBusLock { device: self })
+    }
+
+    /// 以指定的多线方式发送命令、地址和数据
+    ///
+    /// 命令和地址的长度由设备配置中的 `command_bits`、`address_bits` 决定，长度为0时省略。
+    ///
+    /// # 参数
+    /// * `mode` - 各阶段使用的数据线
+    /// * `cmd` - 命令
+    /// * `addr` - 地址
+    /// * `tx_data` - 发送数据，可以为空
+    ///
+    /// # 返回
+    /// * `SpiResult<()>` - 总线线数不够或设备不是半双工时返回参数错误
+    pub fn write_multiline(
+        &self,
+        mode: SpiLineMode,
+        cmd: u16,
+        addr: u32,
+        tx_data: &[u8],
+    ) -> SpiResult<()> {
+        mode.check(self.lines, self.half_duplex)?;
+        let tx_data = (!tx_data.is_empty()).then_some(tx_data);
+        let mut transaction = self.transaction(tx_data, None, line_flags(mode));
+        transaction.cmd = cmd;
+        transaction.addr = u64::from(addr);
+        self.run(&mut transaction, false)
+    }
+
+    /// 以指定的多线方式发送命令和地址后接收数据
+    ///
+    /// 地址与数据之间的等待周期由设备配置中的 `dummy_bits` 决定。
+    ///
+    /// # 参数
+    /// * `mode` - 各阶段使用的数据线
+    /// * `cmd` - 命令
+    /// * `addr` - 地址
+    /// * `rx_data` - 接收数据缓冲区
+    ///
+    /// # 返回
+    /// * `SpiResult<()>` - 缓冲区为空、总线线数不够或设备不是半双工时返回参数错误
+    pub fn read_multiline(
+        &self,
+        mode: SpiLineMode,
+        cmd: u16,
+        addr: u32,
+        rx_data: &mut [u8],
+    ) -> SpiResult<()> {
+        if rx_data.is_empty() {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        mode.check(self.lines, self.half_duplex)?;
+        let mut transaction = self.transaction(None, Some(rx_data), line_flags(mode));
+        transaction.cmd = cmd;
+        transaction.addr = u64::from(addr);
+        self.run(&mut transaction, false)
+    }
+
+    /// 执行一次事务，`polling` 为真时忙等待完成
+    fn execute(
+        &self,
+        tx_data: Option<&[u8]>,
+        rx_data: Option<&mut [u8]>,
+        flags: u32,
+        polling: bool,
+    ) -> SpiResult<()> {
+        let mut transaction = self.transaction(tx_data, rx_data, flags);
+        self.run(&mut transaction, polling)
+    }
+
+    /// 构造事务描述符，缓冲区在事务完成之前必须保持有效
+    fn transaction(
+        &self,
+        tx_data: Option<&[u8]>,
+        rx_data: Option<&mut [u8]>,
+        flags: u32,
+    ) -> sys::spi_transaction_t {
+        let tx_len = tx_data.map_or(0, <[u8]>::len);
+        let rx_len = rx_data.as_ref().map_or(0, |rx| rx.len());
+
+        let mut transaction = sys::spi_transaction_t::default();
+        transaction.flags = flags;
+        transaction.length = if self.half_duplex {
+            tx_len * 8
+        } else {
+            tx_len.max(rx_len) * 8
+        };
+        transaction.rxlength = rx_len * 8;
+        transaction.__bindgen_anon_1.tx_buffer =
+            tx_data.map_or(ptr::null(), |tx| tx.as_ptr() as *const _);
+        transaction.__bindgen_anon_2.rx_buffer =
+            rx_data.map_or(ptr::null_mut(), |rx| rx.as_mut_ptr() as *mut _);
+        transaction
+    }
+
+    /// 执行事务，`polling` 为真时忙等待完成
+    fn run(&self, transaction: *mut sys::spi_transaction_t, polling: bool) -> SpiResult<()> {
+        let (result, operation) = unsafe {
+            if polling {
+                (
+                    sys::spi_device_polling_transmit(self.handle, transaction),
+                    "spi_device_polling_transmit",
+                )
+            } else {
+                (
+                    sys::spi_device_transmit(self.handle, transaction),
+                    "spi_device_transmit",
+                )
+            }
+        };
+
+        if result != sys::ESP_OK {
+            return Err(self.driver_error(result, operation));
+        }
+
+        Ok(())
+    }
+
+    /// 独占总线，期间其他设备的事务会等待
+    pub(super) fn acquire_bus(&self) -> SpiResult<()> {
+        // portMAX_DELAY，一直等待
+        let result = unsafe { sys::spi_device_acquire_bus(self.handle, u32::MAX) };
+
+        if result != sys::ESP_OK {
+            return Err(self.driver_error(result, "spi_device_acquire_bus"));
+        }
+
+        self.bus_locked.set(true);
+        Ok(())
+    }
+
+    /// 释放由 `acquire_bus` 独占的总线
+    pub(super) fn release_bus(&self) {
+        self.bus_locked.set(false);
+        unsafe { sys::spi_device_release_bus(self.handle) };
+    }
+}
+
+impl ChunkTransport for SpiDevice {
+    type Buffer = DmaBuffer;
+
+    fn max_transfer(&self) -> usize {
+        self.max_transfer
+    }
+
+    fn half_duplex(&self) -> bool {
+        self.half_duplex
+    }
+
+    fn dma_capable(&self, data: &[u8]) -> bool {
+        dma::is_dma_capable(data)
+    }
+
+    fn bounce_buffer(&self, len: usize) -> SpiResult<DmaBuffer> {
+        DmaBuffer::new(len)
+    }
+
+    fn bus_locked(&self) -> bool {
+        self.bus_locked.get()
+    }
+
+    fn acquire_bus(&self) -> SpiResult<()> {
+        SpiDevice::acquire_bus(self)
+    }
+
+    fn release_bus(&self) {
+        SpiDevice::release_bus(self)
+    }
+
+    fn transmit_chunk(
+        &self,
+        tx_data: Option<&[u8]>,
+        rx_data: Option<&mut [u8]>,
+        continuation: bool,
+        keep_cs: bool,
+    ) -> SpiResult<()> {
+        let flags = if keep_cs {
+            sys::SPI_TRANS_CS_KEEP_ACTIVE as u32
+        } else {
+            0
+        };
+        if !continuation {
+            return self.transmit(tx_data, rx_data, flags);
+        }
+
+        // 后续块的命令、地址和dummy长度都设为0，只发送数据
+        let mut transaction = sys::spi_transaction_ext_t {
+            base: self.transaction(tx_data, rx_data, flags),
+            ..Default::default()
+        };
+        transaction.base.flags |= (sys::SPI_TRANS_VARIABLE_CMD
+            | sys::SPI_TRANS_VARIABLE_ADDR
+            | sys::SPI_TRANS_VARIABLE_DUMMY) as u32;
+        transaction.command_bits = 0;
+        transaction.address_bits = 0;
+        transaction.dummy_bits = 0;
+        // 驱动按标志把描述符当作 spi_transaction_ext_t 访问
+        self.run(
+            &mut transaction as *mut sys::spi_transaction_ext_t as *mut sys::spi_transaction_t,
+            false,
+        )
+    }
+}
+
+/// 总线独占守卫，由 [`SpiDevice::lock_bus`] 返回，释放时归还总线
+pub struct SpiBusLock<'a> {
+    device: &'a SpiDevice,
+}
+
+impl Deref for SpiBusLock<'_> {
+    type Target = SpiDevice;
+
+    fn deref(&self) -> &SpiDevice {
+        self.device
+    }
+}
+
+impl Drop for SpiBusLock<'_> {
+    fn drop(&mut self) {
+        self.device.release_bus();
+    }
 }
 
 /// SPI3总线（ESP32-S3特有）初始化辅助函数
@@ -0,0 +1,113 @@
+/**
+ * @file dma.rs
+ * @brief DMA缓冲区
+ * @details SPI的DMA只能访问内部SRAM中的部分地址，且要求4字节对齐:
+ *          - `DmaBuffer` 用 heap_caps_calloc(MALLOC_CAP_DMA) 分配，可以直接交给DMA
+ *          - PSRAM、Flash中的常量等其他内存先复制到DMA缓冲区再发送
+ *          - 缓冲区实现 `AsRef<[u8]>`/`AsMut<[u8]>`，可以作为 `SpiQueue` 的自有缓冲区
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::slice;
+
+use esp_idf_svc::sys;
+
+use crate::drivers::spi::types::{SpiError, SpiResult};
+
+/// ESP32-S3 内部SRAM中DMA可以访问的地址范围（soc.h 的 SOC_DMA_LOW、SOC_DMA_HIGH）
+const DMA_LOW: usize = 0x3FC8_8000;
+const DMA_HIGH: usize = 0x3FD0_0000;
+
+/// 数据能否不经复制直接交给SPI的DMA
+pub(super) fn is_dma_capable(data: &[u8]) -> bool {
+    let address = data.as_ptr() as usize;
+    (DMA_LOW..DMA_HIGH).contains(&address) && address % 4 == 0
+}
+
+/// DMA可以访问的字节缓冲区
+///
+/// 分配时清零，容量固定；有效长度可以在容量以内调整，便于每次发送不同长度的数据。
+pub struct DmaBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+    capacity: usize,
+}
+
+// 缓冲区独占所指向的内存
+unsafe impl Send for DmaBuffer {}
+unsafe impl Sync for DmaBuffer {}
+
+impl DmaBuffer {
+    /// 分配指定长度的缓冲区
+    ///
+    /// # 返回
+    /// * `SpiResult<Self>` - 长度为0时返回参数错误，DMA内存不足时返回 `NoMemory`
+    pub fn new(len: usize) -> SpiResult<Self> {
+        if len == 0 {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        let ptr = unsafe { sys::heap_caps_calloc(1, len, sys::MALLOC_CAP_DMA) };
+        let ptr = NonNull::new(ptr as *mut u8).ok_or(SpiError::NoMemory)?;
+        Ok(DmaBuffer {
+            ptr,
+            len,
+            capacity: len,
+        })
+    }
+
+    /// 分配缓冲区并复制数据
+    pub fn from_slice(data: &[u8]) -> SpiResult<Self> {
+        let mut buffer = Self::new(data.len())?;
+        buffer.copy_from_slice(data);
+        Ok(buffer)
+    }
+
+    /// 容量（字节）
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 调整有效长度，不能超过容量；增加的部分保留原来的内容
+    pub fn set_len(&mut self, len: usize) -> SpiResult<()> {
+        if len > self.capacity {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        self.len = len;
+        Ok(())
+    }
+}
+
+impl Deref for DmaBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for DmaBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl AsRef<[u8]> for DmaBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl AsMut<[u8]> for DmaBuffer {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl Drop for DmaBuffer {
+    fn drop(&mut self) {
+        unsafe { sys::heap_caps_free(self.ptr.as_ptr() as *mut _) };
+    }
+}
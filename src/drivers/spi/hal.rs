@@ -0,0 +1,44 @@
+/**
+ * @file hal.rs
+ * @brief SpiDevice 的 embedded-hal 1.0 接口实现
+ * @details 让基于 embedded-hal 的SPI外设驱动可以直接使用本板的SPI设备:
+ *          - `embedded_hal::spi::SpiDevice`，一次事务内片选保持有效，超长操作自动分块
+ *          - 错误类别映射到 `embedded_hal::spi::ErrorKind`，具体原因仍保留在 `Error` 中
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use crate::drivers::spi::chunk;
+use crate::drivers::spi::controller::SpiDevice;
+use crate::drivers::spi::types::SpiError;
+use crate::error::Error;
+use embedded_hal::spi::{self, ErrorKind, ErrorType, Operation};
+use esp_idf_svc::sys;
+
+impl spi::Error for Error {
+    /// 按SPI错误类别映射，其他错误（包括超时和队列已满）归入 `Other`，具体原因仍可通过 `Error::spi` 取得
+    ///
+    /// * `BusBusy` - 无法独占总线，片选不能拉低，对应 `ChipSelectFault`
+    fn kind(&self) -> ErrorKind {
+        match self.spi() {
+            Some(SpiError::BusBusy) => ErrorKind::ChipSelectFault,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+impl ErrorType for SpiDevice {
+    type Error = Error;
+}
+
+impl spi::SpiDevice for SpiDevice {
+    /// 在一次片选有效期内依次执行所有操作
+    ///
+    /// 执行期间独占总线，除最后一个数据操作外片选都保持有效（`SPI_TRANS_CS_KEEP_ACTIVE`），
+    /// 因此片选在操作之间（包括延时）保持有效。超过最大传输长度的操作自动分块。
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        chunk::run_operations(&*self, operations, |ns| unsafe {
+            sys::esp_rom_delay_us(ns.div_ceil(1000))
+        })
+    }
+}
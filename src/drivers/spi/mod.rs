@@ -1,13 +1,37 @@
 // filepath: /Volumes/code/rust_project/esp32-test/src/drivers/spi/mod.rs
 
+mod soft; // 任意GPIO上的软件SPI主机
 mod types;
+// 大块传输的分块，主机上只用于测试
+#[cfg(any(target_os = "espidf", test))]
+mod chunk;
+// 控制器直接调用ESP-IDF驱动，主机上只提供类型定义
+#[cfg(target_os = "espidf")]
 mod controller;
+#[cfg(target_os = "espidf")]
+mod dma; // DMA缓冲区
+#[cfg(target_os = "espidf")]
+mod hal;
+#[cfg(target_os = "espidf")]
+mod queue; // 排队的DMA事务
 
-pub use types::*;
+#[cfg(target_os = "espidf")]
 pub use controller::*;
+#[cfg(target_os = "espidf")]
+pub use dma::DmaBuffer;
+#[cfg(target_os = "espidf")]
+pub use queue::SpiQueue;
+pub use soft::SoftSpi;
+pub use types::*;
 
 /// 导出SPI相关的接口和类型
 pub mod prelude {
-    pub use super::types::*;
+    #[cfg(target_os = "espidf")]
     pub use super::controller::*;
+    #[cfg(target_os = "espidf")]
+    pub use super::dma::DmaBuffer;
+    #[cfg(target_os = "espidf")]
+    pub use super::queue::SpiQueue;
+    pub use super::soft::SoftSpi;
+    pub use super::types::*;
 }
@@ -0,0 +1,296 @@
+/**
+ * @file queue.rs
+ * @brief 排队的SPI事务
+ * @details 通过 spi_device_queue_trans 提交事务、spi_device_get_trans_result 取回结果，
+ *          DMA 发送上一块数据的同时CPU可以准备下一块（例如LCD的下一个条带）:
+ *          - 缓冲区可以是拥有所有权的（`Vec<u8>`、`DmaBuffer` 等），完成后原样交还
+ *          - 也可以在 `SpiDevice::scoped_queue` 中借用，离开作用域前等待所有事务完成
+ *          - 同时在途的事务数不超过设备的 `queue_size`
+ *          - 取回结果可以阻塞、非阻塞或 `.await`，异步等待由事务完成回调唤醒
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::collections::VecDeque;
+use std::ffi::c_void;
+use std::future::poll_fn;
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::Poll;
+
+use embassy_sync::waitqueue::AtomicWaker;
+use esp_idf_svc::sys;
+
+use crate::drivers::spi::controller::SpiDevice;
+use crate::drivers::spi::types::{SpiError, SpiResult};
+
+/// FreeRTOS 的 portMAX_DELAY，一直等待
+const PORT_MAX_DELAY: u32 = u32::MAX;
+
+/// 一个队列的完成通知，地址作为事务的 `user` 传给完成回调
+struct QueueSignal {
+    /// 已完成的事务数，只由完成回调增加
+    completed: AtomicUsize,
+    /// 等待完成的任务
+    waker: AtomicWaker,
+}
+
+/// 在途事务：事务描述符和缓冲区放在同一个堆分配中，完成之前地址不变
+struct Pending<B> {
+    transaction: sys::spi_transaction_t,
+    buffer: B,
+}
+
+/// 事务完成回调，在SPI中断中执行
+///
+/// 由 [`SpiMaster::add_device`](crate::drivers::spi::SpiMaster::add_device) 注册为设备的 `post_cb`。
+pub(super) unsafe extern "C" fn post_transaction(transaction: *mut sys::spi_transaction_t) {
+    let signal = (*transaction).user as *const QueueSignal;
+    if let Some(signal) = signal.as_ref() {
+        signal.completed.fetch_add(1, Ordering::Release);
+        signal.waker.wake();
+    }
+}
+
+/// SPI事务队列
+///
+/// 由 [`SpiDevice::queue`] 或 [`SpiDevice::scoped_queue`] 创建，存在期间独占设备，
+/// 因此不会与阻塞的 `write`/`transfer` 交错。事务按提交顺序完成，`wait` 按同样的顺序交还缓冲区。
+/// 释放时会等待所有在途事务完成。
+pub struct SpiQueue<'d, B> {
+    device: &'d mut SpiDevice,
+    pending: VecDeque<Box<Pending<B>>>,
+    signal: Box<QueueSignal>,
+    /// 已取回结果的事务数
+    collected: usize,
+    // 对 B 不变，保证借用缓冲区的队列不能与 `queue` 创建的队列互换
+    _buffer: PhantomData<fn(B) -> B>,
+}
+
+impl SpiDevice {
+    /// 创建使用自有缓冲区的事务队列
+    ///
+    /// 缓冲区在事务完成后由 [`SpiQueue::wait`] 交还，可以继续复用。
+    ///
+    /// # 返回
+    ///
+    /// 设备的 `queue_size` 为0时无法提交事务，返回 `InvalidParameter`
+    pub fn queue<B: 'static>(&mut self) -> SpiResult<SpiQueue<'_, B>> {
+        SpiQueue::new(self)
+    }
+
+    /// 在作用域内使用借用缓冲区的事务队列
+    ///
+    /// `f` 返回后等待所有在途事务完成，因此缓冲区只需在调用期间有效。
+    ///
+    /// # 返回
+    ///
+    /// `f` 的返回值；`f` 成功但等待在途事务失败时返回该错误，`queue_size` 为0时返回 `InvalidParameter`
+    pub fn scoped_queue<B, R>(
+        &mut self,
+        f: impl FnOnce(&mut SpiQueue<'_, B>) -> SpiResult<R>,
+    ) -> SpiResult<R> {
+        let mut queue = SpiQueue::new(self)?;
+        let result = f(&mut queue);
+        let flushed = queue.flush();
+        let value = result?;
+        flushed?;
+        Ok(value)
+    }
+}
+
+impl<'d, B> SpiQueue<'d, B> {
+    fn new(device: &'d mut SpiDevice) -> SpiResult<Self> {
+        // 容量为0的队列永远是满的，wait 也没有事务可等
+        if device.queue_size == 0 {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        Ok(SpiQueue {
+            device,
+            pending: VecDeque::new(),
+            signal: Box::new(QueueSignal {
+                completed: AtomicUsize::new(0),
+                waker: AtomicWaker::new(),
+            }),
+            collected: 0,
+            _buffer: PhantomData,
+        })
+    }
+
+    /// 在途事务数
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 是否没有在途事务
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// 同时在途的最大事务数，即设备的 `queue_size`
+    pub fn capacity(&self) -> usize {
+        self.device.queue_size
+    }
+
+    /// 在途事务数是否已达到上限，此时需要先 `wait` 再提交
+    pub fn is_full(&self) -> bool {
+        self.pending.len() >= self.device.queue_size
+    }
+
+    /// 提交一个只发送的事务
+    ///
+    /// # 返回
+    ///
+    /// 缓冲区为空或超过总线最大传输长度返回 `InvalidParameter`，队列已满返回 `QueueFull`，
+    /// 失败时缓冲区被释放
+    pub fn write(&mut self, buffer: B) -> SpiResult<()>
+    where
+        B: AsRef<[u8]>,
+    {
+        let mut pending = Box::new(Pending {
+            transaction: sys::spi_transaction_t::default(),
+            buffer,
+        });
+        // 缓冲区已经在堆上，取到的地址在事务完成前不会变化
+        let data = pending.buffer.as_ref();
+        let (len, tx) = (data.len(), data.as_ptr());
+        pending.transaction.length = len * 8;
+        pending.transaction.__bindgen_anon_1.tx_buffer = tx as *const c_void;
+        self.submit(pending)
+    }
+
+    /// 提交一个只接收的事务，接收的数据在 `wait` 交还的缓冲区中
+    ///
+    /// # 返回
+    ///
+    /// 缓冲区为空或超过总线最大传输长度返回 `InvalidParameter`，队列已满返回 `QueueFull`，
+    /// 失败时缓冲区被释放
+    pub fn read(&mut self, buffer: B) -> SpiResult<()>
+    where
+        B: AsMut<[u8]>,
+    {
+        let mut pending = Box::new(Pending {
+            transaction: sys::spi_transaction_t::default(),
+            buffer,
+        });
+        let data = pending.buffer.as_mut();
+        let (len, rx) = (data.len(), data.as_mut_ptr());
+        // 半双工设备只有接收阶段
+        pending.transaction.length = if self.device.half_duplex { 0 } else { len * 8 };
+        pending.transaction.rxlength = len * 8;
+        pending.transaction.__bindgen_anon_2.rx_buffer = rx as *mut c_void;
+        self.submit(pending)
+    }
+
+    fn submit(&mut self, mut pending: Box<Pending<B>>) -> SpiResult<()> {
+        let bits = pending.transaction.length.max(pending.transaction.rxlength);
+        // 排队的事务不分块，不能超过总线的最大传输长度
+        if bits == 0 || bits > self.device.max_transfer_size() * 8 {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        if self.is_full() {
+            return Err(SpiError::QueueFull.into());
+        }
+        pending.transaction.user = &*self.signal as *const QueueSignal as *mut c_void;
+
+        // 在途事务数不超过 queue_size，不会阻塞
+        let result = unsafe {
+            sys::spi_device_queue_trans(
+                self.device.handle,
+                &mut pending.transaction,
+                PORT_MAX_DELAY,
+            )
+        };
+        if result != sys::ESP_OK {
+            return Err(self.device.driver_error(result, "spi_device_queue_trans"));
+        }
+        self.pending.push_back(pending);
+        Ok(())
+    }
+
+    /// 取回最早提交的事务的结果，`ticks` 为0时不等待
+    fn collect(&mut self, ticks: u32) -> SpiResult<Option<B>> {
+        if self.pending.is_empty() {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        let mut done = ptr::null_mut();
+        let result =
+            unsafe { sys::spi_device_get_trans_result(self.device.handle, &mut done, ticks) };
+        if result == sys::ESP_ERR_TIMEOUT {
+            return Ok(None);
+        }
+        if result != sys::ESP_OK {
+            return Err(self
+                .device
+                .driver_error(result, "spi_device_get_trans_result"));
+        }
+
+        // 同一设备的事务按提交顺序完成
+        let pending = self.pending.pop_front().ok_or(SpiError::DriverError)?;
+        debug_assert!(ptr::eq(&pending.transaction, done));
+        self.collected += 1;
+        Ok(Some(pending.buffer))
+    }
+
+    /// 阻塞等待最早提交的事务完成并交还其缓冲区
+    ///
+    /// # 返回
+    ///
+    /// 没有在途事务时返回 `InvalidParameter`
+    pub fn wait(&mut self) -> SpiResult<B> {
+        self.collect(PORT_MAX_DELAY)?
+            .ok_or_else(|| SpiError::Timeout.into())
+    }
+
+    /// 最早提交的事务已完成时交还其缓冲区，否则返回 `None`
+    pub fn try_wait(&mut self) -> SpiResult<Option<B>> {
+        self.collect(0)
+    }
+
+    /// 异步等待最早提交的事务完成并交还其缓冲区
+    pub async fn wait_async(&mut self) -> SpiResult<B> {
+        poll_fn(|cx| {
+            if self.pending.is_empty() {
+                return Poll::Ready(Err(SpiError::InvalidParameter.into()));
+            }
+            // 回调在结果进入返回队列之前执行，因此按完成计数判断，再阻塞取回结果
+            let completed =
+                |queue: &Self| queue.signal.completed.load(Ordering::Acquire) > queue.collected;
+            if !completed(self) {
+                self.signal.waker.register(cx.waker());
+                // 注册之后再检查一次，避免错过注册前完成的事务
+                if !completed(self) {
+                    return Poll::Pending;
+                }
+            }
+            Poll::Ready(self.wait())
+        })
+        .await
+    }
+
+    /// 等待所有在途事务完成，缓冲区随之释放
+    pub fn flush(&mut self) -> SpiResult<()> {
+        while !self.pending.is_empty() {
+            self.wait()?;
+        }
+        Ok(())
+    }
+}
+
+impl<B> Drop for SpiQueue<'_, B> {
+    fn drop(&mut self) {
+        if self.flush().is_err() {
+            // 无法确认事务已经结束，缓冲区和通知可能仍被DMA和中断使用，只能泄漏
+            self.pending.drain(..).for_each(std::mem::forget);
+            let signal = std::mem::replace(
+                &mut self.signal,
+                Box::new(QueueSignal {
+                    completed: AtomicUsize::new(0),
+                    waker: AtomicWaker::new(),
+                }),
+            );
+            std::mem::forget(signal);
+        }
+    }
+}
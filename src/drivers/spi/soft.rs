@@ -0,0 +1,471 @@
+/**
+ * @file soft.rs
+ * @brief 软件SPI主机
+ * @details 用任意GPIO模拟SPI主机时序，不受 SPI2/SPI3 引脚和数量的限制:
+ *          - 使用与硬件驱动相同的 `SpiDeviceConfig`，支持四种 `SpiMode` 和两种 `SpiBitOrder`
+ *          - 片选由 `cs_pin` 指定，默认低电平有效，`flags.cs_active_high` 时高电平有效，
+ *            每次传输期间保持有效
+ *          - MOSI、MISO 可以省略，分别用于只读和只写的设备
+ *          - 命令、地址和dummy长度取自配置，与硬件驱动的 `write_with_cmd_addr` 一致
+ *          延时由GPIO后端提供，在主机上推进模拟芯片的虚拟时钟，可以配合模拟从机测试
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::pin::{GpioPin, Input, Output};
+use crate::drivers::spi::types::{SpiBitOrder, SpiDeviceConfig, SpiError, SpiMode, SpiResult};
+
+/// 软件SPI主机
+pub struct SoftSpi {
+    sclk: GpioPin<Output>,
+    mosi: Option<GpioPin<Output>>,
+    miso: Option<GpioPin<Input>>,
+    cs: Option<GpioPin<Output>>,
+    /// 片选有效电平
+    cs_active: u32,
+    mode: SpiMode,
+    bit_order: SpiBitOrder,
+    command_bits: u8,
+    address_bits: u8,
+    dummy_bits: u8,
+    /// 半个时钟周期（微秒）
+    half_period_us: u32,
+}
+
+impl SoftSpi {
+    /// 创建软件SPI主机
+    ///
+    /// 时钟线创建后处于空闲电平，片选无效。
+    /// 实际时钟频率受微秒级延时精度限制，只会比 `clock_speed_hz` 低。
+    ///
+    /// # 参数
+    /// * `sclk` - 时钟引脚
+    /// * `mosi` - 主机输出引脚，只读设备可以为 `None`
+    /// * `miso` - 主机输入引脚，只写设备可以为 `None`
+    /// * `config` - 设备配置，`queue_size`、时钟源和除 `cs_active_high` 以外的标志不使用
+    ///
+    /// # 返回
+    /// * `SpiResult<Self>` - 频率为0、命令超过16位或地址超过32位时返回参数错误
+    pub fn new(
+        sclk: GpioPin,
+        mosi: Option<GpioPin>,
+        miso: Option<GpioPin>,
+        config: &SpiDeviceConfig,
+    ) -> SpiResult<Self> {
+        if config.clock_speed_hz == 0 || config.command_bits > 16 || config.address_bits > 32 {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        let cs_active = u32::from(config.flags.cs_active_high);
+        let cs = match config.cs_pin {
+            Some(pin) => {
+                let cs = GpioPin::with_owner(pin as u32, "soft_spi.cs")?;
+                // 先写输出寄存器再使能输出，避免片选出现有效电平毛刺
+                cs.set_level(cs_active ^ 1)?;
+                Some(cs.into_push_pull_output()?)
+            }
+            None => None,
+        };
+        let spi = SoftSpi {
+            sclk: sclk.into_push_pull_output()?,
+            mosi: mosi.map(GpioPin::into_push_pull_output).transpose()?,
+            miso: miso.map(GpioPin::into_floating_input).transpose()?,
+            cs,
+            cs_active,
+            mode: config.mode,
+            bit_order: config.bit_order,
+            command_bits: config.command_bits,
+            address_bits: config.address_bits,
+            dummy_bits: config.dummy_bits,
+            half_period_us: 500_000u32.div_ceil(config.clock_speed_hz),
+        };
+        spi.sclk.set_level(spi.idle_level())?;
+        Ok(spi)
+    }
+
+    /// 发送并接收数据
+    ///
+    /// # 参数
+    /// * `tx_data` - 发送数据
+    /// * `rx_data` - 接收数据缓冲区
+    ///
+    /// # 返回
+    /// * `SpiResult<()>` - 成功返回Ok(())，长度为0或缺少MOSI/MISO时返回参数错误
+    pub fn transfer(&self, tx_data: &[u8], rx_data: &mut [u8]) -> SpiResult<()> {
+        let len = tx_data.len().min(rx_data.len());
+        if len == 0 || self.mosi.is_none() || self.miso.is_none() {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        self.selected(|| {
+            for (tx, rx) in tx_data[..len].iter().zip(&mut rx_data[..len]) {
+                *rx = self.shift(u32::from(*tx), 8)? as u8;
+            }
+            Ok(())
+        })
+    }
+
+    /// 只发送数据
+    ///
+    /// # 参数
+    /// * `tx_data` - 发送数据
+    ///
+    /// # 返回
+    /// * `SpiResult<()>` - 成功返回Ok(())，数据为空或缺少MOSI时返回参数错误
+    pub fn write(&self, tx_data: &[u8]) -> SpiResult<()> {
+        if tx_data.is_empty() || self.mosi.is_none() {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        self.selected(|| self.write_bytes(tx_data))
+    }
+
+    /// 只接收数据，MOSI保持低电平
+    ///
+    /// # 参数
+    /// * `rx_data` - 接收数据缓冲区
+    ///
+    /// # 返回
+    /// * `SpiResult<()>` - 成功返回Ok(())，缓冲区为空或缺少MISO时返回参数错误
+    pub fn read(&self, rx_data: &mut [u8]) -> SpiResult<()> {
+        if rx_data.is_empty() || self.miso.is_none() {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        self.selected(|| {
+            for rx in rx_data.iter_mut() {
+                *rx = self.shift(0, 8)? as u8;
+            }
+            Ok(())
+        })
+    }
+
+    /// 带命令和地址的写数据
+    ///
+    /// 命令、地址和dummy的长度由配置中的 `command_bits`、`address_bits`、`dummy_bits` 决定，
+    /// 长度为0时省略；dummy期间MOSI保持低电平。
+    ///
+    /// # 参数
+    /// * `cmd` - 命令
+    /// * `addr` - 地址
+    /// * `tx_data` - 发送数据，可以为空
+    ///
+    /// # 返回
+    /// * `SpiResult<()>` - 成功返回Ok(())，缺少MOSI时返回参数错误
+    pub fn write_with_cmd_addr(&self, cmd: u16, addr: u32, tx_data: &[u8]) -> SpiResult<()> {
+        if self.mosi.is_none() {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        self.selected(|| {
+            self.shift(u32::from(cmd), self.command_bits)?;
+            self.shift(addr, self.address_bits)?;
+            self.shift(0, self.dummy_bits)?;
+            self.write_bytes(tx_data)
+        })
+    }
+
+    /// 释放SPI主机，取回时钟、MOSI和MISO引脚，片选引脚被归还
+    pub fn release(
+        self,
+    ) -> (
+        GpioPin<Output>,
+        Option<GpioPin<Output>>,
+        Option<GpioPin<Input>>,
+    ) {
+        (self.sclk, self.mosi, self.miso)
+    }
+
+    /// 时钟空闲电平（CPOL）
+    fn idle_level(&self) -> u32 {
+        u32::from(matches!(self.mode, SpiMode::Mode2 | SpiMode::Mode3))
+    }
+
+    /// 是否在第二个时钟边沿采样（CPHA）
+    fn sample_on_trailing(&self) -> bool {
+        matches!(self.mode, SpiMode::Mode1 | SpiMode::Mode3)
+    }
+
+    /// 等待半个时钟周期
+    fn delay(&self) {
+        Backend::delay_us(self.half_period_us);
+    }
+
+    /// 在片选有效期间执行传输，出错时同样释放片选
+    fn selected(&self, f: impl FnOnce() -> SpiResult<()>) -> SpiResult<()> {
+        if let Some(cs) = &self.cs {
+            cs.set_level(self.cs_active)?;
+            self.delay();
+        }
+        let result = f();
+        if let Some(cs) = &self.cs {
+            self.delay();
+            cs.set_level(self.cs_active ^ 1)?;
+        }
+        result
+    }
+
+    /// 依次发送所有字节，丢弃接收到的数据
+    fn write_bytes(&self, tx_data: &[u8]) -> SpiResult<()> {
+        tx_data
+            .iter()
+            .try_for_each(|&tx| self.shift(u32::from(tx), 8).map(drop))
+    }
+
+    /// 全双工移位 `bits` 位，按配置的位序发送 `value` 的低 `bits` 位，返回接收到的数值
+    fn shift(&self, value: u32, bits: u8) -> SpiResult<u32> {
+        let idle = self.idle_level();
+        let mut received = 0;
+        for i in 0..u32::from(bits) {
+            let bit = match self.bit_order {
+                SpiBitOrder::MSBFirst => u32::from(bits) - 1 - i,
+                SpiBitOrder::LSBFirst => i,
+            };
+            let out = (value >> bit) & 1;
+            if self.sample_on_trailing() {
+                // 第一个边沿输出数据，第二个边沿采样
+                self.sclk.set_level(idle ^ 1)?;
+                self.put(out)?;
+                self.delay();
+                self.sclk.set_level(idle)?;
+                received |= self.sample() << bit;
+                self.delay();
+            } else {
+                // 数据在第一个边沿之前准备好，第一个边沿采样
+                self.put(out)?;
+                self.delay();
+                self.sclk.set_level(idle ^ 1)?;
+                received |= self.sample() << bit;
+                self.delay();
+                self.sclk.set_level(idle)?;
+            }
+        }
+        Ok(received)
+    }
+
+    /// 输出一位到MOSI
+    fn put(&self, level: u32) -> SpiResult<()> {
+        match &self.mosi {
+            Some(mosi) => mosi.set_level(level),
+            None => Ok(()),
+        }
+    }
+
+    /// 从MISO采样一位，没有MISO时为0
+    fn sample(&self) -> u32 {
+        self.miso.as_ref().map_or(0, |miso| miso.get_level())
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::ffi::c_void;
+
+    use crate::drivers::gpio::backend::sim;
+    use crate::drivers::gpio::types::GpioInterruptType;
+    use crate::drivers::spi::types::SpiDeviceFlags;
+
+    const SCLK: i32 = 12;
+    const MOSI: i32 = 11;
+    const MISO: i32 = 13;
+    const CS: i32 = 10;
+
+    /// 按给定模式和位序工作的模拟从机，接收MOSI并从MISO发送应答数据
+    struct Device {
+        mode: SpiMode,
+        bit_order: SpiBitOrder,
+        response: Vec<u8>,
+        /// 按线上顺序采样到的MOSI位
+        wire: Vec<u32>,
+        /// 片选有效电平
+        cs_active: u32,
+        selected: bool,
+    }
+
+    impl Device {
+        /// 线上第 `index` 位对应的应答位
+        fn response_bit(&self, index: usize) -> u32 {
+            let byte = self.response.get(index / 8).copied().unwrap_or(0);
+            let shift = match self.bit_order {
+                SpiBitOrder::MSBFirst => 7 - index % 8,
+                SpiBitOrder::LSBFirst => index % 8,
+            };
+            u32::from(byte >> shift & 1)
+        }
+
+        /// 把采样到的位按位序组装成字节
+        fn received(&self) -> Vec<u8> {
+            self.wire
+                .chunks(8)
+                .map(|bits| {
+                    bits.iter().enumerate().fold(0, |byte, (i, &bit)| {
+                        let shift = match self.bit_order {
+                            SpiBitOrder::MSBFirst => 7 - i,
+                            SpiBitOrder::LSBFirst => i,
+                        };
+                        byte | (bit as u8) << shift
+                    })
+                })
+                .collect()
+        }
+    }
+
+    thread_local! {
+        static DEVICE: RefCell<Option<Device>> = const { RefCell::new(None) };
+    }
+
+    /// 从机在片选有效时响应时钟边沿，返回MISO需要输出的电平
+    fn on_edge(device: &mut Device, pin: i32) -> Option<u32> {
+        let cpol = u32::from(matches!(device.mode, SpiMode::Mode2 | SpiMode::Mode3));
+        let cpha = matches!(device.mode, SpiMode::Mode1 | SpiMode::Mode3);
+        if pin == CS {
+            device.selected = sim::pad_level(CS) == device.cs_active;
+            if device.selected {
+                device.wire.clear();
+            }
+            // CPHA=0 时第一位在片选有效时就要准备好
+            return (device.selected && !cpha).then(|| device.response_bit(0));
+        }
+        if !device.selected {
+            return None;
+        }
+        let leading = sim::pad_level(SCLK) != cpol;
+        let index = device.wire.len();
+        match (cpha, leading) {
+            // 采样边沿
+            (false, true) | (true, false) => {
+                device.wire.push(sim::pad_level(MOSI));
+                None
+            }
+            // 输出边沿：CPHA=0 在第二个边沿准备下一位，CPHA=1 在第一个边沿输出当前位
+            _ => Some(device.response_bit(index)),
+        }
+    }
+
+    unsafe extern "C" fn on_pin(arg: *mut c_void) {
+        let pin = arg as usize as i32;
+        let level = DEVICE.with(|device| on_edge(device.borrow_mut().as_mut().unwrap(), pin));
+        if let Some(level) = level {
+            sim::drive(MISO, level);
+        }
+    }
+
+    fn pin(pin: i32) -> GpioPin {
+        GpioPin::new(pin as u32).unwrap()
+    }
+
+    /// 创建主机并挂上模拟从机
+    fn attach(config: &SpiDeviceConfig, response: &[u8], miso: bool) -> SoftSpi {
+        sim::reset();
+        let spi =
+            SoftSpi::new(pin(SCLK), Some(pin(MOSI)), miso.then(|| pin(MISO)), config).unwrap();
+        DEVICE.with(|device| {
+            *device.borrow_mut() = Some(Device {
+                mode: config.mode,
+                bit_order: config.bit_order,
+                response: response.to_vec(),
+                wire: Vec::new(),
+                cs_active: u32::from(config.flags.cs_active_high),
+                selected: false,
+            })
+        });
+        Backend::install_isr_service(0).unwrap();
+        for pin in [SCLK, CS] {
+            Backend::set_interrupt_type(pin, GpioInterruptType::AnyEdge).unwrap();
+            Backend::set_interrupt_enabled(pin, true).unwrap();
+            Backend::isr_handler_add(pin, Some(on_pin), pin as usize as *mut c_void).unwrap();
+        }
+        spi
+    }
+
+    fn device<R>(f: impl FnOnce(&Device) -> R) -> R {
+        DEVICE.with(|device| f(device.borrow().as_ref().unwrap()))
+    }
+
+    #[test]
+    fn test_all_modes_and_bit_orders() {
+        let modes = [
+            SpiMode::Mode0,
+            SpiMode::Mode1,
+            SpiMode::Mode2,
+            SpiMode::Mode3,
+        ];
+        for mode in modes {
+            for bit_order in [SpiBitOrder::MSBFirst, SpiBitOrder::LSBFirst] {
+                let config = SpiDeviceConfig {
+                    mode,
+                    bit_order,
+                    cs_pin: Some(CS),
+                    ..SpiDeviceConfig::default()
+                };
+                let spi = attach(&config, &[0x35, 0xC8], true);
+                let idle = u32::from(matches!(mode, SpiMode::Mode2 | SpiMode::Mode3));
+                assert_eq!(sim::pad_level(SCLK), idle);
+
+                let mut rx = [0; 2];
+                spi.transfer(&[0x12, 0xF0], &mut rx).unwrap();
+                assert_eq!(rx, [0x35, 0xC8], "{:?} {:?}", mode, bit_order);
+                assert_eq!(device(Device::received), vec![0x12, 0xF0]);
+
+                // 0x12 在线上的顺序
+                let first: Vec<u32> = device(|d| d.wire[..8].to_vec());
+                let expected = match bit_order {
+                    SpiBitOrder::MSBFirst => [0, 0, 0, 1, 0, 0, 1, 0],
+                    SpiBitOrder::LSBFirst => [0, 1, 0, 0, 1, 0, 0, 0],
+                };
+                assert_eq!(first, expected);
+                assert_eq!((sim::pad_level(SCLK), sim::pad_level(CS)), (idle, 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_cmd_addr_and_pin_checks() {
+        let config = SpiDeviceConfig {
+            cs_pin: Some(CS),
+            command_bits: 8,
+            address_bits: 24,
+            ..SpiDeviceConfig::default()
+        };
+        let spi = attach(&config, &[], false);
+        spi.write_with_cmd_addr(0x02, 0x001234, &[0xAB]).unwrap();
+        assert_eq!(device(Device::received), vec![0x02, 0x00, 0x12, 0x34, 0xAB]);
+
+        // 1MHz 时每位2us，前后各有半个周期的片选建立和保持时间
+        let start = sim::now_us();
+        spi.write(&[0xFF]).unwrap();
+        assert_eq!(sim::now_us() - start, 8 * 2 + 2);
+
+        // 没有MISO时不能读取
+        let error = spi.read(&mut [0; 1]).unwrap_err();
+        assert_eq!(error.spi(), Some(&SpiError::InvalidParameter));
+        assert!(spi.transfer(&[0x00], &mut [0; 1]).is_err());
+        assert!(spi.write(&[]).is_err());
+
+        let config = SpiDeviceConfig {
+            clock_speed_hz: 0,
+            ..SpiDeviceConfig::default()
+        };
+        drop(spi);
+        assert!(SoftSpi::new(pin(SCLK), None, None, &config).is_err());
+    }
+
+    #[test]
+    fn test_active_high_cs_and_dummy_bits() {
+        let config = SpiDeviceConfig {
+            cs_pin: Some(CS),
+            command_bits: 8,
+            dummy_bits: 8,
+            flags: SpiDeviceFlags {
+                cs_active_high: true,
+                ..SpiDeviceFlags::default()
+            },
+            ..SpiDeviceConfig::default()
+        };
+        let spi = attach(&config, &[], false);
+        assert_eq!(sim::pad_level(CS), 0);
+
+        // 从机只在片选为高电平时接收，dummy位在命令和数据之间
+        spi.write_with_cmd_addr(0x0B, 0, &[0x5A]).unwrap();
+        assert_eq!(device(Device::received), vec![0x0B, 0x00, 0x5A]);
+        assert_eq!(sim::pad_level(CS), 0);
+    }
+}
@@ -1,3 +1,16 @@
+use std::fmt;
+
+/// SPI主机总线编号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpiBus {
+    /// SPI1，通常用于Flash访问
+    Spi1 = 1,
+    /// SPI2，通常可用于用户应用
+    Spi2 = 2,
+    /// SPI3，通常可用于用户应用 (ESP32-S3有SPI3)
+    Spi3 = 3,
+}
+
 /// SPI模式枚举
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SpiMode {
@@ -40,21 +53,229 @@ impl From<SpiBitOrder> for bool {
     }
 }
 
+/// 数据线数量
+///
+/// 按线数从少到多排序，可以直接比较。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SpiLines {
+    /// 单线，MOSI发送、MISO接收
+    #[default]
+    Single,
+    /// 双线，D0~D1（MOSI、MISO）
+    Dual,
+    /// 四线，D0~D3（MOSI、MISO、WP、HD）
+    Quad,
+    /// 八线，D0~D7，ESP32-S3只有SPI2支持
+    Octal,
+}
+
+/// 事务各阶段使用的数据线
+///
+/// 多线传输只能用于半双工设备（`SpiDeviceFlags::half_duplex`），线数不能超过总线初始化时提供的引脚。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpiLineMode {
+    /// 数据阶段的线数（SPI_TRANS_MODE_DIO/QIO/OCT）
+    pub data: SpiLines,
+    /// 地址阶段也使用数据阶段的线数（SPI_TRANS_MULTILINE_ADDR）
+    pub address: bool,
+    /// 命令阶段也使用数据阶段的线数（SPI_TRANS_MULTILINE_CMD）
+    pub command: bool,
+}
+
+impl SpiLineMode {
+    /// 只有数据阶段使用多线，命令和地址仍为单线
+    pub fn data(lines: SpiLines) -> Self {
+        Self {
+            data: lines,
+            ..Self::default()
+        }
+    }
+
+    /// 地址和数据阶段使用多线，命令仍为单线，例如QSPI Flash的快速读命令
+    pub fn address_and_data(lines: SpiLines) -> Self {
+        Self {
+            data: lines,
+            address: true,
+            command: false,
+        }
+    }
+
+    /// 命令、地址和数据阶段都使用多线
+    pub fn all(lines: SpiLines) -> Self {
+        Self {
+            data: lines,
+            address: true,
+            command: true,
+        }
+    }
+
+    /// 检查总线和设备是否支持该传输方式
+    ///
+    /// # 参数
+    /// * `bus_lines` - 总线可用的线数
+    /// * `half_duplex` - 设备是否为半双工
+    pub fn check(&self, bus_lines: SpiLines, half_duplex: bool) -> SpiResult<()> {
+        let multiline = self.data != SpiLines::Single;
+        if self.data > bus_lines || (multiline && !half_duplex) {
+            return Err(SpiError::InvalidParameter.into());
+        }
+        Ok(())
+    }
+}
+
+/// SPI总线配置
+///
+/// 单线和双线只需要MOSI、MISO和SCLK，四线还需要WP和HD，八线再加D4~D7。
+#[derive(Debug, Clone)]
+pub struct SpiBusConfig {
+    /// MOSI引脚（D0），-1表示不使用
+    pub mosi_pin: i32,
+    /// MISO引脚（D1），-1表示不使用
+    pub miso_pin: i32,
+    /// SCLK引脚
+    pub sclk_pin: i32,
+    /// WP引脚（D2）
+    pub quadwp_pin: Option<i32>,
+    /// HD引脚（D3）
+    pub quadhd_pin: Option<i32>,
+    /// D4~D7引脚
+    pub octal_pins: Option<[i32; 4]>,
+    /// 最大传输大小，0表示默认值
+    pub max_transfer_size: usize,
+}
+
+impl SpiBusConfig {
+    /// 单线总线配置
+    pub fn new(mosi_pin: i32, miso_pin: i32, sclk_pin: i32) -> Self {
+        Self {
+            mosi_pin,
+            miso_pin,
+            sclk_pin,
+            quadwp_pin: None,
+            quadhd_pin: None,
+            octal_pins: None,
+            max_transfer_size: 0,
+        }
+    }
+
+    /// 加上WP、HD引脚，支持四线传输
+    pub fn quad(mut self, quadwp_pin: i32, quadhd_pin: i32) -> Self {
+        self.quadwp_pin = Some(quadwp_pin);
+        self.quadhd_pin = Some(quadhd_pin);
+        self
+    }
+
+    /// 加上D4~D7引脚，与 [`quad`](Self::quad) 一起支持八线传输
+    pub fn octal(mut self, data_pins: [i32; 4]) -> Self {
+        self.octal_pins = Some(data_pins);
+        self
+    }
+
+    /// 指定最大传输大小（字节），0表示默认值
+    pub fn max_transfer_size(mut self, bytes: usize) -> Self {
+        self.max_transfer_size = bytes;
+        self
+    }
+
+    /// 总线可用的数据线数
+    pub fn lines(&self) -> SpiLines {
+        if self.octal_pins.is_some() {
+            SpiLines::Octal
+        } else if self.quadwp_pin.is_some() {
+            SpiLines::Quad
+        } else if self.mosi_pin >= 0 && self.miso_pin >= 0 {
+            SpiLines::Dual
+        } else {
+            SpiLines::Single
+        }
+    }
+
+    /// 检查引脚组合
+    ///
+    /// SCLK必须有效；WP、HD必须同时给出；八线需要先有四线的引脚，且所有数据线都必须有效。
+    pub fn validate(&self) -> SpiResult<()> {
+        let quad = match (self.quadwp_pin, self.quadhd_pin) {
+            (Some(wp), Some(hd)) => wp >= 0 && hd >= 0,
+            (None, None) => self.octal_pins.is_none(),
+            _ => false,
+        };
+        let multiline = self.quadwp_pin.is_some() || self.octal_pins.is_some();
+        let valid = self.sclk_pin >= 0
+            && quad
+            && (!multiline || (self.mosi_pin >= 0 && self.miso_pin >= 0))
+            && self
+                .octal_pins
+                .map_or(true, |pins| pins.iter().all(|&pin| pin >= 0))
+            && self.max_transfer_size <= i32::MAX as usize;
+        if valid {
+            Ok(())
+        } else {
+            Err(SpiError::InvalidParameter.into())
+        }
+    }
+}
+
+/// SPI设备标志
+///
+/// 对应 `spi_device_interface_config_t::flags` 中的 `SPI_DEVICE_*` 位，默认全部关闭。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpiDeviceFlags {
+    /// 半双工：先发送再接收，两个阶段不重叠（SPI_DEVICE_HALFDUPLEX）
+    pub half_duplex: bool,
+    /// 三线模式：MOSI同时用于发送和接收，通常与半双工一起使用（SPI_DEVICE_3WIRE）
+    pub three_wire: bool,
+    /// 片选高电平有效（SPI_DEVICE_POSITIVE_CS）
+    pub cs_active_high: bool,
+    /// 不插入补偿输入延时的dummy位，高频读取时需要自行保证时序（SPI_DEVICE_NO_DUMMY）
+    pub no_dummy: bool,
+}
+
+/// SPI设备时钟源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpiClockSource {
+    /// 驱动默认的时钟源（APB）
+    #[default]
+    Default,
+    /// APB时钟，80MHz
+    Apb,
+    /// 晶振时钟，40MHz，不受动态调频影响
+    Xtal,
+}
+
 /// SPI传输错误类型
-#[derive(Debug)]
+///
+/// 作为 [`crate::error::ErrorKind::Spi`] 出现在统一错误类型中，驱动返回的 esp_err_t 保存在统一错误中。
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SpiError {
     /// 参数错误
     InvalidParameter,
     /// 驱动程序错误
-    DriverError(i32),
+    DriverError,
     /// 总线被占用
     BusBusy,
     /// 超时错误
     Timeout,
+    /// 在途事务数已达到设备的 `queue_size`
+    QueueFull,
+    /// DMA内存不足
+    NoMemory,
+}
+
+impl fmt::Display for SpiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpiError::InvalidParameter => write!(f, "参数错误"),
+            SpiError::DriverError => write!(f, "驱动程序错误"),
+            SpiError::BusBusy => write!(f, "总线被占用"),
+            SpiError::Timeout => write!(f, "超时"),
+            SpiError::QueueFull => write!(f, "事务队列已满"),
+            SpiError::NoMemory => write!(f, "DMA内存不足"),
+        }
+    }
 }
 
 /// SPI传输结果类型
-pub type SpiResult<T> = Result<T, SpiError>;
+pub type SpiResult<T> = Result<T, crate::error::Error>;
 
 /// SPI设备配置
 #[derive(Debug, Clone)]
@@ -69,9 +290,23 @@ pub struct SpiDeviceConfig {
     pub command_bits: u8,
     /// 地址长度（位）
     pub address_bits: u8,
+    /// 地址与数据之间的dummy位数
+    pub dummy_bits: u8,
+    /// 片选有效后到传输开始前的时钟周期数，只用于半双工
+    pub cs_setup_cycles: u16,
+    /// 传输结束后片选继续保持有效的时钟周期数
+    pub cs_hold_cycles: u8,
+    /// 时钟高电平占空比，单位1/256，0表示默认的50%（128）
+    pub duty_cycle: u16,
+    /// 从机输出的最大有效延时（纳秒），驱动据此补偿高频下的采样时刻
+    pub input_delay_ns: i32,
     /// 片选引脚编号
     pub cs_pin: Option<i32>,
-    /// 队列大小
+    /// 设备标志
+    pub flags: SpiDeviceFlags,
+    /// 时钟源
+    pub clock_source: SpiClockSource,
+    /// 队列大小：通过 `SpiQueue` 同时在途的最大事务数
     pub queue_size: usize,
 }
 
@@ -83,8 +318,71 @@ impl Default for SpiDeviceConfig {
             bit_order: SpiBitOrder::MSBFirst,
             command_bits: 0,
             address_bits: 0,
+            dummy_bits: 0,
+            cs_setup_cycles: 0,
+            cs_hold_cycles: 0,
+            duty_cycle: 0,
+            input_delay_ns: 0,
             cs_pin: None,
+            flags: SpiDeviceFlags::default(),
+            clock_source: SpiClockSource::Default,
             queue_size: 1,
         }
     }
 }
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bus_config_lines_and_validate() {
+        let single = SpiBusConfig::new(11, -1, 12);
+        assert_eq!(single.lines(), SpiLines::Single);
+        assert!(single.validate().is_ok());
+
+        let quad = SpiBusConfig::new(11, 13, 12).quad(14, 9);
+        assert_eq!(quad.lines(), SpiLines::Quad);
+        assert!(quad.validate().is_ok());
+
+        let octal = quad.clone().octal([33, 34, 35, 36]);
+        assert_eq!(octal.lines(), SpiLines::Octal);
+        assert!(octal.validate().is_ok());
+
+        // 缺少HD、八线缺少四线引脚、多线缺少MISO、没有时钟
+        let mut half_quad = SpiBusConfig::new(11, 13, 12);
+        half_quad.quadwp_pin = Some(14);
+        assert!(half_quad.validate().is_err());
+        assert!(SpiBusConfig::new(11, 13, 12)
+            .octal([33, 34, 35, 36])
+            .validate()
+            .is_err());
+        assert!(SpiBusConfig::new(11, -1, 12)
+            .quad(14, 9)
+            .validate()
+            .is_err());
+        assert!(SpiBusConfig::new(11, 13, -1).validate().is_err());
+    }
+
+    #[test]
+    fn test_line_mode_check() {
+        assert!(SpiLineMode::default()
+            .check(SpiLines::Single, false)
+            .is_ok());
+
+        let quad = SpiLineMode::address_and_data(SpiLines::Quad);
+        assert!(quad.check(SpiLines::Quad, true).is_ok());
+        assert!(quad.check(SpiLines::Octal, true).is_ok());
+        // 总线线数不够或设备为全双工
+        assert!(quad.check(SpiLines::Dual, true).is_err());
+        assert!(quad.check(SpiLines::Quad, false).is_err());
+        assert_eq!(
+            SpiLineMode::all(SpiLines::Dual),
+            SpiLineMode {
+                data: SpiLines::Dual,
+                address: true,
+                command: true,
+            }
+        );
+    }
+}
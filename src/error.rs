@@ -0,0 +1,318 @@
+/*!
+ * @file error.rs
+ * @brief 统一错误类型
+ * @details GPIO、SPI、I2C、PWM、PCNT、RMT、MCPWM、睡眠管理、逻辑分析仪和显示驱动共用一个 `Error`:
+ *          - `ErrorKind` 保留各层原有的错误枚举，便于按类别匹配
+ *          - 记录 ESP-IDF 返回的原始 esp_err_t 及其名称（esp_err_to_name）
+ *          - 记录出错的引脚或总线，以及失败的操作（通常是 ESP-IDF 函数名）
+ *          各层的错误枚举都可以通过 `From` 转换为 `Error`，因此 `?` 可以跨层使用
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::fmt;
+
+use crate::diagnostics::logic::LogicError;
+use crate::drivers::atk_md0130::DisplayError;
+use crate::drivers::gpio::GpioError;
+use crate::drivers::i2c::I2cError;
+use crate::drivers::mcpwm::McpwmError;
+use crate::drivers::pcnt::PcntError;
+use crate::drivers::pwm::PwmError;
+use crate::drivers::rmt::RmtError;
+use crate::drivers::spi::SpiError;
+use crate::power::sleep::SleepError;
+
+/// 统一结果类型
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// 定义 `ErrorKind` 的各个类别
+///
+/// 每个类别生成一个变体、`Error` 上的访问方法、`Display` 分支，
+/// 以及从该层错误到 `ErrorKind` 和 `Error` 的 `From` 转换。新增驱动时只需在调用处加一行。
+macro_rules! error_kinds {
+    ($($variant:ident($error:ty) => $accessor:ident, $name:literal;)*) => {
+        /// 错误类别
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum ErrorKind {
+            $(
+                #[doc = concat!($name, "错误")]
+                $variant($error),
+            )*
+        }
+
+        impl Error {
+            $(
+                #[doc = concat!("如果是", $name, "错误，返回具体的", $name, "错误")]
+                pub fn $accessor(&self) -> Option<&$error> {
+                    match &self.kind {
+                        ErrorKind::$variant(error) => Some(error),
+                        _ => None,
+                    }
+                }
+            )*
+        }
+
+        impl fmt::Display for ErrorKind {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    $(ErrorKind::$variant(error) => write!(f, "{}", error),)*
+                }
+            }
+        }
+
+        $(
+            impl From<$error> for ErrorKind {
+                fn from(error: $error) -> Self {
+                    ErrorKind::$variant(error)
+                }
+            }
+
+            impl From<$error> for Error {
+                fn from(error: $error) -> Self {
+                    Error::new(error)
+                }
+            }
+        )*
+    };
+}
+
+error_kinds! {
+    Gpio(GpioError) => gpio, "GPIO";
+    Spi(SpiError) => spi, "SPI";
+    I2c(I2cError) => i2c, "I2C";
+    Pwm(PwmError) => pwm, "PWM";
+    Pcnt(PcntError) => pcnt, "脉冲计数器";
+    Rmt(RmtError) => rmt, "RMT";
+    Mcpwm(McpwmError) => mcpwm, "MCPWM捕获";
+    Sleep(SleepError) => sleep, "睡眠与唤醒";
+    Logic(LogicError) => logic, "逻辑分析仪";
+    Display(DisplayError) => display_error, "显示驱动";
+}
+
+/// 出错的硬件资源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    /// GPIO引脚
+    Pin(u32),
+    /// SPI主机（2表示SPI2，3表示SPI3）
+    SpiHost(u32),
+    /// LEDC定时器
+    LedcTimer(u32),
+    /// LEDC通道
+    LedcChannel(u32),
+}
+
+/// 统一错误类型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    code: Option<i32>,
+    operation: Option<&'static str>,
+    resource: Option<Resource>,
+}
+
+impl Error {
+    /// 创建一个不带上下文的错误
+    pub fn new(kind: impl Into<ErrorKind>) -> Self {
+        Error {
+            kind: kind.into(),
+            code: None,
+            operation: None,
+            resource: None,
+        }
+    }
+
+    /// 由ESP-IDF返回值创建错误
+    ///
+    /// # 参数
+    ///
+    /// * `kind` - 错误类别
+    /// * `code` - esp_err_t 返回值
+    /// * `operation` - 失败的操作，通常是ESP-IDF函数名
+    pub fn esp(kind: impl Into<ErrorKind>, code: i32, operation: &'static str) -> Self {
+        Error::new(kind).with_code(code).with_operation(operation)
+    }
+
+    /// 检查ESP-IDF返回值，非 `ESP_OK` 时返回错误
+    pub fn check(code: i32, kind: impl Into<ErrorKind>, operation: &'static str) -> Result<()> {
+        if code == 0 {
+            Ok(())
+        } else {
+            Err(Error::esp(kind, code, operation))
+        }
+    }
+
+    /// 附加 esp_err_t 返回值
+    pub fn with_code(mut self, code: i32) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// 附加失败的操作，已有操作时保留最内层的操作
+    pub fn with_operation(mut self, operation: &'static str) -> Self {
+        self.operation.get_or_insert(operation);
+        self
+    }
+
+    /// 附加出错的引脚
+    pub fn with_pin(mut self, pin: impl TryInto<u32>) -> Self {
+        if let Ok(pin) = pin.try_into() {
+            self.resource.get_or_insert(Resource::Pin(pin));
+        }
+        self
+    }
+
+    /// 附加出错的SPI主机
+    pub fn with_spi_host(mut self, host: u32) -> Self {
+        self.resource.get_or_insert(Resource::SpiHost(host));
+        self
+    }
+
+    /// 附加出错的LEDC定时器
+    pub fn with_ledc_timer(mut self, timer: u32) -> Self {
+        self.resource.get_or_insert(Resource::LedcTimer(timer));
+        self
+    }
+
+    /// 附加出错的LEDC通道
+    pub fn with_ledc_channel(mut self, channel: u32) -> Self {
+        self.resource.get_or_insert(Resource::LedcChannel(channel));
+        self
+    }
+
+    /// 错误类别
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// ESP-IDF 返回的原始 esp_err_t
+    pub fn code(&self) -> Option<i32> {
+        self.code
+    }
+
+    /// esp_err_t 的名称，例如 `ESP_ERR_INVALID_ARG`
+    pub fn code_name(&self) -> Option<&'static str> {
+        self.code.map(esp_err_name)
+    }
+
+    /// 失败的操作
+    pub fn operation(&self) -> Option<&'static str> {
+        self.operation
+    }
+
+    /// 出错的引脚或总线
+    pub fn resource(&self) -> Option<Resource> {
+        self.resource
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resource::Pin(pin) => write!(f, "GPIO{}", pin),
+            Resource::SpiHost(host) => write!(f, "SPI{}", host),
+            Resource::LedcTimer(timer) => write!(f, "LEDC_TIMER{}", timer),
+            Resource::LedcChannel(channel) => write!(f, "LEDC_CH{}", channel),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    /// 格式为 `GPIO12 gpio_set_level: 配置错误 (ESP_ERR_INVALID_ARG, 0x102)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(resource) = self.resource {
+            write!(f, "{} ", resource)?;
+        }
+        if let Some(operation) = self.operation {
+            write!(f, "{}: ", operation)?;
+        }
+        write!(f, "{}", self.kind)?;
+        if let Some(code) = self.code {
+            write!(f, " ({}, {:#x})", esp_err_name(code), code)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// esp_err_t 的名称
+#[cfg(target_os = "espidf")]
+fn esp_err_name(code: i32) -> &'static str {
+    // esp_err_to_name 返回指向只读数据段的静态字符串
+    unsafe { std::ffi::CStr::from_ptr(esp_idf_svc::sys::esp_err_to_name(code)) }
+        .to_str()
+        .unwrap_or("UNKNOWN ERROR")
+}
+
+/// esp_err_t 的名称（主机上只收录常用错误码）
+#[cfg(not(target_os = "espidf"))]
+fn esp_err_name(code: i32) -> &'static str {
+    use esp_codes::*;
+
+    match code {
+        ESP_OK => "ESP_OK",
+        ESP_FAIL => "ESP_FAIL",
+        ESP_ERR_NO_MEM => "ESP_ERR_NO_MEM",
+        ESP_ERR_INVALID_ARG => "ESP_ERR_INVALID_ARG",
+        ESP_ERR_INVALID_STATE => "ESP_ERR_INVALID_STATE",
+        ESP_ERR_INVALID_SIZE => "ESP_ERR_INVALID_SIZE",
+        ESP_ERR_NOT_FOUND => "ESP_ERR_NOT_FOUND",
+        ESP_ERR_NOT_SUPPORTED => "ESP_ERR_NOT_SUPPORTED",
+        ESP_ERR_TIMEOUT => "ESP_ERR_TIMEOUT",
+        _ => "UNKNOWN ERROR",
+    }
+}
+
+/// 主机模拟使用的 esp_err_t 取值，与 ESP-IDF 的 esp_err.h 一致
+#[cfg(not(target_os = "espidf"))]
+pub(crate) mod esp_codes {
+    pub const ESP_OK: i32 = 0;
+    pub const ESP_FAIL: i32 = -1;
+    pub const ESP_ERR_NO_MEM: i32 = 0x101;
+    pub const ESP_ERR_INVALID_ARG: i32 = 0x102;
+    pub const ESP_ERR_INVALID_STATE: i32 = 0x103;
+    pub const ESP_ERR_INVALID_SIZE: i32 = 0x104;
+    pub const ESP_ERR_NOT_FOUND: i32 = 0x105;
+    pub const ESP_ERR_NOT_SUPPORTED: i32 = 0x106;
+    pub const ESP_ERR_TIMEOUT: i32 = 0x107;
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_with_context() {
+        let error = Error::esp(GpioError::ConfigError, 0x102, "gpio_set_level").with_pin(12);
+        assert_eq!(error.code_name(), Some("ESP_ERR_INVALID_ARG"));
+        assert_eq!(error.resource(), Some(Resource::Pin(12)));
+        assert_eq!(
+            error.to_string(),
+            "GPIO12 gpio_set_level: 配置错误 (ESP_ERR_INVALID_ARG, 0x102)"
+        );
+    }
+
+    #[test]
+    fn test_conversion_keeps_context() {
+        fn spi_layer() -> Result<()> {
+            Err(Error::esp(SpiError::DriverError, 0x103, "spi_device_transmit").with_spi_host(2))
+        }
+        fn display_layer() -> Result<()> {
+            spi_layer()?;
+            Err(DisplayError::BufferTooSmall.into())
+        }
+
+        let error = display_layer().unwrap_err();
+        assert_eq!(error.spi(), Some(&SpiError::DriverError));
+        assert_eq!(error.code(), Some(0x103));
+        assert_eq!(error.operation(), Some("spi_device_transmit"));
+        assert_eq!(error.resource(), Some(Resource::SpiHost(2)));
+
+        let error = Error::from(DisplayError::OutOfBounds);
+        assert_eq!(error.display_error(), Some(&DisplayError::OutOfBounds));
+        assert_eq!(error.spi(), None);
+        assert_eq!(error.to_string(), "坐标超出屏幕范围");
+    }
+}
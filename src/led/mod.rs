@@ -1,21 +1,47 @@
 use esp_idf_svc::sys::{
     gpio_config, gpio_config_t, gpio_int_type_t_GPIO_INTR_DISABLE,
     gpio_mode_t_GPIO_MODE_OUTPUT, gpio_num_t, gpio_pulldown_t_GPIO_PULLDOWN_DISABLE,
-    gpio_pullup_t_GPIO_PULLUP_DISABLE, gpio_set_level,
+    gpio_pullup_t_GPIO_PULLUP_DISABLE,
 };
+use std::time::Duration;
+
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::pwm::{FadeMode, LedcChannel, PwmResult};
+
 pub struct Led {
     pin: gpio_num_t,
     is_on: bool,
+    /// PWM通道，存在时支持亮度调节
+    pwm: Option<LedcChannel>,
+    /// 打开时的亮度（百分比）
+    brightness: f32,
 }
 
 impl Led {
     /// 创建新的LED控制实例
     pub fn new(pin: gpio_num_t) -> Self {
-        let mut led = Led { pin, is_on: false };
+        let mut led = Led {
+            pin,
+            is_on: false,
+            pwm: None,
+            brightness: 100.0,
+        };
         led.init();
         led
     }
 
+    /// 创建由PWM通道驱动的LED，支持亮度调节和呼吸效果
+    ///
+    /// 通道已经持有输出引脚，初始为关闭状态。
+    pub fn with_pwm(pwm: LedcChannel) -> Self {
+        Led {
+            pin: pwm.pin_number(),
+            is_on: false,
+            pwm: Some(pwm),
+            brightness: 100.0,
+        }
+    }
+
     /// 初始化GPIO引脚
     fn init(&mut self) {
         unsafe {
@@ -32,16 +58,28 @@ impl Led {
 
             // 配置GPIO
             gpio_config(&io_conf);
+        }
 
-            // 初始化为关闭状态
-            gpio_set_level(self.pin, 0);
+        // 初始化为关闭状态
+        self.write_level(0);
+    }
+
+    /// 经GPIO后端写输出电平，与 `GpioPort` 等其他输出写入共用临界区
+    fn write_level(&self, level: u32) {
+        if let Err(e) = Backend::set_level(self.pin, level) {
+            log::error!("LED {} 设置电平失败: {}", self.pin, e);
         }
     }
 
     /// 打开LED
     pub fn on(&mut self) {
-        unsafe {
-            gpio_set_level(self.pin, 1);
+        match &self.pwm {
+            Some(pwm) => {
+                if let Err(e) = pwm.set_duty_percent(self.brightness) {
+                    log::error!("LED {} 设置亮度失败: {}", self.pin, e);
+                }
+            }
+            None => self.write_level(u32::from(self.brightness > 0.0)),
         }
         self.is_on = true;
         println!("LED on pin {} is ON", self.pin);
@@ -50,8 +88,13 @@ impl Led {
 
     /// 关闭LED
     pub fn off(&mut self) {
-        unsafe {
-            gpio_set_level(self.pin, 0);
+        match &self.pwm {
+            Some(pwm) => {
+                if let Err(e) = pwm.set_duty(0) {
+                    log::error!("LED {} 设置亮度失败: {}", self.pin, e);
+                }
+            }
+            None => self.write_level(0),
         }
         self.is_on = false;
         println!("LED on pin {} is OFF", self.pin);
@@ -70,6 +113,74 @@ impl Led {
     pub fn is_on(&self) -> bool {
         self.is_on
     }
+
+    /// 设置亮度（百分比），LED打开时立即生效，关闭时只记录亮度
+    ///
+    /// 没有PWM通道的LED只能全亮或熄灭，打开时亮度大于0为全亮。
+    pub fn set_brightness(&mut self, percent: f32) -> PwmResult<()> {
+        self.brightness = percent.clamp(0.0, 100.0);
+        if !self.is_on {
+            return Ok(());
+        }
+        match &self.pwm {
+            Some(pwm) => pwm.set_duty_percent(self.brightness),
+            None => {
+                self.write_level(u32::from(self.brightness > 0.0));
+                Ok(())
+            }
+        }
+    }
+
+    /// 获取亮度（百分比）
+    pub fn brightness(&self) -> f32 {
+        self.brightness
+    }
+
+    /// 在指定时间内渐变到目标亮度并等待完成，需要PWM通道
+    ///
+    /// 渐变到0视为关闭，打开时的亮度保持不变，之后 `on` 仍恢复到原来的亮度。
+    pub fn fade_to(&mut self, percent: f32, duration: Duration) -> PwmResult<()> {
+        let percent = percent.clamp(0.0, 100.0);
+        let Some(pwm) = &self.pwm else {
+            if percent > 0.0 {
+                self.brightness = percent;
+                self.on();
+            } else {
+                self.off();
+            }
+            return Ok(());
+        };
+        pwm.fade_to_percent(percent, duration, FadeMode::WaitDone)?;
+        if percent > 0.0 {
+            self.brightness = percent;
+        }
+        self.is_on = percent > 0.0;
+        Ok(())
+    }
+
+    /// 呼吸效果：在一个周期内从熄灭渐亮到当前亮度再渐暗，重复 `cycles` 次
+    ///
+    /// 阻塞直到所有周期结束，结束时LED熄灭。没有PWM通道时以同样节奏闪烁。
+    pub fn breathe(&mut self, period: Duration, cycles: u32) -> PwmResult<()> {
+        let peak = self.brightness;
+        let half = period / 2;
+        for _ in 0..cycles {
+            match &self.pwm {
+                Some(pwm) => {
+                    pwm.fade_to_percent(peak, half, FadeMode::WaitDone)?;
+                    pwm.fade_to_percent(0.0, half, FadeMode::WaitDone)?;
+                }
+                None => {
+                    self.on();
+                    std::thread::sleep(half);
+                    self.off();
+                    std::thread::sleep(half);
+                }
+            }
+        }
+        self.is_on = false;
+        Ok(())
+    }
 }
 
 impl Drop for Led {
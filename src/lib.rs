@@ -1,3 +1,18 @@
-pub mod led;
+// 专用GPIO指令使用内联汇编，Xtensa 架构的 asm! 仍需启用实验特性
+#![cfg_attr(target_os = "espidf", feature(asm_experimental_arch))]
+
+pub mod board;
+// build.rs 的开发板描述检查随主机测试一起运行
+#[cfg(all(test, not(target_os = "espidf")))]
+#[allow(dead_code)]
+#[path = "../build/board.rs"]
+mod board_build;
+pub mod diagnostics;
+pub mod drivers;
+pub mod error;
 pub mod key;
-pub mod drivers;
\ No newline at end of file
+#[cfg(target_os = "espidf")]
+pub mod led;
+pub mod power;
+
+pub use error::{Error, ErrorKind, Result};
@@ -1,3 +1,4 @@
+#[cfg(target_os = "espidf")]
 fn main() {
     // It is necessary to call this function once. Otherwise some patches to the runtime
     // implemented by esp-idf-sys might not link properly. See https://github.com/esp-rs/esp-idf-template/issues/71
@@ -8,3 +9,6 @@ fn main() {
 
     log::info!("Hello, world!");
 }
+
+#[cfg(not(target_os = "espidf"))]
+fn main() {}
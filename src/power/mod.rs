@@ -0,0 +1,9 @@
+/**
+ * @file mod.rs
+ * @brief 电源管理
+ * @details 芯片级的电源管理功能，目前包括浅睡眠、深度睡眠及其唤醒源
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+pub mod sleep;
@@ -0,0 +1,157 @@
+/**
+ * @file esp.rs
+ * @brief ESP-IDF 睡眠与唤醒
+ * @details 基于 ESP-IDF esp_sleep 接口:
+ *          - 每次睡眠前关闭所有唤醒源，再按配置启用 ext0/ext1/GPIO/定时器唤醒
+ *          - 使用 ext0/ext1 时保持RTC外设域供电，使RTC IO的上下拉在睡眠中有效
+ *          - ext0 的唤醒引脚和锁存的引脚保存在RTC慢速内存中，深度睡眠唤醒后仍可读取
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use esp_idf_svc::sys::{
+    esp_deep_sleep_try_to_start, esp_light_sleep_start, esp_sleep_disable_wakeup_source,
+    esp_sleep_enable_ext0_wakeup, esp_sleep_enable_ext1_wakeup, esp_sleep_enable_gpio_wakeup,
+    esp_sleep_enable_timer_wakeup, esp_sleep_ext1_wakeup_mode_t_ESP_EXT1_WAKEUP_ANY_HIGH,
+    esp_sleep_ext1_wakeup_mode_t_ESP_EXT1_WAKEUP_ANY_LOW, esp_sleep_get_ext1_wakeup_status,
+    esp_sleep_get_wakeup_cause, esp_sleep_pd_config,
+    esp_sleep_pd_domain_t_ESP_PD_DOMAIN_RTC_PERIPH, esp_sleep_pd_option_t_ESP_PD_OPTION_ON,
+    esp_sleep_source_t_ESP_SLEEP_WAKEUP_ALL, esp_sleep_source_t_ESP_SLEEP_WAKEUP_EXT0,
+    esp_sleep_source_t_ESP_SLEEP_WAKEUP_EXT1, esp_sleep_source_t_ESP_SLEEP_WAKEUP_GPIO,
+    esp_sleep_source_t_ESP_SLEEP_WAKEUP_TIMER, esp_sleep_source_t_ESP_SLEEP_WAKEUP_UNDEFINED,
+    gpio_get_level,
+};
+
+use crate::error::Error;
+use crate::power::sleep::types::{
+    Ext1Mode, SleepError, SleepResult, WakeSources, WakeupCause, WakeupReport,
+};
+
+/// RTC内存中表示没有 ext0 引脚
+const NO_PIN: u32 = u32::MAX;
+
+/// 深度睡眠前配置的 ext0 唤醒引脚
+#[link_section = ".rtc.data"]
+static EXT0_PIN: AtomicU32 = AtomicU32::new(NO_PIN);
+
+/// 深度睡眠前锁存的引脚掩码（GPIO0~31、GPIO32~48）
+#[link_section = ".rtc.data"]
+static HELD_PINS: [AtomicU32; 2] = [AtomicU32::new(0), AtomicU32::new(0)];
+
+/// 按配置启用唤醒源
+fn configure(sources: &WakeSources) -> SleepResult<()> {
+    // 关闭上一次睡眠留下的唤醒源，没有启用任何唤醒源时也返回 ESP_OK
+    unsafe { esp_sleep_disable_wakeup_source(esp_sleep_source_t_ESP_SLEEP_WAKEUP_ALL) };
+
+    if let Some((pin, level)) = sources.ext0() {
+        let code = unsafe { esp_sleep_enable_ext0_wakeup(pin as i32, level.level() as i32) };
+        Error::check(
+            code,
+            SleepError::WakeupConfigError,
+            "esp_sleep_enable_ext0_wakeup",
+        )
+        .map_err(|error| error.with_pin(pin))?;
+    }
+    if let Some((mask, mode)) = sources.ext1() {
+        let mode = match mode {
+            Ext1Mode::AnyLow => esp_sleep_ext1_wakeup_mode_t_ESP_EXT1_WAKEUP_ANY_LOW,
+            Ext1Mode::AnyHigh => esp_sleep_ext1_wakeup_mode_t_ESP_EXT1_WAKEUP_ANY_HIGH,
+        };
+        let code = unsafe { esp_sleep_enable_ext1_wakeup(mask, mode) };
+        Error::check(
+            code,
+            SleepError::WakeupConfigError,
+            "esp_sleep_enable_ext1_wakeup",
+        )?;
+    }
+    if sources.ext0().is_some() || sources.ext1().is_some() {
+        let code = unsafe {
+            esp_sleep_pd_config(
+                esp_sleep_pd_domain_t_ESP_PD_DOMAIN_RTC_PERIPH,
+                esp_sleep_pd_option_t_ESP_PD_OPTION_ON,
+            )
+        };
+        Error::check(code, SleepError::WakeupConfigError, "esp_sleep_pd_config")?;
+    }
+    if !sources.gpio().is_empty() {
+        // 各引脚的唤醒电平已由 gpio_wakeup_enable 配置
+        let code = unsafe { esp_sleep_enable_gpio_wakeup() };
+        Error::check(
+            code,
+            SleepError::WakeupConfigError,
+            "esp_sleep_enable_gpio_wakeup",
+        )?;
+    }
+    if let Some(timer) = sources.timer() {
+        let code = unsafe { esp_sleep_enable_timer_wakeup(timer.as_micros() as u64) };
+        Error::check(
+            code,
+            SleepError::WakeupConfigError,
+            "esp_sleep_enable_timer_wakeup",
+        )?;
+    }
+    Ok(())
+}
+
+/// 读取唤醒原因
+fn wakeup_cause() -> WakeupCause {
+    match unsafe { esp_sleep_get_wakeup_cause() } {
+        esp_sleep_source_t_ESP_SLEEP_WAKEUP_UNDEFINED => WakeupCause::Reset,
+        esp_sleep_source_t_ESP_SLEEP_WAKEUP_EXT0 => WakeupCause::Ext0,
+        esp_sleep_source_t_ESP_SLEEP_WAKEUP_EXT1 => WakeupCause::Ext1,
+        esp_sleep_source_t_ESP_SLEEP_WAKEUP_GPIO => WakeupCause::Gpio,
+        esp_sleep_source_t_ESP_SLEEP_WAKEUP_TIMER => WakeupCause::Timer,
+        other => WakeupCause::Other(other as u32),
+    }
+}
+
+/// 进入浅睡眠，返回唤醒报告
+pub(super) fn light_sleep(sources: &WakeSources) -> SleepResult<WakeupReport> {
+    configure(sources)?;
+    let code = unsafe { esp_light_sleep_start() };
+    Error::check(code, SleepError::SleepRejected, "esp_light_sleep_start")?;
+    let ext1_status = unsafe { esp_sleep_get_ext1_wakeup_status() };
+    Ok(sources.report(wakeup_cause(), ext1_status, |pin| unsafe {
+        gpio_get_level(pin as i32) as u32
+    }))
+}
+
+/// 进入深度睡眠，只有进入失败时才会返回
+pub(super) fn deep_sleep(sources: &WakeSources, held: u64) -> SleepResult<()> {
+    configure(sources)?;
+    EXT0_PIN.store(
+        sources.ext0().map_or(NO_PIN, |(pin, _)| pin),
+        Ordering::Relaxed,
+    );
+    HELD_PINS[0].store(held as u32, Ordering::Relaxed);
+    HELD_PINS[1].store((held >> 32) as u32, Ordering::Relaxed);
+    let code = unsafe { esp_deep_sleep_try_to_start() };
+    Err(Error::esp(
+        SleepError::SleepRejected,
+        code,
+        "esp_deep_sleep_try_to_start",
+    ))
+}
+
+/// 本次启动的唤醒报告
+pub(super) fn wakeup_report() -> WakeupReport {
+    let cause = wakeup_cause();
+    let pins = match cause {
+        WakeupCause::Ext0 => match EXT0_PIN.load(Ordering::Relaxed) {
+            NO_PIN => 0,
+            pin => 1 << pin,
+        },
+        WakeupCause::Ext1 => unsafe { esp_sleep_get_ext1_wakeup_status() },
+        _ => 0,
+    };
+    WakeupReport::new(cause, pins)
+}
+
+/// 取出深度睡眠前锁存的引脚掩码
+pub(super) fn take_held() -> u64 {
+    let low = HELD_PINS[0].swap(0, Ordering::Relaxed);
+    let high = HELD_PINS[1].swap(0, Ordering::Relaxed);
+    u64::from(low) | (u64::from(high) << 32)
+}
@@ -0,0 +1,295 @@
+/**
+ * @file manager.rs
+ * @brief 睡眠管理
+ * @details 把 GpioPin 的唤醒和保持功能组织成一次完整的睡眠:
+ *          - 由 GpioPin 配置 ext0/ext1/GPIO 唤醒源，以及定时器唤醒
+ *          - 进入睡眠前锁存LCD背光、LED等输出引脚的电平，醒来后释放
+ *          - 浅睡眠返回唤醒原因和唤醒引脚；深度睡眠唤醒后芯片从头启动，
+ *            由 `wakeup_report` 读取唤醒原因，由 `release_holds` 释放锁存的引脚
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+#[cfg(target_os = "espidf")]
+use super::esp as chip;
+#[cfg(not(target_os = "espidf"))]
+use super::sim as chip;
+use crate::drivers::gpio::backend::{ActiveBackend as Backend, GpioBackend};
+use crate::drivers::gpio::registry::is_valid_gpio;
+use crate::drivers::gpio::{
+    GpioControl, GpioError, GpioInterruptType, GpioPin, InputMode, OutputMode,
+};
+use crate::error::Error;
+use crate::power::sleep::types::{
+    Ext1Mode, SleepMode, SleepResult, WakeLevel, WakeSources, WakeupReport,
+};
+
+/// 睡眠管理器
+///
+/// 只记录引脚编号，不持有引脚，引脚对象在睡眠期间仍由调用者持有。
+/// 配置可以重复用于多次睡眠。
+#[derive(Debug, Clone, Default)]
+pub struct SleepManager {
+    sources: WakeSources,
+    /// 睡眠期间锁存的输出引脚掩码
+    held: u64,
+}
+
+impl SleepManager {
+    /// 创建没有唤醒源的睡眠管理器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 已配置的唤醒源
+    pub fn sources(&self) -> &WakeSources {
+        &self.sources
+    }
+
+    /// 睡眠期间锁存的引脚掩码
+    pub fn held_pins(&self) -> u64 {
+        self.held
+    }
+
+    /// 由一个RTC GPIO以指定电平唤醒（ext0），浅睡眠和深度睡眠均可使用
+    ///
+    /// 引脚的上下拉在睡眠期间保持有效，按键应配置为与唤醒电平相反的上下拉。
+    pub fn wake_on_ext0<M: InputMode>(
+        &mut self,
+        pin: &GpioPin<M>,
+        level: WakeLevel,
+    ) -> SleepResult<()> {
+        self.sources.set_ext0(pin.get_pin_number() as u32, level)
+    }
+
+    /// 由一组RTC GPIO中的任意一个唤醒（ext1），浅睡眠和深度睡眠均可使用
+    pub fn wake_on_ext1<M: InputMode>(
+        &mut self,
+        pins: &[&GpioPin<M>],
+        mode: Ext1Mode,
+    ) -> SleepResult<()> {
+        let numbers: Vec<u32> = pins.iter().map(|pin| pin.get_pin_number() as u32).collect();
+        self.sources.set_ext1(&numbers, mode)
+    }
+
+    /// 由任意GPIO以指定电平唤醒，只能用于浅睡眠
+    ///
+    /// 睡眠期间引脚的中断类型被改为电平触发，醒来后恢复原来的中断类型。
+    pub fn wake_on_gpio<M: InputMode>(
+        &mut self,
+        pin: &GpioPin<M>,
+        level: WakeLevel,
+    ) -> SleepResult<()> {
+        let number = pin.get_pin_number() as u32;
+        if !is_valid_gpio(number) {
+            return Err(Error::new(GpioError::InvalidGpio).with_pin(number));
+        }
+        self.sources.add_gpio(number, level);
+        Ok(())
+    }
+
+    /// 睡眠指定时间后由定时器唤醒
+    pub fn wake_after(&mut self, duration: std::time::Duration) -> SleepResult<()> {
+        self.sources.set_timer(duration)
+    }
+
+    /// 睡眠期间锁存输出引脚的当前电平
+    pub fn hold<M: OutputMode>(&mut self, pin: &GpioPin<M>) {
+        self.held |= 1 << pin.get_pin_number();
+    }
+
+    /// 睡眠期间锁存一组引脚的当前电平
+    ///
+    /// 适用于引脚已交给其他驱动（如LEDC背光）而手中没有 `GpioPin` 的情况。
+    pub fn hold_pins(&mut self, pins: &[u32]) -> SleepResult<()> {
+        if let Some(&pin) = pins.iter().find(|&&pin| !is_valid_gpio(pin)) {
+            return Err(Error::new(GpioError::InvalidGpio).with_pin(pin));
+        }
+        self.held |= pins.iter().fold(0u64, |mask, &pin| mask | (1 << pin));
+        Ok(())
+    }
+
+    /// 清除所有唤醒源和锁存引脚
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 进入浅睡眠，唤醒后返回唤醒原因和唤醒引脚
+    ///
+    /// 醒来后释放锁存的引脚并关闭GPIO唤醒；电平唤醒源在进入时已经满足会立即醒来。
+    pub fn light_sleep(&mut self) -> SleepResult<WakeupReport> {
+        self.sources.check(SleepMode::Light)?;
+        set_holds(self.held, true)?;
+        let saved = self.enable_gpio_wakeup();
+        let result = match &saved {
+            Ok(_) => chip::light_sleep(&self.sources),
+            Err(error) => Err(error.clone()),
+        };
+
+        // 无论是否成功进入睡眠都恢复引脚
+        let restored = self.disable_gpio_wakeup(saved.as_deref().unwrap_or_default());
+        let released = set_holds(self.held, false);
+        let report = result?;
+        restored?;
+        released?;
+        Ok(report)
+    }
+
+    /// 进入深度睡眠
+    ///
+    /// 锁存的引脚在睡眠期间和唤醒后保持电平，直到调用 [`release_holds`]。
+    /// 在芯片上成功时不会返回，唤醒后芯片从头启动；返回错误表示没有进入睡眠，
+    /// 此时锁存已经释放。主机模拟时推进虚拟时钟直到唤醒后返回 `Ok`。
+    pub fn deep_sleep(&mut self) -> SleepResult<()> {
+        self.sources.check(SleepMode::Deep)?;
+        set_holds(self.held, true)?;
+        if self.held != 0 {
+            // 数字GPIO在深度睡眠中还需要全局保持
+            GpioControl::enable_deep_sleep_hold();
+        }
+        let result = chip::deep_sleep(&self.sources, self.held);
+        if result.is_err() {
+            GpioControl::disable_deep_sleep_hold();
+            set_holds(self.held, false)?;
+        }
+        result
+    }
+
+    /// 打开GPIO唤醒，返回各引脚原来的中断类型
+    fn enable_gpio_wakeup(&self) -> SleepResult<Vec<(u32, GpioInterruptType)>> {
+        let mut saved = Vec::with_capacity(self.sources.gpio().len());
+        for &(pin, level) in self.sources.gpio() {
+            let intr_type = Backend::snapshot(pin as i32)?.intr_type;
+            Backend::enable_wakeup(pin as i32, level.interrupt_type())?;
+            saved.push((pin, intr_type));
+        }
+        Ok(saved)
+    }
+
+    /// 关闭GPIO唤醒并恢复原来的中断类型
+    fn disable_gpio_wakeup(&self, saved: &[(u32, GpioInterruptType)]) -> SleepResult<()> {
+        for &(pin, _) in self.sources.gpio() {
+            Backend::disable_wakeup(pin as i32)?;
+        }
+        for &(pin, intr_type) in saved {
+            Backend::set_interrupt_type(pin as i32, intr_type)?;
+        }
+        Ok(())
+    }
+}
+
+/// 锁存或释放掩码中的引脚
+fn set_holds(mask: u64, enable: bool) -> SleepResult<()> {
+    (0..64)
+        .filter(|pin| mask & (1 << pin) != 0)
+        .try_for_each(|pin| Backend::set_hold(pin, enable))
+}
+
+/// 读取本次启动的唤醒原因
+///
+/// 上电或复位时原因为 `WakeupCause::Reset`；从深度睡眠唤醒时报告唤醒源及唤醒引脚，
+/// ext0 的引脚在睡眠前保存在RTC内存中。
+pub fn wakeup_report() -> WakeupReport {
+    chip::wakeup_report()
+}
+
+/// 释放深度睡眠前锁存的引脚
+///
+/// 应在重新配置这些引脚（如重新输出背光电平）之后调用，以免释放时电平跳变。
+/// 不是从深度睡眠唤醒时没有需要释放的引脚，直接返回。
+pub fn release_holds() -> SleepResult<()> {
+    let held = chip::take_held();
+    if held != 0 {
+        GpioControl::disable_deep_sleep_hold();
+    }
+    set_holds(held, false)
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::drivers::gpio::backend::sim::{self, Waveform};
+    use crate::drivers::gpio::Input;
+    use crate::power::sleep::types::WakeupCause;
+
+    #[test]
+    fn test_light_sleep_wakes_on_key_or_timer() {
+        sim::reset();
+        let key = GpioPin::new(0).unwrap().into_pull_up_input().unwrap();
+        key.set_interrupt_type(GpioInterruptType::FallingEdge)
+            .unwrap();
+        let led = GpioPin::new(1).unwrap().into_push_pull_output().unwrap();
+        led.set_high().unwrap();
+
+        let mut sleep = SleepManager::new();
+        assert!(sleep.light_sleep().is_err());
+        sleep.wake_on_gpio(&key, WakeLevel::Low).unwrap();
+        sleep.wake_after(Duration::from_secs(10)).unwrap();
+        sleep.hold(&led);
+
+        // 3秒后按下按键
+        sim::play(
+            0,
+            &Waveform::new()
+                .then(Duration::from_secs(3), 0)
+                .then(Duration::from_millis(100), 1),
+        );
+        let report = sleep.light_sleep().unwrap();
+        assert_eq!(report.cause(), WakeupCause::Gpio);
+        assert_eq!(report.pin(), Some(0));
+        assert_eq!(sim::now_us(), 3_000_000);
+        // 醒来后释放锁存并恢复按键的中断配置
+        let state = sim::pin_state(0);
+        assert_eq!(
+            (state.wakeup, state.intr_type),
+            (None, GpioInterruptType::FallingEdge)
+        );
+        assert!(!sim::pin_state(1).hold);
+
+        // 按键松开后没有其他事件，由定时器唤醒
+        sim::advance(Duration::from_millis(100));
+        let report = sleep.light_sleep().unwrap();
+        assert_eq!((report.cause(), report.pin()), (WakeupCause::Timer, None));
+        assert_eq!(sim::now_us(), 13_100_000);
+    }
+
+    #[test]
+    fn test_deep_sleep_holds_outputs_until_released() {
+        sim::reset();
+        assert!(!wakeup_report().is_wakeup());
+        let key = GpioPin::new(0).unwrap().into_pull_up_input().unwrap();
+        let backlight = GpioPin::new(45).unwrap().into_push_pull_output().unwrap();
+
+        let mut sleep = SleepManager::new();
+        sleep.wake_on_gpio(&key, WakeLevel::Low).unwrap();
+        assert!(sleep.deep_sleep().is_err());
+        sleep.clear();
+        assert!(sleep
+            .wake_on_ext0(&non_rtc_input(), WakeLevel::Low)
+            .is_err());
+        sleep.wake_on_ext0(&key, WakeLevel::Low).unwrap();
+        sleep.hold(&backlight);
+        sleep.hold_pins(&[46]).unwrap();
+        assert!(sleep.hold_pins(&[22]).is_err());
+
+        sim::play(0, &Waveform::new().then(Duration::from_millis(500), 0));
+        sleep.deep_sleep().unwrap();
+        assert!(sim::pin_state(45).hold && sim::pin_state(46).hold);
+        assert!(sim::deep_sleep_hold_enabled());
+
+        let report = wakeup_report();
+        assert_eq!((report.cause(), report.pin()), (WakeupCause::Ext0, Some(0)));
+        release_holds().unwrap();
+        assert!(!sim::pin_state(45).hold && !sim::pin_state(46).hold);
+        assert!(!sim::deep_sleep_hold_enabled());
+        // 锁存只释放一次
+        release_holds().unwrap();
+    }
+
+    /// 不是RTC GPIO的输入引脚
+    fn non_rtc_input() -> GpioPin<Input> {
+        GpioPin::new(38).unwrap().into_floating_input().unwrap()
+    }
+}
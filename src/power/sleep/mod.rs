@@ -0,0 +1,21 @@
+/**
+ * @file mod.rs
+ * @brief 睡眠与唤醒
+ * @details 用于电池供电时的低功耗待机:
+ *          - ext0/ext1/GPIO/定时器唤醒源由 GpioPin 配置
+ *          - 浅睡眠和深度睡眠，睡眠期间锁存背光、LED等输出引脚
+ *          - 唤醒后报告唤醒原因和唤醒引脚
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+// 睡眠在ESP-IDF上使用 esp_sleep 接口，在主机上由GPIO模拟后端的虚拟时钟模拟
+#[cfg(target_os = "espidf")]
+mod esp;
+mod manager;
+#[cfg(not(target_os = "espidf"))]
+mod sim;
+mod types;
+
+pub use manager::*;
+pub use types::*;
@@ -0,0 +1,81 @@
+/**
+ * @file sim.rs
+ * @brief 主机模拟睡眠
+ * @details 在GPIO模拟后端的虚拟时钟上模拟睡眠:
+ *          - 按波形事件推进虚拟时钟，直到电平唤醒源满足或定时器到期
+ *          - 深度睡眠不复位模拟芯片，只记录唤醒原因和锁存的引脚，
+ *            相当于唤醒后重新启动的程序读取到的状态
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::cell::RefCell;
+use std::time::Duration;
+
+use crate::drivers::gpio::backend::sim as gpio_sim;
+use crate::power::sleep::types::{SleepResult, WakeSources, WakeupCause, WakeupReport};
+
+/// 跨越深度睡眠保留的状态（对应芯片的RTC内存）
+#[derive(Default)]
+struct RtcState {
+    boot_report: WakeupReport,
+    held: u64,
+}
+
+thread_local! {
+    static RTC: RefCell<RtcState> = RefCell::new(RtcState::default());
+}
+
+/// 推进虚拟时钟直到被唤醒
+///
+/// 电平唤醒源在进入时已经满足会立即醒来。既没有待回放的波形也没有定时器时永远不会醒来，
+/// 视为测试脚本错误。
+fn wait_for_wakeup(sources: &WakeSources) -> WakeupReport {
+    let deadline = sources
+        .timer()
+        .map(|timer| gpio_sim::now_us() + timer.as_micros() as u64);
+    loop {
+        if let Some(report) = sources.triggered(|pin| gpio_sim::pad_level(pin as i32)) {
+            return report;
+        }
+        let now = gpio_sim::now_us();
+        match (gpio_sim::next_event_us(), deadline) {
+            (Some(next), Some(deadline)) if next <= deadline => {
+                gpio_sim::advance(Duration::from_micros(next - now))
+            }
+            (_, Some(deadline)) => {
+                gpio_sim::advance(Duration::from_micros(deadline - now));
+                return WakeupReport::new(WakeupCause::Timer, 0);
+            }
+            (Some(next), None) => gpio_sim::advance(Duration::from_micros(next - now)),
+            (None, None) => panic!("模拟睡眠没有可以唤醒的事件"),
+        }
+    }
+}
+
+/// 进入浅睡眠，返回唤醒报告
+pub(super) fn light_sleep(sources: &WakeSources) -> SleepResult<WakeupReport> {
+    Ok(wait_for_wakeup(sources))
+}
+
+/// 进入深度睡眠，唤醒后记录唤醒原因和锁存的引脚
+pub(super) fn deep_sleep(sources: &WakeSources, held: u64) -> SleepResult<()> {
+    let report = wait_for_wakeup(sources);
+    RTC.with(|rtc| {
+        *rtc.borrow_mut() = RtcState {
+            boot_report: report,
+            held,
+        }
+    });
+    Ok(())
+}
+
+/// 最近一次深度睡眠的唤醒报告
+pub(super) fn wakeup_report() -> WakeupReport {
+    RTC.with(|rtc| rtc.borrow().boot_report)
+}
+
+/// 取出深度睡眠前锁存的引脚掩码
+pub(super) fn take_held() -> u64 {
+    RTC.with(|rtc| std::mem::take(&mut rtc.borrow_mut().held))
+}
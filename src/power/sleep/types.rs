@@ -0,0 +1,400 @@
+/**
+ * @file types.rs
+ * @brief 睡眠与唤醒类型定义
+ * @details 错误类型、唤醒电平、唤醒源配置以及唤醒原因报告，
+ *          唤醒源的校验和唤醒引脚的判断不依赖硬件，可以在主机上测试
+ * @author xwx
+ * @date 2025-05-13
+ * @version 1.0
+ */
+use std::fmt;
+use std::time::Duration;
+
+use crate::drivers::gpio::GpioInterruptType;
+use crate::error::Error;
+
+/// ESP32-S3 的RTC GPIO数量（GPIO0 ~ GPIO21），只有这些引脚可以用于 ext0/ext1 唤醒
+pub const RTC_GPIO_COUNT: u32 = 22;
+
+/// 判断引脚是否为RTC GPIO
+pub fn is_rtc_gpio(pin: u32) -> bool {
+    pin < RTC_GPIO_COUNT
+}
+
+/// 睡眠错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SleepError {
+    /// 参数错误（ext1 引脚为空、定时时间为0等）
+    InvalidParameter,
+    /// 引脚不是RTC GPIO，不能用于 ext0/ext1 唤醒
+    NotRtcGpio,
+    /// 没有配置唤醒源，或唤醒源不适用于所选的睡眠模式
+    NoWakeupSource,
+    /// 唤醒源配置失败
+    WakeupConfigError,
+    /// 进入睡眠被拒绝（如唤醒源在进入前已经触发）
+    SleepRejected,
+}
+
+impl fmt::Display for SleepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SleepError::InvalidParameter => write!(f, "参数错误"),
+            SleepError::NotRtcGpio => write!(f, "不是RTC GPIO"),
+            SleepError::NoWakeupSource => write!(f, "没有可用的唤醒源"),
+            SleepError::WakeupConfigError => write!(f, "唤醒源配置错误"),
+            SleepError::SleepRejected => write!(f, "进入睡眠被拒绝"),
+        }
+    }
+}
+
+/// 睡眠操作结果类型
+pub type SleepResult<T> = Result<T, crate::error::Error>;
+
+/// 睡眠模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SleepMode {
+    /// 浅睡眠：CPU暂停，内存和外设状态保留，唤醒后从调用处继续执行
+    Light,
+    /// 深度睡眠：只有RTC域保持供电，唤醒后芯片从头启动
+    Deep,
+}
+
+/// 唤醒电平
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WakeLevel {
+    /// 低电平唤醒，适用于接地的按键
+    #[default]
+    Low,
+    /// 高电平唤醒
+    High,
+}
+
+impl WakeLevel {
+    /// 引脚电平是否满足唤醒条件
+    pub fn matches(self, level: u32) -> bool {
+        (level != 0) == (self == WakeLevel::High)
+    }
+
+    /// 电平数值
+    pub fn level(self) -> u32 {
+        u32::from(self == WakeLevel::High)
+    }
+
+    /// 对应的GPIO唤醒中断类型
+    pub fn interrupt_type(self) -> GpioInterruptType {
+        match self {
+            WakeLevel::Low => GpioInterruptType::LowLevel,
+            WakeLevel::High => GpioInterruptType::HighLevel,
+        }
+    }
+}
+
+/// ext1 唤醒的触发方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ext1Mode {
+    /// 任意一个引脚为低电平时唤醒
+    #[default]
+    AnyLow,
+    /// 任意一个引脚为高电平时唤醒
+    AnyHigh,
+}
+
+impl Ext1Mode {
+    /// 触发唤醒的电平
+    pub fn level(self) -> WakeLevel {
+        match self {
+            Ext1Mode::AnyLow => WakeLevel::Low,
+            Ext1Mode::AnyHigh => WakeLevel::High,
+        }
+    }
+}
+
+/// 唤醒原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WakeupCause {
+    /// 上电或复位，不是从睡眠中唤醒
+    #[default]
+    Reset,
+    /// RTC_IO 单引脚唤醒
+    Ext0,
+    /// RTC_CNTL 多引脚唤醒
+    Ext1,
+    /// GPIO唤醒（仅浅睡眠）
+    Gpio,
+    /// 定时器唤醒
+    Timer,
+    /// 其他唤醒源（触摸、ULP、UART等），保留 esp_sleep_source_t 原始值
+    Other(u32),
+}
+
+impl fmt::Display for WakeupCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WakeupCause::Reset => write!(f, "上电复位"),
+            WakeupCause::Ext0 => write!(f, "ext0唤醒"),
+            WakeupCause::Ext1 => write!(f, "ext1唤醒"),
+            WakeupCause::Gpio => write!(f, "GPIO唤醒"),
+            WakeupCause::Timer => write!(f, "定时器唤醒"),
+            WakeupCause::Other(source) => write!(f, "其他唤醒源({})", source),
+        }
+    }
+}
+
+/// 唤醒报告：唤醒原因及触发唤醒的引脚
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WakeupReport {
+    cause: WakeupCause,
+    pin_mask: u64,
+}
+
+impl WakeupReport {
+    /// 创建唤醒报告
+    ///
+    /// # 参数
+    ///
+    /// * `cause` - 唤醒原因
+    /// * `pin_mask` - 触发唤醒的引脚掩码，第n位对应GPIOn
+    pub fn new(cause: WakeupCause, pin_mask: u64) -> Self {
+        WakeupReport { cause, pin_mask }
+    }
+
+    /// 唤醒原因
+    pub fn cause(&self) -> WakeupCause {
+        self.cause
+    }
+
+    /// 是否是从睡眠中唤醒
+    pub fn is_wakeup(&self) -> bool {
+        self.cause != WakeupCause::Reset
+    }
+
+    /// 触发唤醒的引脚掩码
+    pub fn pin_mask(&self) -> u64 {
+        self.pin_mask
+    }
+
+    /// 触发唤醒的引脚，多个引脚同时满足条件时返回编号最小的一个
+    pub fn pin(&self) -> Option<u32> {
+        (self.pin_mask != 0).then(|| self.pin_mask.trailing_zeros())
+    }
+
+    /// 所有触发唤醒的引脚
+    pub fn pins(&self) -> Vec<u32> {
+        (0..64)
+            .filter(|pin| self.pin_mask & (1 << pin) != 0)
+            .collect()
+    }
+}
+
+impl fmt::Display for WakeupReport {
+    /// 格式为 `ext1唤醒 (GPIO0, GPIO4)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.cause)?;
+        let pins = self.pins();
+        if !pins.is_empty() {
+            let names: Vec<_> = pins.iter().map(|pin| format!("GPIO{}", pin)).collect();
+            write!(f, " ({})", names.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// 唤醒源配置
+///
+/// ext0/ext1 由RTC域检测，浅睡眠和深度睡眠都可以使用，引脚必须是RTC GPIO；
+/// GPIO唤醒可以使用任意引脚，但ESP32-S3只支持在浅睡眠中使用。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WakeSources {
+    ext0: Option<(u32, WakeLevel)>,
+    ext1: Option<(u64, Ext1Mode)>,
+    gpio: Vec<(u32, WakeLevel)>,
+    timer: Option<Duration>,
+}
+
+impl WakeSources {
+    /// 设置 ext0 唤醒，替换原有的 ext0 配置
+    pub fn set_ext0(&mut self, pin: u32, level: WakeLevel) -> SleepResult<()> {
+        if !is_rtc_gpio(pin) {
+            return Err(Error::new(SleepError::NotRtcGpio).with_pin(pin));
+        }
+        self.ext0 = Some((pin, level));
+        Ok(())
+    }
+
+    /// 设置 ext1 唤醒，替换原有的 ext1 配置
+    pub fn set_ext1(&mut self, pins: &[u32], mode: Ext1Mode) -> SleepResult<()> {
+        if pins.is_empty() {
+            return Err(Error::new(SleepError::InvalidParameter));
+        }
+        if let Some(&pin) = pins.iter().find(|&&pin| !is_rtc_gpio(pin)) {
+            return Err(Error::new(SleepError::NotRtcGpio).with_pin(pin));
+        }
+        let mask = pins.iter().fold(0u64, |mask, &pin| mask | (1 << pin));
+        self.ext1 = Some((mask, mode));
+        Ok(())
+    }
+
+    /// 添加一个GPIO唤醒引脚，同一引脚重复添加时替换唤醒电平
+    pub fn add_gpio(&mut self, pin: u32, level: WakeLevel) {
+        self.gpio.retain(|&(existing, _)| existing != pin);
+        self.gpio.push((pin, level));
+    }
+
+    /// 设置定时器唤醒
+    pub fn set_timer(&mut self, duration: Duration) -> SleepResult<()> {
+        if duration.is_zero() {
+            return Err(Error::new(SleepError::InvalidParameter));
+        }
+        self.timer = Some(duration);
+        Ok(())
+    }
+
+    /// ext0 唤醒引脚及电平
+    pub fn ext0(&self) -> Option<(u32, WakeLevel)> {
+        self.ext0
+    }
+
+    /// ext1 唤醒引脚掩码及触发方式
+    pub fn ext1(&self) -> Option<(u64, Ext1Mode)> {
+        self.ext1
+    }
+
+    /// GPIO唤醒引脚及电平
+    pub fn gpio(&self) -> &[(u32, WakeLevel)] {
+        &self.gpio
+    }
+
+    /// 定时器唤醒时间
+    pub fn timer(&self) -> Option<Duration> {
+        self.timer
+    }
+
+    /// 是否没有配置任何唤醒源
+    pub fn is_empty(&self) -> bool {
+        self.ext0.is_none() && self.ext1.is_none() && self.gpio.is_empty() && self.timer.is_none()
+    }
+
+    /// 检查唤醒源能否用于指定的睡眠模式
+    ///
+    /// 没有唤醒源时芯片无法醒来；深度睡眠中GPIO唤醒不起作用，配置了也视为错误，
+    /// 避免以为按键可以唤醒而实际只能等定时器。
+    pub fn check(&self, mode: SleepMode) -> SleepResult<()> {
+        if self.is_empty() {
+            return Err(Error::new(SleepError::NoWakeupSource));
+        }
+        if let (SleepMode::Deep, Some(&(pin, _))) = (mode, self.gpio.first()) {
+            return Err(Error::new(SleepError::NoWakeupSource).with_pin(pin));
+        }
+        Ok(())
+    }
+
+    /// 按引脚当前电平判断是否有电平唤醒源已经满足，依次检查 ext0、ext1 和 GPIO
+    pub fn triggered(&self, level: impl Fn(u32) -> u32) -> Option<WakeupReport> {
+        if let Some((pin, wake)) = self.ext0 {
+            if wake.matches(level(pin)) {
+                return Some(WakeupReport::new(WakeupCause::Ext0, 1 << pin));
+            }
+        }
+        if let Some((mask, mode)) = self.ext1 {
+            let pins = self.matching(mask, mode.level(), &level);
+            if pins != 0 {
+                return Some(WakeupReport::new(WakeupCause::Ext1, pins));
+            }
+        }
+        let pins = self
+            .gpio
+            .iter()
+            .filter(|&&(pin, wake)| wake.matches(level(pin)))
+            .fold(0u64, |mask, &(pin, _)| mask | (1 << pin));
+        (pins != 0).then(|| WakeupReport::new(WakeupCause::Gpio, pins))
+    }
+
+    /// 由芯片报告的唤醒原因生成唤醒报告
+    ///
+    /// ext1 的唤醒引脚由硬件记录；ext0 只有一个引脚；GPIO唤醒没有硬件记录，
+    /// 以醒来时仍满足唤醒电平的引脚为准。
+    ///
+    /// # 参数
+    ///
+    /// * `cause` - 唤醒原因
+    /// * `ext1_status` - esp_sleep_get_ext1_wakeup_status 返回的引脚掩码
+    /// * `level` - 读取引脚当前电平
+    pub fn report(
+        &self,
+        cause: WakeupCause,
+        ext1_status: u64,
+        level: impl Fn(u32) -> u32,
+    ) -> WakeupReport {
+        let pins = match cause {
+            WakeupCause::Ext0 => self.ext0.map_or(0, |(pin, _)| 1 << pin),
+            WakeupCause::Ext1 => ext1_status,
+            WakeupCause::Gpio => self
+                .gpio
+                .iter()
+                .filter(|&&(pin, wake)| wake.matches(level(pin)))
+                .fold(0u64, |mask, &(pin, _)| mask | (1 << pin)),
+            _ => 0,
+        };
+        WakeupReport::new(cause, pins)
+    }
+
+    /// 掩码中满足唤醒电平的引脚
+    fn matching(&self, mask: u64, wake: WakeLevel, level: &impl Fn(u32) -> u32) -> u64 {
+        (0..64)
+            .filter(|pin| mask & (1 << pin) != 0 && wake.matches(level(*pin)))
+            .fold(0u64, |pins, pin| pins | (1 << pin))
+    }
+}
+
+#[cfg(all(test, not(target_os = "espidf")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sources_validate_and_resolve_pins() {
+        let mut sources = WakeSources::default();
+        assert_eq!(
+            sources.check(SleepMode::Light).unwrap_err().sleep(),
+            Some(&SleepError::NoWakeupSource)
+        );
+        let error = sources.set_ext0(38, WakeLevel::Low).unwrap_err();
+        assert_eq!(error.sleep(), Some(&SleepError::NotRtcGpio));
+        assert!(sources.set_ext1(&[], Ext1Mode::AnyLow).is_err());
+        assert!(sources.set_ext1(&[2, 40], Ext1Mode::AnyLow).is_err());
+        assert!(sources.set_timer(Duration::ZERO).is_err());
+
+        sources.set_ext0(0, WakeLevel::Low).unwrap();
+        sources.set_ext1(&[4, 5], Ext1Mode::AnyHigh).unwrap();
+        sources.add_gpio(40, WakeLevel::High);
+        sources.add_gpio(40, WakeLevel::Low);
+        assert_eq!(sources.gpio(), &[(40, WakeLevel::Low)]);
+        assert!(sources.check(SleepMode::Light).is_ok());
+        // GPIO唤醒不能用于深度睡眠
+        let error = sources.check(SleepMode::Deep).unwrap_err();
+        assert_eq!(error.sleep(), Some(&SleepError::NoWakeupSource));
+        assert_eq!(error.resource(), Some(crate::error::Resource::Pin(40)));
+
+        // 空闲时按键为高电平、其余为低电平，不满足任何唤醒条件
+        let idle = |pin: u32| u32::from(pin == 0 || pin == 40);
+        assert_eq!(sources.triggered(idle), None);
+        let report = sources
+            .triggered(|pin| u32::from(pin == 0 || pin == 5))
+            .unwrap();
+        assert_eq!(report.cause(), WakeupCause::Ext1);
+        assert_eq!(report.pins(), vec![5]);
+        let report = sources.triggered(|_| 0).unwrap();
+        assert_eq!((report.cause(), report.pin()), (WakeupCause::Ext0, Some(0)));
+
+        let report = sources.report(WakeupCause::Ext1, (1 << 4) | (1 << 5), idle);
+        assert_eq!(report.to_string(), "ext1唤醒 (GPIO4, GPIO5)");
+        let report = sources.report(WakeupCause::Gpio, 0, |_| 0);
+        assert_eq!(report.pin(), Some(40));
+        let report = sources.report(WakeupCause::Timer, 0, idle);
+        assert_eq!(
+            (report.pin(), report.to_string().as_str()),
+            (None, "定时器唤醒")
+        );
+        assert!(!WakeupReport::default().is_wakeup());
+    }
+}